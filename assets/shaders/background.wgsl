@@ -0,0 +1,38 @@
+#import bevy_sprite::mesh2d_vertex_output::VertexOutput
+
+struct Background {
+    base_color: vec4<f32>,
+    camera_position: vec2<f32>,
+    time: f32,
+    intensity: f32,
+    parallax: f32,
+}
+
+@group(2) @binding(0) var<uniform> background: Background;
+
+fn hash(p: vec2<f32>) -> f32 {
+    return fract(sin(dot(p, vec2<f32>(127.1, 311.7))) * 43758.5453);
+}
+
+fn value_noise(p: vec2<f32>) -> f32 {
+    let i = floor(p);
+    let f = fract(p);
+    let u = f * f * (3.0 - 2.0 * f);
+    let a = hash(i);
+    let b = hash(i + vec2<f32>(1.0, 0.0));
+    let c = hash(i + vec2<f32>(0.0, 1.0));
+    let d = hash(i + vec2<f32>(1.0, 1.0));
+    return mix(mix(a, b, u.x), mix(c, d, u.x), u.y);
+}
+
+@fragment
+fn fragment(mesh: VertexOutput) -> @location(0) vec4<f32> {
+    // slow parallax so the background drifts behind the camera instead of sticking to it
+    let p = (mesh.world_position.xy - background.camera_position * (1.0 - background.parallax)) / 600.0;
+    let drift = vec2<f32>(background.time * 0.03, background.time * 0.05);
+    let n = value_noise(p + drift) * 0.6 + value_noise(p * 2.3 - drift) * 0.4;
+    let gradient = clamp(mesh.uv.y, 0.0, 1.0);
+    let warm = vec3<f32>(0.35, 0.08, 0.02) * background.intensity * gradient;
+    let color = background.base_color.rgb * (0.85 + 0.3 * n) + warm;
+    return vec4<f32>(color, 1.0);
+}
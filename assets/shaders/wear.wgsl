@@ -0,0 +1,61 @@
+#import bevy_sprite::mesh2d_vertex_output::VertexOutput
+
+struct Wear {
+    color: vec4<f32>,
+    wear: f32,
+    seed: f32,
+}
+
+@group(2) @binding(0) var<uniform> wear: Wear;
+
+fn hash2(p: vec2<f32>) -> vec2<f32> {
+    let q = vec2<f32>(dot(p, vec2<f32>(127.1, 311.7)), dot(p, vec2<f32>(269.5, 183.3)));
+    return fract(sin(q) * 43758.5453);
+}
+
+fn value_noise(p: vec2<f32>) -> f32 {
+    let i = floor(p);
+    let f = fract(p);
+    let u = f * f * (3.0 - 2.0 * f);
+    let a = hash2(i).x;
+    let b = hash2(i + vec2<f32>(1.0, 0.0)).x;
+    let c = hash2(i + vec2<f32>(0.0, 1.0)).x;
+    let d = hash2(i + vec2<f32>(1.0, 1.0)).x;
+    return mix(mix(a, b, u.x), mix(c, d, u.x), u.y);
+}
+
+// distance to the nearest edge between voronoi cells, the edges are the cracks
+fn cell_edge(p: vec2<f32>) -> f32 {
+    let i = floor(p);
+    let f = fract(p);
+    var nearest = 8.0;
+    var second = 8.0;
+    for (var y = -1; y <= 1; y++) {
+        for (var x = -1; x <= 1; x++) {
+            let cell = vec2<f32>(f32(x), f32(y));
+            let d = length(cell + hash2(i + cell) - f);
+            if d < nearest {
+                second = nearest;
+                nearest = d;
+            } else if d < second {
+                second = d;
+            }
+        }
+    }
+    return second - nearest;
+}
+
+@fragment
+fn fragment(mesh: VertexOutput) -> @location(0) vec4<f32> {
+    let p = mesh.uv + vec2<f32>(wear.seed, wear.seed * 0.37);
+    // scuffs show up first, a fine grain that darkens as the rect takes hits
+    let scuff = value_noise(p * 24.0) * value_noise(p * 7.0);
+    var shade = 1.0 - scuff * wear.wear * 0.5;
+    // cracks only run through the part of the rect the wear has reached so far, so
+    // they spread across it as the run goes on
+    let reach = value_noise(p * 3.0);
+    let width = 0.02 + 0.04 * wear.wear;
+    let crack = (1.0 - smoothstep(0.0, width, cell_edge(p * 4.0))) * step(reach, wear.wear * 1.2 - 0.2);
+    shade = shade * (1.0 - crack * 0.6);
+    return vec4<f32>(wear.color.rgb * shade, wear.color.a);
+}
@@ -0,0 +1,158 @@
+use bevy::prelude::*;
+
+use crate::hud::ShowToast;
+use crate::i18n::Localization;
+use crate::menu::{MenuAction, MenuPage, MenuRow, MenuRows, MenuSet};
+use crate::save::SaveData;
+use crate::score::RunStats;
+use crate::{GameSet, PlayerCrushed, PlayerWallJumped};
+
+const SURVIVAL_SECS: f32 = 5.0 * 60.0;
+const WALL_JUMP_CHAIN: u32 = 10;
+const CRUSHING_RECT_SIZE: f32 = 500.0;
+
+pub struct AchievementsPlugin;
+
+impl Plugin for AchievementsPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<AchievementUnlocked>().add_systems(
+            Update,
+            (
+                (
+                    track_survival,
+                    track_wall_jumps,
+                    track_crushes,
+                    announce_unlocks,
+                )
+                    .chain()
+                    .in_set(GameSet::Reactions),
+                achievement_menu_rows.in_set(MenuSet::Rows),
+            ),
+        );
+    }
+}
+
+pub struct Achievement {
+    pub id: &'static str,
+}
+
+pub const ACHIEVEMENTS: &[Achievement] = &[
+    Achievement {
+        id: "survive_5_minutes",
+    },
+    Achievement {
+        id: "chain_10_wall_jumps",
+    },
+    Achievement {
+        id: "crushed_by_500px_rect",
+    },
+];
+
+impl Achievement {
+    pub fn find(id: &str) -> Option<&'static Achievement> {
+        ACHIEVEMENTS.iter().find(|achievement| achievement.id == id)
+    }
+
+    pub fn name_key(&self) -> String {
+        format!("achievement.{}.name", self.id)
+    }
+
+    pub fn description_key(&self) -> String {
+        format!("achievement.{}.description", self.id)
+    }
+}
+
+#[derive(Event)]
+pub struct AchievementUnlocked(pub &'static str);
+
+// only touches the save mutably on a new unlock so it isn't marked dirty every frame
+fn unlock(
+    id: &'static str,
+    save: &mut ResMut<SaveData>,
+    unlocked: &mut EventWriter<AchievementUnlocked>,
+) {
+    if save.achievements.iter().any(|existing| existing == id) {
+        return;
+    }
+    save.achievements.push(id.to_string());
+    unlocked.send(AchievementUnlocked(id));
+}
+
+fn track_survival(
+    stats: Res<RunStats>,
+    mut save: ResMut<SaveData>,
+    mut unlocked: EventWriter<AchievementUnlocked>,
+) {
+    if stats.time_survived >= SURVIVAL_SECS {
+        unlock("survive_5_minutes", &mut save, &mut unlocked);
+    }
+}
+
+fn track_wall_jumps(
+    mut wall_jumps: EventReader<PlayerWallJumped>,
+    mut save: ResMut<SaveData>,
+    mut unlocked: EventWriter<AchievementUnlocked>,
+) {
+    for wall_jump in wall_jumps.read() {
+        if wall_jump.chain >= WALL_JUMP_CHAIN {
+            unlock("chain_10_wall_jumps", &mut save, &mut unlocked);
+        }
+    }
+}
+
+fn track_crushes(
+    mut crushes: EventReader<PlayerCrushed>,
+    mut save: ResMut<SaveData>,
+    mut unlocked: EventWriter<AchievementUnlocked>,
+) {
+    for crush in crushes.read() {
+        if crush.rect_size.max_element() >= CRUSHING_RECT_SIZE {
+            unlock("crushed_by_500px_rect", &mut save, &mut unlocked);
+        }
+    }
+}
+
+fn announce_unlocks(
+    mut unlocked: EventReader<AchievementUnlocked>,
+    mut toasts: EventWriter<ShowToast>,
+    localization: Res<Localization>,
+) {
+    for AchievementUnlocked(id) in unlocked.read() {
+        if let Some(achievement) = Achievement::find(id) {
+            toasts.send(ShowToast(localization.format(
+                "achievement.unlocked",
+                &[("name", &localization.get(&achievement.name_key()))],
+            )));
+        }
+    }
+}
+
+fn achievement_menu_rows(
+    page: Res<MenuPage>,
+    save: Res<SaveData>,
+    localization: Res<Localization>,
+    mut rows: ResMut<MenuRows>,
+) {
+    if *page != MenuPage::Achievements {
+        return;
+    }
+    for achievement in ACHIEVEMENTS {
+        let done = save
+            .achievements
+            .iter()
+            .any(|existing| existing == achievement.id);
+        let mark = if done { "[x]" } else { "[ ]" };
+        rows.push(MenuRow::new(
+            format!(
+                "{mark} {} - {}",
+                localization.get(&achievement.name_key()),
+                localization.get(&achievement.description_key())
+            ),
+            MenuAction::None,
+        ));
+    }
+    rows.push(MenuRow::new(
+        localization.get("menu.back"),
+        MenuAction::Back,
+    ));
+}
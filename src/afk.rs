@@ -0,0 +1,142 @@
+use bevy::input::mouse::AccumulatedMouseMotion;
+use bevy::prelude::*;
+
+use crate::i18n::Localization;
+use crate::input::{Action, ActionState};
+use crate::menu::GameState;
+use crate::palette::Palette;
+use crate::score::RunStats;
+use crate::spawner::Spawner;
+use crate::ui_layout::UiRoot;
+use crate::GameSet;
+
+// no input for this long counts as away from the keyboard
+const AFK_SECS: f32 = 45.0;
+const DIM_ALPHA: f32 = 0.6;
+// the screen dims slowly, so it doesn't startle someone who's only reading
+const DIM_FADE_SECS: f32 = 2.0;
+const HINT_FONT_SIZE: f32 = 28.0;
+
+pub struct AfkPlugin;
+
+impl Plugin for AfkPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Afk>()
+            .add_systems(Startup, spawn_afk_overlay)
+            .add_systems(Update, detect_afk.in_set(GameSet::Input))
+            .add_systems(Update, fade_afk_overlay.in_set(GameSet::Vfx));
+    }
+}
+
+#[derive(Resource, Default)]
+struct Afk {
+    quiet_secs: f32,
+}
+
+impl Afk {
+    fn away(&self) -> bool {
+        self.quiet_secs >= AFK_SECS
+    }
+}
+
+#[derive(Component)]
+struct AfkOverlay;
+
+fn spawn_afk_overlay(
+    mut commands: Commands,
+    palette: Res<Palette>,
+    root: Single<Entity, With<UiRoot>>,
+) {
+    commands
+        .spawn((
+            Name::new("AfkOverlay"),
+            AfkOverlay,
+            Node {
+                position_type: PositionType::Absolute,
+                width: Val::Percent(100.0),
+                height: Val::Percent(100.0),
+                justify_content: JustifyContent::Center,
+                align_items: AlignItems::Center,
+                ..default()
+            },
+            BackgroundColor(Color::BLACK.with_alpha(0.0)),
+            GlobalZIndex(5),
+            Visibility::Hidden,
+        ))
+        .set_parent(*root)
+        .with_child((
+            Text::new(""),
+            TextFont {
+                font_size: HINT_FONT_SIZE,
+                ..default()
+            },
+            TextColor(palette.ui_text),
+        ));
+}
+
+// only runs that are already off the leaderboard get held, a competitive run keeps
+// going whether anyone is watching or not
+fn detect_afk(
+    actions: Res<ActionState>,
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    mouse_input: Res<ButtonInput<MouseButton>>,
+    mouse_motion: Res<AccumulatedMouseMotion>,
+    stats: Res<RunStats>,
+    time: Res<Time>,
+    mut afk: ResMut<Afk>,
+    mut spawner: ResMut<Spawner>,
+) {
+    let active = Action::ALL
+        .into_iter()
+        .any(|action| actions.pressed(action))
+        || keyboard_input.get_pressed().next().is_some()
+        || mouse_input.get_pressed().next().is_some()
+        || mouse_motion.delta != Vec2::ZERO;
+    if active || !stats.assisted {
+        afk.quiet_secs = 0.0;
+    } else {
+        afk.quiet_secs += time.delta_secs();
+    }
+    if spawner.held != afk.away() {
+        spawner.held = afk.away();
+        if spawner.held {
+            debug!(target: "spawner", "player is away, holding the drops");
+        } else {
+            debug!(target: "spawner", "player is back, letting the drops go");
+        }
+    }
+}
+
+fn fade_afk_overlay(
+    afk: Res<Afk>,
+    state: Res<State<GameState>>,
+    localization: Res<Localization>,
+    time: Res<Time<Real>>,
+    overlay: Single<(&mut BackgroundColor, &mut Visibility, &Children), With<AfkOverlay>>,
+    mut texts: Query<(&mut Text, &mut TextColor)>,
+) {
+    let (mut background, mut visibility, children) = overlay.into_inner();
+    let dimmed = afk.away() && *state.get() == GameState::Playing;
+    let step = DIM_ALPHA * time.delta_secs() / DIM_FADE_SECS;
+    // coming back is instant, there's no reason to make anyone wait for the fade
+    let alpha = if dimmed {
+        (background.0.alpha() + step).min(DIM_ALPHA)
+    } else {
+        0.0
+    };
+    if alpha == background.0.alpha() {
+        return;
+    }
+    background.0.set_alpha(alpha);
+    *visibility = if alpha > 0.0 {
+        Visibility::Inherited
+    } else {
+        Visibility::Hidden
+    };
+    for child in children.iter() {
+        if let Ok((mut text, mut color)) = texts.get_mut(*child) {
+            text.0 = localization.get("hud.afk").to_string();
+            color.0.set_alpha(alpha / DIM_ALPHA);
+        }
+    }
+}
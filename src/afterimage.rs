@@ -0,0 +1,146 @@
+use avian2d::prelude::*;
+use bevy::prelude::*;
+
+use crate::common_assets::CommonAssets;
+use crate::settings::Settings;
+use crate::z_layer::ZLayer;
+use crate::{GameSet, Player, PlayerWallJumped};
+
+// below this speed the player leaves no afterimages, at the full speed they're densest
+const MIN_SPEED: f32 = 450.0;
+const FULL_SPEED: f32 = 1200.0;
+const SLOW_INTERVAL_SECS: f32 = 0.1;
+const FAST_INTERVAL_SECS: f32 = 0.03;
+const LIFETIME_SECS: f32 = 0.3;
+const MAX_ALPHA: f32 = 0.5;
+// a wall jump is the closest thing to a dash, it gets the full trail for a moment
+const WALL_JUMP_BURST_SECS: f32 = 0.25;
+const MAX_AFTERIMAGES: usize = 12;
+
+pub struct AfterimagePlugin;
+
+impl Plugin for AfterimagePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<AfterimagePool>().add_systems(
+            Update,
+            (emit_afterimages, fade_afterimages)
+                .chain()
+                .in_set(GameSet::Vfx),
+        );
+    }
+}
+
+#[derive(Component)]
+struct Afterimage {
+    age: f32,
+    alpha: f32,
+}
+
+// each afterimage fades on its own, so every pooled entity keeps its own material
+#[derive(Resource, Default)]
+struct AfterimagePool {
+    free: Vec<Entity>,
+    // oldest first
+    active: Vec<Entity>,
+    since_last: f32,
+    burst: f32,
+}
+
+// 0 when the player moves normally, 1 during long falls and right after wall jumps
+fn intensity(speed: f32, burst: f32) -> f32 {
+    let from_speed = ((speed - MIN_SPEED) / (FULL_SPEED - MIN_SPEED)).clamp(0.0, 1.0);
+    let from_burst = (burst / WALL_JUMP_BURST_SECS).clamp(0.0, 1.0);
+    from_speed.max(from_burst)
+}
+
+fn emit_afterimages(
+    mut commands: Commands,
+    mut wall_jumps: EventReader<PlayerWallJumped>,
+    settings: Res<Settings>,
+    player: Single<(&Transform, &LinearVelocity, &MeshMaterial2d<ColorMaterial>), With<Player>>,
+    mut pool: ResMut<AfterimagePool>,
+    assets: Res<CommonAssets>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+    afterimages: Query<&MeshMaterial2d<ColorMaterial>, With<Afterimage>>,
+    time: Res<Time>,
+) {
+    if wall_jumps.read().count() > 0 {
+        pool.burst = WALL_JUMP_BURST_SECS;
+    }
+    pool.burst = (pool.burst - time.delta_secs()).max(0.0);
+    let (transform, linear, material) = *player;
+    let intensity = intensity(linear.length(), pool.burst);
+    if !settings.afterimages || intensity <= 0.0 {
+        pool.since_last = 0.0;
+        return;
+    }
+    pool.since_last += time.delta_secs();
+    let interval = SLOW_INTERVAL_SECS.lerp(FAST_INTERVAL_SECS, intensity);
+    if pool.since_last < interval {
+        return;
+    }
+    pool.since_last = 0.0;
+    // follows the skin and theme, whatever the player looks like right now
+    let color = materials
+        .get(material)
+        .map_or(Color::WHITE, |material| material.color);
+    let alpha = MAX_ALPHA * intensity;
+    let tint = color.with_alpha(alpha);
+    let entity = if let Some(entity) = pool.free.pop() {
+        entity
+    } else if pool.active.len() + pool.free.len() < MAX_AFTERIMAGES {
+        commands
+            .spawn((
+                Name::new("Afterimage"),
+                ZLayer::Trail,
+                Mesh2d(assets.rect.clone()),
+                MeshMaterial2d(materials.add(tint)),
+            ))
+            .id()
+    } else {
+        pool.active.remove(0)
+    };
+    pool.active.push(entity);
+    if let Some(material) = afterimages
+        .get(entity)
+        .ok()
+        .and_then(|material| materials.get_mut(material))
+    {
+        material.color = tint;
+    }
+    commands.entity(entity).insert((
+        Afterimage { age: 0.0, alpha },
+        Transform::from_translation(transform.translation.truncate().extend(0.0))
+            .with_scale(transform.scale),
+        Visibility::Inherited,
+    ));
+}
+
+fn fade_afterimages(
+    mut afterimages: Query<(
+        Entity,
+        &mut Afterimage,
+        &MeshMaterial2d<ColorMaterial>,
+        &mut Visibility,
+    )>,
+    mut pool: ResMut<AfterimagePool>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+    time: Res<Time>,
+) {
+    for (entity, mut afterimage, material, mut visibility) in &mut afterimages {
+        if *visibility == Visibility::Hidden {
+            continue;
+        }
+        afterimage.age += time.delta_secs();
+        let t = afterimage.age / LIFETIME_SECS;
+        if t >= 1.0 {
+            *visibility = Visibility::Hidden;
+            pool.active.retain(|active| *active != entity);
+            pool.free.push(entity);
+            continue;
+        }
+        if let Some(material) = materials.get_mut(material) {
+            material.color.set_alpha(afterimage.alpha * (1.0 - t));
+        }
+    }
+}
@@ -0,0 +1,387 @@
+use avian2d::prelude::*;
+use bevy::prelude::*;
+use rand::Rng;
+
+use crate::common_assets::CommonAssets;
+use crate::console::{Console, ConsoleAppExt, ConsoleCommand};
+use crate::palette::Palette;
+use crate::quality::Quality;
+use crate::settings::Settings;
+use crate::spawner::{Difficulty, FallingRect};
+use crate::z_layer::ZLayer;
+use crate::GameSet;
+
+// the difficulty level each weather starts at, the arena gets gloomier as a run goes on
+const WEATHER_BY_LEVEL: [(f32, Weather); 3] = [
+    (1.0, Weather::Dust),
+    (2.0, Weather::Snow),
+    (3.5, Weather::Rain),
+];
+const SPLASH_INTERVAL_SECS: f32 = 0.08;
+const SPLASH_LIFETIME_SECS: f32 = 0.35;
+const SPLASH_SIZE: Vec2 = Vec2::new(10.0, 2.0);
+const SPLASH_ALPHA: f32 = 0.35;
+const MAX_SPLASHES: usize = 16;
+
+pub struct AmbiencePlugin;
+
+impl Plugin for AmbiencePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Ambience>()
+            .init_resource::<AmbientPool>()
+            .add_console_command("weather", "weather [auto|none|dust|snow|rain]")
+            .add_systems(Update, run_weather_command)
+            .add_systems(
+                Update,
+                (
+                    pick_weather,
+                    move_ambient_particles,
+                    spawn_splashes,
+                    fade_splashes,
+                )
+                    .chain()
+                    .in_set(GameSet::Vfx),
+            );
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub enum Weather {
+    None,
+    #[default]
+    Dust,
+    Snow,
+    Rain,
+}
+
+struct WeatherStyle {
+    // at full particle density
+    count: usize,
+    velocity: Vec2,
+    // side to side drift, in pixels per second
+    sway: f32,
+    size: Vec2,
+    alpha: f32,
+    round: bool,
+}
+
+impl Weather {
+    fn parse(name: &str) -> Option<Weather> {
+        match name {
+            "none" => Some(Weather::None),
+            "dust" => Some(Weather::Dust),
+            "snow" => Some(Weather::Snow),
+            "rain" => Some(Weather::Rain),
+            _ => None,
+        }
+    }
+
+    fn style(self) -> Option<WeatherStyle> {
+        match self {
+            Weather::None => None,
+            Weather::Dust => Some(WeatherStyle {
+                count: 40,
+                velocity: Vec2::new(10.0, -15.0),
+                sway: 20.0,
+                size: Vec2::splat(3.0),
+                alpha: 0.25,
+                round: true,
+            }),
+            Weather::Snow => Some(WeatherStyle {
+                count: 80,
+                velocity: Vec2::new(0.0, -60.0),
+                sway: 30.0,
+                size: Vec2::splat(5.0),
+                alpha: 0.6,
+                round: true,
+            }),
+            Weather::Rain => Some(WeatherStyle {
+                count: 120,
+                velocity: Vec2::new(-80.0, -900.0),
+                sway: 0.0,
+                size: Vec2::new(2.0, 18.0),
+                alpha: 0.35,
+                round: false,
+            }),
+        }
+    }
+}
+
+// follows the difficulty level unless the `weather` console command pinned one
+#[derive(Resource, Default)]
+pub struct Ambience {
+    pub forced: Option<Weather>,
+    current: Weather,
+}
+
+impl Ambience {
+    pub fn weather(&self) -> Weather {
+        self.current
+    }
+}
+
+#[derive(Component)]
+struct AmbientParticle {
+    // offsets the sway so the particles don't all move in step
+    phase: f32,
+}
+
+#[derive(Component)]
+struct Splash {
+    age: f32,
+}
+
+// particles are hidden rather than despawned when the weather or density drops.
+// they all share one material, which follows the weather and the palette
+#[derive(Resource, Default)]
+struct AmbientPool {
+    particles: Vec<Entity>,
+    particle_material: Option<Handle<ColorMaterial>>,
+    splashes: Vec<Entity>,
+    splash_material: Option<Handle<ColorMaterial>>,
+    next_splash: usize,
+    since_last_splash: f32,
+}
+
+fn weather_for_level(level: f32) -> Weather {
+    WEATHER_BY_LEVEL
+        .iter()
+        .rev()
+        .find(|(from, _)| level >= *from)
+        .map_or(Weather::None, |(_, weather)| *weather)
+}
+
+fn run_weather_command(
+    mut commands: EventReader<ConsoleCommand>,
+    mut console: ResMut<Console>,
+    mut ambience: ResMut<Ambience>,
+) {
+    for command in commands.read().filter(|command| command.name == "weather") {
+        match command.args.first().map(String::as_str) {
+            None => console.print(format!("weather: {:?}", ambience.weather())),
+            Some("auto") => {
+                ambience.forced = None;
+                console.print("weather follows the difficulty again");
+            }
+            Some(name) => match Weather::parse(name) {
+                Some(weather) => {
+                    ambience.forced = Some(weather);
+                    console.print(format!("weather: {weather:?}"));
+                }
+                None => console.print("weather: auto, none, dust, snow or rain"),
+            },
+        }
+    }
+}
+
+fn pick_weather(mut ambience: ResMut<Ambience>, difficulty: Res<Difficulty>) {
+    let weather = ambience
+        .forced
+        .unwrap_or_else(|| weather_for_level(difficulty.level));
+    if ambience.current != weather {
+        debug!(target: "spawner", "weather changed to {weather:?}");
+        ambience.current = weather;
+    }
+}
+
+fn visible_area(camera: &Transform, projection: &OrthographicProjection) -> Rect {
+    Rect::from_center_size(camera.translation.truncate(), projection.area.size())
+}
+
+fn random_point(area: Rect) -> Vec2 {
+    let mut rng = rand::thread_rng();
+    Vec2::new(
+        rng.gen_range(area.min.x..=area.max.x),
+        rng.gen_range(area.min.y..=area.max.y),
+    )
+}
+
+// particles live in world space and wrap around the edges of the view, so one that
+// falls out of the bottom comes back in at the top
+fn move_ambient_particles(
+    mut commands: Commands,
+    ambience: Res<Ambience>,
+    settings: Res<Settings>,
+    quality: Res<Quality>,
+    palette: Res<Palette>,
+    assets: Res<CommonAssets>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+    mut pool: ResMut<AmbientPool>,
+    camera: Single<(&Transform, &OrthographicProjection), With<Camera>>,
+    mut particles: Query<
+        (
+            &AmbientParticle,
+            &mut Transform,
+            &mut Visibility,
+            &mut Mesh2d,
+        ),
+        Without<Camera>,
+    >,
+    time: Res<Time>,
+) {
+    let area = visible_area(camera.0, camera.1);
+    let style = ambience.weather().style();
+    let wanted = style.as_ref().map_or(0, |style| {
+        (style.count as f32 * quality.particles(settings.particles).ambient_density()).round()
+            as usize
+    });
+    let Some(style) = style else {
+        for entity in &pool.particles {
+            if let Ok((_, _, mut visibility, _)) = particles.get_mut(*entity) {
+                *visibility = Visibility::Hidden;
+            }
+        }
+        return;
+    };
+    let color = palette.ui_text.with_alpha(style.alpha);
+    let material = pool
+        .particle_material
+        .get_or_insert_with(|| materials.add(color))
+        .clone();
+    // get_mut alone would re-upload the material every frame
+    if materials
+        .get(&material)
+        .is_some_and(|material| material.color != color)
+    {
+        if let Some(material) = materials.get_mut(&material) {
+            material.color = color;
+        }
+    }
+    let mesh = if style.round {
+        assets.circle.clone()
+    } else {
+        assets.rect.clone()
+    };
+    while pool.particles.len() < wanted {
+        let entity = commands
+            .spawn((
+                Name::new("AmbientParticle"),
+                ZLayer::Ambience,
+                AmbientParticle {
+                    phase: rand::thread_rng().gen_range(0.0..std::f32::consts::TAU),
+                },
+                Mesh2d(mesh.clone()),
+                MeshMaterial2d(material.clone()),
+                Transform::from_translation(random_point(area).extend(0.0))
+                    .with_scale(style.size.extend(1.0)),
+            ))
+            .id();
+        pool.particles.push(entity);
+    }
+    let elapsed = time.elapsed_secs();
+    for (index, entity) in pool.particles.iter().enumerate() {
+        let Ok((particle, mut transform, mut visibility, mut particle_mesh)) =
+            particles.get_mut(*entity)
+        else {
+            continue;
+        };
+        if ambience.is_changed() {
+            particle_mesh.0 = mesh.clone();
+            transform.scale = style.size.extend(1.0);
+        }
+        if index >= wanted {
+            *visibility = Visibility::Hidden;
+            continue;
+        }
+        if *visibility == Visibility::Hidden {
+            *visibility = Visibility::Inherited;
+            transform.translation = random_point(area).extend(transform.translation.z);
+        }
+        let sway = Vec2::X * style.sway * (elapsed + particle.phase).sin();
+        let position =
+            transform.translation.truncate() + (style.velocity + sway) * time.delta_secs();
+        let position = area.min + (position - area.min).rem_euclid(area.size());
+        transform.translation = position.extend(transform.translation.z);
+    }
+}
+
+// rain leaves faint flat splashes on top of the rects it lands on
+fn spawn_splashes(
+    mut commands: Commands,
+    ambience: Res<Ambience>,
+    settings: Res<Settings>,
+    quality: Res<Quality>,
+    palette: Res<Palette>,
+    assets: Res<CommonAssets>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+    mut pool: ResMut<AmbientPool>,
+    camera: Single<(&Transform, &OrthographicProjection), With<Camera>>,
+    rects: Query<&ColliderAabb, With<FallingRect>>,
+    time: Res<Time>,
+) {
+    let density = quality.particles(settings.particles).ambient_density();
+    if ambience.weather() != Weather::Rain || density <= 0.0 {
+        pool.since_last_splash = 0.0;
+        return;
+    }
+    pool.since_last_splash += time.delta_secs();
+    if pool.since_last_splash < SPLASH_INTERVAL_SECS / density {
+        return;
+    }
+    pool.since_last_splash = 0.0;
+    let area = visible_area(camera.0, camera.1);
+    let tops: Vec<(f32, f32, f32)> = rects
+        .iter()
+        .filter(|aabb| area.contains(Vec2::new(aabb.center().x, aabb.max.y)))
+        .map(|aabb| (aabb.min.x, aabb.max.x, aabb.max.y))
+        .collect();
+    if tops.is_empty() {
+        return;
+    }
+    let mut rng = rand::thread_rng();
+    let (min_x, max_x, top) = tops[rng.gen_range(0..tops.len())];
+    let position = Vec2::new(rng.gen_range(min_x..=max_x), top);
+    let color = palette.ui_text.with_alpha(SPLASH_ALPHA);
+    let material = pool
+        .splash_material
+        .get_or_insert_with(|| materials.add(color))
+        .clone();
+    if materials
+        .get(&material)
+        .is_some_and(|material| material.color != color)
+    {
+        if let Some(material) = materials.get_mut(&material) {
+            material.color = color;
+        }
+    }
+    let entity = if pool.splashes.len() < MAX_SPLASHES {
+        let entity = commands
+            .spawn((
+                Name::new("Splash"),
+                ZLayer::Decals,
+                Mesh2d(assets.rect.clone()),
+                MeshMaterial2d(material),
+            ))
+            .id();
+        pool.splashes.push(entity);
+        entity
+    } else {
+        let entity = pool.splashes[pool.next_splash];
+        pool.next_splash = (pool.next_splash + 1) % MAX_SPLASHES;
+        entity
+    };
+    commands.entity(entity).insert((
+        Splash { age: 0.0 },
+        Transform::from_translation(position.extend(0.0)).with_scale(SPLASH_SIZE.extend(1.0)),
+        Visibility::Inherited,
+    ));
+}
+
+// splashes spread out and flatten until they're gone
+fn fade_splashes(
+    mut splashes: Query<(&mut Splash, &mut Transform, &mut Visibility)>,
+    time: Res<Time>,
+) {
+    for (mut splash, mut transform, mut visibility) in &mut splashes {
+        if *visibility == Visibility::Hidden {
+            continue;
+        }
+        splash.age += time.delta_secs();
+        let t = splash.age / SPLASH_LIFETIME_SECS;
+        if t >= 1.0 {
+            *visibility = Visibility::Hidden;
+            continue;
+        }
+        transform.scale = Vec3::new(SPLASH_SIZE.x * (1.0 + t), SPLASH_SIZE.y * (1.0 - t), 1.0);
+    }
+}
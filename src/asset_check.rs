@@ -0,0 +1,255 @@
+use std::fmt;
+use std::time::Duration;
+
+use bevy::asset::io::AssetReaderError;
+use bevy::asset::{AssetLoadError, LoadState, UntypedAssetId};
+use bevy::prelude::*;
+
+use crate::i18n::Localization;
+use crate::palette::Palette;
+use crate::ui_layout::UiRoot;
+use crate::GameSet;
+
+const MAX_RETRIES: u32 = 3;
+// doubled after every retry
+const RETRY_BACKOFF_SECS: f32 = 0.5;
+// a reload only clears the old failure once its task starts, until then it still shows
+const RELOAD_GRACE_SECS: f32 = 0.25;
+const ERROR_FONT_SIZE: f32 = 18.0;
+const ERROR_DURATION_SECS: f32 = 10.0;
+
+pub struct AssetCheckPlugin;
+
+impl Plugin for AssetCheckPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<TrackedAssets>()
+            .add_event::<AssetFailed>()
+            .add_systems(Startup, spawn_error_banner)
+            .add_systems(Update, validate_assets)
+            .add_systems(
+                Update,
+                (
+                    show_failed_assets.after(validate_assets),
+                    hide_failed_assets,
+                )
+                    .chain()
+                    .in_set(GameSet::Vfx),
+            );
+    }
+}
+
+#[derive(Debug, Clone)]
+pub enum AssetError {
+    Missing { path: String },
+    Failed { path: String, reason: String },
+}
+
+impl AssetError {
+    pub fn path(&self) -> &str {
+        match self {
+            AssetError::Missing { path } | AssetError::Failed { path, .. } => path,
+        }
+    }
+}
+
+impl fmt::Display for AssetError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            AssetError::Missing { path } => write!(f, "missing asset '{path}'"),
+            AssetError::Failed { path, reason } => write!(f, "failed to load '{path}': {reason}"),
+        }
+    }
+}
+
+// sent once an asset has failed for good, after any retries
+#[derive(Event)]
+pub struct AssetFailed {
+    pub id: UntypedAssetId,
+    pub error: AssetError,
+}
+
+struct PendingAsset {
+    handle: UntypedHandle,
+    retries: u32,
+    // when the next reload is due, and when the last one was sent
+    retry_at: Option<Duration>,
+    reloaded_at: Option<Duration>,
+}
+
+// handles the game can't do without are tracked here until they load or fail
+#[derive(Resource, Default)]
+pub struct TrackedAssets {
+    pending: Vec<PendingAsset>,
+    failed: Vec<(UntypedAssetId, AssetError)>,
+}
+
+impl TrackedAssets {
+    pub fn track(&mut self, handle: impl Into<UntypedHandle>) {
+        self.pending.push(PendingAsset {
+            handle: handle.into(),
+            retries: 0,
+            retry_at: None,
+            reloaded_at: None,
+        });
+    }
+
+    pub fn failed(&self, id: impl Into<UntypedAssetId>) -> bool {
+        let id = id.into();
+        self.failed.iter().any(|(failed, _)| *failed == id)
+    }
+}
+
+fn asset_error(handle: &UntypedHandle, error: &AssetLoadError) -> AssetError {
+    let path = handle
+        .path()
+        .map_or_else(|| format!("{:?}", handle.id()), ToString::to_string);
+    match error {
+        AssetLoadError::AssetReaderError(AssetReaderError::NotFound(_)) => {
+            AssetError::Missing { path }
+        }
+        other => AssetError::Failed {
+            path,
+            reason: other.to_string(),
+        },
+    }
+}
+
+// only a failed fetch is worth another try, a file that's there but won't parse
+// won't parse the second time either
+// the itch cdn drops the odd request, so the web build asks again before giving up.
+// natively a read that failed once fails every time
+fn retryable(error: &AssetLoadError, retries: u32) -> bool {
+    cfg!(target_arch = "wasm32")
+        && retries < MAX_RETRIES
+        && matches!(error, AssetLoadError::AssetReaderError(_))
+}
+
+fn validate_assets(
+    asset_server: Res<AssetServer>,
+    mut tracked: ResMut<TrackedAssets>,
+    mut failed: EventWriter<AssetFailed>,
+    time: Res<Time<Real>>,
+) {
+    if tracked.pending.is_empty() {
+        return;
+    }
+    let now = time.elapsed();
+    let TrackedAssets {
+        pending,
+        failed: failures,
+    } = &mut *tracked;
+    pending.retain_mut(|asset| match asset_server.load_state(asset.handle.id()) {
+        LoadState::Loaded => false,
+        LoadState::Failed(error) => {
+            let reloading = asset.reloaded_at.is_some_and(|reloaded_at| {
+                now < reloaded_at + Duration::from_secs_f32(RELOAD_GRACE_SECS)
+            });
+            if reloading {
+                return true;
+            }
+            let Some(path) = asset
+                .handle
+                .path()
+                .filter(|_| retryable(&error, asset.retries))
+            else {
+                let error = asset_error(&asset.handle, &error);
+                error!("{error}");
+                failures.push((asset.handle.id(), error.clone()));
+                failed.send(AssetFailed {
+                    id: asset.handle.id(),
+                    error,
+                });
+                return false;
+            };
+            match asset.retry_at {
+                None => {
+                    let backoff = RETRY_BACKOFF_SECS * 2f32.powi(asset.retries as i32);
+                    warn!("couldn't fetch '{path}', retrying in {backoff}s: {error}");
+                    asset.retry_at = Some(now + Duration::from_secs_f32(backoff));
+                }
+                Some(retry_at) if now >= retry_at => {
+                    asset_server.reload(path.clone());
+                    asset.retries += 1;
+                    asset.retry_at = None;
+                    asset.reloaded_at = Some(now);
+                }
+                Some(_) => {}
+            }
+            true
+        }
+        LoadState::NotLoaded | LoadState::Loading => true,
+    });
+}
+
+// lists everything that failed for good until it's been quiet for a while. shown in
+// release builds too, a silent run with no music is worse than a line of text
+#[derive(Component)]
+struct FailedAssetsText {
+    age: f32,
+}
+
+fn spawn_error_banner(
+    mut commands: Commands,
+    palette: Res<Palette>,
+    root: Single<Entity, With<UiRoot>>,
+) {
+    commands
+        .spawn((
+            Name::new("FailedAssets"),
+            Node {
+                position_type: PositionType::Absolute,
+                width: Val::Percent(100.0),
+                bottom: Val::Px(12.0),
+                justify_content: JustifyContent::Center,
+                ..default()
+            },
+        ))
+        .set_parent(*root)
+        .with_child((
+            FailedAssetsText {
+                age: ERROR_DURATION_SECS,
+            },
+            Text::new(""),
+            TextFont {
+                font_size: ERROR_FONT_SIZE,
+                ..default()
+            },
+            TextColor(palette.hazard),
+            Visibility::Hidden,
+        ));
+}
+
+fn show_failed_assets(
+    mut failed: EventReader<AssetFailed>,
+    tracked: Res<TrackedAssets>,
+    localization: Res<Localization>,
+    text: Single<(&mut Text, &mut FailedAssetsText, &mut Visibility)>,
+) {
+    if failed.read().count() == 0 {
+        return;
+    }
+    let (mut text, mut banner, mut visibility) = text.into_inner();
+    let paths = tracked
+        .failed
+        .iter()
+        .map(|(_, error)| error.path())
+        .collect::<Vec<_>>()
+        .join(", ");
+    text.0 = localization.format("hud.assets_failed", &[("paths", &paths)]);
+    banner.age = 0.0;
+    *visibility = Visibility::Inherited;
+}
+
+fn hide_failed_assets(
+    text: Single<(&mut FailedAssetsText, &mut Visibility)>,
+    time: Res<Time<Real>>,
+) {
+    let (mut banner, mut visibility) = text.into_inner();
+    if banner.age >= ERROR_DURATION_SECS {
+        return;
+    }
+    banner.age += time.delta_secs();
+    if banner.age >= ERROR_DURATION_SECS {
+        *visibility = Visibility::Hidden;
+    }
+}
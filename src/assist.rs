@@ -0,0 +1,217 @@
+use bevy::prelude::*;
+
+use crate::i18n::Localization;
+use crate::input::{Action, ActionSet, ActionState};
+use crate::menu::{MenuAction, MenuActivated, MenuPage, MenuRow, MenuRows, MenuSet};
+use crate::settings::{on_off, Settings};
+use crate::{GameSet, PlayerDied, PlayerWallJumped};
+
+const COYOTE_SECS: f32 = 0.1;
+const ASSISTED_COYOTE_SECS: f32 = 0.3;
+const JUMP_BUFFER_SECS: f32 = 0.1;
+const ASSISTED_JUMP_BUFFER_SECS: f32 = 0.3;
+const MIN_GAME_SPEED_PERCENT: u32 = 50;
+const GAME_SPEED_STEP_PERCENT: u32 = 10;
+const MAX_EXTRA_HEALTH: u32 = 3;
+
+pub struct AssistPlugin;
+
+impl Plugin for AssistPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<AssistState>()
+            .add_systems(PreUpdate, apply_assists.in_set(ActionSet::Assist))
+            .add_systems(Update, flip_auto_run.in_set(GameSet::Reactions))
+            .add_systems(Update, apply_game_speed)
+            .add_systems(
+                Update,
+                (
+                    assist_menu_rows.in_set(MenuSet::Rows),
+                    apply_assist_actions.in_set(MenuSet::Actions),
+                ),
+            );
+    }
+}
+
+// anything that makes a run easier than the default, shown next to the score
+pub fn assists_active(settings: &Settings) -> bool {
+    settings.game_speed_percent < 100
+        || settings.extra_health > 0
+        || settings.crush_invulnerable
+        || settings.practice_mode
+}
+
+pub fn coyote_secs(settings: &Settings) -> f32 {
+    if settings.generous_timing {
+        ASSISTED_COYOTE_SECS
+    } else {
+        COYOTE_SECS
+    }
+}
+
+fn jump_buffer_secs(settings: &Settings) -> f32 {
+    if settings.generous_timing {
+        ASSISTED_JUMP_BUFFER_SECS
+    } else {
+        JUMP_BUFFER_SECS
+    }
+}
+
+#[derive(Resource)]
+struct AssistState {
+    run_direction: Action,
+    jump_held: bool,
+    jump_toggled: bool,
+    jump_buffer: f32,
+}
+
+impl Default for AssistState {
+    fn default() -> AssistState {
+        AssistState {
+            run_direction: Action::Right,
+            jump_held: false,
+            jump_toggled: false,
+            jump_buffer: 0.0,
+        }
+    }
+}
+
+// rewrites the collected actions, so it has to run after every input source
+fn apply_assists(
+    settings: Res<Settings>,
+    mut actions: ResMut<ActionState>,
+    mut assist: ResMut<AssistState>,
+    time: Res<Time<Real>>,
+) {
+    // the previous frame's actions already include the assists, so track the raw button here
+    let jump_held = actions.pressed(Action::Jump);
+    let jump_pressed = jump_held && !assist.jump_held;
+    assist.jump_held = jump_held;
+    if settings.toggle_jump {
+        if jump_pressed {
+            assist.jump_toggled = !assist.jump_toggled;
+        }
+        actions.release(Action::Jump);
+        if assist.jump_toggled {
+            actions.press(Action::Jump);
+        }
+    } else if assist.jump_toggled {
+        assist.jump_toggled = false;
+    }
+    // a short tap keeps jump held for a moment so it still counts right before landing
+    if jump_pressed {
+        assist.jump_buffer = jump_buffer_secs(&settings);
+    } else {
+        assist.jump_buffer = (assist.jump_buffer - time.delta_secs()).max(0.0);
+    }
+    if assist.jump_buffer > 0.0 && !settings.toggle_jump {
+        actions.press(Action::Jump);
+    }
+    if settings.one_switch {
+        actions.release(Action::Left);
+        actions.release(Action::Right);
+        actions.release(Action::Down);
+        actions.press(assist.run_direction);
+    }
+}
+
+// wall jumps push the player away from the wall, keep running that way
+fn flip_auto_run(
+    mut wall_jumped: EventReader<PlayerWallJumped>,
+    mut died: EventReader<PlayerDied>,
+    mut assist: ResMut<AssistState>,
+) {
+    for _ in wall_jumped.read() {
+        assist.run_direction = match assist.run_direction {
+            Action::Right => Action::Left,
+            _ => Action::Right,
+        };
+    }
+    if died.read().count() > 0 {
+        assist.run_direction = Action::Right;
+    }
+}
+
+// the settings file can be edited by hand, anything outside the menu's range is clamped
+fn game_speed_percent(settings: &Settings) -> u32 {
+    settings
+        .game_speed_percent
+        .clamp(MIN_GAME_SPEED_PERCENT, 100)
+}
+
+// virtual time drives the physics clock too, so this slows the whole game and not only the rects
+fn apply_game_speed(settings: Res<Settings>, mut time: ResMut<Time<Virtual>>) {
+    let speed = game_speed_percent(&settings) as f32 / 100.0;
+    if time.relative_speed() != speed {
+        time.set_relative_speed(speed);
+    }
+}
+
+fn assist_menu_rows(
+    page: Res<MenuPage>,
+    settings: Res<Settings>,
+    localization: Res<Localization>,
+    mut rows: ResMut<MenuRows>,
+) {
+    if *page != MenuPage::Assist {
+        return;
+    }
+    rows.push(MenuRow::new(
+        localization.format(
+            "assist.game_speed",
+            &[("value", &settings.game_speed_percent)],
+        ),
+        MenuAction::CycleGameSpeed,
+    ));
+    rows.push(MenuRow::new(
+        localization.format("assist.extra_health", &[("value", &settings.extra_health)]),
+        MenuAction::CycleExtraHealth,
+    ));
+    rows.push(MenuRow::new(
+        localization.format(
+            "assist.crush_invulnerable",
+            &[("value", &on_off(settings.crush_invulnerable, &localization))],
+        ),
+        MenuAction::ToggleCrushInvulnerable,
+    ));
+    rows.push(MenuRow::new(
+        localization.format(
+            "assist.practice_mode",
+            &[("value", &on_off(settings.practice_mode, &localization))],
+        ),
+        MenuAction::TogglePracticeMode,
+    ));
+    rows.push(MenuRow::new(
+        localization.get("menu.back"),
+        MenuAction::Back,
+    ));
+}
+
+fn apply_assist_actions(mut activated: EventReader<MenuActivated>, mut settings: ResMut<Settings>) {
+    for event in activated.read() {
+        match event.action {
+            MenuAction::CycleGameSpeed => {
+                // confirming steps down, like pressing left
+                let delta = if event.delta == 0 { -1 } else { event.delta };
+                let steps = (100 - MIN_GAME_SPEED_PERCENT) / GAME_SPEED_STEP_PERCENT + 1;
+                let step = (game_speed_percent(&settings) - MIN_GAME_SPEED_PERCENT)
+                    / GAME_SPEED_STEP_PERCENT;
+                let step = (step as i32 + delta).rem_euclid(steps as i32) as u32;
+                settings.game_speed_percent =
+                    MIN_GAME_SPEED_PERCENT + step * GAME_SPEED_STEP_PERCENT;
+            }
+            MenuAction::CycleExtraHealth => {
+                let delta = if event.delta == 0 { 1 } else { event.delta };
+                settings.extra_health = (settings.extra_health as i32 + delta)
+                    .rem_euclid(MAX_EXTRA_HEALTH as i32 + 1)
+                    as u32;
+            }
+            MenuAction::ToggleCrushInvulnerable => {
+                settings.crush_invulnerable = !settings.crush_invulnerable;
+            }
+            MenuAction::TogglePracticeMode => {
+                settings.practice_mode = !settings.practice_mode;
+            }
+            _ => {}
+        }
+    }
+}
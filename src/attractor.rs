@@ -0,0 +1,121 @@
+use avian2d::prelude::*;
+use bevy::prelude::*;
+
+use crate::common_assets::CommonAssets;
+use crate::palette::PaletteRole;
+use crate::prefab::{spawn_piece, PieceShape, PrefabAppExt, PrefabParams};
+use crate::z_layer::ZLayer;
+use crate::{GameSet, Player};
+
+const ATTRACTOR_SIZE: f32 = 60.0;
+const ATTRACTOR_RADIUS: f32 = 400.0;
+// px^3/s^2, pulls at 2000 px/s^2 from 100px away
+const ATTRACTOR_STRENGTH: f32 = 20_000_000.0;
+// keeps the pull finite for bodies sitting right on top of it
+const MIN_PULL_DISTANCE: f32 = 40.0;
+
+pub struct AttractorPlugin;
+
+impl Plugin for AttractorPlugin {
+    fn build(&self, app: &mut App) {
+        app.register_prefab("attractor", spawn_attractor)
+            .register_prefab("magnet", spawn_magnet)
+            .add_systems(FixedPreUpdate, pull_bodies.in_set(GameSet::Movement));
+    }
+}
+
+// pulls dynamic bodies in range towards it with an inverse-square force. it has no
+// collider of its own, whatever it pulls in ends up orbiting or clumping around it
+#[derive(Component)]
+pub struct Attractor {
+    pub strength: f32,
+    pub radius: f32,
+    pub pulls_player: bool,
+}
+
+impl Default for Attractor {
+    fn default() -> Attractor {
+        Attractor {
+            strength: ATTRACTOR_STRENGTH,
+            radius: ATTRACTOR_RADIUS,
+            pulls_player: false,
+        }
+    }
+}
+
+fn spawn_attractor(commands: &mut Commands, params: &PrefabParams) -> Entity {
+    spawn_with(commands, params, Attractor::default())
+}
+
+// an attractor strong enough to drag the player in too
+fn spawn_magnet(commands: &mut Commands, params: &PrefabParams) -> Entity {
+    let attractor = Attractor {
+        pulls_player: true,
+        ..default()
+    };
+    spawn_with(commands, params, attractor)
+}
+
+fn spawn_with(commands: &mut Commands, params: &PrefabParams, attractor: Attractor) -> Entity {
+    let size = params.size.unwrap_or(Vec2::splat(ATTRACTOR_SIZE));
+    let entity = spawn_piece(
+        commands,
+        "Attractor",
+        PaletteRole::Hazard,
+        PieceShape::Circle,
+        size,
+        params.position,
+        |assets| &assets.hazard,
+    );
+    // a faint disc showing the reach, in the attractor's unit space
+    let reach = attractor.radius * 2.0 / size.x;
+    commands.entity(entity).insert((attractor, ZLayer::Pile));
+    commands.queue(move |world: &mut World| {
+        let assets = world.resource::<CommonAssets>();
+        let disc = (
+            Mesh2d(assets.circle.clone()),
+            MeshMaterial2d(assets.shadow.clone()),
+            Transform::from_xyz(0.0, 0.0, -0.1).with_scale(Vec3::new(reach, reach, 1.0)),
+        );
+        if let Ok(mut entity) = world.get_entity_mut(entity) {
+            entity.with_child(disc);
+        }
+    });
+    entity
+}
+
+fn pull_bodies(
+    mut commands: Commands,
+    attractors: Query<(&Attractor, &Transform)>,
+    mut bodies: Query<
+        (
+            Entity,
+            &RigidBody,
+            &Transform,
+            &mut LinearVelocity,
+            Has<Player>,
+            Has<Sleeping>,
+        ),
+        Without<Attractor>,
+    >,
+    time: Res<Time>,
+) {
+    for (attractor, attractor_transform) in &attractors {
+        let center = attractor_transform.translation.truncate();
+        for (entity, body, transform, mut linear, is_player, sleeping) in &mut bodies {
+            if !body.is_dynamic() || (is_player && !attractor.pulls_player) {
+                continue;
+            }
+            let offset = center - transform.translation.truncate();
+            let distance = offset.length();
+            if distance > attractor.radius || distance == 0.0 {
+                continue;
+            }
+            if sleeping {
+                commands.entity(entity).remove::<Sleeping>();
+            }
+            let acceleration = attractor.strength / distance.max(MIN_PULL_DISTANCE).powi(2);
+            linear.0 += offset / distance * acceleration * time.delta_secs();
+        }
+    }
+}
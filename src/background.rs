@@ -0,0 +1,146 @@
+use bevy::prelude::*;
+use bevy::render::render_resource::{AsBindGroup, ShaderRef};
+use bevy::sprite::{Material2d, Material2dPlugin};
+
+use crate::asset_check::TrackedAssets;
+use crate::common_assets::CommonAssets;
+use crate::quality::Quality;
+use crate::settings::Settings;
+use crate::spawner::Difficulty;
+use crate::z_layer::ZLayer;
+use crate::GameSet;
+use uniform::BackgroundUniform;
+
+const MAX_INTENSITY_LEVEL: f32 = 5.0;
+const PARALLAX: f32 = 0.2;
+const SHADER_PATH: &str = "shaders/background.wgsl";
+
+pub struct BackgroundPlugin;
+
+impl Plugin for BackgroundPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_plugins(Material2dPlugin::<BackgroundMaterial>::default())
+            .add_systems(PostStartup, spawn_background)
+            .add_systems(
+                Update,
+                (update_background, toggle_background).in_set(GameSet::Vfx),
+            );
+    }
+}
+
+#[derive(Asset, TypePath, AsBindGroup, Debug, Clone)]
+pub struct BackgroundMaterial {
+    #[uniform(0)]
+    uniform: BackgroundUniform,
+}
+
+// the ShaderType derive leaves an uncalled `check` fn behind for every field, which the
+// compiler reports as dead code
+#[allow(dead_code)]
+mod uniform {
+    use bevy::prelude::*;
+    use bevy::render::render_resource::ShaderType;
+
+    #[derive(ShaderType, Debug, Clone, Copy, Default)]
+    pub struct BackgroundUniform {
+        pub base_color: Vec4,
+        pub camera_position: Vec2,
+        pub time: f32,
+        pub intensity: f32,
+        pub parallax: f32,
+    }
+}
+
+impl Material2d for BackgroundMaterial {
+    fn fragment_shader() -> ShaderRef {
+        SHADER_PATH.into()
+    }
+}
+
+#[derive(Component)]
+struct Background;
+
+#[derive(Resource)]
+struct BackgroundShader(Handle<Shader>);
+
+// a unit quad parented to the camera, stretched to cover the view every frame
+fn spawn_background(
+    mut commands: Commands,
+    assets: Res<CommonAssets>,
+    mut materials: ResMut<Assets<BackgroundMaterial>>,
+    camera: Single<Entity, With<Camera>>,
+    asset_server: Res<AssetServer>,
+    mut tracked: ResMut<TrackedAssets>,
+) {
+    let shader = asset_server.load(SHADER_PATH);
+    tracked.track(shader.clone());
+    commands.insert_resource(BackgroundShader(shader));
+    commands.entity(*camera).with_child((
+        Name::new("Background"),
+        Background,
+        ZLayer::Background,
+        Mesh2d(assets.rect.clone()),
+        MeshMaterial2d(materials.add(BackgroundMaterial {
+            uniform: BackgroundUniform::default(),
+        })),
+        Transform::default(),
+    ));
+}
+
+fn update_background(
+    mut materials: ResMut<Assets<BackgroundMaterial>>,
+    background: Single<
+        (&MeshMaterial2d<BackgroundMaterial>, &mut Transform),
+        (With<Background>, Without<Camera>),
+    >,
+    camera: Single<(&Transform, &OrthographicProjection), With<Camera>>,
+    clear_color: Res<ClearColor>,
+    difficulty: Res<Difficulty>,
+    settings: Res<Settings>,
+    time: Res<Time>,
+) {
+    let (camera_transform, projection) = camera.into_inner();
+    let (material, mut transform) = background.into_inner();
+    transform.scale = projection.area.size().extend(1.0);
+    let Some(material) = materials.get_mut(material) else {
+        return;
+    };
+    material.uniform = BackgroundUniform {
+        base_color: clear_color.0.to_linear().to_vec4(),
+        camera_position: camera_transform.translation.truncate(),
+        // reduced motion pins the pattern to the screen and stops the drift
+        time: if settings.reduce_motion {
+            0.0
+        } else {
+            time.elapsed_secs()
+        },
+        intensity: ((difficulty.level - 1.0) / (MAX_INTENSITY_LEVEL - 1.0)).clamp(0.0, 1.0),
+        parallax: if settings.reduce_motion {
+            0.0
+        } else {
+            PARALLAX
+        },
+    };
+}
+
+// with the animated background disabled, its shader missing, or the quality too low for
+// it, the plain ClearColor shows through
+fn toggle_background(
+    settings: Res<Settings>,
+    quality: Res<Quality>,
+    shader: Res<BackgroundShader>,
+    tracked: Res<TrackedAssets>,
+    mut background: Single<&mut Visibility, With<Background>>,
+) {
+    if !settings.is_changed() && !quality.is_changed() && !tracked.is_changed() {
+        return;
+    }
+    **background = if settings.animated_background
+        && quality.animated_background()
+        && !tracked.failed(&shader.0)
+    {
+        Visibility::Inherited
+    } else {
+        Visibility::Hidden
+    };
+}
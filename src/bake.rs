@@ -0,0 +1,94 @@
+use bevy::prelude::*;
+
+use crate::menu::GameState;
+use crate::prefab::TerrainBlock;
+
+// edges closer than this count as touching, authored sizes are whole pixels anyway
+const TOUCH_EPSILON: f32 = 0.5;
+
+pub struct BakePlugin;
+
+impl Plugin for BakePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(OnExit(GameState::Loading), bake_terrain);
+    }
+}
+
+// a block that owns its rect after the bake, and the ones folded into it
+struct BakedBlock {
+    entity: Entity,
+    rect: Rect,
+    merged: Vec<Entity>,
+}
+
+fn close(a: f32, b: f32) -> bool {
+    (a - b).abs() < TOUCH_EPSILON
+}
+
+// folds each block into the one before it when they share a whole edge along the axis.
+// blocks have to be sorted so neighbours along the axis come one after the other
+fn merge_along(blocks: Vec<BakedBlock>, horizontal: bool) -> Vec<BakedBlock> {
+    let mut baked: Vec<BakedBlock> = Vec::with_capacity(blocks.len());
+    for block in blocks {
+        if let Some(last) = baked.last_mut() {
+            let (a, b) = (last.rect, block.rect);
+            let touching = if horizontal {
+                close(a.min.y, b.min.y) && close(a.max.y, b.max.y) && close(a.max.x, b.min.x)
+            } else {
+                close(a.min.x, b.min.x) && close(a.max.x, b.max.x) && close(a.max.y, b.min.y)
+            };
+            if touching {
+                last.rect = a.union(b);
+                last.merged.push(block.entity);
+                last.merged.extend(block.merged);
+                continue;
+            }
+        }
+        baked.push(block);
+    }
+    baked
+}
+
+// tile heavy terrain turns into a few long strips, so the physics has fewer contacts to
+// track and the renderer fewer meshes to draw. it also takes away the seams between
+// tiles that the player could catch on. rotated blocks are left as they are
+fn bake_terrain(
+    mut commands: Commands,
+    mut blocks: Query<(Entity, &mut Transform), With<TerrainBlock>>,
+) {
+    let mut rows: Vec<BakedBlock> = blocks
+        .iter()
+        .filter(|(_, transform)| transform.rotation == Quat::IDENTITY)
+        .map(|(entity, transform)| BakedBlock {
+            entity,
+            rect: Rect::from_center_size(
+                transform.translation.truncate(),
+                transform.scale.truncate(),
+            ),
+            merged: Vec::new(),
+        })
+        .collect();
+    let before = rows.len();
+    rows.sort_by(|a, b| {
+        (a.rect.min.y, a.rect.max.y, a.rect.min.x)
+            .partial_cmp(&(b.rect.min.y, b.rect.max.y, b.rect.min.x))
+            .unwrap_or(std::cmp::Ordering::Equal)
+    });
+    let mut columns = merge_along(rows, true);
+    columns.sort_by(|a, b| {
+        (a.rect.min.x, a.rect.max.x, a.rect.min.y)
+            .partial_cmp(&(b.rect.min.x, b.rect.max.x, b.rect.min.y))
+            .unwrap_or(std::cmp::Ordering::Equal)
+    });
+    let baked = merge_along(columns, false);
+    for block in baked.iter().filter(|block| !block.merged.is_empty()) {
+        if let Ok((_, mut transform)) = blocks.get_mut(block.entity) {
+            transform.translation = block.rect.center().extend(transform.translation.z);
+            transform.scale = block.rect.size().extend(1.0);
+        }
+        for entity in &block.merged {
+            commands.entity(*entity).despawn_recursive();
+        }
+    }
+    debug!("baked {before} terrain blocks into {}", baked.len());
+}
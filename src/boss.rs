@@ -0,0 +1,190 @@
+use avian2d::prelude::*;
+use bevy::prelude::*;
+use rand::Rng;
+
+use crate::console::{ConsoleAppExt, ConsoleCommand};
+use crate::highlight::Interactable;
+use crate::hud::ShowBanner;
+use crate::i18n::Localization;
+use crate::popups::{PopupStyle, ShowPopup};
+use crate::prefab::{add_inset, spawn_falling_rect, PrefabAppExt, PrefabParams};
+use crate::score::RunStats;
+use crate::shake::ScreenShake;
+use crate::shockwave::EmitShockwave;
+use crate::spawner::{Difficulty, PlannedDrop, Spawner, WeightClass};
+use crate::{GameSet, Player, PlayerDied};
+
+const BOSS_SIZE: Vec2 = Vec2::new(520.0, 140.0);
+// the first one waits for the run to get going, the rest come every few minutes
+const FIRST_BOSS_SECS: f32 = 90.0;
+const MIN_BOSS_GAP_SECS: f32 = 120.0;
+const MAX_BOSS_GAP_SECS: f32 = 180.0;
+// far more warning than a regular drop gets
+const BOSS_LEAD_SECS: f32 = 5.0;
+const IMPACT_TRAUMA: f32 = 1.0;
+// bigger than any regular impact can make
+const SHOCKWAVE_STRENGTH: f32 = 4.0;
+// from the edge of the boss, staying alive this close to the impact pays out
+const NEAR_RADIUS: f32 = 300.0;
+const SURVIVE_SECS: f32 = 1.5;
+const SURVIVAL_BONUS: u32 = 500;
+
+pub struct BossPlugin;
+
+impl Plugin for BossPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<BossScheduler>()
+            .register_prefab("boss", spawn_boss)
+            .add_console_command("boss", "boss")
+            .add_systems(
+                Update,
+                (schedule_bosses, land_bosses, reward_survivors)
+                    .chain()
+                    .in_set(GameSet::Reactions),
+            );
+    }
+}
+
+// a rare scripted mega drop, it only does its big impact once
+#[derive(Component)]
+pub struct BossRect {
+    landed: bool,
+}
+
+#[derive(Resource)]
+struct BossScheduler {
+    until_next: f32,
+    // seconds left before a near survivor gets the bonus
+    survive: Option<f32>,
+}
+
+impl Default for BossScheduler {
+    fn default() -> BossScheduler {
+        BossScheduler {
+            until_next: FIRST_BOSS_SECS,
+            survive: None,
+        }
+    }
+}
+
+fn spawn_boss(commands: &mut Commands, params: &PrefabParams) -> Entity {
+    let params = PrefabParams {
+        size: params.size.or(Some(BOSS_SIZE)),
+        ..*params
+    };
+    let entity = spawn_falling_rect(commands, &params, WeightClass::Steel);
+    // too heavy to be worth highlighting for the mouse
+    commands
+        .entity(entity)
+        .insert(Name::new("BossRect"))
+        .insert(BossRect { landed: false })
+        .remove::<Interactable>();
+    add_inset(commands, entity, 0.35, |assets| &assets.hazard);
+    entity
+}
+
+// the warning goes up straight away, and the random drops wait for the boss to land
+fn schedule_bosses(
+    mut scheduler: ResMut<BossScheduler>,
+    mut spawner: ResMut<Spawner>,
+    mut died: EventReader<PlayerDied>,
+    mut commands: EventReader<ConsoleCommand>,
+    mut banners: EventWriter<ShowBanner>,
+    localization: Res<Localization>,
+    difficulty: Res<Difficulty>,
+    time: Res<Time>,
+    mut stats: ResMut<RunStats>,
+) {
+    if died.read().count() > 0 {
+        *scheduler = BossScheduler::default();
+    }
+    let forced = commands.read().any(|command| command.name == "boss");
+    // a boss on demand is practice, the run stays off the leaderboard
+    stats.assisted |= forced;
+    scheduler.until_next -= time.delta_secs();
+    if scheduler.until_next > 0.0 && !forced {
+        return;
+    }
+    let mut rng = rand::thread_rng();
+    scheduler.until_next = rng.gen_range(MIN_BOSS_GAP_SECS..MAX_BOSS_GAP_SECS);
+    let now = time.elapsed_secs();
+    let spawn_at = spawner
+        .last_spawn_at()
+        .map_or(now, |last| last + difficulty.spawn_interval())
+        .max(now + BOSS_LEAD_SECS);
+    let id = spawner.push(PlannedDrop {
+        id: 0,
+        x: 0.0,
+        size: BOSS_SIZE,
+        class: WeightClass::Steel,
+        shape: default(),
+        spin: 0.0,
+        bomb: false,
+        boss: true,
+        warn_at: now,
+        spawn_at,
+    });
+    debug!(target: "spawner", "boss drop {id} at {spawn_at:.2}s");
+    banners.send(ShowBanner(localization.get("boss.incoming").to_string()));
+}
+
+// the first thing a boss hits shakes the whole screen and sends a wave along the ground
+fn land_bosses(
+    mut collisions: EventReader<CollisionStarted>,
+    mut bosses: Query<(&mut BossRect, &ColliderAabb)>,
+    mut scheduler: ResMut<BossScheduler>,
+    mut shake: ResMut<ScreenShake>,
+    mut shockwaves: EventWriter<EmitShockwave>,
+    player: Single<&Transform, With<Player>>,
+) {
+    let player = player.translation.truncate();
+    for CollisionStarted(a, b) in collisions.read() {
+        for entity in [*a, *b] {
+            let Ok((mut boss, aabb)) = bosses.get_mut(entity) else {
+                continue;
+            };
+            if boss.landed {
+                continue;
+            }
+            boss.landed = true;
+            shake.add(IMPACT_TRAUMA);
+            let origin = Vec2::new(aabb.center().x, aabb.min.y);
+            shockwaves.send(EmitShockwave {
+                origin,
+                strength: SHOCKWAVE_STRENGTH,
+            });
+            let distance = (player - player.clamp(aabb.min, aabb.max)).length();
+            debug!(target: "physics", "boss landed at {origin}, player {distance:.0}px away");
+            if distance <= NEAR_RADIUS {
+                scheduler.survive = Some(SURVIVE_SECS);
+            }
+        }
+    }
+}
+
+fn reward_survivors(
+    mut scheduler: ResMut<BossScheduler>,
+    mut died: EventReader<PlayerDied>,
+    mut stats: ResMut<RunStats>,
+    mut popups: EventWriter<ShowPopup>,
+    player: Single<&Transform, With<Player>>,
+    time: Res<Time>,
+) {
+    if died.read().count() > 0 {
+        scheduler.survive = None;
+    }
+    let Some(remaining) = &mut scheduler.survive else {
+        return;
+    };
+    *remaining -= time.delta_secs();
+    if *remaining > 0.0 {
+        return;
+    }
+    scheduler.survive = None;
+    stats.bonus += SURVIVAL_BONUS;
+    popups.send(ShowPopup {
+        text: format!("+{SURVIVAL_BONUS}"),
+        position: player.translation.truncate(),
+        style: PopupStyle::Combo,
+    });
+}
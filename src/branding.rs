@@ -0,0 +1,98 @@
+use bevy::prelude::*;
+
+#[cfg(not(target_arch = "wasm32"))]
+use crate::palette::{Palette, Theme};
+
+const GAME_NAME: &str = "RectFall";
+// the app id on wayland and the window class on x11, what the taskbar groups by
+pub const APP_ID: &str = "rectfall";
+#[cfg(not(target_arch = "wasm32"))]
+const ICON_SIZE: u32 = 32;
+
+pub struct BrandingPlugin;
+
+impl Plugin for BrandingPlugin {
+    fn build(&self, app: &mut App) {
+        #[cfg(not(target_arch = "wasm32"))]
+        app.add_systems(Update, set_window_icon);
+        #[cfg(target_arch = "wasm32")]
+        app.add_systems(Update, describe_page);
+    }
+}
+
+pub fn window_title() -> String {
+    format!("{GAME_NAME} {}", env!("CARGO_PKG_VERSION"))
+}
+
+// drawn instead of shipped as a file: a player standing on a pile with a hazard rect
+// coming down, in the classic palette so it doesn't change with the theme setting
+#[cfg(not(target_arch = "wasm32"))]
+fn icon_rgba() -> Vec<u8> {
+    let palette = Palette::from_theme(Theme::Classic);
+    // (min x, min y, max x, max y) in icon pixels from the top left, later ones on top
+    let shapes = [
+        ((0, 0, 32, 32), palette.background),
+        ((0, 26, 32, 32), palette.terrain),
+        ((3, 14, 15, 26), palette.debris),
+        ((17, 18, 29, 26), palette.debris),
+        ((19, 10, 27, 18), palette.player),
+        ((7, 2, 15, 8), palette.hazard),
+    ];
+    let mut pixels = Vec::with_capacity((ICON_SIZE * ICON_SIZE * 4) as usize);
+    for y in 0..ICON_SIZE {
+        for x in 0..ICON_SIZE {
+            let color = shapes
+                .iter()
+                .rev()
+                .find(|((min_x, min_y, max_x, max_y), _)| {
+                    (*min_x..*max_x).contains(&x) && (*min_y..*max_y).contains(&y)
+                })
+                .map_or(Color::NONE, |(_, color)| *color);
+            pixels.extend_from_slice(&color.to_srgba().to_u8_array());
+        }
+    }
+    pixels
+}
+
+// winit only has the window after the first few frames, so this keeps trying until it does
+#[cfg(not(target_arch = "wasm32"))]
+fn set_window_icon(
+    windows: Option<NonSend<bevy::winit::WinitWindows>>,
+    primary: Option<Single<Entity, With<bevy::window::PrimaryWindow>>>,
+    mut done: Local<bool>,
+) {
+    if *done {
+        return;
+    }
+    let (Some(windows), Some(primary)) = (windows, primary) else {
+        return;
+    };
+    let Some(window) = windows.get_window(*primary) else {
+        return;
+    };
+    *done = true;
+    match winit::window::Icon::from_rgba(icon_rgba(), ICON_SIZE, ICON_SIZE) {
+        Ok(icon) => window.set_window_icon(Some(icon)),
+        Err(err) => warn!("couldn't set the window icon: {err}"),
+    }
+}
+
+// the tab title, and a label on the canvas for screen readers and link previews. the
+// canvas shows up with the window, so this also waits for it
+#[cfg(target_arch = "wasm32")]
+fn describe_page(mut done: Local<bool>) {
+    if *done {
+        return;
+    }
+    let Some(document) = web_sys::window().and_then(|window| window.document()) else {
+        return;
+    };
+    let Ok(Some(canvas)) = document.query_selector("canvas") else {
+        return;
+    };
+    *done = true;
+    let title = window_title();
+    document.set_title(&title);
+    let _ = canvas.set_attribute("title", &title);
+    let _ = canvas.set_attribute("aria-label", GAME_NAME);
+}
@@ -0,0 +1,116 @@
+use bevy::prelude::*;
+
+use crate::config::GameConfig;
+use crate::input::{Action, ActionState};
+use crate::menu::GameState;
+use crate::settings::Settings;
+use crate::z_layer::ZLayer;
+use crate::{GameSet, Player};
+
+// the whole level stays up for this long before zooming in
+const HOLD_SECS: f32 = 1.0;
+const ZOOM_SECS: f32 = 1.5;
+// world pixels kept around the level
+const FRAME_MARGIN: f32 = 100.0;
+const MAX_ZOOM: f32 = 4.0;
+
+pub struct CameraIntroPlugin;
+
+impl Plugin for CameraIntroPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<CameraIntro>()
+            .add_systems(OnExit(GameState::Loading), start_camera_intro)
+            .add_systems(Update, run_camera_intro.in_set(GameSet::Movement));
+    }
+}
+
+// where the camera frames the level from, and the gameplay view it zooms back to
+#[derive(Resource, Default)]
+struct CameraIntro {
+    playing: bool,
+    elapsed: f32,
+    from: Vec3,
+    from_scale: f32,
+    home: Vec3,
+    home_scale: f32,
+}
+
+// the level is whatever sits on the terrain layer, plus the player. boxes are framed by
+// their corners, so tilted ramps fit too
+fn level_bounds<'a>(boxes: impl Iterator<Item = &'a Transform>) -> Option<Rect> {
+    boxes
+        .flat_map(|transform| {
+            [(-0.5, -0.5), (0.5, -0.5), (-0.5, 0.5), (0.5, 0.5)]
+                .map(|(x, y)| transform.transform_point(Vec3::new(x, y, 0.0)).truncate())
+        })
+        .map(|corner| Rect::from_center_size(corner, Vec2::ZERO))
+        .reduce(|bounds, corner| bounds.union(corner))
+}
+
+fn start_camera_intro(
+    config: Res<GameConfig>,
+    mut intro: ResMut<CameraIntro>,
+    terrain: Query<(&Transform, &ZLayer)>,
+    player: Query<&Transform, (With<Player>, Without<Camera>)>,
+    camera: Single<(&mut Transform, &mut OrthographicProjection), (With<Camera>, Without<ZLayer>)>,
+) {
+    let boxes = terrain
+        .iter()
+        .filter(|(_, layer)| **layer == ZLayer::Terrain)
+        .map(|(transform, _)| transform)
+        .chain(&player);
+    let Some(bounds) = config.start_view.or_else(|| level_bounds(boxes)) else {
+        return;
+    };
+    let bounds = bounds.inflate(FRAME_MARGIN);
+    let (mut transform, mut projection) = camera.into_inner();
+    let view = projection.area.size() / projection.scale;
+    let fit = (bounds.size() / view).max_element();
+    *intro = CameraIntro {
+        playing: true,
+        elapsed: 0.0,
+        from: bounds.center().extend(transform.translation.z),
+        from_scale: fit.clamp(projection.scale, MAX_ZOOM),
+        home: transform.translation,
+        home_scale: projection.scale,
+    };
+    transform.translation = intro.from;
+    projection.scale = intro.from_scale;
+    debug!("camera intro framing {bounds:?}");
+}
+
+// any action skips straight to the gameplay view, the player is already in control
+fn run_camera_intro(
+    mut intro: ResMut<CameraIntro>,
+    actions: Res<ActionState>,
+    settings: Res<Settings>,
+    time: Res<Time>,
+    camera: Single<(&mut Transform, &mut OrthographicProjection), With<Camera>>,
+) {
+    if !intro.playing {
+        return;
+    }
+    intro.elapsed += time.delta_secs();
+    let skipped = Action::ALL
+        .into_iter()
+        .any(|action| actions.just_pressed(action));
+    let t = if skipped {
+        1.0
+    } else if settings.reduce_motion {
+        // cut instead of zooming once the beat is over
+        if intro.elapsed >= HOLD_SECS {
+            1.0
+        } else {
+            0.0
+        }
+    } else {
+        ((intro.elapsed - HOLD_SECS) / ZOOM_SECS).clamp(0.0, 1.0)
+    };
+    let eased = t * t * (3.0 - 2.0 * t);
+    let (mut transform, mut projection) = camera.into_inner();
+    transform.translation = intro.from.lerp(intro.home, eased);
+    projection.scale = intro.from_scale.lerp(intro.home_scale, eased);
+    if t >= 1.0 {
+        intro.playing = false;
+    }
+}
@@ -0,0 +1,191 @@
+use bevy::prelude::*;
+
+pub struct CheatsPlugin;
+
+// does nothing unless the game is built with `--features cheats`, which `make run-dev`
+// turns on and no release target does
+impl Plugin for CheatsPlugin {
+    fn build(&self, _app: &mut App) {
+        #[cfg(feature = "cheats")]
+        backend::build(_app);
+    }
+}
+
+#[cfg(all(feature = "cheats", feature = "steam"))]
+compile_error!("the cheats feature must never ship in a steam build");
+
+#[cfg(feature = "cheats")]
+mod backend {
+    use avian2d::prelude::*;
+    use bevy::prelude::*;
+
+    use crate::climb::Stamina;
+    use crate::console::{Console, ConsoleAppExt, ConsoleCommand};
+    use crate::health::{Health, Invulnerable};
+    use crate::input::{Action, ActionState};
+    use crate::prefab::{PrefabParams, Prefabs};
+    use crate::ui_layout::UiRoot;
+    use crate::{step_up_ledges, GameSet, Player};
+
+    const NOCLIP_SPEED: f32 = 600.0;
+    const SPAWN_HEIGHT_ABOVE_PLAYER: f32 = 400.0;
+    const SPAWN_SIZE: Vec2 = Vec2::new(120.0, 80.0);
+    const SPAWN_KEYS: [(KeyCode, &str); 4] = [
+        (KeyCode::F5, "foam"),
+        (KeyCode::F6, "crate"),
+        (KeyCode::F7, "heavy"),
+        (KeyCode::F8, "bomb"),
+    ];
+
+    #[derive(Resource, Default)]
+    struct Cheats {
+        noclip: bool,
+        god: bool,
+    }
+
+    pub fn build(app: &mut App) {
+        warn!("cheats are enabled, this build is for testing only");
+        app.init_resource::<Cheats>()
+            .add_console_command("noclip", "noclip")
+            .add_console_command("god", "god")
+            .add_console_command("give", "give <health|stamina>")
+            .add_systems(Startup, spawn_cheats_label)
+            .add_systems(
+                Update,
+                (
+                    (run_cheat_commands, cheat_hotkeys).in_set(GameSet::Input),
+                    apply_cheats.in_set(GameSet::Reactions),
+                ),
+            )
+            .add_systems(
+                FixedPreUpdate,
+                fly.in_set(GameSet::Movement).after(step_up_ledges),
+            );
+    }
+
+    // a permanent reminder in the corner, so a cheat build never passes for a real one
+    fn spawn_cheats_label(mut commands: Commands, root: Single<Entity, With<UiRoot>>) {
+        commands
+            .spawn((
+                Name::new("CheatsLabel"),
+                Text::new("CHEATS"),
+                TextFont {
+                    font_size: 14.0,
+                    ..default()
+                },
+                TextColor(Color::srgb(1.0, 0.3, 0.3)),
+                Node {
+                    position_type: PositionType::Absolute,
+                    right: Val::Px(8.0),
+                    bottom: Val::Px(8.0),
+                    ..default()
+                },
+                GlobalZIndex(20),
+            ))
+            .set_parent(*root);
+    }
+
+    fn run_cheat_commands(
+        mut commands: EventReader<ConsoleCommand>,
+        mut console: ResMut<Console>,
+        mut cheats: ResMut<Cheats>,
+        player: Single<(&mut Health, &mut Stamina), With<Player>>,
+    ) {
+        let (mut health, mut stamina) = player.into_inner();
+        for command in commands.read() {
+            match command.name.as_str() {
+                "noclip" => {
+                    cheats.noclip = !cheats.noclip;
+                    console.print(format!("noclip: {}", cheats.noclip));
+                }
+                "god" => {
+                    cheats.god = !cheats.god;
+                    console.print(format!("god mode: {}", cheats.god));
+                }
+                "give" => match command.args.first().map(String::as_str) {
+                    Some("health") => {
+                        health.current = health.max;
+                        console.print("health refilled");
+                    }
+                    Some("stamina") => {
+                        stamina.current = stamina.max;
+                        console.print("stamina refilled");
+                    }
+                    _ => console.print("give: health or stamina"),
+                },
+                _ => {}
+            }
+        }
+    }
+
+    // F5 to F8 drop a foam, normal, steel rect or a bomb right above the player
+    fn cheat_hotkeys(
+        mut commands: Commands,
+        keyboard_input: Res<ButtonInput<KeyCode>>,
+        prefabs: Res<Prefabs>,
+        player: Single<&Transform, With<Player>>,
+    ) {
+        for (key, prefab) in SPAWN_KEYS {
+            if !keyboard_input.just_pressed(key) {
+                continue;
+            }
+            let params = PrefabParams {
+                position: player.translation.truncate() + Vec2::Y * SPAWN_HEIGHT_ABOVE_PLAYER,
+                size: (prefab != "bomb").then_some(SPAWN_SIZE),
+                ..default()
+            };
+            prefabs.spawn(prefab, &mut commands, &params);
+            debug!(target: "spawner", "cheat spawned {prefab}");
+        }
+    }
+
+    // noclip turns the player into a kinematic sensor, so nothing pushes or blocks it
+    fn apply_cheats(
+        mut commands: Commands,
+        cheats: Res<Cheats>,
+        player: Single<(Entity, Has<Invulnerable>, &RigidBody), With<Player>>,
+    ) {
+        let (entity, invulnerable, body) = *player;
+        let mut player = commands.entity(entity);
+        if cheats.god != invulnerable {
+            if cheats.god {
+                player.insert(Invulnerable);
+            } else {
+                player.remove::<Invulnerable>();
+            }
+        }
+        let flying = *body == RigidBody::Kinematic;
+        if cheats.noclip != flying {
+            if cheats.noclip {
+                player.insert((RigidBody::Kinematic, Sensor));
+            } else {
+                player.insert(RigidBody::Dynamic).remove::<Sensor>();
+            }
+        }
+    }
+
+    // runs after regular movement and overrides it with free flight
+    fn fly(
+        cheats: Res<Cheats>,
+        actions: Res<ActionState>,
+        player: Single<(&mut Player, &mut LinearVelocity)>,
+    ) {
+        if !cheats.noclip {
+            return;
+        }
+        let (mut player, mut linear) = player.into_inner();
+        player.reset_jump();
+        let mut direction = Vec2::ZERO;
+        for (action, step) in [
+            (Action::Left, Vec2::NEG_X),
+            (Action::Right, Vec2::X),
+            (Action::Jump, Vec2::Y),
+            (Action::Down, Vec2::NEG_Y),
+        ] {
+            if actions.pressed(action) {
+                direction += step;
+            }
+        }
+        linear.0 = direction.normalize_or_zero() * NOCLIP_SPEED;
+    }
+}
@@ -0,0 +1,147 @@
+use avian2d::prelude::*;
+use bevy::prelude::*;
+
+use crate::input::{Action, ActionState};
+use crate::palette::Palette;
+use crate::ui_layout::UiRoot;
+use crate::{move_player, GameSet, Player};
+
+const MAX_STAMINA: f32 = 100.0;
+const CLIMB_SPEED: f32 = 200.0;
+// hanging still is cheaper than moving
+const HANG_DRAIN_PER_SEC: f32 = 15.0;
+const CLIMB_DRAIN_PER_SEC: f32 = 40.0;
+const REFILL_PER_SEC: f32 = 200.0;
+const STAMINA_BAR_WIDTH: f32 = 120.0;
+const STAMINA_BAR_HEIGHT: f32 = 8.0;
+
+pub struct ClimbPlugin;
+
+impl Plugin for ClimbPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Startup, spawn_stamina_bar)
+            .add_systems(
+                FixedPreUpdate,
+                climb_walls.in_set(GameSet::Movement).before(move_player),
+            )
+            .add_systems(Update, update_stamina_bar.in_set(GameSet::Vfx));
+    }
+}
+
+#[derive(Component)]
+pub struct Stamina {
+    pub current: f32,
+    pub max: f32,
+}
+
+impl Default for Stamina {
+    fn default() -> Stamina {
+        Stamina {
+            current: MAX_STAMINA,
+            max: MAX_STAMINA,
+        }
+    }
+}
+
+#[derive(Component)]
+struct StaminaBar;
+
+#[derive(Component)]
+struct StaminaFill;
+
+// holding grab against a wall hangs on it, jump and down climb up and down. while
+// climbing, jump doesn't wall jump; let go of grab first. once stamina runs out the
+// player slides off and the regular wall jump still works.
+pub fn climb_walls(
+    actions: Res<ActionState>,
+    player: Single<(
+        &mut Player,
+        &mut LinearVelocity,
+        &mut GravityScale,
+        &mut Stamina,
+    )>,
+    time: Res<Time>,
+) {
+    let (mut player, mut linear, mut gravity, mut stamina) = player.into_inner();
+    let delta_secs = time.delta_secs();
+    if player.ground_normal.is_some() {
+        stamina.current = (stamina.current + REFILL_PER_SEC * delta_secs).min(stamina.max);
+    }
+    let climbing = player.wall_normal.is_some()
+        && !player.pulling
+        && actions.pressed(Action::Grab)
+        && stamina.current > 0.0;
+    if climbing != player.climbing {
+        debug!(target: "player", "climbing: {climbing}");
+    }
+    player.climbing = climbing;
+    if !climbing {
+        gravity.0 = 1.0;
+        return;
+    }
+    gravity.0 = 0.0;
+    let vertical = match (actions.pressed(Action::Jump), actions.pressed(Action::Down)) {
+        (true, false) => 1.0,
+        (false, true) => -1.0,
+        _ => 0.0,
+    };
+    linear.y = vertical * CLIMB_SPEED;
+    let drain = if vertical == 0.0 {
+        HANG_DRAIN_PER_SEC
+    } else {
+        CLIMB_DRAIN_PER_SEC
+    };
+    stamina.current = (stamina.current - drain * delta_secs).max(0.0);
+}
+
+fn spawn_stamina_bar(
+    mut commands: Commands,
+    palette: Res<Palette>,
+    root: Single<Entity, With<UiRoot>>,
+) {
+    commands
+        .spawn((
+            Name::new("StaminaBar"),
+            StaminaBar,
+            Node {
+                position_type: PositionType::Absolute,
+                left: Val::Px(12.0),
+                top: Val::Px(44.0),
+                width: Val::Px(STAMINA_BAR_WIDTH),
+                height: Val::Px(STAMINA_BAR_HEIGHT),
+                ..default()
+            },
+            BackgroundColor(Color::srgba(0.0, 0.0, 0.0, 0.5)),
+            Visibility::Hidden,
+        ))
+        .set_parent(*root)
+        .with_child((
+            StaminaFill,
+            Node {
+                width: Val::Percent(100.0),
+                height: Val::Percent(100.0),
+                ..default()
+            },
+            BackgroundColor(palette.ui_accent),
+        ));
+}
+
+// only shown while stamina is being used or refilled
+fn update_stamina_bar(
+    stamina: Single<Ref<Stamina>, With<Player>>,
+    palette: Res<Palette>,
+    mut bar: Single<&mut Visibility, With<StaminaBar>>,
+    fill: Single<(&mut Node, &mut BackgroundColor), With<StaminaFill>>,
+) {
+    if !stamina.is_changed() && !palette.is_changed() {
+        return;
+    }
+    let (mut node, mut color) = fill.into_inner();
+    node.width = Val::Percent(stamina.current / stamina.max * 100.0);
+    color.0 = palette.ui_accent;
+    **bar = if stamina.current < stamina.max {
+        Visibility::Inherited
+    } else {
+        Visibility::Hidden
+    };
+}
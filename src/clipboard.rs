@@ -0,0 +1,41 @@
+// copying text out of the game, for crash reports and exported runs
+
+#[cfg(not(target_arch = "wasm32"))]
+use bevy::prelude::*;
+
+// on linux the copied text only stays available while the clipboard is alive, so it's
+// kept around instead of dropped after copying
+#[cfg(not(target_arch = "wasm32"))]
+#[derive(Default)]
+pub struct Clipboard(Option<arboard::Clipboard>);
+
+#[cfg(not(target_arch = "wasm32"))]
+impl Clipboard {
+    pub fn copy(&mut self, text: String) {
+        if self.0.is_none() {
+            self.0 = arboard::Clipboard::new()
+                .map_err(|err| warn!("no clipboard available: {err}"))
+                .ok();
+        }
+        if let Some(clipboard) = &mut self.0 {
+            if let Err(err) = clipboard.set_text(text) {
+                warn!("failed to copy to the clipboard: {err}");
+            }
+        }
+    }
+}
+
+#[cfg(target_arch = "wasm32")]
+#[derive(Default)]
+pub struct Clipboard;
+
+#[cfg(target_arch = "wasm32")]
+impl Clipboard {
+    pub fn copy(&mut self, text: String) {
+        let Some(window) = web_sys::window() else {
+            return;
+        };
+        // the browser finishes the copy on its own, nothing to wait for
+        let _ = window.navigator().clipboard().write_text(&text);
+    }
+}
@@ -0,0 +1,79 @@
+use avian2d::prelude::*;
+
+// which physics layer everything in the world is on, and what each one collides with.
+// every collider is spawned with the layers from here, so the rules live in one place
+#[derive(PhysicsLayer, Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub enum GameLayer {
+    #[default]
+    Terrain,
+    Player,
+    FallingRect,
+    // spikes and anything else that hurts on touch
+    Hazard,
+    // triggers like checkpoints and the player's ground sensor, never pushed around
+    Sensor,
+    Pickup,
+    Projectile,
+}
+
+impl GameLayer {
+    // what a layer collides with. a pair only collides when both list each other, so
+    // pickups only ever see the player and projectiles never see other projectiles
+    fn filters(self) -> &'static [GameLayer] {
+        match self {
+            GameLayer::Terrain => &[
+                GameLayer::Player,
+                GameLayer::FallingRect,
+                GameLayer::Sensor,
+                GameLayer::Projectile,
+            ],
+            GameLayer::Player => &[
+                GameLayer::Terrain,
+                GameLayer::FallingRect,
+                GameLayer::Hazard,
+                GameLayer::Sensor,
+                GameLayer::Pickup,
+                GameLayer::Projectile,
+            ],
+            GameLayer::FallingRect => &[
+                GameLayer::Terrain,
+                GameLayer::Player,
+                GameLayer::FallingRect,
+                GameLayer::Sensor,
+                GameLayer::Projectile,
+            ],
+            GameLayer::Hazard | GameLayer::Pickup => &[GameLayer::Player],
+            GameLayer::Sensor => &[
+                GameLayer::Terrain,
+                GameLayer::Player,
+                GameLayer::FallingRect,
+            ],
+            GameLayer::Projectile => &[
+                GameLayer::Terrain,
+                GameLayer::Player,
+                GameLayer::FallingRect,
+            ],
+        }
+    }
+
+    pub fn layers(self) -> CollisionLayers {
+        CollisionLayers::new(self, mask(self.filters()))
+    }
+}
+
+pub fn mask(layers: &[GameLayer]) -> LayerMask {
+    layers.iter().fold(LayerMask::NONE, |mask, layer| {
+        mask | LayerMask::from(*layer)
+    })
+}
+
+// what can be stood on, for the spatial queries that look for ground and walls
+pub fn solid_mask() -> LayerMask {
+    mask(&[GameLayer::Terrain, GameLayer::FallingRect])
+}
+
+// the ground sensor only looks for something to stand on, so checkpoints and hazards
+// don't count as ground
+pub fn ground_sensor_layers() -> CollisionLayers {
+    CollisionLayers::new(GameLayer::Sensor, solid_mask())
+}
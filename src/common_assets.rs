@@ -0,0 +1,125 @@
+use bevy::prelude::*;
+use bevy::render::mesh::{Indices, PrimitiveTopology};
+use bevy::render::render_asset::RenderAssetUsages;
+
+use crate::palette::Palette;
+use crate::prefab::PieceShape;
+
+pub struct CommonAssetsPlugin;
+
+impl Plugin for CommonAssetsPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<CommonAssets>();
+    }
+}
+
+// every rectangle in the game is this unit mesh stretched through its transform,
+// and most of them share one of these materials
+#[derive(Resource)]
+pub struct CommonAssets {
+    pub rect: Handle<Mesh>,
+    pub circle: Handle<Mesh>,
+    pub triangle: Handle<Mesh>,
+    pub capsule: Handle<Mesh>,
+    pub l_piece: Handle<Mesh>,
+    pub t_piece: Handle<Mesh>,
+    pub terrain: Handle<ColorMaterial>,
+    pub debris: Handle<ColorMaterial>,
+    pub foam: Handle<ColorMaterial>,
+    // only one player is alive at a time, so skins can recolor this one in place
+    pub player: Handle<ColorMaterial>,
+    pub hazard: Handle<ColorMaterial>,
+    pub checkpoint: Handle<ColorMaterial>,
+    pub marker: Handle<ColorMaterial>,
+    pub shadow: Handle<ColorMaterial>,
+    pub accent: Handle<ColorMaterial>,
+}
+
+impl FromWorld for CommonAssets {
+    fn from_world(world: &mut World) -> CommonAssets {
+        let palette = *world.resource::<Palette>();
+        let mut meshes = world.resource_mut::<Assets<Mesh>>();
+        let rect = meshes.add(Rectangle::new(1.0, 1.0));
+        let circle = meshes.add(Circle::new(0.5));
+        let triangle = meshes.add(Triangle2d::new(
+            Vec2::new(-0.5, -0.5),
+            Vec2::new(0.5, -0.5),
+            Vec2::new(0.0, 0.5),
+        ));
+        let capsule = meshes.add(Capsule2d::new(0.25, 0.5));
+        let l_piece = meshes.add(bars_mesh(PieceShape::LPiece.bars()));
+        let t_piece = meshes.add(bars_mesh(PieceShape::TPiece.bars()));
+        let mut materials = world.resource_mut::<Assets<ColorMaterial>>();
+        CommonAssets {
+            rect,
+            circle,
+            triangle,
+            capsule,
+            l_piece,
+            t_piece,
+            terrain: materials.add(palette.terrain),
+            debris: materials.add(palette.debris),
+            foam: materials.add(palette.debris.with_alpha(0.5)),
+            player: materials.add(palette.player),
+            hazard: materials.add(palette.hazard),
+            checkpoint: materials.add(palette.player.with_alpha(0.4)),
+            marker: materials.add(palette.hazard.with_alpha(0.8)),
+            shadow: materials.add(Color::srgba(0.0, 0.0, 0.0, 0.4)),
+            accent: materials.add(Color::BLACK),
+        }
+    }
+}
+
+// one quad per (center, size) bar, all in the same unit box as the other meshes
+fn bars_mesh(bars: &[(Vec2, Vec2)]) -> Mesh {
+    let mut positions = Vec::new();
+    let mut uvs = Vec::new();
+    let mut indices = Vec::new();
+    for (center, size) in bars {
+        let base = positions.len() as u32;
+        for corner in [
+            Vec2::new(-0.5, -0.5),
+            Vec2::new(0.5, -0.5),
+            Vec2::new(0.5, 0.5),
+            Vec2::new(-0.5, 0.5),
+        ] {
+            let position = *center + corner * *size;
+            positions.push([position.x, position.y, 0.0]);
+            uvs.push([position.x + 0.5, 0.5 - position.y]);
+        }
+        indices.extend([base, base + 1, base + 2, base, base + 2, base + 3]);
+    }
+    let normals = vec![[0.0, 0.0, 1.0]; positions.len()];
+    Mesh::new(
+        PrimitiveTopology::TriangleList,
+        RenderAssetUsages::RENDER_WORLD,
+    )
+    .with_inserted_attribute(Mesh::ATTRIBUTE_POSITION, positions)
+    .with_inserted_attribute(Mesh::ATTRIBUTE_NORMAL, normals)
+    .with_inserted_attribute(Mesh::ATTRIBUTE_UV_0, uvs)
+    .with_inserted_indices(Indices::U32(indices))
+}
+
+// a convex outline in the unit box, fanned out from its first point
+pub fn polygon_mesh(outline: &[Vec2]) -> Mesh {
+    let positions: Vec<[f32; 3]> = outline
+        .iter()
+        .map(|point| [point.x, point.y, 0.0])
+        .collect();
+    let uvs: Vec<[f32; 2]> = outline
+        .iter()
+        .map(|point| [point.x + 0.5, 0.5 - point.y])
+        .collect();
+    let indices = (1..outline.len().saturating_sub(1) as u32)
+        .flat_map(|i| [0, i, i + 1])
+        .collect();
+    let normals = vec![[0.0, 0.0, 1.0]; positions.len()];
+    Mesh::new(
+        PrimitiveTopology::TriangleList,
+        RenderAssetUsages::RENDER_WORLD,
+    )
+    .with_inserted_attribute(Mesh::ATTRIBUTE_POSITION, positions)
+    .with_inserted_attribute(Mesh::ATTRIBUTE_NORMAL, normals)
+    .with_inserted_attribute(Mesh::ATTRIBUTE_UV_0, uvs)
+    .with_inserted_indices(Indices::U32(indices))
+}
@@ -0,0 +1,191 @@
+use bevy::app::PluginGroupBuilder;
+use bevy::asset::AssetMetaCheck;
+use bevy::prelude::*;
+
+use crate::branding::{window_title, APP_ID};
+use crate::director::DifficultyConfig;
+use crate::logging::log_plugin;
+use crate::player::PlayerConfig;
+use crate::prefab::PieceShape;
+use crate::spawner::SpawnerConfig;
+
+// everything an embedding app or another binary can change about the game, built up
+// from the defaults:
+//
+//     RectfallPlugin::new(GameConfig::default().with_gravity(1500.0))
+#[derive(Resource, Clone)]
+pub struct GameConfig {
+    pub(crate) gravity: f32,
+    // fastest the player runs, and the upward speed a held jump keeps pushing to
+    pub(crate) move_speed: f32,
+    // added to the player's speed every movement tick while running
+    pub(crate) acceleration: f32,
+    pub(crate) jump_boost: f32,
+    pub(crate) player: PlayerConfig,
+    pub(crate) spawner: SpawnerConfig,
+    pub(crate) difficulty: DifficultyConfig,
+    pub(crate) wall_jump: WallJumpConfig,
+    pub(crate) camera_pan: CameraPanConfig,
+    // what the camera frames when the level starts, before zooming in to play. the
+    // whole level when not set
+    pub(crate) start_view: Option<Rect>,
+    // relative to the executable, or the page on the web
+    pub(crate) asset_root: String,
+}
+
+// how wall jumps chain into each other before the player lands again. walls are told
+// apart by the side they're on, so a column of rects counts as one wall
+#[derive(Clone, Debug)]
+pub struct WallJumpConfig {
+    // kicking off the same side again sooner than this does nothing
+    pub same_wall_cooldown_secs: f32,
+    // every jump to the opposite wall in a row goes this much higher than a regular one
+    pub alternating_height_step: f32,
+    pub max_alternating_steps: u32,
+    // only wall jump while holding the direction away from the wall
+    pub require_direction: bool,
+}
+
+impl Default for WallJumpConfig {
+    fn default() -> WallJumpConfig {
+        WallJumpConfig {
+            same_wall_cooldown_secs: 0.35,
+            alternating_height_step: 0.15,
+            max_alternating_steps: 3,
+            require_direction: false,
+        }
+    }
+}
+
+// panning with the cursor at the window's edge or the arrow keys, while the free camera
+// is on. speeds are in world pixels per second at the default zoom
+#[derive(Clone, Debug)]
+pub struct CameraPanConfig {
+    pub edge_panning: bool,
+    pub key_panning: bool,
+    pub edge_speed: f32,
+    pub key_speed: f32,
+    // how close the cursor has to be to the edge, in logical pixels
+    pub edge_margin: f32,
+}
+
+impl Default for CameraPanConfig {
+    fn default() -> CameraPanConfig {
+        CameraPanConfig {
+            edge_panning: true,
+            key_panning: true,
+            edge_speed: 900.0,
+            key_speed: 700.0,
+            edge_margin: 24.0,
+        }
+    }
+}
+
+impl Default for GameConfig {
+    fn default() -> GameConfig {
+        GameConfig {
+            gravity: 1000.0,
+            move_speed: 300.0,
+            acceleration: 10.0,
+            jump_boost: 100.0,
+            player: PlayerConfig::default(),
+            spawner: SpawnerConfig::default(),
+            difficulty: DifficultyConfig::default(),
+            wall_jump: WallJumpConfig::default(),
+            camera_pan: CameraPanConfig::default(),
+            start_view: None,
+            asset_root: "assets".to_string(),
+        }
+    }
+}
+
+impl GameConfig {
+    pub fn with_gravity(mut self, gravity: f32) -> GameConfig {
+        self.gravity = gravity;
+        self
+    }
+
+    pub fn with_move_speed(mut self, move_speed: f32) -> GameConfig {
+        self.move_speed = move_speed;
+        self
+    }
+
+    pub fn with_acceleration(mut self, acceleration: f32) -> GameConfig {
+        self.acceleration = acceleration;
+        self
+    }
+
+    pub fn with_jump_boost(mut self, jump_boost: f32) -> GameConfig {
+        self.jump_boost = jump_boost;
+        self
+    }
+
+    pub fn with_player_size(mut self, size: Vec2) -> GameConfig {
+        self.player.size = size;
+        self
+    }
+
+    pub fn with_player_capsule(mut self, capsule: bool) -> GameConfig {
+        self.player.shape = if capsule {
+            PieceShape::Capsule
+        } else {
+            PieceShape::Rect
+        };
+        self
+    }
+
+    pub fn with_spawner(mut self, spawner: SpawnerConfig) -> GameConfig {
+        self.spawner = spawner;
+        self
+    }
+
+    pub fn with_difficulty(mut self, difficulty: DifficultyConfig) -> GameConfig {
+        self.difficulty = difficulty;
+        self
+    }
+
+    pub fn with_wall_jump(mut self, wall_jump: WallJumpConfig) -> GameConfig {
+        self.wall_jump = wall_jump;
+        self
+    }
+
+    pub fn with_camera_pan(mut self, camera_pan: CameraPanConfig) -> GameConfig {
+        self.camera_pan = camera_pan;
+        self
+    }
+
+    pub fn with_start_view(mut self, start_view: Rect) -> GameConfig {
+        self.start_view = Some(start_view);
+        self
+    }
+
+    pub fn with_asset_root(mut self, asset_root: impl Into<String>) -> GameConfig {
+        self.asset_root = asset_root.into();
+        self
+    }
+
+    // DefaultPlugins reading assets from the asset root, with the game's window and
+    // logging. hosts that already add their own need at least the same AssetPlugin
+    pub fn default_plugins(&self) -> PluginGroupBuilder {
+        DefaultPlugins
+            .set(AssetPlugin {
+                file_path: self.asset_root.clone(),
+                // Wasm builds will check for meta files (that don't exist) if this isn't set.
+                // This causes errors and even panics in web builds on itch.
+                // See https://github.com/bevyengine/bevy_github_ci_template/issues/48.
+                meta_check: AssetMetaCheck::Never,
+                ..default()
+            })
+            .set(WindowPlugin {
+                primary_window: Some(Window {
+                    title: window_title(),
+                    name: Some(APP_ID.to_string()),
+                    fit_canvas_to_parent: true,
+                    prevent_default_event_handling: false,
+                    ..default()
+                }),
+                ..default()
+            })
+            .set(log_plugin())
+    }
+}
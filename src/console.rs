@@ -0,0 +1,200 @@
+use bevy::input::keyboard::{Key, KeyboardInput};
+use bevy::input::ButtonState;
+use bevy::prelude::*;
+
+use crate::input::{Action, ActionSet, ActionState};
+use crate::palette::Palette;
+
+const CONSOLE_FONT_SIZE: f32 = 16.0;
+const MAX_LINES: usize = 12;
+
+pub struct ConsolePlugin;
+
+impl Plugin for ConsolePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Console>()
+            .add_event::<ConsoleCommand>()
+            .add_console_command("help", "help")
+            .add_systems(Startup, spawn_console)
+            .add_systems(
+                PreUpdate,
+                block_actions
+                    .after(ActionSet::Assist)
+                    .run_if(|console: Res<Console>| console.open),
+            )
+            .add_systems(
+                Update,
+                (toggle_console, type_into_console, run_help, render_console).chain(),
+            );
+    }
+}
+
+#[derive(Resource, Default)]
+pub struct Console {
+    pub open: bool,
+    input: String,
+    lines: Vec<String>,
+    commands: Vec<(&'static str, &'static str)>,
+}
+
+impl Console {
+    pub fn print(&mut self, line: impl Into<String>) {
+        self.lines.push(line.into());
+        if self.lines.len() > MAX_LINES {
+            self.lines.remove(0);
+        }
+    }
+}
+
+// sent for every line entered in the console, the module owning the command handles it
+#[derive(Event)]
+pub struct ConsoleCommand {
+    pub name: String,
+    pub args: Vec<String>,
+}
+
+pub trait ConsoleAppExt {
+    fn add_console_command(&mut self, name: &'static str, usage: &'static str) -> &mut Self;
+}
+
+impl ConsoleAppExt for App {
+    fn add_console_command(&mut self, name: &'static str, usage: &'static str) -> &mut Self {
+        self.init_resource::<Console>();
+        self.world_mut()
+            .resource_mut::<Console>()
+            .commands
+            .push((name, usage));
+        self
+    }
+}
+
+#[derive(Component)]
+struct ConsoleRoot;
+
+#[derive(Component)]
+struct ConsoleText;
+
+fn spawn_console(mut commands: Commands, palette: Res<Palette>) {
+    commands
+        .spawn((
+            Name::new("Console"),
+            ConsoleRoot,
+            Node {
+                position_type: PositionType::Absolute,
+                width: Val::Percent(100.0),
+                padding: UiRect::all(Val::Px(8.0)),
+                ..default()
+            },
+            BackgroundColor(Color::srgba(0.0, 0.0, 0.0, 0.8)),
+            GlobalZIndex(20),
+            Visibility::Hidden,
+        ))
+        .with_child((
+            ConsoleText,
+            Text::new(""),
+            TextFont {
+                font_size: CONSOLE_FONT_SIZE,
+                ..default()
+            },
+            TextColor(palette.ui_text),
+        ));
+}
+
+fn toggle_console(
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    mut console: ResMut<Console>,
+    mut root: Single<&mut Visibility, With<ConsoleRoot>>,
+) {
+    // a dev tool, release builds without the cheats can't open it
+    if !cfg!(any(debug_assertions, feature = "cheats"))
+        || !keyboard_input.just_pressed(KeyCode::Backquote)
+    {
+        return;
+    }
+    console.open = !console.open;
+    **root = if console.open {
+        Visibility::Inherited
+    } else {
+        Visibility::Hidden
+    };
+}
+
+fn type_into_console(
+    mut keyboard_events: EventReader<KeyboardInput>,
+    mut console: ResMut<Console>,
+    mut commands: EventWriter<ConsoleCommand>,
+) {
+    if !console.open {
+        keyboard_events.clear();
+        return;
+    }
+    for event in keyboard_events.read() {
+        if event.state != ButtonState::Pressed || event.key_code == KeyCode::Backquote {
+            continue;
+        }
+        match &event.logical_key {
+            Key::Character(text) => console.input.push_str(text),
+            Key::Space => console.input.push(' '),
+            Key::Backspace => {
+                console.input.pop();
+            }
+            Key::Enter => {
+                let line = std::mem::take(&mut console.input);
+                console.print(format!("> {line}"));
+                let mut words = line.split_whitespace().map(str::to_string);
+                let Some(name) = words.next() else {
+                    continue;
+                };
+                if console.commands.iter().any(|(known, _)| *known == name) {
+                    commands.send(ConsoleCommand {
+                        name,
+                        args: words.collect(),
+                    });
+                } else {
+                    console.print(format!("unknown command '{name}', try 'help'"));
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+fn run_help(mut commands: EventReader<ConsoleCommand>, mut console: ResMut<Console>) {
+    for _ in commands.read().filter(|command| command.name == "help") {
+        let usages: Vec<&str> = console.commands.iter().map(|(_, usage)| *usage).collect();
+        console.print(usages.join("\n"));
+    }
+}
+
+// keep the player still while typing
+fn block_actions(mut actions: ResMut<ActionState>) {
+    for action in [
+        Action::Left,
+        Action::Right,
+        Action::Jump,
+        Action::Down,
+        Action::Grab,
+        Action::Freeze,
+        Action::Interact,
+        Action::SaveState,
+        Action::LoadState,
+    ] {
+        actions.release(action);
+    }
+}
+
+fn render_console(
+    console: Res<Console>,
+    palette: Res<Palette>,
+    text: Single<(&mut Text, &mut TextColor), With<ConsoleText>>,
+) {
+    let (mut text, mut color) = text.into_inner();
+    if palette.is_changed() {
+        color.0 = palette.ui_text;
+    }
+    if !console.is_changed() {
+        return;
+    }
+    text.0 = console.lines.join("\n");
+    text.0.push_str(&format!("\n> {}_", console.input));
+}
@@ -0,0 +1,298 @@
+use bevy::prelude::*;
+
+use crate::achievements::Achievement;
+use crate::common_assets::CommonAssets;
+use crate::i18n::Localization;
+use crate::menu::{MenuAction, MenuActivated, MenuPage, MenuRow, MenuRows, MenuSet};
+use crate::palette::{PaletteRole, Themed};
+use crate::player::PlayerOwned;
+use crate::quality::Quality;
+use crate::save::SaveData;
+use crate::settings::Settings;
+use crate::z_layer::ZLayer;
+use crate::{GameSet, Player};
+
+const TRAIL_INTERVAL_SECS: f32 = 0.05;
+const TRAIL_LIFETIME_SECS: f32 = 0.4;
+const TRAIL_SIZE: f32 = 30.0;
+
+pub struct CosmeticsPlugin;
+
+impl Plugin for CosmeticsPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            Update,
+            (
+                skin_menu_rows.in_set(MenuSet::Rows),
+                select_skin.in_set(MenuSet::Actions),
+            ),
+        )
+        .add_systems(PostUpdate, apply_skin)
+        .add_systems(Update, (emit_trail, fade_trail).in_set(GameSet::Vfx));
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum SkinPattern {
+    Solid,
+    Striped,
+    Core,
+}
+
+#[derive(Clone, Copy)]
+pub enum Unlock {
+    Always,
+    BestScore(u32),
+    Achievement(&'static str),
+    // bought with shards in the unlocks menu
+    Meta(&'static str),
+}
+
+pub struct Skin {
+    pub id: &'static str,
+    // None follows the palette's player color
+    pub color: Option<Color>,
+    pub pattern: SkinPattern,
+    pub trail: Option<Color>,
+    pub unlock: Unlock,
+}
+
+pub const SKINS: &[Skin] = &[
+    Skin {
+        id: "classic",
+        color: None,
+        pattern: SkinPattern::Solid,
+        trail: None,
+        unlock: Unlock::Always,
+    },
+    Skin {
+        id: "ember",
+        color: Some(Color::srgb(0.85, 0.3, 0.1)),
+        pattern: SkinPattern::Solid,
+        trail: Some(Color::srgba(1.0, 0.6, 0.1, 0.6)),
+        unlock: Unlock::BestScore(500),
+    },
+    Skin {
+        id: "zebra",
+        color: Some(Color::WHITE),
+        pattern: SkinPattern::Striped,
+        trail: None,
+        unlock: Unlock::BestScore(1500),
+    },
+    Skin {
+        id: "reactor",
+        color: Some(Color::srgb(0.1, 0.15, 0.2)),
+        pattern: SkinPattern::Core,
+        trail: Some(Color::srgba(0.3, 1.0, 0.8, 0.6)),
+        unlock: Unlock::BestScore(4000),
+    },
+    Skin {
+        id: "climber",
+        color: Some(Color::srgb(0.2, 0.5, 0.25)),
+        pattern: SkinPattern::Striped,
+        trail: Some(Color::srgba(0.6, 1.0, 0.6, 0.5)),
+        unlock: Unlock::Achievement("chain_10_wall_jumps"),
+    },
+    Skin {
+        id: "glacier",
+        color: Some(Color::srgb(0.65, 0.85, 0.95)),
+        pattern: SkinPattern::Core,
+        trail: Some(Color::srgba(0.8, 0.95, 1.0, 0.5)),
+        unlock: Unlock::Meta("skin_glacier"),
+    },
+];
+
+impl Skin {
+    pub fn find(id: &str) -> &'static Skin {
+        SKINS.iter().find(|skin| skin.id == id).unwrap_or(&SKINS[0])
+    }
+
+    pub fn is_unlocked(&self, save: &SaveData) -> bool {
+        match self.unlock {
+            Unlock::Always => true,
+            Unlock::BestScore(score) => save.best_score >= score,
+            Unlock::Achievement(id) => save.achievements.iter().any(|existing| existing == id),
+            Unlock::Meta(id) => save.unlocks.iter().any(|existing| existing == id),
+        }
+    }
+
+    pub fn name_key(&self) -> String {
+        format!("skin.{}", self.id)
+    }
+
+    fn unlock_hint(&self, localization: &Localization) -> String {
+        match self.unlock {
+            Unlock::Always => String::new(),
+            Unlock::BestScore(score) => {
+                localization.format("skin.unlock_score", &[("score", &score)])
+            }
+            Unlock::Achievement(id) => match Achievement::find(id) {
+                Some(achievement) => localization.get(&achievement.name_key()).to_string(),
+                None => id.to_string(),
+            },
+            Unlock::Meta(_) => localization.get("skin.unlock_meta").to_string(),
+        }
+    }
+}
+
+#[derive(Component)]
+struct SkinDecoration;
+
+#[derive(Component)]
+struct TrailParticle {
+    age: f32,
+}
+
+fn skin_menu_rows(
+    page: Res<MenuPage>,
+    save: Res<SaveData>,
+    localization: Res<Localization>,
+    mut rows: ResMut<MenuRows>,
+) {
+    if *page != MenuPage::Skins {
+        return;
+    }
+    for (index, skin) in SKINS.iter().enumerate() {
+        let name = localization.get(&skin.name_key()).to_string();
+        let label = if !skin.is_unlocked(&save) {
+            localization.format(
+                "skin.locked",
+                &[("name", &name), ("hint", &skin.unlock_hint(&localization))],
+            )
+        } else if skin.id == save.selected_skin {
+            localization.format("skin.selected", &[("name", &name)])
+        } else {
+            name
+        };
+        rows.push(MenuRow::new(label, MenuAction::SelectSkin(index)));
+    }
+    rows.push(MenuRow::new(
+        localization.get("menu.back"),
+        MenuAction::Back,
+    ));
+}
+
+fn select_skin(mut activated: EventReader<MenuActivated>, mut save: ResMut<SaveData>) {
+    for event in activated.read() {
+        let MenuAction::SelectSkin(index) = event.action else {
+            continue;
+        };
+        let skin = &SKINS[index];
+        if event.delta == 0 && skin.is_unlocked(&save) && save.selected_skin != skin.id {
+            save.selected_skin = skin.id.to_string();
+        }
+    }
+}
+
+fn apply_skin(
+    mut commands: Commands,
+    save: Res<SaveData>,
+    player: Single<(Entity, Ref<Player>, &MeshMaterial2d<ColorMaterial>)>,
+    decorations: Query<Entity, With<SkinDecoration>>,
+    assets: Res<CommonAssets>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+    mut core_material: Local<Option<Handle<ColorMaterial>>>,
+) {
+    let (player_entt, player, material) = player.into_inner();
+    if !save.is_changed() && !player.is_added() {
+        return;
+    }
+    let skin = Skin::find(&save.selected_skin);
+    match skin.color {
+        Some(color) => {
+            commands.entity(player_entt).remove::<Themed>();
+            if let Some(material) = materials.get_mut(material) {
+                material.color = color;
+            }
+        }
+        None => {
+            commands
+                .entity(player_entt)
+                .insert(Themed(PaletteRole::Player));
+        }
+    }
+    for entity in &decorations {
+        commands.entity(entity).despawn_recursive();
+    }
+    // decorations live in the player's unit space, the player's scale sizes them
+    commands
+        .entity(player_entt)
+        .with_children(|parent| match skin.pattern {
+            SkinPattern::Solid => {}
+            SkinPattern::Striped => {
+                for y in [-0.3, 0.0, 0.3] {
+                    parent.spawn((
+                        SkinDecoration,
+                        Mesh2d(assets.rect.clone()),
+                        MeshMaterial2d(assets.accent.clone()),
+                        Transform::from_xyz(0.0, y, 0.1).with_scale(Vec3::new(1.0, 0.12, 1.0)),
+                    ));
+                }
+            }
+            SkinPattern::Core => {
+                let core =
+                    core_material.get_or_insert_with(|| materials.add(Color::srgb(0.3, 1.0, 0.8)));
+                parent.spawn((
+                    SkinDecoration,
+                    Mesh2d(assets.rect.clone()),
+                    MeshMaterial2d(core.clone()),
+                    Transform::from_xyz(0.0, 0.0, 0.1).with_scale(Vec3::splat(0.4)),
+                ));
+            }
+        });
+}
+
+fn emit_trail(
+    mut commands: Commands,
+    save: Res<SaveData>,
+    settings: Res<Settings>,
+    quality: Res<Quality>,
+    player: Single<(Entity, &Transform), With<Player>>,
+    particles: Query<(), With<TrailParticle>>,
+    assets: Res<CommonAssets>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+    time: Res<Time>,
+    mut since_last: Local<f32>,
+    mut trail_material: Local<Option<Handle<ColorMaterial>>>,
+) {
+    let Some(color) = Skin::find(&save.selected_skin).trail else {
+        return;
+    };
+    if save.is_changed() {
+        *trail_material = None;
+    }
+    *since_last += time.delta_secs();
+    if *since_last < TRAIL_INTERVAL_SECS {
+        return;
+    }
+    *since_last = 0.0;
+    if particles.iter().len() >= quality.particles(settings.particles).max_particles() {
+        return;
+    }
+    let material = trail_material.get_or_insert_with(|| materials.add(color));
+    commands.spawn((
+        Name::new("TrailParticle"),
+        ZLayer::Trail,
+        TrailParticle { age: 0.0 },
+        PlayerOwned(player.0),
+        Mesh2d(assets.rect.clone()),
+        MeshMaterial2d(material.clone()),
+        Transform::from_translation(player.1.translation.truncate().extend(0.0))
+            .with_scale(Vec3::splat(TRAIL_SIZE)),
+    ));
+}
+
+fn fade_trail(
+    mut commands: Commands,
+    mut particles: Query<(Entity, &mut TrailParticle, &mut Transform)>,
+    time: Res<Time>,
+) {
+    for (entity, mut particle, mut transform) in &mut particles {
+        particle.age += time.delta_secs();
+        if particle.age >= TRAIL_LIFETIME_SECS {
+            commands.entity(entity).despawn();
+            continue;
+        }
+        transform.scale = Vec3::splat(TRAIL_SIZE * (1.0 - particle.age / TRAIL_LIFETIME_SECS));
+    }
+}
@@ -0,0 +1,251 @@
+use std::collections::VecDeque;
+use std::fmt::Write as _;
+use std::sync::Mutex;
+
+use bevy::core::FrameCount;
+use bevy::log::tracing_subscriber::layer::Context;
+use bevy::log::tracing_subscriber::Layer;
+use bevy::prelude::*;
+use bevy::utils::tracing::field::{Field, Visit};
+use bevy::utils::tracing::{Event, Subscriber};
+use serde::{Deserialize, Serialize};
+
+use crate::clipboard::Clipboard;
+use crate::i18n::Localization;
+use crate::menu::{GameState, MenuAction, MenuActivated, MenuPage, MenuRow, MenuRows, MenuSet};
+use crate::score::RunStats;
+use crate::settings::Settings;
+use crate::spawner::Difficulty;
+use crate::storage::{store, Persistent, PersistentStorage, StorageAppExt};
+
+const MAX_LOG_LINES: usize = 100;
+const CONTEXT_INTERVAL_SECS: f32 = 1.0;
+
+// the panic hook can't reach the world, so everything it reports is kept out here
+static RECENT_LOGS: Mutex<VecDeque<String>> = Mutex::new(VecDeque::new());
+static GAME_CONTEXT: Mutex<Vec<String>> = Mutex::new(Vec::new());
+
+pub struct CrashPlugin;
+
+impl Plugin for CrashPlugin {
+    fn build(&self, app: &mut App) {
+        install_panic_hook(app.world().resource::<PersistentStorage>().clone());
+        app.add_persistent_resource::<CrashReport>()
+            .add_systems(PreStartup, show_crash_dialog)
+            .add_systems(
+                Update,
+                (
+                    crash_menu_rows.in_set(MenuSet::Rows),
+                    apply_crash_actions.in_set(MenuSet::Actions),
+                ),
+            )
+            .add_systems(Last, record_game_context);
+    }
+}
+
+// the last crash, kept until the player has seen it
+#[derive(Resource, Serialize, Deserialize, Default)]
+#[serde(default)]
+struct CrashReport {
+    message: String,
+    location: String,
+    version: String,
+    // runs aren't seeded yet, this gets filled in once they are
+    seed: Option<u64>,
+    context: Vec<String>,
+    logs: Vec<String>,
+}
+
+impl Persistent for CrashReport {
+    const KEY: &'static str = "crash_report";
+    const VERSION: u32 = 1;
+}
+
+impl CrashReport {
+    fn is_empty(&self) -> bool {
+        self.message.is_empty()
+    }
+
+    fn to_text(&self) -> String {
+        let mut text = String::new();
+        let _ = writeln!(
+            text,
+            "rectfall {} crashed at {}",
+            self.version, self.location
+        );
+        let _ = writeln!(text, "{}", self.message);
+        let seed = self
+            .seed
+            .map_or("none".to_string(), |seed| seed.to_string());
+        let _ = writeln!(text, "\nseed: {seed}");
+        for line in &self.context {
+            let _ = writeln!(text, "{line}");
+        }
+        let _ = writeln!(text, "\nrecent log:");
+        for line in &self.logs {
+            let _ = writeln!(text, "{line}");
+        }
+        text
+    }
+}
+
+// keeps whatever hook was there before running too, bevy's prints to the browser console
+fn install_panic_hook(storage: PersistentStorage) {
+    let previous = std::panic::take_hook();
+    std::panic::set_hook(Box::new(move |info| {
+        let payload = info.payload();
+        let message = payload
+            .downcast_ref::<&str>()
+            .map(|message| message.to_string())
+            .or_else(|| payload.downcast_ref::<String>().cloned())
+            .unwrap_or_else(|| "unknown panic".to_string());
+        // try_lock, the panic may have happened while one of these was held
+        let report = CrashReport {
+            message,
+            location: info
+                .location()
+                .map_or(String::new(), |location| location.to_string()),
+            version: env!("CARGO_PKG_VERSION").to_string(),
+            seed: None,
+            context: GAME_CONTEXT
+                .try_lock()
+                .map_or(Vec::new(), |context| context.clone()),
+            logs: RECENT_LOGS
+                .try_lock()
+                .map_or(Vec::new(), |logs| logs.iter().cloned().collect()),
+        };
+        error!("crash report:\n{}", report.to_text());
+        store(&storage, &report, true);
+        previous(info);
+    }));
+}
+
+// a tracing layer that remembers the last few log lines for the crash report
+pub struct RecentLogs;
+
+impl<S: Subscriber> Layer<S> for RecentLogs {
+    fn on_event(&self, event: &Event<'_>, _: Context<'_, S>) {
+        let metadata = event.metadata();
+        let mut line = format!("{} {}:", metadata.level(), metadata.target());
+        event.record(&mut LineVisitor(&mut line));
+        let Ok(mut logs) = RECENT_LOGS.lock() else {
+            return;
+        };
+        if logs.len() == MAX_LOG_LINES {
+            logs.pop_front();
+        }
+        logs.push_back(line);
+    }
+}
+
+struct LineVisitor<'a>(&'a mut String);
+
+impl Visit for LineVisitor<'_> {
+    fn record_debug(&mut self, field: &Field, value: &dyn std::fmt::Debug) {
+        if field.name() == "message" {
+            let _ = write!(self.0, " {value:?}");
+        } else {
+            let _ = write!(self.0, " {}={value:?}", field.name());
+        }
+    }
+}
+
+fn record_game_context(
+    state: Res<State<GameState>>,
+    page: Res<MenuPage>,
+    stats: Res<RunStats>,
+    difficulty: Res<Difficulty>,
+    settings: Res<Settings>,
+    frames: Res<FrameCount>,
+    entities: Query<Entity>,
+    time: Res<Time<Real>>,
+    mut last_update: Local<Option<f32>>,
+) {
+    let now = time.elapsed_secs();
+    if last_update.is_some_and(|last| now - last < CONTEXT_INTERVAL_SECS) {
+        return;
+    }
+    *last_update = Some(now);
+    let context = vec![
+        format!("state: {:?}, menu page: {:?}", state.get(), *page),
+        format!(
+            "run: score {}, height {:.0}, {:.1}s, assisted {}",
+            stats.score, stats.max_height, stats.time_survived, stats.assisted
+        ),
+        format!(
+            "difficulty: level {:.2}, spawn rate {:.2}",
+            difficulty.level, difficulty.spawn_rate
+        ),
+        format!(
+            "settings: line clears {}, language {}, theme {:?}",
+            settings.line_clear, settings.language, settings.theme
+        ),
+        format!(
+            "frame {}, {} entities, up for {now:.0}s",
+            frames.0,
+            entities.iter().count()
+        ),
+    ];
+    if let Ok(mut shared) = GAME_CONTEXT.lock() {
+        *shared = context;
+    }
+}
+
+// runs before Startup so the crash dialog wins over any other startup prompt
+fn show_crash_dialog(
+    report: Res<CrashReport>,
+    mut page: ResMut<MenuPage>,
+    mut next_state: ResMut<NextState<GameState>>,
+) {
+    if !report.is_empty() {
+        *page = MenuPage::Crash;
+        next_state.set(GameState::Paused);
+    }
+}
+
+fn crash_menu_rows(
+    page: Res<MenuPage>,
+    report: Res<CrashReport>,
+    localization: Res<Localization>,
+    mut rows: ResMut<MenuRows>,
+) {
+    if *page != MenuPage::Crash {
+        return;
+    }
+    rows.push(MenuRow::new(
+        localization.get("crash.title"),
+        MenuAction::None,
+    ));
+    rows.push(MenuRow::new(report.message.clone(), MenuAction::None));
+    rows.push(MenuRow::new(
+        localization.get("crash.copy"),
+        MenuAction::CopyCrashReport,
+    ));
+    rows.push(MenuRow::new(
+        localization.get("crash.dismiss"),
+        MenuAction::DismissCrashReport,
+    ));
+}
+
+fn apply_crash_actions(
+    mut activated: EventReader<MenuActivated>,
+    mut report: ResMut<CrashReport>,
+    mut page: ResMut<MenuPage>,
+    mut next_state: ResMut<NextState<GameState>>,
+    mut clipboard: Local<Clipboard>,
+) {
+    for event in activated.read() {
+        if event.delta != 0 {
+            continue;
+        }
+        match event.action {
+            MenuAction::CopyCrashReport => clipboard.copy(report.to_text()),
+            MenuAction::DismissCrashReport => {
+                *report = CrashReport::default();
+                *page = MenuPage::Main;
+                next_state.set(GameState::Playing);
+            }
+            _ => {}
+        }
+    }
+}
@@ -0,0 +1,456 @@
+use bevy::asset::io::Reader;
+use bevy::asset::{AssetLoader, LoadContext};
+use bevy::prelude::*;
+use bevy::utils::HashMap;
+use serde::Deserialize;
+
+use crate::asset_check::TrackedAssets;
+use crate::console::{Console, ConsoleAppExt, ConsoleCommand};
+use crate::i18n::Localization;
+use crate::input::{Action, ActionState};
+use crate::menu::GameState;
+use crate::palette::Palette;
+use crate::save::SaveData;
+use crate::ui_layout::UiRoot;
+use crate::Player;
+
+// (name, path), the intro plays once per save
+const CUTSCENES: &[(&str, &str)] = &[
+    ("intro", "cutscenes/intro.cutscene.ron"),
+    ("npc_hello", "cutscenes/npc_hello.cutscene.ron"),
+];
+const INTRO: &str = "intro";
+const CHARS_PER_SEC: f32 = 40.0;
+const SPEAKER_FONT_SIZE: f32 = 22.0;
+const TEXT_FONT_SIZE: f32 = 26.0;
+const HINT_FONT_SIZE: f32 = 16.0;
+const PORTRAIT_SIZE: f32 = 96.0;
+
+pub struct CutscenePlugin;
+
+impl Plugin for CutscenePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_asset::<Cutscene>()
+            .init_asset_loader::<CutsceneLoader>()
+            .init_resource::<CutscenePlayer>()
+            .add_event::<PlayCutscene>()
+            .add_console_command("cutscene", "cutscene <name>")
+            .add_systems(Startup, (load_cutscenes, spawn_text_box))
+            .add_systems(
+                Update,
+                (play_intro, run_cutscene_command, start_cutscenes).chain(),
+            )
+            .add_systems(OnEnter(GameState::Cutscene), pause_time)
+            .add_systems(OnExit(GameState::Cutscene), (resume_time, hide_text_box))
+            .add_systems(
+                Update,
+                (skip_cutscene, advance_cutscene, render_text_box)
+                    .chain()
+                    .run_if(in_state(GameState::Cutscene)),
+            );
+    }
+}
+
+// anything can start a cutscene by name, it waits for the file if it's still loading
+#[derive(Event)]
+pub struct PlayCutscene(pub String);
+
+#[derive(Asset, TypePath, Deserialize, Clone)]
+pub struct Cutscene {
+    pub steps: Vec<CutsceneStep>,
+}
+
+// speaker and text are locale keys
+#[derive(Deserialize, Clone, Debug)]
+pub enum CutsceneStep {
+    // moves the camera to an offset from the player over a few seconds
+    Camera {
+        to: (f32, f32),
+        secs: f32,
+    },
+    Say {
+        speaker: String,
+        text: String,
+        #[serde(default)]
+        portrait: Option<String>,
+    },
+    Wait(f32),
+}
+
+#[derive(Default)]
+struct CutsceneLoader;
+
+impl AssetLoader for CutsceneLoader {
+    type Asset = Cutscene;
+    type Settings = ();
+    type Error = std::io::Error;
+
+    async fn load(
+        &self,
+        reader: &mut dyn Reader,
+        _settings: &(),
+        _load_context: &mut LoadContext<'_>,
+    ) -> Result<Cutscene, Self::Error> {
+        let mut bytes = Vec::new();
+        reader.read_to_end(&mut bytes).await?;
+        ron::de::from_bytes(&bytes)
+            .map_err(|err| std::io::Error::new(std::io::ErrorKind::InvalidData, err))
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["cutscene.ron"]
+    }
+}
+
+#[derive(Resource)]
+struct CutsceneHandles(HashMap<&'static str, Handle<Cutscene>>);
+
+#[derive(Resource, Default)]
+struct CutscenePlayer {
+    // waiting for its file to load
+    pending: Option<String>,
+    name: String,
+    steps: Vec<CutsceneStep>,
+    step: usize,
+    elapsed: f32,
+    // the camera goes back here once the cutscene is over
+    camera_home: Vec3,
+    camera_from: Vec3,
+}
+
+impl CutscenePlayer {
+    fn current(&self) -> Option<&CutsceneStep> {
+        self.steps.get(self.step)
+    }
+
+    fn next_step(&mut self, camera: Vec3) {
+        self.step += 1;
+        self.elapsed = 0.0;
+        self.camera_from = camera;
+    }
+}
+
+#[derive(Component)]
+struct TextBox;
+
+#[derive(Component)]
+struct SpeakerText;
+
+#[derive(Component)]
+struct LineText;
+
+#[derive(Component)]
+struct SkipHint;
+
+#[derive(Component)]
+struct Portrait;
+
+fn load_cutscenes(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    mut tracked: ResMut<TrackedAssets>,
+) {
+    let handles: HashMap<&'static str, Handle<Cutscene>> = CUTSCENES
+        .iter()
+        .map(|(name, path)| (*name, asset_server.load(*path)))
+        .collect();
+    for handle in handles.values() {
+        tracked.track(handle.clone());
+    }
+    commands.insert_resource(CutsceneHandles(handles));
+}
+
+fn spawn_text_box(
+    mut commands: Commands,
+    palette: Res<Palette>,
+    root: Single<Entity, With<UiRoot>>,
+) {
+    commands
+        .spawn((
+            Name::new("CutsceneTextBox"),
+            TextBox,
+            Node {
+                position_type: PositionType::Absolute,
+                left: Val::Percent(10.0),
+                right: Val::Percent(10.0),
+                bottom: Val::Px(24.0),
+                padding: UiRect::all(Val::Px(16.0)),
+                column_gap: Val::Px(16.0),
+                align_items: AlignItems::FlexStart,
+                ..default()
+            },
+            BackgroundColor(Color::BLACK.with_alpha(0.75)),
+            GlobalZIndex(10),
+            Visibility::Hidden,
+        ))
+        .set_parent(*root)
+        .with_children(|parent| {
+            parent.spawn((
+                Portrait,
+                ImageNode::default(),
+                Node {
+                    width: Val::Px(PORTRAIT_SIZE),
+                    height: Val::Px(PORTRAIT_SIZE),
+                    display: Display::None,
+                    ..default()
+                },
+            ));
+            parent
+                .spawn(Node {
+                    flex_direction: FlexDirection::Column,
+                    row_gap: Val::Px(6.0),
+                    flex_grow: 1.0,
+                    ..default()
+                })
+                .with_children(|column| {
+                    column.spawn((
+                        SpeakerText,
+                        Text::new(""),
+                        TextFont {
+                            font_size: SPEAKER_FONT_SIZE,
+                            ..default()
+                        },
+                        TextColor(palette.ui_accent),
+                    ));
+                    column.spawn((
+                        LineText,
+                        Text::new(""),
+                        TextFont {
+                            font_size: TEXT_FONT_SIZE,
+                            ..default()
+                        },
+                        TextColor(palette.ui_text),
+                    ));
+                    column.spawn((
+                        SkipHint,
+                        Text::new(""),
+                        TextFont {
+                            font_size: HINT_FONT_SIZE,
+                            ..default()
+                        },
+                        TextColor(palette.ui_text.with_alpha(0.6)),
+                    ));
+                });
+        });
+}
+
+fn play_intro(
+    save: Res<SaveData>,
+    mut played: Local<bool>,
+    mut cutscenes: EventWriter<PlayCutscene>,
+) {
+    if *played {
+        return;
+    }
+    *played = true;
+    if !save.seen_cutscenes.iter().any(|name| name == INTRO) {
+        cutscenes.send(PlayCutscene(INTRO.to_string()));
+    }
+}
+
+fn run_cutscene_command(
+    mut commands: EventReader<ConsoleCommand>,
+    mut console: ResMut<Console>,
+    mut cutscenes: EventWriter<PlayCutscene>,
+) {
+    for command in commands.read().filter(|command| command.name == "cutscene") {
+        match command.args.first() {
+            Some(name) if CUTSCENES.iter().any(|(known, _)| known == name) => {
+                cutscenes.send(PlayCutscene(name.clone()));
+            }
+            _ => {
+                let names: Vec<&str> = CUTSCENES.iter().map(|(name, _)| *name).collect();
+                console.print(format!("cutscenes: {}", names.join(", ")));
+            }
+        }
+    }
+}
+
+// a cutscene that fails to load is dropped, the game just carries on. one asked for
+// while the menu is open starts once it closes
+fn start_cutscenes(
+    mut requests: EventReader<PlayCutscene>,
+    state: Res<State<GameState>>,
+    mut player: ResMut<CutscenePlayer>,
+    handles: Res<CutsceneHandles>,
+    cutscenes: Res<Assets<Cutscene>>,
+    tracked: Res<TrackedAssets>,
+    camera: Single<&Transform, With<Camera>>,
+    mut next_state: ResMut<NextState<GameState>>,
+) {
+    if let Some(PlayCutscene(name)) = requests.read().last() {
+        player.pending = Some(name.clone());
+    }
+    if *state.get() != GameState::Playing {
+        return;
+    }
+    let Some(name) = player.pending.clone() else {
+        return;
+    };
+    let Some(handle) = handles.0.get(name.as_str()) else {
+        player.pending = None;
+        return;
+    };
+    if tracked.failed(handle) {
+        player.pending = None;
+        return;
+    }
+    let Some(cutscene) = cutscenes.get(handle) else {
+        return;
+    };
+    debug!("playing cutscene {name}");
+    *player = CutscenePlayer {
+        pending: None,
+        name,
+        steps: cutscene.steps.clone(),
+        step: 0,
+        elapsed: 0.0,
+        camera_home: camera.translation,
+        camera_from: camera.translation,
+    };
+    next_state.set(GameState::Cutscene);
+}
+
+fn finish_cutscene(
+    player: &mut CutscenePlayer,
+    save: &mut SaveData,
+    camera: &mut Transform,
+    next_state: &mut NextState<GameState>,
+) {
+    if !save.seen_cutscenes.contains(&player.name) {
+        save.seen_cutscenes.push(player.name.clone());
+    }
+    camera.translation = player.camera_home;
+    player.steps.clear();
+    next_state.set(GameState::Playing);
+}
+
+fn skip_cutscene(
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    gamepads: Query<&Gamepad>,
+    mut player: ResMut<CutscenePlayer>,
+    mut save: ResMut<SaveData>,
+    mut camera: Single<&mut Transform, With<Camera>>,
+    mut next_state: ResMut<NextState<GameState>>,
+) {
+    if keyboard_input.just_pressed(KeyCode::Escape)
+        || gamepads
+            .iter()
+            .any(|gamepad| gamepad.just_pressed(GamepadButton::Start))
+    {
+        debug!("skipped cutscene {}", player.name);
+        finish_cutscene(&mut player, &mut save, &mut camera, &mut next_state);
+    }
+}
+
+// jump shows the rest of a line at once, or moves on once it's all there
+fn advance_cutscene(
+    actions: Res<ActionState>,
+    mut player: ResMut<CutscenePlayer>,
+    mut save: ResMut<SaveData>,
+    mut camera: Single<&mut Transform, With<Camera>>,
+    target: Single<&Transform, (With<Player>, Without<Camera>)>,
+    localization: Res<Localization>,
+    mut next_state: ResMut<NextState<GameState>>,
+    time: Res<Time<Real>>,
+) {
+    if player.steps.is_empty() {
+        return;
+    }
+    player.elapsed += time.delta_secs();
+    let confirm = actions.just_pressed(Action::Jump);
+    let Some(step) = player.current().cloned() else {
+        finish_cutscene(&mut player, &mut save, &mut camera, &mut next_state);
+        return;
+    };
+    let done = match step {
+        CutsceneStep::Camera { to, secs } => {
+            let to = target.translation + Vec3::new(to.0, to.1, 0.0);
+            let t = (player.elapsed / secs.max(f32::EPSILON)).min(1.0);
+            let eased = t * t * (3.0 - 2.0 * t);
+            camera.translation = player
+                .camera_from
+                .lerp(to.with_z(player.camera_from.z), eased);
+            t >= 1.0
+        }
+        CutsceneStep::Say { text, .. } => {
+            let length = localization.get(&text).chars().count() as f32;
+            let shown = player.elapsed * CHARS_PER_SEC;
+            if confirm && shown < length {
+                player.elapsed = length / CHARS_PER_SEC;
+                false
+            } else {
+                confirm
+            }
+        }
+        CutsceneStep::Wait(secs) => player.elapsed >= secs,
+    };
+    if done {
+        player.next_step(camera.translation);
+    }
+}
+
+fn render_text_box(
+    player: Res<CutscenePlayer>,
+    localization: Res<Localization>,
+    asset_server: Res<AssetServer>,
+    mut text_box: Single<&mut Visibility, With<TextBox>>,
+    mut texts: ParamSet<(
+        Single<&mut Text, With<SpeakerText>>,
+        Single<&mut Text, With<LineText>>,
+        Single<&mut Text, With<SkipHint>>,
+    )>,
+    portrait: Single<(&mut ImageNode, &mut Node), With<Portrait>>,
+) {
+    let Some(CutsceneStep::Say {
+        speaker: speaker_key,
+        text,
+        portrait: portrait_path,
+    }) = player.current()
+    else {
+        if **text_box != Visibility::Hidden {
+            **text_box = Visibility::Hidden;
+        }
+        return;
+    };
+    if **text_box != Visibility::Inherited {
+        **text_box = Visibility::Inherited;
+    }
+    let shown = (player.elapsed * CHARS_PER_SEC) as usize;
+    let typed: String = localization.get(text).chars().take(shown).collect();
+    set_text(&mut texts.p0(), localization.get(speaker_key));
+    set_text(&mut texts.p1(), &typed);
+    set_text(&mut texts.p2(), localization.get("cutscene.skip"));
+    let (mut image, mut node) = portrait.into_inner();
+    let display = match portrait_path {
+        Some(path) => {
+            let handle = asset_server.load(path.as_str());
+            if image.image != handle {
+                image.image = handle;
+            }
+            Display::Flex
+        }
+        None => Display::None,
+    };
+    if node.display != display {
+        node.display = display;
+    }
+}
+
+fn set_text(text: &mut Text, wanted: &str) {
+    if text.0 != wanted {
+        text.0 = wanted.to_string();
+    }
+}
+
+fn hide_text_box(mut text_box: Single<&mut Visibility, With<TextBox>>) {
+    **text_box = Visibility::Hidden;
+}
+
+fn pause_time(mut time: ResMut<Time<Virtual>>) {
+    time.pause();
+}
+
+fn resume_time(mut time: ResMut<Time<Virtual>>) {
+    time.unpause();
+}
@@ -0,0 +1,162 @@
+use std::f32::consts::PI;
+
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::health::PlayerHurt;
+use crate::palette::Palette;
+use crate::settings::Settings;
+use crate::GameSet;
+
+const FLASH_SECS: f32 = 0.25;
+const FLASH_ALPHA: f32 = 0.5;
+const DESATURATE_SECS: f32 = 0.6;
+// one slow swell in and out, well under the 3 per second that counts as flashing
+const BORDER_PULSE_SECS: f32 = 0.8;
+const BORDER_WIDTH: f32 = 24.0;
+// a hit that leaves the player on their last point of health hits harder
+const LAST_HIT_SCALE: f32 = 1.5;
+
+pub struct DamageFeedbackPlugin;
+
+impl Plugin for DamageFeedbackPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<DamageFeedback>()
+            .add_systems(Startup, spawn_hit_overlay)
+            .add_systems(Update, play_damage_feedback.in_set(GameSet::Vfx));
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default, Serialize, Deserialize)]
+pub enum HitFeedback {
+    #[default]
+    Flash,
+    Desaturate,
+    BorderPulse,
+    Off,
+}
+
+impl HitFeedback {
+    pub fn key(self) -> &'static str {
+        match self {
+            HitFeedback::Flash => "settings.hit_feedback_flash",
+            HitFeedback::Desaturate => "settings.hit_feedback_desaturate",
+            HitFeedback::BorderPulse => "settings.hit_feedback_border",
+            HitFeedback::Off => "common.off",
+        }
+    }
+
+    pub fn cycle(self, delta: i32) -> HitFeedback {
+        const ORDER: [HitFeedback; 4] = [
+            HitFeedback::Flash,
+            HitFeedback::Desaturate,
+            HitFeedback::BorderPulse,
+            HitFeedback::Off,
+        ];
+        let index = ORDER.iter().position(|style| *style == self).unwrap_or(0);
+        ORDER[(index as i32 + delta).rem_euclid(ORDER.len() as i32) as usize]
+    }
+
+    // reduce flashing always wins over the picked style
+    fn resolve(settings: &Settings) -> HitFeedback {
+        match settings.hit_feedback {
+            HitFeedback::Flash if settings.reduce_flashing => HitFeedback::BorderPulse,
+            style => style,
+        }
+    }
+
+    fn duration_secs(self) -> f32 {
+        match self {
+            HitFeedback::Flash => FLASH_SECS,
+            HitFeedback::Desaturate => DESATURATE_SECS,
+            HitFeedback::BorderPulse => BORDER_PULSE_SECS,
+            HitFeedback::Off => 0.0,
+        }
+    }
+}
+
+// the effect currently playing, only one runs at a time and a new hit restarts it
+#[derive(Resource, Default)]
+struct DamageFeedback {
+    active: Option<(HitFeedback, f32)>,
+    strength: f32,
+}
+
+#[derive(Component)]
+struct HitOverlay;
+
+fn spawn_hit_overlay(mut commands: Commands) {
+    commands.spawn((
+        Name::new("HitOverlay"),
+        HitOverlay,
+        Node {
+            position_type: PositionType::Absolute,
+            width: Val::Percent(100.0),
+            height: Val::Percent(100.0),
+            border: UiRect::all(Val::Px(BORDER_WIDTH)),
+            ..default()
+        },
+        BackgroundColor(Color::NONE),
+        BorderColor(Color::NONE),
+    ));
+}
+
+fn play_damage_feedback(
+    mut hurt: EventReader<PlayerHurt>,
+    mut feedback: ResMut<DamageFeedback>,
+    settings: Res<Settings>,
+    mut palette: ResMut<Palette>,
+    mut clear_color: ResMut<ClearColor>,
+    overlay: Single<(&mut BackgroundColor, &mut BorderColor), With<HitOverlay>>,
+    time: Res<Time<Real>>,
+) {
+    let (mut background, mut border) = overlay.into_inner();
+    if let Some(hit) = hurt.read().last() {
+        // a desaturation cut short by the new hit must not leave the world grey
+        if let Some((HitFeedback::Desaturate, _)) = feedback.active {
+            *palette = Palette::from_theme(settings.theme);
+            clear_color.0 = palette.background;
+        }
+        background.0 = Color::NONE;
+        border.0 = Color::NONE;
+        let style = HitFeedback::resolve(&settings);
+        feedback.active = (style != HitFeedback::Off).then_some((style, 0.0));
+        // heavy hits and the one that leaves the player on their last point stand out
+        feedback.strength = if hit.damage > 1 || hit.remaining <= 1 {
+            LAST_HIT_SCALE
+        } else {
+            1.0
+        };
+    }
+    let strength = feedback.strength;
+    let Some((style, age)) = &mut feedback.active else {
+        return;
+    };
+    let style = *style;
+    *age += time.delta_secs();
+    let t = (*age / style.duration_secs()).min(1.0);
+    match style {
+        HitFeedback::Flash => {
+            background.0 = palette
+                .hazard
+                .with_alpha(FLASH_ALPHA * strength * (1.0 - t));
+        }
+        HitFeedback::Desaturate => {
+            // the palette gets rebuilt from the theme each frame so nothing drifts
+            *palette =
+                Palette::from_theme(settings.theme).desaturated((1.0 - t) * strength.min(1.0));
+            clear_color.0 = palette.background;
+        }
+        HitFeedback::BorderPulse => {
+            border.0 = palette
+                .hazard
+                .with_alpha(((t * PI).sin() * strength).min(1.0));
+        }
+        HitFeedback::Off => {}
+    }
+    if t >= 1.0 {
+        background.0 = Color::NONE;
+        border.0 = Color::NONE;
+        feedback.active = None;
+    }
+}
@@ -0,0 +1,278 @@
+use avian2d::prelude::*;
+use bevy::prelude::*;
+
+use crate::i18n::Localization;
+use crate::input::{Action, ActionState};
+use crate::killcam::{play_kill_cam, KillCam};
+use crate::menu::GameState;
+use crate::palette::Palette;
+use crate::score::RunStats;
+use crate::settings::Settings;
+use crate::spawner::FallingRect;
+use crate::ui_layout::UiRoot;
+use crate::{GameSet, Player, PlayerDied, PlayerKilled};
+
+const ZOOM_SECS: f32 = 2.5;
+// the next run starts on its own if nobody presses anything
+const RESULTS_SECS: f32 = 8.0;
+// world pixels kept around the pile
+const FRAME_MARGIN: f32 = 150.0;
+const MAX_ZOOM: f32 = 4.0;
+const HIGHLIGHT_WIDTH: f32 = 8.0;
+const HIGHLIGHT_PULSE_PER_SEC: f32 = 4.0;
+const TITLE_FONT_SIZE: f32 = 40.0;
+const LINE_FONT_SIZE: f32 = 24.0;
+const HINT_FONT_SIZE: f32 = 18.0;
+
+pub struct DeathCamPlugin;
+
+impl Plugin for DeathCamPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<DeathCam>()
+            .add_systems(Startup, spawn_results)
+            .add_systems(Update, start_death_cam.in_set(GameSet::Reactions))
+            .add_systems(OnEnter(GameState::DeathCam), pause_time)
+            .add_systems(OnExit(GameState::DeathCam), (resume_time, hide_results))
+            .add_systems(
+                Update,
+                (run_death_cam, pulse_highlight, render_results)
+                    .chain()
+                    // a jump that ends the kill cam shouldn't skip the zoom as well
+                    .before(play_kill_cam)
+                    .run_if(in_state(GameState::DeathCam)),
+            );
+    }
+}
+
+#[derive(Resource, Default)]
+struct DeathCam {
+    elapsed: f32,
+    camera_home: Vec3,
+    home_scale: f32,
+    target: Vec3,
+    target_scale: f32,
+    // RunStats resets with PlayerDied, so the results keep their own copy
+    score: u32,
+    height: f32,
+    time_survived: f32,
+}
+
+// an outline around the rect that got the player
+#[derive(Component)]
+struct KillerHighlight;
+
+// the results panel shown once the camera is done zooming out
+#[derive(Component)]
+pub struct Results;
+
+fn spawn_results(
+    mut commands: Commands,
+    palette: Res<Palette>,
+    root: Single<Entity, With<UiRoot>>,
+) {
+    commands
+        .spawn((
+            Name::new("Results"),
+            Results,
+            Node {
+                position_type: PositionType::Absolute,
+                width: Val::Percent(100.0),
+                height: Val::Percent(100.0),
+                flex_direction: FlexDirection::Column,
+                justify_content: JustifyContent::Center,
+                align_items: AlignItems::Center,
+                row_gap: Val::Px(8.0),
+                ..default()
+            },
+            BackgroundColor(Color::BLACK.with_alpha(0.5)),
+            GlobalZIndex(10),
+            Visibility::Hidden,
+        ))
+        .set_parent(*root)
+        .with_children(|parent| {
+            for (font_size, color) in [
+                (TITLE_FONT_SIZE, palette.ui_accent),
+                (LINE_FONT_SIZE, palette.ui_text),
+                (LINE_FONT_SIZE, palette.ui_text),
+                (LINE_FONT_SIZE, palette.ui_text),
+                (HINT_FONT_SIZE, palette.ui_text.with_alpha(0.6)),
+            ] {
+                parent.spawn((
+                    Text::new(""),
+                    TextFont {
+                        font_size,
+                        ..default()
+                    },
+                    TextColor(color),
+                ));
+            }
+        });
+}
+
+// frames everything that fell so far, the player included
+fn pile_bounds(rects: &Query<&ColliderAabb, With<FallingRect>>, player: Vec2) -> Rect {
+    rects.iter().fold(
+        Rect::from_center_size(player, Vec2::ZERO),
+        |bounds, aabb| bounds.union(Rect::from_corners(aabb.min, aabb.max)),
+    )
+}
+
+fn start_death_cam(
+    mut commands: Commands,
+    mut killed: EventReader<PlayerKilled>,
+    mut died: EventReader<PlayerDied>,
+    mut death_cam: ResMut<DeathCam>,
+    stats: Res<RunStats>,
+    rects: Query<&ColliderAabb, With<FallingRect>>,
+    meshes: Query<(&Mesh2d, &Transform)>,
+    player: Single<&Transform, With<Player>>,
+    camera: Single<(&Transform, &OrthographicProjection), With<Camera>>,
+    palette: Res<Palette>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+    mut next_state: ResMut<NextState<GameState>>,
+) {
+    // a kill plane hit lingers until the respawn, the run it ended is already over
+    if died.read().count() > 0 {
+        killed.clear();
+        return;
+    }
+    let Some(PlayerKilled { by }) = killed.read().last() else {
+        return;
+    };
+    let (camera_transform, projection) = camera.into_inner();
+    let bounds = pile_bounds(&rects, player.translation.truncate()).inflate(FRAME_MARGIN);
+    let view = projection.area.size() / projection.scale;
+    let fit = (bounds.size() / view).max_element();
+    *death_cam = DeathCam {
+        elapsed: 0.0,
+        camera_home: camera_transform.translation,
+        home_scale: projection.scale,
+        target: bounds.center().extend(camera_transform.translation.z),
+        target_scale: fit.clamp(projection.scale, MAX_ZOOM),
+        score: stats.score,
+        height: stats.max_height,
+        time_survived: stats.time_survived,
+    };
+    if let Some((rect, (mesh, transform))) =
+        by.and_then(|rect| Some((rect, meshes.get(rect).ok()?)))
+    {
+        // the rect's scale is its size, the outline lives in that unit space
+        let size = transform.scale.truncate().max(Vec2::ONE);
+        let scale = Vec2::ONE + 2.0 * HIGHLIGHT_WIDTH / size;
+        commands.entity(rect).with_child((
+            Name::new("KillerHighlight"),
+            KillerHighlight,
+            Mesh2d(mesh.0.clone()),
+            MeshMaterial2d(materials.add(palette.hazard)),
+            Transform::from_xyz(0.0, 0.0, -0.05).with_scale(scale.extend(1.0)),
+        ));
+    }
+    debug!(target: "player", "killed, death camera framing {bounds:?}");
+    next_state.set(GameState::DeathCam);
+}
+
+// zooms out over the pile once the kill cam is done, then leaves the results up. jump
+// skips either part
+fn run_death_cam(
+    mut commands: Commands,
+    mut death_cam: ResMut<DeathCam>,
+    actions: Res<ActionState>,
+    settings: Res<Settings>,
+    camera: Single<(&mut Transform, &mut OrthographicProjection), With<Camera>>,
+    highlights: Query<Entity, With<KillerHighlight>>,
+    mut died: EventWriter<PlayerDied>,
+    mut next_state: ResMut<NextState<GameState>>,
+    kill_cam: Res<KillCam>,
+    time: Res<Time<Real>>,
+) {
+    if kill_cam.is_playing() {
+        return;
+    }
+    let (mut transform, mut projection) = camera.into_inner();
+    death_cam.elapsed += time.delta_secs();
+    if actions.just_pressed(Action::Jump) {
+        if death_cam.elapsed < ZOOM_SECS {
+            death_cam.elapsed = ZOOM_SECS;
+        } else {
+            death_cam.elapsed = ZOOM_SECS + RESULTS_SECS;
+        }
+    }
+    if death_cam.elapsed >= ZOOM_SECS + RESULTS_SECS {
+        transform.translation = death_cam.camera_home;
+        projection.scale = death_cam.home_scale;
+        for entity in &highlights {
+            commands.entity(entity).despawn();
+        }
+        died.send(PlayerDied);
+        next_state.set(GameState::Playing);
+        return;
+    }
+    let t = if settings.reduce_motion {
+        1.0
+    } else {
+        (death_cam.elapsed / ZOOM_SECS).min(1.0)
+    };
+    let eased = t * t * (3.0 - 2.0 * t);
+    transform.translation = death_cam.camera_home.lerp(death_cam.target, eased);
+    projection.scale = death_cam.home_scale.lerp(death_cam.target_scale, eased);
+}
+
+fn pulse_highlight(
+    highlights: Query<&MeshMaterial2d<ColorMaterial>, With<KillerHighlight>>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+    time: Res<Time<Real>>,
+) {
+    let alpha = 0.6 + 0.4 * (time.elapsed_secs() * HIGHLIGHT_PULSE_PER_SEC).sin();
+    for material in &highlights {
+        if let Some(material) = materials.get_mut(material) {
+            material.color.set_alpha(alpha);
+        }
+    }
+}
+
+fn render_results(
+    death_cam: Res<DeathCam>,
+    localization: Res<Localization>,
+    results: Single<(&mut Visibility, &Children), With<Results>>,
+    mut texts: Query<&mut Text>,
+) {
+    if death_cam.elapsed < ZOOM_SECS {
+        return;
+    }
+    let (mut visibility, children) = results.into_inner();
+    if *visibility != Visibility::Inherited {
+        *visibility = Visibility::Inherited;
+    }
+    let minutes = (death_cam.time_survived / 60.0) as u32;
+    let seconds = death_cam.time_survived as u32 % 60;
+    let lines = [
+        localization.get("deathcam.title").to_string(),
+        localization.format("deathcam.score", &[("value", &death_cam.score)]),
+        localization.format("deathcam.height", &[("value", &(death_cam.height as u32))]),
+        localization.format(
+            "deathcam.time",
+            &[("minutes", &minutes), ("seconds", &format!("{seconds:02}"))],
+        ),
+        localization.get("deathcam.continue").to_string(),
+    ];
+    for (child, line) in children.iter().zip(lines) {
+        let Ok(mut text) = texts.get_mut(*child) else {
+            continue;
+        };
+        if text.0 != line {
+            text.0 = line;
+        }
+    }
+}
+
+fn hide_results(mut results: Single<&mut Visibility, With<Results>>) {
+    **results = Visibility::Hidden;
+}
+
+fn pause_time(mut time: ResMut<Time<Virtual>>) {
+    time.pause();
+}
+
+fn resume_time(mut time: ResMut<Time<Virtual>>) {
+    time.unpause();
+}
@@ -0,0 +1,131 @@
+use avian2d::prelude::*;
+use bevy::prelude::*;
+
+use crate::health::Health;
+use crate::score::RunStats;
+use crate::{GameSet, Player, PlayerDied};
+
+const IDLE_SPEED: f32 = 20.0;
+
+pub struct DirectorPlugin;
+
+impl Plugin for DirectorPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<DifficultyConfig>()
+            .init_resource::<Director>()
+            .add_systems(Update, direct_difficulty.in_set(GameSet::Reactions));
+    }
+}
+
+#[derive(Resource, Clone)]
+pub struct DifficultyConfig {
+    // seconds for the time based level to go up by one
+    pub ramp_secs: f32,
+    // bounds for the director's multiplier on top of the time based level
+    pub min_intensity: f32,
+    pub max_intensity: f32,
+    // px/s of height gained that counts as keeping up, faster than this gets harder
+    pub target_climb_rate: f32,
+    // standing still for this long puts full pressure on the player
+    pub idle_grace_secs: f32,
+    // how long getting hurt keeps easing things off
+    pub relief_secs: f32,
+    // how fast the intensity follows the player's performance, per second
+    pub adapt_rate: f32,
+}
+
+impl Default for DifficultyConfig {
+    fn default() -> DifficultyConfig {
+        DifficultyConfig {
+            ramp_secs: 60.0,
+            min_intensity: 0.7,
+            max_intensity: 1.5,
+            target_climb_rate: 15.0,
+            idle_grace_secs: 8.0,
+            relief_secs: 10.0,
+            adapt_rate: 0.2,
+        }
+    }
+}
+
+// watches how the run is going and scales the difficulty level by `intensity`
+#[derive(Resource)]
+pub struct Director {
+    pub intensity: f32,
+    // -1 is struggling, 1 is cruising
+    performance: f32,
+    climb_rate: f32,
+    last_height: f32,
+    idle_secs: f32,
+    relief: f32,
+    last_health: Option<u32>,
+}
+
+impl Default for Director {
+    fn default() -> Director {
+        Director {
+            intensity: 1.0,
+            performance: 0.0,
+            climb_rate: 0.0,
+            last_height: 0.0,
+            idle_secs: 0.0,
+            relief: 0.0,
+            last_health: None,
+        }
+    }
+}
+
+impl Director {
+    pub fn performance(&self) -> f32 {
+        self.performance
+    }
+}
+
+fn direct_difficulty(
+    config: Res<DifficultyConfig>,
+    mut director: ResMut<Director>,
+    mut died: EventReader<PlayerDied>,
+    stats: Res<RunStats>,
+    player: Single<(&LinearVelocity, &Health), With<Player>>,
+    time: Res<Time>,
+) {
+    if died.read().count() > 0 {
+        *director = Director::default();
+        return;
+    }
+    let delta_secs = time.delta_secs();
+    if delta_secs <= 0.0 {
+        return;
+    }
+    let (linear, health) = player.into_inner();
+    // smoothed over a few seconds so a single jump doesn't count as a great run
+    let climbed = (stats.max_height - director.last_height).max(0.0) / delta_secs;
+    director.last_height = stats.max_height;
+    director.climb_rate += (climbed - director.climb_rate) * (delta_secs / 5.0).min(1.0);
+    if linear.length() < IDLE_SPEED {
+        director.idle_secs += delta_secs;
+    } else {
+        director.idle_secs = 0.0;
+    }
+    if director
+        .last_health
+        .is_some_and(|last| health.current < last)
+    {
+        // a close call eases off harder than a regular hit
+        director.relief = if health.current <= 1 { 1.0 } else { 0.5 };
+        debug!(target: "spawner", "player got hurt, easing off");
+    }
+    director.last_health = Some(health.current);
+    director.relief = (director.relief - delta_secs / config.relief_secs).max(0.0);
+
+    let climbing = (director.climb_rate / config.target_climb_rate - 1.0).clamp(-1.0, 1.0);
+    let idling = (director.idle_secs / config.idle_grace_secs).min(1.0);
+    let target = (climbing * 0.5 + idling * 0.5 - director.relief).clamp(-1.0, 1.0);
+    let step = config.adapt_rate * delta_secs;
+    director.performance += (target - director.performance).clamp(-step, step);
+    director.intensity = if director.performance >= 0.0 {
+        1.0 + director.performance * (config.max_intensity - 1.0)
+    } else {
+        1.0 + director.performance * (1.0 - config.min_intensity)
+    };
+}
@@ -0,0 +1,117 @@
+use bevy::prelude::*;
+
+pub struct DiscordPlugin;
+
+// does nothing unless the game is built with `--features discord`
+impl Plugin for DiscordPlugin {
+    fn build(&self, _app: &mut App) {
+        #[cfg(feature = "discord")]
+        backend::build(_app);
+    }
+}
+
+#[cfg(feature = "discord")]
+mod backend {
+    use std::time::{SystemTime, UNIX_EPOCH};
+
+    use bevy::prelude::*;
+    use discord_rich_presence::activity::{Activity, Timestamps};
+    use discord_rich_presence::{DiscordIpc, DiscordIpcClient};
+
+    use crate::i18n::Localization;
+    use crate::save::SaveData;
+    use crate::score::RunStats;
+    use crate::settings::Settings;
+    use crate::spawner::Difficulty;
+
+    // discord drops updates sent more often than about every 4 seconds anyway
+    const UPDATE_INTERVAL_SECS: f32 = 15.0;
+
+    struct Presence {
+        client: DiscordIpcClient,
+        connected: bool,
+        shown: bool,
+    }
+
+    pub fn build(app: &mut App) {
+        // the application id comes from the discord developer portal at build time
+        let Some(client_id) = option_env!("RECTFALL_DISCORD_CLIENT_ID") else {
+            warn!("built with discord support but without RECTFALL_DISCORD_CLIENT_ID");
+            return;
+        };
+        app.insert_non_send_resource(Presence {
+            client: DiscordIpcClient::new(client_id),
+            connected: false,
+            shown: false,
+        })
+        .add_systems(Last, update_presence);
+    }
+
+    // discord may start or quit while the game runs, so a failed update just means
+    // trying to connect again next time
+    fn update_presence(
+        mut presence: NonSendMut<Presence>,
+        settings: Res<Settings>,
+        stats: Res<RunStats>,
+        difficulty: Res<Difficulty>,
+        save: Res<SaveData>,
+        localization: Res<Localization>,
+        time: Res<Time<Real>>,
+        mut last_update: Local<Option<f32>>,
+    ) {
+        let now = time.elapsed_secs();
+        let due = last_update.is_none_or(|last| now - last >= UPDATE_INTERVAL_SECS);
+        if !due && !settings.is_changed() {
+            return;
+        }
+        *last_update = Some(now);
+        if !settings.discord_presence {
+            if presence.shown && presence.client.clear_activity().is_ok() {
+                presence.shown = false;
+            }
+            return;
+        }
+        if !presence.connected {
+            if let Err(err) = presence.client.connect() {
+                debug!("discord isn't reachable: {err}");
+                return;
+            }
+            presence.connected = true;
+        }
+        let mode = localization.get(if settings.line_clear {
+            "discord.mode_line_clear"
+        } else {
+            "discord.mode_classic"
+        });
+        let details = localization.format(
+            "discord.details",
+            &[
+                ("mode", &mode),
+                ("level", &format!("{:.1}", difficulty.level)),
+            ],
+        );
+        let state = localization.format(
+            "discord.state",
+            &[
+                ("height", &format!("{:.0}", stats.max_height)),
+                ("best", &format!("{:.0}", save.stats.best_height)),
+            ],
+        );
+        let unix_millis = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |since| since.as_millis() as i64);
+        let started = unix_millis - (stats.time_survived * 1000.0) as i64;
+        let activity = Activity::new()
+            .details(details)
+            .state(state)
+            .timestamps(Timestamps::new().start(started));
+        match presence.client.set_activity(activity) {
+            Ok(()) => presence.shown = true,
+            Err(err) => {
+                debug!("lost discord: {err}");
+                presence.connected = false;
+                presence.shown = false;
+            }
+        }
+    }
+}
@@ -0,0 +1,736 @@
+use std::collections::BTreeMap;
+
+use bevy::input::keyboard::{Key, KeyboardInput};
+use bevy::input::ButtonState;
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::clipboard::Clipboard;
+use crate::console::{Console, ConsoleAppExt, ConsoleCommand};
+use crate::free_camera::FreeCamera;
+use crate::hud::ShowToast;
+use crate::i18n::Localization;
+use crate::input::{Action, ActionSet, ActionState};
+use crate::level::{spawn_level_piece, LevelPiece, Placed};
+use crate::palette::Palette;
+use crate::prefab::Prefabs;
+use crate::score::RunStats;
+use crate::storage::{Persistent, StorageAppExt};
+use crate::ui_layout::UiRoot;
+use crate::GameSet;
+
+const PANEL_WIDTH: f32 = 300.0;
+const TITLE_FONT_SIZE: f32 = 20.0;
+const ROW_FONT_SIZE: f32 = 16.0;
+const HINT_FONT_SIZE: f32 = 14.0;
+// the search narrows a long level down to these
+const MAX_ROWS: usize = 24;
+const MAX_LINE_COPIES: usize = 50;
+// framing leaves this much of the view around the piece
+const FRAME_MARGIN: f32 = 1.5;
+// every paste of the same copy lands this much further along
+const PASTE_OFFSET: Vec2 = Vec2::new(40.0, -40.0);
+const CONTROL_KEYS: [KeyCode; 4] = [
+    KeyCode::ControlLeft,
+    KeyCode::ControlRight,
+    KeyCode::SuperLeft,
+    KeyCode::SuperRight,
+];
+
+pub struct EditorPlugin;
+
+impl Plugin for EditorPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Editor>()
+            .add_persistent_resource::<PrefabGroups>()
+            .add_console_command("editor", "editor")
+            .add_console_command("group", "group [save|stamp|delete <name>]")
+            .add_systems(Startup, spawn_editor_panel)
+            .add_systems(
+                PreUpdate,
+                hold_actions
+                    .after(ActionSet::Assist)
+                    .run_if(|editor: Res<Editor>| editor.open),
+            )
+            .add_systems(
+                Update,
+                (
+                    (toggle_editor, run_group_command),
+                    (
+                        type_search,
+                        copy_paste,
+                        click_rows,
+                        click_group_rows,
+                        click_tools,
+                        render_editor_panel,
+                        render_group_list,
+                        outline_selection,
+                    )
+                        .chain()
+                        .run_if(|editor: Res<Editor>| editor.open),
+                )
+                    .chain()
+                    .in_set(GameSet::Input),
+            );
+    }
+}
+
+// a sidebar over the loaded level's pieces. the level is paused while it's open and the
+// free camera is on for getting around
+#[derive(Resource, Default)]
+struct Editor {
+    open: bool,
+    search: String,
+    // in the order they were picked, the tools line things up with the first one
+    selected: Vec<Entity>,
+    free_camera_before: bool,
+    // where the pieces were when they were copied
+    copied: Vec<LevelPiece>,
+    pastes: u32,
+}
+
+// selections saved for stamping into any level, as level pieces around the group's
+// center
+#[derive(Resource, Serialize, Deserialize, Default)]
+#[serde(default)]
+struct PrefabGroups {
+    groups: BTreeMap<String, Vec<LevelPiece>>,
+}
+
+impl Persistent for PrefabGroups {
+    const KEY: &'static str = "prefab_groups";
+    const VERSION: u32 = 1;
+}
+
+#[derive(Component, Clone, Copy, PartialEq, Eq)]
+enum EditorTool {
+    AlignX,
+    AlignY,
+    Distribute,
+    FillLine,
+}
+
+impl EditorTool {
+    const ALL: [EditorTool; 4] = [
+        EditorTool::AlignX,
+        EditorTool::AlignY,
+        EditorTool::Distribute,
+        EditorTool::FillLine,
+    ];
+
+    fn key(self) -> &'static str {
+        match self {
+            EditorTool::AlignX => "editor.align_x",
+            EditorTool::AlignY => "editor.align_y",
+            EditorTool::Distribute => "editor.distribute",
+            EditorTool::FillLine => "editor.fill_line",
+        }
+    }
+}
+
+#[derive(Component)]
+struct EditorPanel;
+
+#[derive(Component)]
+struct SearchText;
+
+#[derive(Component)]
+struct HintText;
+
+#[derive(Component)]
+struct EditorList;
+
+#[derive(Component)]
+struct EditorRow(Entity);
+
+#[derive(Component)]
+struct GroupsText;
+
+#[derive(Component)]
+struct GroupList;
+
+#[derive(Component)]
+struct GroupRow(String);
+
+fn spawn_editor_panel(
+    mut commands: Commands,
+    palette: Res<Palette>,
+    root: Single<Entity, With<UiRoot>>,
+) {
+    commands
+        .spawn((
+            Name::new("EditorPanel"),
+            EditorPanel,
+            Node {
+                position_type: PositionType::Absolute,
+                width: Val::Px(PANEL_WIDTH),
+                height: Val::Percent(100.0),
+                flex_direction: FlexDirection::Column,
+                padding: UiRect::all(Val::Px(12.0)),
+                row_gap: Val::Px(6.0),
+                overflow: Overflow::clip(),
+                ..default()
+            },
+            BackgroundColor(Color::BLACK.with_alpha(0.75)),
+            GlobalZIndex(9),
+            Visibility::Hidden,
+            // tracked so the camera knows the cursor is over the panel
+            Interaction::default(),
+        ))
+        .set_parent(*root)
+        .with_children(|parent| {
+            parent.spawn((
+                SearchText,
+                Text::new(""),
+                TextFont {
+                    font_size: TITLE_FONT_SIZE,
+                    ..default()
+                },
+                TextColor(palette.ui_accent),
+            ));
+            parent
+                .spawn(Node {
+                    flex_wrap: FlexWrap::Wrap,
+                    column_gap: Val::Px(10.0),
+                    row_gap: Val::Px(4.0),
+                    ..default()
+                })
+                .with_children(|tools| {
+                    for tool in EditorTool::ALL {
+                        tools.spawn((
+                            Button,
+                            tool,
+                            Text::new(""),
+                            TextFont {
+                                font_size: ROW_FONT_SIZE,
+                                ..default()
+                            },
+                            TextColor(palette.ui_accent),
+                        ));
+                    }
+                });
+            parent.spawn((
+                EditorList,
+                Node {
+                    flex_direction: FlexDirection::Column,
+                    row_gap: Val::Px(2.0),
+                    ..default()
+                },
+            ));
+            parent.spawn((
+                GroupsText,
+                Text::new(""),
+                TextFont {
+                    font_size: ROW_FONT_SIZE,
+                    ..default()
+                },
+                TextColor(palette.ui_text.with_alpha(0.6)),
+                Node {
+                    margin: UiRect::top(Val::Px(8.0)),
+                    ..default()
+                },
+            ));
+            parent.spawn((
+                GroupList,
+                Node {
+                    flex_direction: FlexDirection::Column,
+                    row_gap: Val::Px(2.0),
+                    ..default()
+                },
+            ));
+            parent.spawn((
+                HintText,
+                Text::new(""),
+                TextFont {
+                    font_size: HINT_FONT_SIZE,
+                    ..default()
+                },
+                TextColor(palette.ui_text.with_alpha(0.6)),
+            ));
+        });
+}
+
+fn toggle_editor(
+    mut commands: EventReader<ConsoleCommand>,
+    mut console: ResMut<Console>,
+    mut editor: ResMut<Editor>,
+    mut free_camera: ResMut<FreeCamera>,
+    mut time: ResMut<Time<Virtual>>,
+    mut panel: Single<&mut Visibility, With<EditorPanel>>,
+    mut stats: ResMut<RunStats>,
+) {
+    for _ in commands.read().filter(|command| command.name == "editor") {
+        editor.open = !editor.open;
+        if editor.open {
+            // closing it goes back to the same run, with whatever was placed or stamped in it
+            stats.assisted = true;
+            editor.free_camera_before = free_camera.0;
+            free_camera.0 = true;
+            console.print("editor open, the level is paused");
+        } else {
+            free_camera.0 = editor.free_camera_before;
+            editor.selected.clear();
+            time.unpause();
+            console.print("editor closed");
+        }
+        **panel = if editor.open {
+            Visibility::Inherited
+        } else {
+            Visibility::Hidden
+        };
+    }
+    // the pause menu unpauses on its way out
+    if editor.open && !time.is_paused() {
+        time.pause();
+    }
+}
+
+fn hold_actions(mut actions: ResMut<ActionState>) {
+    for action in Action::ALL {
+        actions.release(action);
+    }
+}
+
+fn type_search(
+    mut keyboard_events: EventReader<KeyboardInput>,
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    console: Res<Console>,
+    mut editor: ResMut<Editor>,
+) {
+    // shortcuts aren't typing
+    if console.open || keyboard_input.any_pressed(CONTROL_KEYS) {
+        keyboard_events.clear();
+        return;
+    }
+    for event in keyboard_events.read() {
+        if event.state != ButtonState::Pressed || event.key_code == KeyCode::Backquote {
+            continue;
+        }
+        match &event.logical_key {
+            Key::Character(text) => editor.search.push_str(text),
+            Key::Space => editor.search.push(' '),
+            Key::Backspace => {
+                editor.search.pop();
+            }
+            _ => {}
+        }
+    }
+}
+
+// the name and tags, which is also what the search looks through
+fn piece_label(name: &Name, placed: &Placed) -> String {
+    let mut label = name.as_str().to_string();
+    for tag in &placed.0.tags {
+        label.push_str(" #");
+        label.push_str(tag);
+    }
+    label
+}
+
+// the pieces as they are now, wherever they've been moved to
+fn selected_pieces(selected: &[Entity], pieces: &Query<(&Transform, &Placed)>) -> Vec<LevelPiece> {
+    selected
+        .iter()
+        .filter_map(|entity| {
+            let (transform, placed) = pieces.get(*entity).ok()?;
+            Some(LevelPiece {
+                position: transform.translation.truncate().into(),
+                ..placed.0.clone()
+            })
+        })
+        .collect()
+}
+
+fn stamp(
+    commands: &mut Commands,
+    prefabs: &Prefabs,
+    pieces: &[LevelPiece],
+    offset: Vec2,
+) -> Vec<Entity> {
+    pieces
+        .iter()
+        .filter_map(|piece| {
+            let position = Vec2::from(piece.position) + offset;
+            let piece = LevelPiece {
+                position: position.into(),
+                ..piece.clone()
+            };
+            spawn_level_piece(commands, prefabs, &piece)
+        })
+        .collect()
+}
+
+// ctrl+c also puts the pieces on the system clipboard, written the way a level lists
+// them so they can go straight into a level file. the pasted copies become the selection
+fn copy_paste(
+    mut commands: Commands,
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    console: Res<Console>,
+    mut editor: ResMut<Editor>,
+    pieces: Query<(&Transform, &Placed)>,
+    prefabs: Res<Prefabs>,
+    localization: Res<Localization>,
+    mut toasts: EventWriter<ShowToast>,
+    mut clipboard: Local<Clipboard>,
+) {
+    if console.open || !keyboard_input.any_pressed(CONTROL_KEYS) {
+        return;
+    }
+    if keyboard_input.just_pressed(KeyCode::KeyC) {
+        let copied = selected_pieces(&editor.selected, &pieces);
+        if copied.is_empty() {
+            return;
+        }
+        match ron::ser::to_string_pretty(&copied, default()) {
+            Ok(text) => clipboard.copy(text),
+            Err(err) => error!("couldn't copy level pieces: {err}"),
+        }
+        toasts.send(ShowToast(
+            localization.format("editor.copied", &[("count", &copied.len())]),
+        ));
+        editor.copied = copied;
+        editor.pastes = 0;
+    }
+    if keyboard_input.just_pressed(KeyCode::KeyV) && !editor.copied.is_empty() {
+        editor.pastes += 1;
+        let offset = PASTE_OFFSET * editor.pastes as f32;
+        editor.selected = stamp(&mut commands, &prefabs, &editor.copied, offset);
+    }
+}
+
+fn run_group_command(
+    mut commands: Commands,
+    mut console_commands: EventReader<ConsoleCommand>,
+    mut console: ResMut<Console>,
+    mut groups: ResMut<PrefabGroups>,
+    mut editor: ResMut<Editor>,
+    pieces: Query<(&Transform, &Placed)>,
+    prefabs: Res<Prefabs>,
+    camera: Single<&Transform, With<Camera>>,
+) {
+    for command in console_commands
+        .read()
+        .filter(|command| command.name == "group")
+    {
+        let args: Vec<&str> = command.args.iter().map(String::as_str).collect();
+        match args[..] {
+            ["save", name] => {
+                let selected = selected_pieces(&editor.selected, &pieces);
+                let Some(bounds) = selected
+                    .iter()
+                    .map(|piece| Rect::from_center_size(piece.position.into(), Vec2::ZERO))
+                    .reduce(|bounds, rect| bounds.union(rect))
+                else {
+                    console.print("pick some pieces in the editor first");
+                    continue;
+                };
+                let center = bounds.center();
+                let group: Vec<LevelPiece> = selected
+                    .into_iter()
+                    .map(|piece| LevelPiece {
+                        position: (Vec2::from(piece.position) - center).into(),
+                        ..piece
+                    })
+                    .collect();
+                console.print(format!("saved group {name}, {} pieces", group.len()));
+                groups.groups.insert(name.to_string(), group);
+            }
+            ["stamp", name] => {
+                let Some(group) = groups.groups.get(name) else {
+                    console.print(format!("no group called {name}"));
+                    continue;
+                };
+                let center = camera.translation.truncate();
+                editor.selected = stamp(&mut commands, &prefabs, group, center);
+            }
+            ["delete", name] => {
+                if groups.groups.remove(name).is_some() {
+                    console.print(format!("deleted group {name}"));
+                } else {
+                    console.print(format!("no group called {name}"));
+                }
+            }
+            _ => {
+                let names: Vec<&str> = groups.groups.keys().map(String::as_str).collect();
+                console.print(format!("groups: {}", names.join(", ")));
+            }
+        }
+    }
+}
+
+// a click on a group stamps it in the middle of the view
+fn click_group_rows(
+    mut commands: Commands,
+    rows: Query<(&Interaction, &GroupRow), Changed<Interaction>>,
+    groups: Res<PrefabGroups>,
+    mut editor: ResMut<Editor>,
+    prefabs: Res<Prefabs>,
+    camera: Single<&Transform, With<Camera>>,
+) {
+    for (interaction, row) in &rows {
+        if *interaction != Interaction::Pressed {
+            continue;
+        }
+        if let Some(group) = groups.groups.get(&row.0) {
+            let center = camera.translation.truncate();
+            editor.selected = stamp(&mut commands, &prefabs, group, center);
+        }
+    }
+}
+
+// a click picks the piece and moves the camera to it, shift-click adds it to the
+// selection or takes it back out
+fn click_rows(
+    rows: Query<(&Interaction, &EditorRow), Changed<Interaction>>,
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    mut editor: ResMut<Editor>,
+    pieces: Query<&Transform, (With<Placed>, Without<Camera>)>,
+    camera: Single<(&mut Transform, &mut OrthographicProjection), With<Camera>>,
+) {
+    let (mut camera_transform, mut projection) = camera.into_inner();
+    for (interaction, row) in &rows {
+        if *interaction != Interaction::Pressed {
+            continue;
+        }
+        if keyboard_input.any_pressed([KeyCode::ShiftLeft, KeyCode::ShiftRight]) {
+            match editor.selected.iter().position(|entity| *entity == row.0) {
+                Some(index) => {
+                    editor.selected.remove(index);
+                }
+                None => editor.selected.push(row.0),
+            }
+            continue;
+        }
+        editor.selected = vec![row.0];
+        let Ok(transform) = pieces.get(row.0) else {
+            continue;
+        };
+        camera_transform.translation = transform
+            .translation
+            .truncate()
+            .extend(camera_transform.translation.z);
+        // pieces scale with their size
+        let view = projection.area.size() / projection.scale;
+        let fit = (transform.scale.truncate() * FRAME_MARGIN / view).max_element();
+        projection.scale = projection.scale.max(fit);
+    }
+}
+
+fn click_tools(
+    mut commands: Commands,
+    tools: Query<(&Interaction, &EditorTool), Changed<Interaction>>,
+    editor: Res<Editor>,
+    mut pieces: Query<(&mut Transform, &Placed)>,
+    prefabs: Res<Prefabs>,
+) {
+    for (interaction, tool) in &tools {
+        if *interaction != Interaction::Pressed {
+            continue;
+        }
+        let mut positions: Vec<(Entity, Vec2)> = editor
+            .selected
+            .iter()
+            .filter_map(|entity| {
+                let (transform, _) = pieces.get(*entity).ok()?;
+                Some((*entity, transform.translation.truncate()))
+            })
+            .collect();
+        let Some(&(first, first_position)) = positions.first() else {
+            continue;
+        };
+        let mut moved: Vec<(Entity, Vec2)> = Vec::new();
+        match tool {
+            EditorTool::AlignX => {
+                for (entity, position) in &positions[1..] {
+                    moved.push((*entity, Vec2::new(first_position.x, position.y)));
+                }
+            }
+            EditorTool::AlignY => {
+                for (entity, position) in &positions[1..] {
+                    moved.push((*entity, Vec2::new(position.x, first_position.y)));
+                }
+            }
+            // even gaps between the two furthest apart, along whichever axis they're
+            // more spread out on
+            EditorTool::Distribute => {
+                if positions.len() < 3 {
+                    continue;
+                }
+                let (min, max) = positions.iter().fold(
+                    (Vec2::splat(f32::MAX), Vec2::splat(f32::MIN)),
+                    |(min, max), (_, position)| (min.min(*position), max.max(*position)),
+                );
+                let axis = if max.x - min.x >= max.y - min.y { 0 } else { 1 };
+                positions.sort_by(|(_, a), (_, b)| a[axis].total_cmp(&b[axis]));
+                let step = (max[axis] - min[axis]) / (positions.len() - 1) as f32;
+                for (index, (entity, position)) in positions.iter().enumerate() {
+                    let mut position = *position;
+                    position[axis] = min[axis] + step * index as f32;
+                    moved.push((*entity, position));
+                }
+            }
+            // copies of the first piece, end to end, up to the second one
+            EditorTool::FillLine => {
+                let Some(&(_, to)) = positions.get(1) else {
+                    continue;
+                };
+                let Ok((transform, placed)) = pieces.get(first) else {
+                    continue;
+                };
+                let Ok(direction) = Dir2::new(to - first_position) else {
+                    continue;
+                };
+                let size = transform.scale.truncate();
+                let extent = direction.x.abs() * size.x + direction.y.abs() * size.y;
+                let copies = ((first_position.distance(to) / extent.max(1.0)) as usize)
+                    .saturating_sub(1)
+                    .min(MAX_LINE_COPIES);
+                let mut piece = placed.0.clone();
+                for index in 1..=copies {
+                    let position = first_position + *direction * extent * index as f32;
+                    piece.position = position.into();
+                    spawn_level_piece(&mut commands, &prefabs, &piece);
+                }
+            }
+        }
+        for (entity, position) in moved {
+            if let Ok((mut transform, _)) = pieces.get_mut(entity) {
+                transform.translation = position.extend(transform.translation.z);
+            }
+        }
+    }
+}
+
+fn set_text(text: &mut Text, value: String) {
+    if text.0 != value {
+        text.0 = value;
+    }
+}
+
+fn render_editor_panel(
+    mut commands: Commands,
+    mut editor: ResMut<Editor>,
+    pieces: Query<(Entity, &Name, &Placed)>,
+    palette: Res<Palette>,
+    localization: Res<Localization>,
+    list: Single<Entity, With<EditorList>>,
+    mut search_text: Single<&mut Text, (With<SearchText>, Without<HintText>)>,
+    mut hint_text: Single<&mut Text, (With<HintText>, Without<SearchText>)>,
+    mut tools: Query<(&EditorTool, &mut Text), (Without<SearchText>, Without<HintText>)>,
+    mut shown: Local<Vec<(Entity, String, bool)>>,
+) {
+    // pieces go away with the level they belong to
+    editor.selected.retain(|entity| pieces.contains(*entity));
+    let search = editor.search.to_lowercase();
+    let mut matches: Vec<(Entity, String)> = pieces
+        .iter()
+        .map(|(entity, name, placed)| (entity, piece_label(name, placed)))
+        .filter(|(_, label)| label.to_lowercase().contains(&search))
+        .collect();
+    matches.sort_by(|(a_entity, a), (b_entity, b)| a.cmp(b).then(a_entity.cmp(b_entity)));
+    set_text(
+        &mut search_text,
+        localization.format(
+            "editor.search",
+            &[
+                ("query", &editor.search),
+                ("shown", &matches.len()),
+                ("total", &pieces.iter().len()),
+            ],
+        ),
+    );
+    set_text(&mut hint_text, localization.get("editor.hint").to_string());
+    for (tool, mut text) in &mut tools {
+        set_text(&mut text, localization.get(tool.key()).to_string());
+    }
+    let rows: Vec<(Entity, String, bool)> = matches
+        .into_iter()
+        .take(MAX_ROWS)
+        .map(|(entity, label)| (entity, label, editor.selected.contains(&entity)))
+        .collect();
+    if *shown == rows && !palette.is_changed() {
+        return;
+    }
+    commands
+        .entity(*list)
+        .despawn_descendants()
+        .with_children(|parent| {
+            for (entity, label, selected) in &rows {
+                let color = if *selected {
+                    palette.ui_accent
+                } else {
+                    palette.ui_text
+                };
+                parent.spawn((
+                    Button,
+                    EditorRow(*entity),
+                    Text::new(label.clone()),
+                    TextFont {
+                        font_size: ROW_FONT_SIZE,
+                        ..default()
+                    },
+                    TextColor(color),
+                ));
+            }
+        });
+    *shown = rows;
+}
+
+fn render_group_list(
+    mut commands: Commands,
+    groups: Res<PrefabGroups>,
+    palette: Res<Palette>,
+    localization: Res<Localization>,
+    list: Single<Entity, With<GroupList>>,
+    mut heading: Single<&mut Text, With<GroupsText>>,
+    mut shown: Local<Option<Vec<String>>>,
+) {
+    set_text(&mut heading, localization.get("editor.groups").to_string());
+    let names: Vec<String> = groups.groups.keys().cloned().collect();
+    if shown.as_ref() == Some(&names) && !palette.is_changed() {
+        return;
+    }
+    commands
+        .entity(*list)
+        .despawn_descendants()
+        .with_children(|parent| {
+            for name in &names {
+                parent.spawn((
+                    Button,
+                    GroupRow(name.clone()),
+                    Text::new(name.clone()),
+                    TextFont {
+                        font_size: ROW_FONT_SIZE,
+                        ..default()
+                    },
+                    TextColor(palette.ui_text),
+                ));
+            }
+        });
+    *shown = Some(names);
+}
+
+fn outline_selection(
+    editor: Res<Editor>,
+    pieces: Query<&Transform, With<Placed>>,
+    palette: Res<Palette>,
+    mut gizmos: Gizmos,
+) {
+    for (index, entity) in editor.selected.iter().enumerate() {
+        let Ok(transform) = pieces.get(*entity) else {
+            continue;
+        };
+        // the first one picked is what the tools go by
+        let color = if index == 0 {
+            palette.ui_accent
+        } else {
+            palette.ui_text
+        };
+        let (angle, _, _) = transform.rotation.to_euler(EulerRot::ZYX);
+        gizmos.rect_2d(
+            Isometry2d::new(transform.translation.truncate(), Rot2::radians(angle)),
+            transform.scale.truncate(),
+            color,
+        );
+    }
+}
@@ -0,0 +1,178 @@
+use avian2d::prelude::*;
+use bevy::prelude::*;
+use bevy::utils::HashSet;
+
+use crate::collision_layer::{mask, GameLayer};
+use crate::common_assets::CommonAssets;
+use crate::prefab::{add_inset, spawn_falling_rect, PrefabAppExt, PrefabParams};
+use crate::settings::Settings;
+use crate::spawner::WeightClass;
+use crate::z_layer::ZLayer;
+use crate::GameSet;
+
+const FUSE_SECS: f32 = 4.0;
+// bombs caught in another blast go off shortly after, so chains ripple outwards
+const CHAIN_FUSE_SECS: f32 = 0.15;
+const IMPACT_SPEED: f32 = 700.0;
+const EXPLOSION_RADIUS: f32 = 250.0;
+// impulses scale with mass, a 100x100 rect at density 1 weighs 10000
+const EXPLOSION_IMPULSE: f32 = 8_000_000.0;
+const FLASH_LIFETIME_SECS: f32 = 0.3;
+
+pub struct ExplosionPlugin;
+
+impl Plugin for ExplosionPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<Explosion>()
+            .register_prefab("bomb", spawn_bomb)
+            .add_systems(
+                Update,
+                (detonate_bombs, apply_explosions)
+                    .chain()
+                    .in_set(GameSet::Reactions),
+            )
+            .add_systems(Update, update_flashes.in_set(GameSet::Vfx));
+    }
+}
+
+#[derive(Component)]
+pub struct Bomb {
+    fuse: f32,
+    // speed during the previous frame, the collision has already slowed it down
+    last_speed: f32,
+}
+
+// destroyed outright by explosions instead of being pushed
+#[derive(Component)]
+pub struct Breakable;
+
+#[derive(Event)]
+pub struct Explosion {
+    pub position: Vec2,
+    pub radius: f32,
+}
+
+#[derive(Component)]
+struct ExplosionFlash {
+    radius: f32,
+    age: f32,
+}
+
+fn spawn_bomb(commands: &mut Commands, params: &PrefabParams) -> Entity {
+    let params = PrefabParams {
+        size: params.size.or(Some(Vec2::splat(80.0))),
+        ..*params
+    };
+    let entity = spawn_falling_rect(commands, &params, WeightClass::Normal);
+    commands.entity(entity).insert((
+        Name::new("Bomb"),
+        Bomb {
+            fuse: FUSE_SECS,
+            last_speed: 0.0,
+        },
+    ));
+    add_inset(commands, entity, 0.5, |assets| &assets.hazard);
+    entity
+}
+
+fn detonate_bombs(
+    mut commands: Commands,
+    mut collisions: EventReader<CollisionStarted>,
+    mut bombs: Query<(Entity, &mut Bomb, &Transform, &LinearVelocity)>,
+    mut explosions: EventWriter<Explosion>,
+    time: Res<Time>,
+) {
+    let hit: HashSet<Entity> = collisions
+        .read()
+        .flat_map(|CollisionStarted(a, b)| [*a, *b])
+        .collect();
+    for (entity, mut bomb, transform, linear) in &mut bombs {
+        bomb.fuse -= time.delta_secs();
+        let hard_impact = hit.contains(&entity) && bomb.last_speed >= IMPACT_SPEED;
+        bomb.last_speed = linear.length();
+        if bomb.fuse > 0.0 && !hard_impact {
+            continue;
+        }
+        let position = transform.translation.truncate();
+        debug!(target: "physics", "bomb exploded at {position}");
+        explosions.send(Explosion {
+            position,
+            radius: EXPLOSION_RADIUS,
+        });
+        commands.entity(entity).despawn_recursive();
+    }
+}
+
+// pushes every dynamic body in range away from the blast, weaker towards the edge
+fn apply_explosions(
+    mut commands: Commands,
+    mut explosions: EventReader<Explosion>,
+    spatial_query: SpatialQuery,
+    bodies: Query<(&Transform, &RigidBody)>,
+    breakables: Query<(), With<Breakable>>,
+    mut bombs: Query<&mut Bomb>,
+    assets: Res<CommonAssets>,
+    settings: Res<Settings>,
+) {
+    for explosion in explosions.read() {
+        let blast = Collider::circle(explosion.radius);
+        let caught = spatial_query.shape_intersections(
+            &blast,
+            explosion.position,
+            0.0,
+            &SpatialQueryFilter::from_mask(mask(&[GameLayer::Player, GameLayer::FallingRect])),
+        );
+        for entity in caught {
+            let Ok((transform, body)) = bodies.get(entity) else {
+                continue;
+            };
+            if breakables.contains(entity) {
+                commands.entity(entity).despawn_recursive();
+                continue;
+            }
+            if let Ok(mut bomb) = bombs.get_mut(entity) {
+                bomb.fuse = bomb.fuse.min(CHAIN_FUSE_SECS);
+            }
+            if !body.is_dynamic() {
+                continue;
+            }
+            let offset = transform.translation.truncate() - explosion.position;
+            let falloff = (1.0 - offset.length() / explosion.radius).clamp(0.0, 1.0);
+            let impulse = offset.normalize_or(Vec2::Y) * EXPLOSION_IMPULSE * falloff;
+            commands
+                .entity(entity)
+                .insert(ExternalImpulse::new(impulse));
+        }
+        if !settings.reduce_flashing {
+            commands.spawn((
+                Name::new("ExplosionFlash"),
+                ZLayer::Particles,
+                ExplosionFlash {
+                    radius: explosion.radius,
+                    age: 0.0,
+                },
+                Mesh2d(assets.circle.clone()),
+                MeshMaterial2d(assets.marker.clone()),
+                Transform::from_translation(explosion.position.extend(0.0)),
+            ));
+        }
+    }
+}
+
+fn update_flashes(
+    mut commands: Commands,
+    mut flashes: Query<(Entity, &mut ExplosionFlash, &mut Transform)>,
+    time: Res<Time>,
+) {
+    for (entity, mut flash, mut transform) in &mut flashes {
+        flash.age += time.delta_secs();
+        if flash.age >= FLASH_LIFETIME_SECS {
+            commands.entity(entity).despawn();
+            continue;
+        }
+        // grows out to the blast radius, then shrinks away
+        let t = flash.age / FLASH_LIFETIME_SECS;
+        let size = flash.radius * 2.0 * (1.0 - (2.0 * t - 1.0).abs());
+        transform.scale = Vec3::splat(size);
+    }
+}
@@ -0,0 +1,64 @@
+use std::time::Duration;
+
+use bevy::prelude::*;
+use bevy::window::{WindowFocused, WindowOccluded};
+use bevy::winit::{UpdateMode, WinitSettings};
+
+use crate::menu::{GameState, MenuPage, MenuSelection};
+use crate::settings::Settings;
+
+// how often an unfocused window still redraws, enough to keep the pause menu responsive
+const UNFOCUSED_FRAME_SECS: f64 = 1.0 / 10.0;
+
+pub struct FocusPlugin;
+
+impl Plugin for FocusPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            Update,
+            (
+                pause_on_focus_loss.run_if(in_state(GameState::Playing)),
+                throttle_when_unfocused,
+            ),
+        );
+    }
+}
+
+// alt-tabbing away or hiding the browser tab opens the pause menu
+fn pause_on_focus_loss(
+    mut focused: EventReader<WindowFocused>,
+    mut occluded: EventReader<WindowOccluded>,
+    settings: Res<Settings>,
+    mut page: ResMut<MenuPage>,
+    mut selection: ResMut<MenuSelection>,
+    mut next_state: ResMut<NextState<GameState>>,
+) {
+    let lost_focus = focused.read().any(|event| !event.focused);
+    let hidden = occluded.read().any(|event| event.occluded);
+    if !settings.pause_on_focus_loss || !(lost_focus || hidden) {
+        return;
+    }
+    debug!("window lost focus, pausing");
+    *page = MenuPage::Main;
+    selection.0 = 0;
+    next_state.set(GameState::Paused);
+}
+
+// with auto-pause off the game keeps running in the background, so it keeps its frame
+// rate too
+fn throttle_when_unfocused(settings: Res<Settings>, winit: Option<ResMut<WinitSettings>>) {
+    let Some(mut winit) = winit else {
+        return;
+    };
+    if !settings.is_changed() && !winit.is_added() {
+        return;
+    }
+    let unfocused_mode = if settings.pause_on_focus_loss {
+        UpdateMode::reactive_low_power(Duration::from_secs_f64(UNFOCUSED_FRAME_SECS))
+    } else {
+        UpdateMode::Continuous
+    };
+    if winit.unfocused_mode != unfocused_mode {
+        winit.unfocused_mode = unfocused_mode;
+    }
+}
@@ -0,0 +1,54 @@
+use bevy::prelude::*;
+
+use crate::console::{Console, ConsoleAppExt, ConsoleCommand};
+use crate::hud::ShowToast;
+use crate::i18n::Localization;
+use crate::input::{Action, ActionSet, ActionState};
+use crate::GameSet;
+
+// the actions the arrow keys are bound to by default, held back while they pan
+const HELD_ACTIONS: [Action; 4] = [Action::Left, Action::Right, Action::Jump, Action::Down];
+
+pub struct FreeCameraPlugin;
+
+impl Plugin for FreeCameraPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<FreeCamera>()
+            .add_console_command("freecam", "freecam")
+            .add_systems(Update, toggle_free_camera.in_set(GameSet::Input))
+            .add_systems(PreUpdate, hold_player.after(ActionSet::Assist));
+    }
+}
+
+// for looking around the level: move_camera pans with the window's edges and the arrow
+// keys while it's on, and the player stands still
+#[derive(Resource, Default)]
+pub struct FreeCamera(pub bool);
+
+fn toggle_free_camera(
+    mut commands: EventReader<ConsoleCommand>,
+    mut console: ResMut<Console>,
+    mut free_camera: ResMut<FreeCamera>,
+    localization: Res<Localization>,
+    mut toasts: EventWriter<ShowToast>,
+) {
+    for _ in commands.read().filter(|command| command.name == "freecam") {
+        free_camera.0 = !free_camera.0;
+        let key = if free_camera.0 {
+            "hud.free_camera_on"
+        } else {
+            "hud.free_camera_off"
+        };
+        console.print(localization.get(key));
+        toasts.send(ShowToast(localization.get(key).to_string()));
+    }
+}
+
+fn hold_player(free_camera: Res<FreeCamera>, mut actions: ResMut<ActionState>) {
+    if !free_camera.0 {
+        return;
+    }
+    for action in HELD_ACTIONS {
+        actions.release(action);
+    }
+}
@@ -0,0 +1,166 @@
+use avian2d::prelude::*;
+use bevy::prelude::*;
+
+use crate::common_assets::CommonAssets;
+use crate::input::{Action, ActionState};
+use crate::settings::Settings;
+use crate::spawner::FallingRect;
+use crate::{GameSet, Player, PlayerDied};
+
+// from the player to the edge of a rect's collider
+const FREEZE_RADIUS: f32 = 250.0;
+const FREEZE_SECS: f32 = 5.0;
+const COOLDOWN_SECS: f32 = 12.0;
+// frozen rects blink for this long before they thaw
+const THAW_WARNING_SECS: f32 = 1.0;
+const BLINK_HZ: f32 = 8.0;
+const ICE_TINT: Color = Color::srgba(0.55, 0.8, 1.0, 0.55);
+
+pub struct FreezePlugin;
+
+impl Plugin for FreezePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<FreezeCooldown>()
+            .init_resource::<IceMaterial>()
+            .add_systems(
+                Update,
+                (
+                    (freeze_nearby_rects, thaw_rects)
+                        .chain()
+                        .in_set(GameSet::Reactions),
+                    blink_thawing_rects.in_set(GameSet::Vfx),
+                ),
+            );
+    }
+}
+
+// a frozen rect is a static body for a while. what it was doing before is kept so it
+// carries on from there when it thaws, mid-air or not
+#[derive(Component)]
+struct Frozen {
+    remaining: f32,
+    linear: Vec2,
+    angular: f32,
+    tint: Entity,
+}
+
+#[derive(Resource, Default)]
+struct FreezeCooldown(f32);
+
+#[derive(Resource)]
+struct IceMaterial(Handle<ColorMaterial>);
+
+impl FromWorld for IceMaterial {
+    fn from_world(world: &mut World) -> IceMaterial {
+        IceMaterial(world.resource_mut::<Assets<ColorMaterial>>().add(ICE_TINT))
+    }
+}
+
+fn distance_to(aabb: &ColliderAabb, point: Vec2) -> f32 {
+    (point - point.clamp(aabb.min, aabb.max)).length()
+}
+
+fn freeze_nearby_rects(
+    mut commands: Commands,
+    actions: Res<ActionState>,
+    mut cooldown: ResMut<FreezeCooldown>,
+    mut died: EventReader<PlayerDied>,
+    player: Single<&Transform, With<Player>>,
+    rects: Query<
+        (
+            Entity,
+            &RigidBody,
+            &ColliderAabb,
+            &LinearVelocity,
+            &AngularVelocity,
+            Option<&Mesh2d>,
+        ),
+        (With<FallingRect>, Without<Frozen>),
+    >,
+    ice: Res<IceMaterial>,
+    assets: Res<CommonAssets>,
+    time: Res<Time>,
+) {
+    if died.read().count() > 0 {
+        cooldown.0 = 0.0;
+    }
+    cooldown.0 = (cooldown.0 - time.delta_secs()).max(0.0);
+    if cooldown.0 > 0.0 || !actions.just_pressed(Action::Freeze) {
+        return;
+    }
+    cooldown.0 = COOLDOWN_SECS;
+    let player = player.translation.truncate();
+    let mut frozen = 0;
+    for (entity, body, aabb, linear, angular, mesh) in &rects {
+        if *body != RigidBody::Dynamic || distance_to(aabb, player) > FREEZE_RADIUS {
+            continue;
+        }
+        let mesh = mesh.map_or_else(|| assets.rect.clone(), |mesh| mesh.0.clone());
+        let tint = commands
+            .spawn((
+                Name::new("IceTint"),
+                Mesh2d(mesh),
+                MeshMaterial2d(ice.0.clone()),
+                Transform::from_xyz(0.0, 0.0, 0.2),
+            ))
+            .id();
+        commands
+            .entity(entity)
+            .insert((
+                Frozen {
+                    remaining: FREEZE_SECS,
+                    linear: linear.0,
+                    angular: angular.0,
+                    tint,
+                },
+                RigidBody::Static,
+            ))
+            .add_child(tint);
+        frozen += 1;
+    }
+    debug!(target: "player", "froze {frozen} rects");
+}
+
+fn thaw_rects(mut commands: Commands, mut frozen: Query<(Entity, &mut Frozen)>, time: Res<Time>) {
+    for (entity, mut rect) in &mut frozen {
+        rect.remaining -= time.delta_secs();
+        if rect.remaining > 0.0 {
+            continue;
+        }
+        commands.entity(rect.tint).despawn_recursive();
+        commands
+            .entity(entity)
+            .remove::<Frozen>()
+            .remove::<Sleeping>()
+            .insert((
+                RigidBody::Dynamic,
+                LinearVelocity(rect.linear),
+                AngularVelocity(rect.angular),
+            ));
+    }
+}
+
+fn blink_thawing_rects(
+    settings: Res<Settings>,
+    frozen: Query<&Frozen>,
+    mut tints: Query<&mut Visibility>,
+    time: Res<Time>,
+) {
+    for rect in &frozen {
+        let Ok(mut visibility) = tints.get_mut(rect.tint) else {
+            continue;
+        };
+        // with reduced flashing the tint just stays until the thaw
+        let hidden = !settings.reduce_flashing
+            && rect.remaining < THAW_WARNING_SECS
+            && ((time.elapsed_secs() * BLINK_HZ) as u32).is_multiple_of(2);
+        let wanted = if hidden {
+            Visibility::Hidden
+        } else {
+            Visibility::Inherited
+        };
+        if *visibility != wanted {
+            *visibility = wanted;
+        }
+    }
+}
@@ -0,0 +1,103 @@
+use avian2d::prelude::*;
+use bevy::prelude::*;
+
+use crate::common_assets::CommonAssets;
+use crate::config::GameConfig;
+use crate::input::{Action, ActionState};
+use crate::player::Abilities;
+use crate::{move_player, GameSet, Player};
+
+const GLIDE_FALL_SPEED: f32 = 120.0;
+// on top of the regular air acceleration
+const GLIDE_DRIFT_FACTOR: f32 = 0.6;
+// in the player's unit box, a flat canopy just over its head
+const CANOPY_SIZE: Vec2 = Vec2::new(1.6, 0.2);
+const CANOPY_OFFSET: f32 = 0.9;
+
+pub struct GlidePlugin;
+
+impl Plugin for GlidePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            FixedPreUpdate,
+            glide.in_set(GameSet::Movement).after(move_player),
+        )
+        .add_systems(Update, (add_canopy, show_canopy).in_set(GameSet::Vfx));
+    }
+}
+
+#[derive(Component)]
+struct Canopy;
+
+// holding jump on the way down opens the canopy, letting go or pressing down folds it.
+// the landing impact only counts the fall speed from under the canopy
+fn glide(
+    actions: Res<ActionState>,
+    config: Res<GameConfig>,
+    player: Single<(&mut Player, &mut LinearVelocity, &Abilities)>,
+    time: Res<Time>,
+) {
+    let (mut player, mut linear, abilities) = player.into_inner();
+    let gliding = abilities.glide
+        && actions.pressed(Action::Jump)
+        && !actions.pressed(Action::Down)
+        && linear.y < 0.0
+        && player.ground_normal.is_none()
+        && player.wall_normal.is_none()
+        && !player.climbing
+        && !player.pulling;
+    if gliding != player.gliding {
+        debug!(target: "player", "gliding: {gliding}");
+    }
+    player.gliding = gliding;
+    if !gliding {
+        return;
+    }
+    linear.y = linear.y.max(-GLIDE_FALL_SPEED);
+    player.fall_speed = player.fall_speed.min(GLIDE_FALL_SPEED);
+    let drift = match (
+        actions.pressed(Action::Left),
+        actions.pressed(Action::Right),
+    ) {
+        (true, false) => -1.0,
+        (false, true) => 1.0,
+        _ => return,
+    };
+    if linear.x * drift < config.move_speed {
+        linear.x += 100.0 * drift * config.acceleration * GLIDE_DRIFT_FACTOR * time.delta_secs();
+    }
+}
+
+// every new player gets a folded canopy, whether it can glide or not
+fn add_canopy(
+    mut commands: Commands,
+    assets: Res<CommonAssets>,
+    added: Query<Entity, Added<Player>>,
+) {
+    for player in &added {
+        commands.entity(player).with_child((
+            Name::new("Canopy"),
+            Canopy,
+            Mesh2d(assets.rect.clone()),
+            MeshMaterial2d(assets.accent.clone()),
+            Transform::from_xyz(0.0, CANOPY_OFFSET, 0.1).with_scale(CANOPY_SIZE.extend(1.0)),
+            Visibility::Hidden,
+        ));
+    }
+}
+
+fn show_canopy(
+    player: Single<(&Player, &Children)>,
+    mut canopies: Query<&mut Visibility, With<Canopy>>,
+) {
+    let (player, children) = player.into_inner();
+    for child in children.iter() {
+        if let Ok(mut visibility) = canopies.get_mut(*child) {
+            *visibility = if player.gliding {
+                Visibility::Inherited
+            } else {
+                Visibility::Hidden
+            };
+        }
+    }
+}
@@ -0,0 +1,102 @@
+use bevy::input::InputSystem;
+use bevy::prelude::*;
+
+use crate::i18n::Localization;
+use crate::input::STICK_DEADZONE;
+
+pub struct GlyphsPlugin;
+
+impl Plugin for GlyphsPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<InputDevice>()
+            .add_systems(PreUpdate, track_input_device.after(InputSystem));
+    }
+}
+
+// the device family the player touched last, prompts follow it
+#[derive(Resource, Default, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum InputDevice {
+    #[default]
+    Keyboard,
+    Gamepad,
+    Touch,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Prompt {
+    Confirm,
+    Back,
+    Adjust,
+    Interact,
+}
+
+// glyphs starting with '@' are locale keys, the rest are printed as is
+const KEYBOARD_GLYPHS: [(Prompt, &str); 4] = [
+    (Prompt::Confirm, "Enter"),
+    (Prompt::Back, "Esc"),
+    (Prompt::Adjust, "Left/Right"),
+    (Prompt::Interact, "F"),
+];
+const GAMEPAD_GLYPHS: [(Prompt, &str); 4] = [
+    (Prompt::Confirm, "A"),
+    (Prompt::Back, "B"),
+    (Prompt::Adjust, "D-Pad"),
+    (Prompt::Interact, "B"),
+];
+const TOUCH_GLYPHS: [(Prompt, &str); 1] = [(Prompt::Confirm, "@glyph.tap")];
+
+impl InputDevice {
+    fn glyphs(self) -> &'static [(Prompt, &'static str)] {
+        match self {
+            InputDevice::Keyboard => &KEYBOARD_GLYPHS,
+            InputDevice::Gamepad => &GAMEPAD_GLYPHS,
+            InputDevice::Touch => &TOUCH_GLYPHS,
+        }
+    }
+
+    pub fn glyph(self, prompt: Prompt, localization: &Localization) -> Option<&str> {
+        let (_, glyph) = self.glyphs().iter().find(|(entry, _)| *entry == prompt)?;
+        Some(match glyph.strip_prefix('@') {
+            Some(key) => localization.get(key),
+            None => glyph,
+        })
+    }
+
+    // "[A] Select  [B] Back", skipping prompts the device has no glyph for
+    pub fn hint(self, prompts: &[(Prompt, &str)], localization: &Localization) -> String {
+        prompts
+            .iter()
+            .filter_map(|(prompt, key)| {
+                let glyph = self.glyph(*prompt, localization)?;
+                Some(format!("[{glyph}] {}", localization.get(key)))
+            })
+            .collect::<Vec<_>>()
+            .join("  ")
+    }
+}
+
+fn track_input_device(
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    mouse_input: Res<ButtonInput<MouseButton>>,
+    gamepads: Query<&Gamepad>,
+    touches: Res<Touches>,
+    mut device: ResMut<InputDevice>,
+) {
+    let used = if touches.any_just_pressed() {
+        InputDevice::Touch
+    } else if gamepads.iter().any(|gamepad| {
+        gamepad.get_just_pressed().next().is_some()
+            || gamepad.left_stick().length() > STICK_DEADZONE
+    }) {
+        InputDevice::Gamepad
+    } else if keyboard_input.get_just_pressed().next().is_some()
+        || mouse_input.get_just_pressed().next().is_some()
+    {
+        InputDevice::Keyboard
+    } else {
+        return;
+    };
+    if *device != used {
+        *device = used;
+    }
+}
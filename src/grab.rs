@@ -0,0 +1,134 @@
+use avian2d::prelude::*;
+use bevy::prelude::*;
+use bevy::window::PrimaryWindow;
+
+use crate::collision_layer::{solid_mask, GameLayer};
+use crate::palette::Palette;
+use crate::spawner::FallingRect;
+use crate::trajectory::predict_trajectory;
+use crate::GameSet;
+
+const GRAB_STIFFNESS: f32 = 15.0;
+const TRAJECTORY_STEPS: usize = 96;
+
+pub struct GrabPlugin;
+
+impl Plugin for GrabPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<GrabTarget>()
+            .add_systems(
+                Update,
+                (
+                    (grab_rect, release_rect).in_set(GameSet::Input),
+                    draw_throw_trajectory.in_set(GameSet::Vfx),
+                ),
+            )
+            .add_systems(FixedPreUpdate, drag_grabbed_rect.in_set(GameSet::Movement));
+    }
+}
+
+#[derive(Component)]
+struct Grabbed;
+
+#[derive(Resource, Default)]
+struct GrabTarget(Vec2);
+
+pub fn cursor_world_position(
+    window: &Window,
+    camera: &Camera,
+    camera_transform: &GlobalTransform,
+) -> Option<Vec2> {
+    let cursor = window.cursor_position()?;
+    camera.viewport_to_world_2d(camera_transform, cursor).ok()
+}
+
+fn grab_rect(
+    mut commands: Commands,
+    mut target: ResMut<GrabTarget>,
+    mouse_input: Res<ButtonInput<MouseButton>>,
+    window: Single<&Window, With<PrimaryWindow>>,
+    camera: Single<(&Camera, &GlobalTransform)>,
+    spatial_query: SpatialQuery,
+    rects: Query<(), With<FallingRect>>,
+) {
+    let (camera, camera_transform) = camera.into_inner();
+    let Some(cursor) = cursor_world_position(&window, camera, camera_transform) else {
+        return;
+    };
+    target.0 = cursor;
+    if !mouse_input.just_pressed(MouseButton::Right) {
+        return;
+    }
+    let grabbed = spatial_query
+        .point_intersections(
+            cursor,
+            &SpatialQueryFilter::from_mask(GameLayer::FallingRect),
+        )
+        .into_iter()
+        .find(|entity| rects.contains(*entity));
+    if let Some(entity) = grabbed {
+        commands.entity(entity).insert((Grabbed, GravityScale(0.0)));
+    }
+}
+
+fn release_rect(
+    mut commands: Commands,
+    mouse_input: Res<ButtonInput<MouseButton>>,
+    grabbed: Query<Entity, With<Grabbed>>,
+) {
+    if mouse_input.pressed(MouseButton::Right) {
+        return;
+    }
+    for entity in &grabbed {
+        // keep the current velocity so releasing while moving throws the rect
+        commands
+            .entity(entity)
+            .remove::<Grabbed>()
+            .insert(GravityScale(1.0));
+    }
+}
+
+fn drag_grabbed_rect(
+    target: Res<GrabTarget>,
+    mut grabbed: Query<(&Transform, &mut LinearVelocity), With<Grabbed>>,
+) {
+    for (transform, mut linear) in &mut grabbed {
+        linear.0 = (target.0 - transform.translation.truncate()) * GRAB_STIFFNESS;
+    }
+}
+
+fn draw_throw_trajectory(
+    mut gizmos: Gizmos,
+    grabbed: Query<(Entity, &Transform, &LinearVelocity), With<Grabbed>>,
+    gravity: Res<Gravity>,
+    fixed_time: Res<Time<Fixed>>,
+    spatial_query: SpatialQuery,
+    palette: Res<Palette>,
+) {
+    for (entity, transform, linear) in &grabbed {
+        let points = predict_trajectory(
+            transform.translation.truncate(),
+            linear.0,
+            gravity.0,
+            fixed_time.timestep().as_secs_f32(),
+            TRAJECTORY_STEPS,
+        );
+        let filter = SpatialQueryFilter::from_mask(solid_mask()).with_excluded_entities([entity]);
+        let mut arc = vec![points[0]];
+        for segment in points.windows(2) {
+            let delta = segment[1] - segment[0];
+            let Ok(direction) = Dir2::new(delta) else {
+                continue;
+            };
+            // stop the arc at the first surface it would hit
+            if let Some(hit) =
+                spatial_query.cast_ray(segment[0], direction, delta.length(), true, &filter)
+            {
+                arc.push(segment[0] + *direction * hit.distance);
+                break;
+            }
+            arc.push(segment[1]);
+        }
+        gizmos.linestrip_2d(arc, palette.ui_accent);
+    }
+}
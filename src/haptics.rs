@@ -0,0 +1,103 @@
+#[cfg(not(target_arch = "wasm32"))]
+use std::time::Duration;
+
+#[cfg(not(target_arch = "wasm32"))]
+use bevy::input::gamepad::{GamepadRumbleIntensity, GamepadRumbleRequest};
+use bevy::prelude::*;
+
+use crate::explosion::Explosion;
+#[cfg(not(target_arch = "wasm32"))]
+use crate::settings::Settings;
+use crate::{GameSet, PlayerCrushed, PlayerLanded};
+
+const MIN_LANDING_IMPACT: f32 = 300.0;
+const HARD_LANDING_IMPACT: f32 = 1500.0;
+
+pub struct HapticsPlugin;
+
+impl Plugin for HapticsPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<HapticPulse>().add_systems(
+            Update,
+            (
+                (landing_pulses, damage_pulses, explosion_pulses),
+                play_pulses,
+            )
+                .chain()
+                .in_set(GameSet::Reactions),
+        );
+    }
+}
+
+// gameplay code asks for a pulse, the backend decides if and how it can be played
+#[derive(Event)]
+pub struct HapticPulse {
+    pub strength: f32,
+    pub duration_secs: f32,
+}
+
+fn landing_pulses(mut landed: EventReader<PlayerLanded>, mut pulses: EventWriter<HapticPulse>) {
+    for landing in landed.read() {
+        if landing.impact < MIN_LANDING_IMPACT {
+            continue;
+        }
+        pulses.send(HapticPulse {
+            strength: (landing.impact / HARD_LANDING_IMPACT).min(1.0),
+            duration_secs: 0.1,
+        });
+    }
+}
+
+fn damage_pulses(mut crushed: EventReader<PlayerCrushed>, mut pulses: EventWriter<HapticPulse>) {
+    for _ in crushed.read() {
+        pulses.send(HapticPulse {
+            strength: 1.0,
+            duration_secs: 0.3,
+        });
+    }
+}
+
+fn explosion_pulses(mut explosions: EventReader<Explosion>, mut pulses: EventWriter<HapticPulse>) {
+    for _ in explosions.read() {
+        pulses.send(HapticPulse {
+            strength: 0.8,
+            duration_secs: 0.25,
+        });
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn play_pulses(
+    mut pulses: EventReader<HapticPulse>,
+    settings: Res<Settings>,
+    gamepads: Query<Entity, With<Gamepad>>,
+    mut rumble: EventWriter<GamepadRumbleRequest>,
+) {
+    for pulse in pulses.read() {
+        if !settings.rumble {
+            continue;
+        }
+        for gamepad in &gamepads {
+            rumble.send(GamepadRumbleRequest::Add {
+                gamepad,
+                duration: Duration::from_secs_f32(pulse.duration_secs),
+                intensity: GamepadRumbleIntensity {
+                    strong_motor: pulse.strength,
+                    weak_motor: pulse.strength * 0.5,
+                },
+            });
+        }
+    }
+}
+
+// browsers don't expose rumble through gilrs, drop the pulses
+#[cfg(target_arch = "wasm32")]
+fn play_pulses(mut pulses: EventReader<HapticPulse>) {
+    for pulse in pulses.read() {
+        trace!(
+            "no rumble on the web, dropped a {:.1} pulse for {:.2}s",
+            pulse.strength,
+            pulse.duration_secs
+        );
+    }
+}
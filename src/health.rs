@@ -0,0 +1,235 @@
+use avian2d::prelude::*;
+use bevy::ecs::system::SystemParam;
+use bevy::prelude::*;
+
+use crate::explosion::Explosion;
+use crate::i18n::Localization;
+use crate::popups::{PopupStyle, ShowPopup};
+use crate::prefab::Spike;
+use crate::settings::Settings;
+use crate::shop::RunUpgrades;
+use crate::{GameSet, Player, PlayerCrushed, PlayerKilled};
+
+const BASE_HEALTH: u32 = 3;
+const CRUSHED_POPUP_OFFSET: Vec2 = Vec2::new(0.0, 40.0);
+const SPAWN_PROTECTION_SECS: f32 = 2.0;
+// hidden every other step, so two blinks a second
+const PROTECTION_BLINK_HZ: f32 = 4.0;
+
+pub struct HealthPlugin;
+
+impl Plugin for HealthPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<PlayerHurt>().add_systems(
+            Update,
+            (
+                sync_max_health,
+                wear_off_spawn_protection,
+                damage_on_crush,
+                damage_on_spikes,
+                damage_on_explosions,
+                show_damage_popups,
+            )
+                .chain()
+                .in_set(GameSet::Reactions),
+        );
+    }
+}
+
+#[derive(Component)]
+pub struct Health {
+    pub current: u32,
+    pub max: u32,
+}
+
+impl Default for Health {
+    fn default() -> Health {
+        Health {
+            current: BASE_HEALTH,
+            max: BASE_HEALTH,
+        }
+    }
+}
+
+// nothing can hurt a player with this, the dev cheats use it for god mode
+#[derive(Component)]
+pub struct Invulnerable;
+
+// a respawned player can't be hurt for a moment, so a rect that was already coming down
+// on the checkpoint doesn't end the new life straight away. the player blinks until it
+// wears off
+#[derive(Component)]
+pub struct SpawnProtection(Timer);
+
+impl Default for SpawnProtection {
+    fn default() -> SpawnProtection {
+        SpawnProtection(Timer::from_seconds(SPAWN_PROTECTION_SECS, TimerMode::Once))
+    }
+}
+
+type Vulnerable = (
+    With<Player>,
+    Without<Invulnerable>,
+    Without<SpawnProtection>,
+);
+
+// every hit that actually costs health, all damage feedback hangs off this
+#[derive(Event)]
+pub struct PlayerHurt {
+    pub damage: u32,
+    pub remaining: u32,
+}
+
+#[derive(SystemParam)]
+struct HitEvents<'w> {
+    hurt: EventWriter<'w, PlayerHurt>,
+    killed: EventWriter<'w, PlayerKilled>,
+}
+
+fn max_health(settings: &Settings, upgrades: &RunUpgrades) -> u32 {
+    BASE_HEALTH + settings.extra_health + upgrades.extra_health()
+}
+
+fn sync_max_health(
+    settings: Res<Settings>,
+    upgrades: Res<RunUpgrades>,
+    mut health: Single<&mut Health, With<Player>>,
+) {
+    let max = max_health(&settings, &upgrades);
+    // a freshly spawned player starts with full health
+    if health.is_added() {
+        health.max = max;
+        health.current = max;
+    } else if health.max != max {
+        health.max = max;
+        health.current = health.current.min(max);
+    }
+}
+
+fn wear_off_spawn_protection(
+    mut commands: Commands,
+    settings: Res<Settings>,
+    time: Res<Time>,
+    mut protected: Query<(Entity, &mut SpawnProtection, &mut Visibility)>,
+) {
+    for (entity, mut protection, mut visibility) in &mut protected {
+        protection.0.tick(time.delta());
+        // with reduced flashing the player just stays visible
+        let hidden = !settings.reduce_flashing
+            && !protection.0.finished()
+            && (protection.0.elapsed_secs() * PROTECTION_BLINK_HZ) as u32 % 2 == 1;
+        let wanted = if hidden {
+            Visibility::Hidden
+        } else {
+            Visibility::Inherited
+        };
+        if *visibility != wanted {
+            *visibility = wanted;
+        }
+        if protection.0.finished() {
+            debug!(target: "player", "spawn protection wore off");
+            commands.entity(entity).remove::<SpawnProtection>();
+        }
+    }
+}
+
+fn take_hit(health: &mut Health, damage: u32, by: Option<Entity>, events: &mut HitEvents) {
+    if health.current == 0 || damage == 0 {
+        return;
+    }
+    health.current = health.current.saturating_sub(damage);
+    events.hurt.send(PlayerHurt {
+        damage,
+        remaining: health.current,
+    });
+    if health.current == 0 {
+        events.killed.send(PlayerKilled { by });
+    }
+}
+
+fn damage_on_crush(
+    mut crushed: EventReader<PlayerCrushed>,
+    settings: Res<Settings>,
+    localization: Res<Localization>,
+    player: Option<Single<(&Transform, &mut Health), Vulnerable>>,
+    mut events: HitEvents,
+    mut popups: EventWriter<ShowPopup>,
+) {
+    // hits taken while protected are dropped rather than landing once it wears off
+    let Some((transform, mut health)) = player.map(Single::into_inner) else {
+        crushed.clear();
+        return;
+    };
+    for crush in crushed.read() {
+        let damage = crush.class.crush_damage();
+        // a dead player doesn't get crushed again
+        if settings.crush_invulnerable || damage == 0 || health.current == 0 {
+            continue;
+        }
+        take_hit(&mut health, damage, Some(crush.rect), &mut events);
+        popups.send(ShowPopup {
+            text: localization.get("popup.crushed").to_string(),
+            position: transform.translation.truncate() + CRUSHED_POPUP_OFFSET,
+            style: PopupStyle::Damage,
+        });
+    }
+}
+
+fn damage_on_spikes(
+    mut collisions: EventReader<CollisionStarted>,
+    spikes: Query<(), With<Spike>>,
+    player: Option<Single<(Entity, &mut Health), Vulnerable>>,
+    mut events: HitEvents,
+) {
+    let Some((player, mut health)) = player.map(Single::into_inner) else {
+        collisions.clear();
+        return;
+    };
+    for CollisionStarted(a, b) in collisions.read() {
+        let other = if *a == player {
+            *b
+        } else if *b == player {
+            *a
+        } else {
+            continue;
+        };
+        if spikes.contains(other) {
+            take_hit(&mut health, 1, None, &mut events);
+        }
+    }
+}
+
+fn damage_on_explosions(
+    mut explosions: EventReader<Explosion>,
+    player: Option<Single<(&Transform, &mut Health), Vulnerable>>,
+    mut events: HitEvents,
+) {
+    let Some((transform, mut health)) = player.map(Single::into_inner) else {
+        explosions.clear();
+        return;
+    };
+    for explosion in explosions.read() {
+        if transform
+            .translation
+            .truncate()
+            .distance(explosion.position)
+            < explosion.radius
+        {
+            take_hit(&mut health, 1, None, &mut events);
+        }
+    }
+}
+
+fn show_damage_popups(
+    mut hurt: EventReader<PlayerHurt>,
+    player: Single<&Transform, With<Player>>,
+    mut popups: EventWriter<ShowPopup>,
+) {
+    for PlayerHurt { damage, .. } in hurt.read() {
+        popups.send(ShowPopup {
+            text: format!("-{damage}"),
+            position: player.translation.truncate(),
+            style: PopupStyle::Damage,
+        });
+    }
+}
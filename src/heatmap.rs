@@ -0,0 +1,204 @@
+use std::collections::BTreeMap;
+
+use avian2d::prelude::*;
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::deathcam::Results;
+use crate::i18n::Localization;
+use crate::menu::GameState;
+use crate::palette::Palette;
+use crate::prefab::TerrainBlock;
+use crate::spawner::FallingRect;
+use crate::storage::{Persistent, StorageAppExt};
+use crate::{GameSet, Player};
+
+// world pixels per heatmap cell
+const CELL_SIZE: f32 = 100.0;
+// the longest side of the map on the results screen, in ui pixels
+const MAP_SIZE: f32 = 240.0;
+const MIN_HEAT_ALPHA: f32 = 0.15;
+const MAX_HEAT_ALPHA: f32 = 0.8;
+const DEATH_MARK_SIZE: f32 = 6.0;
+const CAPTION_FONT_SIZE: f32 = 16.0;
+
+pub struct HeatmapPlugin;
+
+impl Plugin for HeatmapPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_persistent_resource::<Heatmap>()
+            .init_resource::<RunHeat>()
+            .add_systems(Update, sample_heat.in_set(GameSet::Reactions))
+            .add_systems(OnEnter(GameState::DeathCam), show_heatmap)
+            .add_systems(OnExit(GameState::DeathCam), hide_heatmap);
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Default)]
+struct HeatCell {
+    secs: f32,
+    deaths: u32,
+}
+
+// every run so far, by cell
+#[derive(Resource, Serialize, Deserialize, Default)]
+#[serde(default)]
+struct Heatmap {
+    cells: BTreeMap<(i32, i32), HeatCell>,
+}
+
+impl Persistent for Heatmap {
+    const KEY: &'static str = "heatmap";
+    const VERSION: u32 = 1;
+}
+
+// the run in progress, folded into the heatmap once it ends so the save isn't
+// touched every frame
+#[derive(Resource, Default)]
+struct RunHeat {
+    secs: BTreeMap<(i32, i32), f32>,
+}
+
+#[derive(Component)]
+struct HeatmapPanel;
+
+fn cell_of(position: Vec2) -> (i32, i32) {
+    let cell = (position / CELL_SIZE).floor();
+    (cell.x as i32, cell.y as i32)
+}
+
+fn cell_rect((x, y): (i32, i32)) -> Rect {
+    let min = Vec2::new(x as f32, y as f32) * CELL_SIZE;
+    Rect::from_corners(min, min + Vec2::splat(CELL_SIZE))
+}
+
+fn sample_heat(
+    mut heat: ResMut<RunHeat>,
+    player: Single<&Transform, With<Player>>,
+    time: Res<Time>,
+) {
+    let cell = cell_of(player.translation.truncate());
+    *heat.secs.entry(cell).or_default() += time.delta_secs();
+}
+
+// a small map of the level and the pile under the results, with the time spent in
+// each cell shaded in and a mark wherever runs ended
+fn show_heatmap(
+    mut commands: Commands,
+    mut heatmap: ResMut<Heatmap>,
+    mut heat: ResMut<RunHeat>,
+    palette: Res<Palette>,
+    localization: Res<Localization>,
+    player: Single<&Transform, With<Player>>,
+    outlines: Query<&ColliderAabb, Or<(With<TerrainBlock>, With<FallingRect>)>>,
+    results: Single<Entity, With<Results>>,
+) {
+    for (cell, secs) in std::mem::take(&mut heat.secs) {
+        heatmap.cells.entry(cell).or_default().secs += secs;
+    }
+    let outlines: Vec<Rect> = outlines
+        .iter()
+        .map(|aabb| Rect::from_corners(aabb.min, aabb.max))
+        .collect();
+    let Some(bounds) = outlines
+        .iter()
+        .copied()
+        .reduce(|bounds, rect| bounds.union(rect))
+    else {
+        return;
+    };
+    let bounds = bounds.inflate(CELL_SIZE);
+    // falling off the map counts at the edge it went over
+    let death = player
+        .translation
+        .truncate()
+        .clamp(bounds.min, bounds.max - Vec2::splat(1.0));
+    heatmap.cells.entry(cell_of(death)).or_default().deaths += 1;
+    let scale = MAP_SIZE / bounds.size().max_element().max(1.0);
+    // ui y goes down, the world's goes up
+    let node = |rect: Rect| Node {
+        position_type: PositionType::Absolute,
+        left: Val::Px((rect.min.x - bounds.min.x) * scale),
+        top: Val::Px((bounds.max.y - rect.max.y) * scale),
+        width: Val::Px((rect.width() * scale).max(1.0)),
+        height: Val::Px((rect.height() * scale).max(1.0)),
+        ..default()
+    };
+    let hottest = heatmap
+        .cells
+        .values()
+        .map(|cell| cell.secs)
+        .fold(0.0, f32::max)
+        .max(f32::EPSILON);
+    commands.entity(*results).with_children(|parent| {
+        parent
+            .spawn((
+                Name::new("HeatmapPanel"),
+                HeatmapPanel,
+                Node {
+                    flex_direction: FlexDirection::Column,
+                    align_items: AlignItems::Center,
+                    row_gap: Val::Px(4.0),
+                    margin: UiRect::top(Val::Px(8.0)),
+                    ..default()
+                },
+            ))
+            .with_children(|panel| {
+                panel
+                    .spawn(Node {
+                        width: Val::Px(bounds.width() * scale),
+                        height: Val::Px(bounds.height() * scale),
+                        overflow: Overflow::clip(),
+                        ..default()
+                    })
+                    .with_children(|map| {
+                        for rect in &outlines {
+                            map.spawn((
+                                Node {
+                                    border: UiRect::all(Val::Px(1.0)),
+                                    ..node(*rect)
+                                },
+                                BorderColor(palette.ui_text.with_alpha(0.5)),
+                            ));
+                        }
+                        // older runs can have gone places this pile doesn't reach
+                        let shown = heatmap
+                            .cells
+                            .iter()
+                            .filter(|(cell, _)| !cell_rect(**cell).intersect(bounds).is_empty());
+                        for (cell, heat) in shown {
+                            if heat.secs > 0.0 {
+                                let alpha = MIN_HEAT_ALPHA
+                                    + (MAX_HEAT_ALPHA - MIN_HEAT_ALPHA) * heat.secs / hottest;
+                                map.spawn((
+                                    node(cell_rect(*cell)),
+                                    BackgroundColor(palette.ui_accent.with_alpha(alpha)),
+                                ));
+                            }
+                            if heat.deaths > 0 {
+                                let center = cell_rect(*cell).center();
+                                let size = DEATH_MARK_SIZE * (heat.deaths as f32).sqrt() / scale;
+                                map.spawn((
+                                    node(Rect::from_center_size(center, Vec2::splat(size))),
+                                    BackgroundColor(palette.hazard),
+                                ));
+                            }
+                        }
+                    });
+                panel.spawn((
+                    Text::new(localization.get("deathcam.heatmap")),
+                    TextFont {
+                        font_size: CAPTION_FONT_SIZE,
+                        ..default()
+                    },
+                    TextColor(palette.ui_text.with_alpha(0.6)),
+                ));
+            });
+    });
+}
+
+fn hide_heatmap(mut commands: Commands, panels: Query<Entity, With<HeatmapPanel>>) {
+    for panel in &panels {
+        commands.entity(panel).despawn_recursive();
+    }
+}
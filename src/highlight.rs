@@ -0,0 +1,122 @@
+use avian2d::prelude::*;
+use bevy::prelude::*;
+use bevy::window::PrimaryWindow;
+
+use crate::grab::cursor_world_position;
+use crate::palette::Palette;
+use crate::{GameSet, Player};
+
+// in world pixels, measured from the edge of the collider
+const CURSOR_REACH: f32 = 24.0;
+const PLAYER_REACH: f32 = 120.0;
+const OUTLINE_WIDTH: f32 = 4.0;
+
+pub struct HighlightPlugin;
+
+impl Plugin for HighlightPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<OutlineMaterial>().add_systems(
+            Update,
+            (add_outlines, highlight_nearby, recolor_outlines)
+                .chain()
+                .in_set(GameSet::Vfx),
+        );
+    }
+}
+
+// things the player can do something with get an outline when they're in reach
+#[derive(Component, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Interactable {
+    // picked up with the mouse, so the cursor has to be near
+    Grabbable,
+    // triggered by walking into it, so the player has to be near
+    Touch,
+}
+
+// the same mesh as its parent, a little larger and drawn just behind it
+#[derive(Component)]
+struct Outline(Entity);
+
+#[derive(Resource)]
+struct OutlineMaterial(Handle<ColorMaterial>);
+
+impl FromWorld for OutlineMaterial {
+    fn from_world(world: &mut World) -> OutlineMaterial {
+        let color = world.resource::<Palette>().ui_accent;
+        OutlineMaterial(world.resource_mut::<Assets<ColorMaterial>>().add(color))
+    }
+}
+
+// prefabs get their mesh a command later, so the outline waits for it
+fn add_outlines(
+    mut commands: Commands,
+    interactables: Query<(Entity, &Mesh2d, &Transform), (With<Interactable>, Without<Outline>)>,
+    material: Res<OutlineMaterial>,
+) {
+    for (entity, mesh, transform) in &interactables {
+        // the parent's scale is its size, the outline is in that unit space
+        let size = transform.scale.truncate().max(Vec2::ONE);
+        let scale = Vec2::ONE + 2.0 * OUTLINE_WIDTH / size;
+        let outline = commands
+            .spawn((
+                Name::new("Outline"),
+                Mesh2d(mesh.0.clone()),
+                MeshMaterial2d(material.0.clone()),
+                Transform::from_xyz(0.0, 0.0, -0.05).with_scale(scale.extend(1.0)),
+                Visibility::Hidden,
+            ))
+            .id();
+        commands
+            .entity(entity)
+            .insert(Outline(outline))
+            .add_child(outline);
+    }
+}
+
+fn distance_to(aabb: &ColliderAabb, point: Vec2) -> f32 {
+    (point - point.clamp(aabb.min, aabb.max)).length()
+}
+
+fn highlight_nearby(
+    interactables: Query<(&Interactable, &Outline, &ColliderAabb)>,
+    mut outlines: Query<&mut Visibility>,
+    player: Single<&Transform, With<Player>>,
+    window: Single<&Window, With<PrimaryWindow>>,
+    camera: Single<(&Camera, &GlobalTransform)>,
+) {
+    let (camera, camera_transform) = camera.into_inner();
+    let cursor = cursor_world_position(&window, camera, camera_transform);
+    let player = player.translation.truncate();
+    for (interactable, outline, aabb) in &interactables {
+        let near = match interactable {
+            Interactable::Grabbable => {
+                cursor.is_some_and(|cursor| distance_to(aabb, cursor) <= CURSOR_REACH)
+            }
+            Interactable::Touch => distance_to(aabb, player) <= PLAYER_REACH,
+        };
+        let Ok(mut visibility) = outlines.get_mut(outline.0) else {
+            continue;
+        };
+        let wanted = if near {
+            Visibility::Inherited
+        } else {
+            Visibility::Hidden
+        };
+        if *visibility != wanted {
+            *visibility = wanted;
+        }
+    }
+}
+
+fn recolor_outlines(
+    palette: Res<Palette>,
+    material: Res<OutlineMaterial>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+) {
+    if !palette.is_changed() {
+        return;
+    }
+    if let Some(material) = materials.get_mut(&material.0) {
+        material.color = palette.ui_accent;
+    }
+}
@@ -0,0 +1,372 @@
+use bevy::prelude::*;
+use bevy::utils::SystemTime;
+use serde::{Deserialize, Serialize};
+
+use crate::clipboard::Clipboard;
+use crate::i18n::Localization;
+use crate::menu::{MenuAction, MenuActivated, MenuPage, MenuRow, MenuRows, MenuSelection, MenuSet};
+use crate::score::RunStats;
+use crate::settings::Settings;
+use crate::storage::{Persistent, StorageAppExt};
+use crate::{GameSet, PlayerDied};
+
+// the oldest runs are dropped past this
+const MAX_RUNS: usize = 100;
+const RUNS_PER_PAGE: usize = 8;
+
+pub struct HistoryPlugin;
+
+impl Plugin for HistoryPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_persistent_resource::<RunHistory>()
+            .init_resource::<HistoryView>()
+            .add_systems(
+                Update,
+                (
+                    record_finished_runs.in_set(GameSet::Reactions),
+                    (history_menu_rows, run_menu_rows).in_set(MenuSet::Rows),
+                    apply_history_actions.in_set(MenuSet::Actions),
+                ),
+            );
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum RunMode {
+    Normal,
+    // any assist was on at some point
+    Assisted,
+    Practice,
+}
+
+impl RunMode {
+    const ALL: [RunMode; 3] = [RunMode::Normal, RunMode::Assisted, RunMode::Practice];
+
+    fn key(self) -> &'static str {
+        match self {
+            RunMode::Normal => "history.mode_normal",
+            RunMode::Assisted => "history.mode_assisted",
+            RunMode::Practice => "history.mode_practice",
+        }
+    }
+}
+
+pub fn run_mode(settings: &Settings, stats: &RunStats) -> RunMode {
+    if settings.practice_mode {
+        RunMode::Practice
+    } else if stats.assisted {
+        RunMode::Assisted
+    } else {
+        RunMode::Normal
+    }
+}
+
+// everything stays on this machine, nothing here is ever uploaded
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct RunRecord {
+    // seconds since the unix epoch, in utc
+    pub finished_at: u64,
+    pub mode: RunMode,
+    pub score: u32,
+    pub height: f32,
+    pub duration_secs: f32,
+    // runs aren't seeded or recorded yet, these get filled in once they are
+    pub seed: Option<u64>,
+    pub replay: Option<String>,
+}
+
+// oldest first
+#[derive(Resource, Serialize, Deserialize, Default)]
+#[serde(default)]
+struct RunHistory {
+    runs: Vec<RunRecord>,
+}
+
+impl Persistent for RunHistory {
+    const KEY: &'static str = "run_history";
+    const VERSION: u32 = 1;
+}
+
+#[derive(Resource, Default)]
+struct HistoryView {
+    // None shows every mode
+    filter: Option<RunMode>,
+    page: usize,
+    // index into the history of the run open on the HistoryRun page
+    selected: Option<usize>,
+}
+
+impl HistoryView {
+    // newest first, with their index in the history
+    fn shown<'a>(&self, history: &'a RunHistory) -> Vec<(usize, &'a RunRecord)> {
+        history
+            .runs
+            .iter()
+            .enumerate()
+            .rev()
+            .filter(|(_, run)| self.filter.is_none_or(|mode| run.mode == mode))
+            .collect()
+    }
+}
+
+fn unix_now() -> u64 {
+    SystemTime::now()
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .map_or(0, |since| since.as_secs())
+}
+
+// yyyy-mm-dd hh:mm in utc, the days to date part is howard hinnant's civil_from_days
+fn format_date(secs: u64) -> String {
+    let days = (secs / 86400) as i64 + 719_468;
+    let minutes = secs % 86400 / 60;
+    let era = days.div_euclid(146_097);
+    let day_of_era = days.rem_euclid(146_097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_index = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * month_index + 2) / 5 + 1;
+    let month = if month_index < 10 {
+        month_index + 3
+    } else {
+        month_index - 9
+    };
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+    format!(
+        "{year:04}-{month:02}-{day:02} {:02}:{:02}",
+        minutes / 60,
+        minutes % 60
+    )
+}
+
+fn format_duration(secs: f32) -> String {
+    let secs = secs as u32;
+    format!("{}:{:02}", secs / 60, secs % 60)
+}
+
+// RunStats is already reset on the frame the player dies, so the run is remembered
+// from the frame before
+fn record_finished_runs(
+    mut died: EventReader<PlayerDied>,
+    stats: Res<RunStats>,
+    settings: Res<Settings>,
+    mut history: ResMut<RunHistory>,
+    mut last_run: Local<Option<(RunMode, u32, f32, f32)>>,
+) {
+    if died.read().count() == 0 {
+        let mode = run_mode(&settings, &stats);
+        *last_run =
+            (stats.score > 0).then_some((mode, stats.score, stats.max_height, stats.time_survived));
+        return;
+    }
+    let Some((mode, score, height, duration_secs)) = last_run.take() else {
+        return;
+    };
+    history.runs.push(RunRecord {
+        finished_at: unix_now(),
+        mode,
+        score,
+        height,
+        duration_secs,
+        seed: None,
+        replay: None,
+    });
+    let over = history.runs.len().saturating_sub(MAX_RUNS);
+    history.runs.drain(..over);
+}
+
+fn history_menu_rows(
+    page: Res<MenuPage>,
+    history: Res<RunHistory>,
+    view: Res<HistoryView>,
+    localization: Res<Localization>,
+    mut rows: ResMut<MenuRows>,
+) {
+    if *page != MenuPage::History {
+        return;
+    }
+    let filter = view
+        .filter
+        .map_or(localization.get("history.mode_all"), |mode| {
+            localization.get(mode.key())
+        });
+    rows.push(MenuRow::new(
+        localization.format("history.filter", &[("value", &filter)]),
+        MenuAction::CycleHistoryFilter,
+    ));
+    let shown = view.shown(&history);
+    let pages = shown.len().div_ceil(RUNS_PER_PAGE).max(1);
+    let current = view.page.min(pages - 1);
+    if pages > 1 {
+        rows.push(MenuRow::new(
+            localization.format(
+                "history.page",
+                &[("page", &(current + 1)), ("pages", &pages)],
+            ),
+            MenuAction::CycleHistoryPage,
+        ));
+    }
+    if shown.is_empty() {
+        rows.push(MenuRow::new(
+            localization.get("history.empty"),
+            MenuAction::None,
+        ));
+    }
+    for (index, run) in shown
+        .iter()
+        .skip(current * RUNS_PER_PAGE)
+        .take(RUNS_PER_PAGE)
+    {
+        rows.push(MenuRow::new(
+            localization.format(
+                "history.entry",
+                &[
+                    ("date", &format_date(run.finished_at)),
+                    ("mode", &localization.get(run.mode.key())),
+                    ("score", &run.score),
+                    ("duration", &format_duration(run.duration_secs)),
+                ],
+            ),
+            MenuAction::OpenRun(*index),
+        ));
+    }
+    if !shown.is_empty() {
+        rows.push(MenuRow::new(
+            localization.get("history.export_all"),
+            MenuAction::ExportRuns,
+        ));
+    }
+    rows.push(MenuRow::new(
+        localization.get("menu.back"),
+        MenuAction::Back,
+    ));
+}
+
+fn run_menu_rows(
+    page: Res<MenuPage>,
+    history: Res<RunHistory>,
+    view: Res<HistoryView>,
+    localization: Res<Localization>,
+    mut rows: ResMut<MenuRows>,
+) {
+    if *page != MenuPage::HistoryRun {
+        return;
+    }
+    let Some(run) = view.selected.and_then(|index| history.runs.get(index)) else {
+        rows.push(MenuRow::new(
+            localization.get("menu.back"),
+            MenuAction::Back,
+        ));
+        return;
+    };
+    let height = format!("{:.0}", run.height);
+    let seed = run
+        .seed
+        .map_or(localization.get("history.none").to_string(), |seed| {
+            format!("{seed:016x}")
+        });
+    let replay = run
+        .replay
+        .as_deref()
+        .unwrap_or(localization.get("history.not_recorded"));
+    for label in [
+        format_date(run.finished_at),
+        localization.format(
+            "history.mode",
+            &[("value", &localization.get(run.mode.key()))],
+        ),
+        localization.format(
+            "history.score",
+            &[("score", &run.score), ("height", &height)],
+        ),
+        localization.format(
+            "history.duration",
+            &[("value", &format_duration(run.duration_secs))],
+        ),
+        localization.format("history.seed", &[("value", &seed)]),
+        localization.format("history.replay", &[("value", &replay)]),
+    ] {
+        rows.push(MenuRow::new(label, MenuAction::None));
+    }
+    rows.push(MenuRow::new(
+        localization.get("history.export"),
+        MenuAction::ExportRun,
+    ));
+    rows.push(MenuRow::new(
+        localization.get("history.delete"),
+        MenuAction::DeleteRun,
+    ));
+    rows.push(MenuRow::new(
+        localization.get("menu.back"),
+        MenuAction::Back,
+    ));
+}
+
+// exports are the same ron the history is saved as, so they can be pasted back into a
+// save by hand or read by other tools
+fn export(runs: &[&RunRecord], clipboard: &mut Clipboard) {
+    match ron::ser::to_string_pretty(runs, default()) {
+        Ok(text) => clipboard.copy(text),
+        Err(err) => error!("couldn't export runs: {err}"),
+    }
+}
+
+fn apply_history_actions(
+    mut activated: EventReader<MenuActivated>,
+    mut history: ResMut<RunHistory>,
+    mut view: ResMut<HistoryView>,
+    mut page: ResMut<MenuPage>,
+    mut selection: ResMut<MenuSelection>,
+    mut clipboard: Local<Clipboard>,
+) {
+    for event in activated.read() {
+        let delta = if event.delta == 0 { 1 } else { event.delta };
+        match event.action {
+            MenuAction::CycleHistoryFilter => {
+                let filters: Vec<Option<RunMode>> = std::iter::once(None)
+                    .chain(RunMode::ALL.map(Some))
+                    .collect();
+                let current = filters
+                    .iter()
+                    .position(|filter| *filter == view.filter)
+                    .unwrap_or(0);
+                let next = (current as i32 + delta).rem_euclid(filters.len() as i32);
+                view.filter = filters[next as usize];
+                view.page = 0;
+            }
+            MenuAction::CycleHistoryPage => {
+                let pages = view.shown(&history).len().div_ceil(RUNS_PER_PAGE).max(1);
+                let current = view.page.min(pages - 1);
+                view.page = (current as i32 + delta).rem_euclid(pages as i32) as usize;
+            }
+            MenuAction::OpenRun(index) if event.delta == 0 => {
+                view.selected = Some(index);
+                *page = MenuPage::HistoryRun;
+                selection.0 = 0;
+            }
+            MenuAction::ExportRuns if event.delta == 0 => {
+                let shown: Vec<&RunRecord> = view
+                    .shown(&history)
+                    .into_iter()
+                    .map(|(_, run)| run)
+                    .collect();
+                export(&shown, &mut clipboard);
+            }
+            MenuAction::ExportRun if event.delta == 0 => {
+                if let Some(run) = view.selected.and_then(|index| history.runs.get(index)) {
+                    export(&[run], &mut clipboard);
+                }
+            }
+            MenuAction::DeleteRun if event.delta == 0 => {
+                if let Some(index) = view.selected.take() {
+                    if index < history.runs.len() {
+                        history.runs.remove(index);
+                    }
+                }
+                *page = MenuPage::History;
+                selection.0 = 0;
+            }
+            _ => {}
+        }
+    }
+}
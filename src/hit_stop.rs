@@ -0,0 +1,96 @@
+use avian2d::prelude::*;
+use bevy::prelude::*;
+
+use crate::health::PlayerHurt;
+use crate::spawner::{FallingRect, WeightClass};
+use crate::{GameSet, Player};
+
+const HURT_FREEZE_FRAMES: u32 = 4;
+const HEAVY_LANDING_FREEZE_FRAMES: u32 = 2;
+// a steel rect has to hit this fast and this close to the player to count
+const HEAVY_LANDING_SPEED: f32 = 300.0;
+const HEAVY_LANDING_RADIUS: f32 = 250.0;
+// after a freeze ends nothing can start another one for a moment, so a pile of
+// landings reads as one hit instead of a stutter
+const REFREEZE_COOLDOWN_SECS: f32 = 0.3;
+
+pub struct HitStopPlugin;
+
+impl Plugin for HitStopPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<HitStop>().add_systems(
+            Update,
+            (freeze_on_hurt, freeze_on_heavy_landings, run_hit_stop)
+                .chain()
+                .in_set(GameSet::Reactions),
+        );
+    }
+}
+
+// only physics stops, so particles, shake and popups carry on through the freeze
+#[derive(Resource, Default)]
+struct HitStop {
+    frames_left: u32,
+    cooldown: f32,
+    frozen: bool,
+}
+
+impl HitStop {
+    // overlapping triggers don't add up, the longest one wins
+    fn request(&mut self, frames: u32) {
+        if self.cooldown > 0.0 {
+            return;
+        }
+        self.frames_left = self.frames_left.max(frames);
+    }
+}
+
+fn freeze_on_hurt(mut hurt: EventReader<PlayerHurt>, mut hit_stop: ResMut<HitStop>) {
+    if hurt.read().count() > 0 {
+        hit_stop.request(HURT_FREEZE_FRAMES);
+    }
+}
+
+fn freeze_on_heavy_landings(
+    mut collisions: EventReader<CollisionStarted>,
+    rects: Query<(&Transform, &LinearVelocity, &WeightClass), With<FallingRect>>,
+    player: Single<&Transform, With<Player>>,
+    mut hit_stop: ResMut<HitStop>,
+) {
+    let player = player.translation.truncate();
+    for CollisionStarted(a, b) in collisions.read() {
+        let heavy_landing = [*a, *b].into_iter().any(|entity| {
+            rects.get(entity).is_ok_and(|(transform, velocity, class)| {
+                *class == WeightClass::Steel
+                    && velocity.length() >= HEAVY_LANDING_SPEED
+                    && transform.translation.truncate().distance(player) <= HEAVY_LANDING_RADIUS
+            })
+        });
+        if heavy_landing {
+            hit_stop.request(HEAVY_LANDING_FREEZE_FRAMES);
+        }
+    }
+}
+
+// counts rendered frames rather than seconds, a freeze should look the same at any
+// frame rate
+fn run_hit_stop(
+    mut hit_stop: ResMut<HitStop>,
+    mut physics_time: ResMut<Time<Physics>>,
+    time: Res<Time<Real>>,
+) {
+    if hit_stop.frames_left > 0 {
+        if !hit_stop.frozen {
+            physics_time.pause();
+            hit_stop.frozen = true;
+        }
+        hit_stop.frames_left -= 1;
+        return;
+    }
+    if hit_stop.frozen {
+        physics_time.unpause();
+        hit_stop.frozen = false;
+        hit_stop.cooldown = REFREEZE_COOLDOWN_SECS;
+    }
+    hit_stop.cooldown = (hit_stop.cooldown - time.delta_secs()).max(0.0);
+}
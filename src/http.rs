@@ -0,0 +1,50 @@
+// small http helpers shared by the online features. the native ones block, so they only
+// ever run on the io task pool
+
+#[cfg(not(target_arch = "wasm32"))]
+pub use native::*;
+#[cfg(target_arch = "wasm32")]
+pub use web::*;
+
+#[cfg(not(target_arch = "wasm32"))]
+mod native {
+    pub async fn get(url: &str) -> Result<String, String> {
+        reqwest::blocking::get(url)
+            .and_then(|response| response.error_for_status())
+            .and_then(|response| response.text())
+            .map_err(|err| err.to_string())
+    }
+
+    pub async fn post(url: &str, body: String) -> Result<(), String> {
+        reqwest::blocking::Client::new()
+            .post(url)
+            .header("content-type", "application/json")
+            .body(body)
+            .send()
+            .and_then(|response| response.error_for_status())
+            .map(|_| ())
+            .map_err(|err| err.to_string())
+    }
+}
+
+// reqwest goes through the browser's fetch on the web
+#[cfg(target_arch = "wasm32")]
+mod web {
+    pub async fn get(url: &str) -> Result<String, String> {
+        let response = reqwest::get(url).await.map_err(|err| err.to_string())?;
+        let response = response.error_for_status().map_err(|err| err.to_string())?;
+        response.text().await.map_err(|err| err.to_string())
+    }
+
+    pub async fn post(url: &str, body: String) -> Result<(), String> {
+        reqwest::Client::new()
+            .post(url)
+            .header("content-type", "application/json")
+            .body(body)
+            .send()
+            .await
+            .and_then(|response| response.error_for_status())
+            .map(|_| ())
+            .map_err(|err| err.to_string())
+    }
+}
@@ -0,0 +1,220 @@
+use bevy::prelude::*;
+
+use crate::health::Health;
+use crate::i18n::Localization;
+use crate::palette::Palette;
+use crate::score::{RunStats, ScoreChanged};
+use crate::ui_layout::UiRoot;
+use crate::{GameSet, Player};
+
+const HUD_FONT_SIZE: f32 = 24.0;
+const TOAST_FONT_SIZE: f32 = 20.0;
+const TOAST_DURATION_SECS: f32 = 3.0;
+const TOAST_FADE_SECS: f32 = 0.5;
+const BANNER_FONT_SIZE: f32 = 48.0;
+const BANNER_DURATION_SECS: f32 = 2.5;
+
+pub struct HudPlugin;
+
+impl Plugin for HudPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<ShowToast>()
+            .add_event::<ShowBanner>()
+            .add_systems(Startup, spawn_hud)
+            .add_systems(
+                Update,
+                (
+                    update_score_text,
+                    spawn_toasts,
+                    expire_toasts,
+                    show_banner,
+                    fade_banner,
+                )
+                    .in_set(GameSet::Vfx),
+            );
+    }
+}
+
+#[derive(Event)]
+pub struct ShowToast(pub String);
+
+// big centered text for things happening to the whole world
+#[derive(Event)]
+pub struct ShowBanner(pub String);
+
+#[derive(Component)]
+struct ScoreText;
+
+#[derive(Component)]
+struct Banner {
+    age: f32,
+}
+
+#[derive(Component)]
+struct ToastStack;
+
+#[derive(Component)]
+struct Toast {
+    age: f32,
+}
+
+fn spawn_hud(mut commands: Commands, palette: Res<Palette>, root: Single<Entity, With<UiRoot>>) {
+    commands
+        .spawn((
+            Name::new("Hud"),
+            Node {
+                width: Val::Percent(100.0),
+                height: Val::Percent(100.0),
+                justify_content: JustifyContent::SpaceBetween,
+                padding: UiRect::all(Val::Px(12.0)),
+                ..default()
+            },
+        ))
+        .set_parent(*root)
+        .with_children(|parent| {
+            parent.spawn((
+                ScoreText,
+                Text::new(""),
+                TextFont {
+                    font_size: HUD_FONT_SIZE,
+                    ..default()
+                },
+                TextColor(palette.ui_text),
+            ));
+            parent.spawn((
+                ToastStack,
+                Node {
+                    flex_direction: FlexDirection::Column,
+                    align_items: AlignItems::FlexEnd,
+                    row_gap: Val::Px(6.0),
+                    ..default()
+                },
+            ));
+        });
+    commands
+        .spawn((
+            Name::new("BannerRoot"),
+            Node {
+                position_type: PositionType::Absolute,
+                width: Val::Percent(100.0),
+                top: Val::Percent(20.0),
+                justify_content: JustifyContent::Center,
+                ..default()
+            },
+        ))
+        .set_parent(*root)
+        .with_child((
+            Banner {
+                age: BANNER_DURATION_SECS,
+            },
+            Text::new(""),
+            TextFont {
+                font_size: BANNER_FONT_SIZE,
+                ..default()
+            },
+            TextColor(palette.ui_accent),
+            Visibility::Hidden,
+        ));
+}
+
+fn update_score_text(
+    mut changed: EventReader<ScoreChanged>,
+    stats: Res<RunStats>,
+    palette: Res<Palette>,
+    localization: Res<Localization>,
+    health: Single<Ref<Health>, With<Player>>,
+    text: Single<(&mut Text, &mut TextColor), With<ScoreText>>,
+) {
+    let (mut text, mut color) = text.into_inner();
+    let score_changed = changed.read().count() > 0;
+    if palette.is_changed() {
+        color.0 = palette.ui_text;
+    }
+    if !score_changed && !health.is_changed() && !localization.is_changed() {
+        return;
+    }
+    let height = format!("{:.0}", stats.max_height);
+    text.0 = localization.format("hud.score", &[("score", &stats.score), ("height", &height)]);
+    text.0.push_str("  ");
+    text.0.push_str(&localization.format(
+        "hud.health",
+        &[("current", &health.current), ("max", &health.max)],
+    ));
+    if stats.assisted {
+        text.0.push_str("  ");
+        text.0.push_str(localization.get("hud.assisted"));
+    }
+}
+
+fn spawn_toasts(
+    mut commands: Commands,
+    mut toasts: EventReader<ShowToast>,
+    stack: Single<Entity, With<ToastStack>>,
+    palette: Res<Palette>,
+) {
+    for toast in toasts.read() {
+        commands.entity(*stack).with_child((
+            Toast { age: 0.0 },
+            Node {
+                padding: UiRect::axes(Val::Px(12.0), Val::Px(6.0)),
+                ..default()
+            },
+            BackgroundColor(Color::srgba(0.0, 0.0, 0.0, 0.7)),
+            Text::new(toast.0.clone()),
+            TextFont {
+                font_size: TOAST_FONT_SIZE,
+                ..default()
+            },
+            TextColor(palette.ui_accent),
+        ));
+    }
+}
+
+// toasts use real time so they still go away while the game is paused
+fn expire_toasts(
+    mut commands: Commands,
+    mut toasts: Query<(Entity, &mut Toast, &mut TextColor)>,
+    time: Res<Time<Real>>,
+) {
+    for (entity, mut toast, mut color) in &mut toasts {
+        toast.age += time.delta_secs();
+        if toast.age >= TOAST_DURATION_SECS {
+            commands.entity(entity).despawn_recursive();
+            continue;
+        }
+        let fade = ((TOAST_DURATION_SECS - toast.age) / TOAST_FADE_SECS).min(1.0);
+        color.0.set_alpha(fade);
+    }
+}
+
+fn show_banner(
+    mut banners: EventReader<ShowBanner>,
+    palette: Res<Palette>,
+    banner: Single<(&mut Banner, &mut Text, &mut TextColor, &mut Visibility)>,
+) {
+    let Some(ShowBanner(message)) = banners.read().last() else {
+        return;
+    };
+    let (mut banner, mut text, mut color, mut visibility) = banner.into_inner();
+    banner.age = 0.0;
+    text.0 = message.clone();
+    color.0 = palette.ui_accent;
+    *visibility = Visibility::Inherited;
+}
+
+fn fade_banner(
+    banner: Single<(&mut Banner, &mut TextColor, &mut Visibility)>,
+    time: Res<Time<Real>>,
+) {
+    let (mut banner, mut color, mut visibility) = banner.into_inner();
+    if banner.age >= BANNER_DURATION_SECS {
+        return;
+    }
+    banner.age += time.delta_secs();
+    if banner.age >= BANNER_DURATION_SECS {
+        *visibility = Visibility::Hidden;
+        return;
+    }
+    let fade = ((BANNER_DURATION_SECS - banner.age) / TOAST_FADE_SECS).min(1.0);
+    color.0.set_alpha(fade);
+}
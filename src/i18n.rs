@@ -0,0 +1,164 @@
+use std::fmt::Display;
+
+use bevy::asset::io::Reader;
+use bevy::asset::{AssetLoader, LoadContext};
+use bevy::prelude::*;
+use bevy::utils::HashMap;
+
+use crate::asset_check::TrackedAssets;
+use crate::settings::Settings;
+
+pub const FALLBACK_LANGUAGE: &str = "en";
+
+// (code, name shown in the language picker)
+pub const LANGUAGES: &[(&str, &str)] = &[
+    ("en", "English"),
+    ("pt-BR", "Português (Brasil)"),
+    ("es", "Español"),
+];
+
+pub struct I18nPlugin;
+
+impl Plugin for I18nPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_asset::<LocaleFile>()
+            .init_asset_loader::<LocaleLoader>()
+            .init_resource::<Localization>()
+            .add_systems(Startup, load_locales)
+            .add_systems(PreUpdate, rebuild_localization);
+    }
+}
+
+#[derive(Asset, TypePath)]
+pub struct LocaleFile(HashMap<String, String>);
+
+#[derive(Default)]
+struct LocaleLoader;
+
+impl AssetLoader for LocaleLoader {
+    type Asset = LocaleFile;
+    type Settings = ();
+    type Error = std::io::Error;
+
+    async fn load(
+        &self,
+        reader: &mut dyn Reader,
+        _settings: &(),
+        _load_context: &mut LoadContext<'_>,
+    ) -> Result<LocaleFile, Self::Error> {
+        let mut bytes = Vec::new();
+        reader.read_to_end(&mut bytes).await?;
+        Ok(LocaleFile(parse_locale(&String::from_utf8_lossy(&bytes))))
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["lang"]
+    }
+}
+
+// one `key = value` pair per line, `#` starts a comment
+fn parse_locale(contents: &str) -> HashMap<String, String> {
+    contents
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .filter_map(|line| line.split_once('='))
+        .map(|(key, value)| (key.trim().to_string(), value.trim().to_string()))
+        .collect()
+}
+
+// "pt-BR" -> ["pt-BR", "pt", "en"]
+fn fallback_chain(language: &str) -> Vec<&str> {
+    let mut chain = vec![language];
+    if let Some((base, _)) = language.split_once('-') {
+        chain.push(base);
+    }
+    if language != FALLBACK_LANGUAGE {
+        chain.push(FALLBACK_LANGUAGE);
+    }
+    chain
+}
+
+pub fn language_name(code: &str) -> &str {
+    LANGUAGES
+        .iter()
+        .find(|(language, _)| *language == code)
+        .map_or(code, |(_, name)| name)
+}
+
+pub fn cycle_language(code: &str, delta: i32) -> &'static str {
+    let index = LANGUAGES
+        .iter()
+        .position(|(language, _)| *language == code)
+        .unwrap_or(0);
+    let len = LANGUAGES.len() as i32;
+    LANGUAGES[(index as i32 + delta).rem_euclid(len) as usize].0
+}
+
+#[derive(Resource)]
+struct LocaleHandles(HashMap<&'static str, Handle<LocaleFile>>);
+
+// the active language merged over its fallbacks, missing keys render as the key itself
+#[derive(Resource, Default)]
+pub struct Localization {
+    strings: HashMap<String, String>,
+}
+
+impl Localization {
+    pub fn get<'a>(&'a self, key: &'a str) -> &'a str {
+        self.strings.get(key).map_or(key, String::as_str)
+    }
+
+    pub fn format(&self, key: &str, args: &[(&str, &dyn Display)]) -> String {
+        let mut text = self.get(key).to_string();
+        for (name, value) in args {
+            text = text.replace(&format!("{{{name}}}"), &value.to_string());
+        }
+        text
+    }
+}
+
+// a locale that fails to load is skipped, its keys come from the fallback chain
+fn load_locales(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    mut tracked: ResMut<TrackedAssets>,
+) {
+    let handles: HashMap<&'static str, Handle<LocaleFile>> = LANGUAGES
+        .iter()
+        .map(|(code, _)| (*code, asset_server.load(format!("locales/{code}.lang"))))
+        .collect();
+    for handle in handles.values() {
+        tracked.track(handle.clone());
+    }
+    commands.insert_resource(LocaleHandles(handles));
+}
+
+fn rebuild_localization(
+    settings: Res<Settings>,
+    handles: Res<LocaleHandles>,
+    locale_files: Res<Assets<LocaleFile>>,
+    mut locale_events: EventReader<AssetEvent<LocaleFile>>,
+    mut localization: ResMut<Localization>,
+) {
+    let locales_changed = locale_events.read().count() > 0;
+    if !settings.is_changed() && !locales_changed {
+        return;
+    }
+    let mut strings = HashMap::new();
+    for code in fallback_chain(&settings.language).into_iter().rev() {
+        let Some(file) = handles
+            .0
+            .get(code)
+            .and_then(|handle| locale_files.get(handle))
+        else {
+            continue;
+        };
+        strings.extend(
+            file.0
+                .iter()
+                .map(|(key, value)| (key.clone(), value.clone())),
+        );
+    }
+    localization.strings = strings;
+}
@@ -0,0 +1,469 @@
+use bevy::input::keyboard::{Key, KeyboardInput};
+use bevy::input::InputSystem;
+use bevy::prelude::*;
+use bevy::utils::{HashMap, HashSet};
+use serde::{Deserialize, Serialize};
+
+use crate::settings::Settings;
+use crate::storage::{Persistent, StorageAppExt};
+
+pub const STICK_DEADZONE: f32 = 0.3;
+const MIN_STICK_DEADZONE_PERCENT: u32 = 5;
+const MAX_STICK_DEADZONE_PERCENT: u32 = 60;
+const STICK_DEADZONE_STEP_PERCENT: u32 = 5;
+const MIN_STICK_SENSITIVITY_PERCENT: u32 = 50;
+const MAX_STICK_SENSITIVITY_PERCENT: u32 = 200;
+const STICK_SENSITIVITY_STEP_PERCENT: u32 = 25;
+
+pub struct InputPlugin;
+
+impl Plugin for InputPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<ActionState>()
+            .init_resource::<DetectedLayout>()
+            .insert_resource(Bindings::defaults(KeyboardLayout::default()))
+            .add_persistent_resource::<BindingOverrides>()
+            .configure_sets(
+                PreUpdate,
+                (ActionSet::Clear, ActionSet::Collect, ActionSet::Assist)
+                    .chain()
+                    .after(InputSystem),
+            )
+            .add_systems(
+                PreUpdate,
+                (
+                    clear_actions.in_set(ActionSet::Clear),
+                    (collect_keyboard_actions, collect_gamepad_actions).in_set(ActionSet::Collect),
+                    (detect_keyboard_layout, apply_bindings).chain(),
+                ),
+            );
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug, Serialize, Deserialize)]
+pub enum Action {
+    Left,
+    Right,
+    Jump,
+    Down,
+    Grab,
+    Freeze,
+    Interact,
+    // practice mode save states
+    SaveState,
+    LoadState,
+}
+
+impl Action {
+    pub fn key(self) -> &'static str {
+        match self {
+            Action::Left => "action.left",
+            Action::Right => "action.right",
+            Action::Jump => "action.jump",
+            Action::Down => "action.down",
+            Action::Grab => "action.grab",
+            Action::Freeze => "action.freeze",
+            Action::Interact => "action.interact",
+            Action::SaveState => "action.save_state",
+            Action::LoadState => "action.load_state",
+        }
+    }
+
+    pub const ALL: [Action; 9] = [
+        Action::Left,
+        Action::Right,
+        Action::Jump,
+        Action::Down,
+        Action::Grab,
+        Action::Freeze,
+        Action::Interact,
+        Action::SaveState,
+        Action::LoadState,
+    ];
+}
+
+// other input sources (gamepad, touch, ...) add their actions in ActionSet::Collect,
+// accessibility options adjust the result in ActionSet::Assist
+#[derive(SystemSet, Debug, Clone, PartialEq, Eq, Hash)]
+pub enum ActionSet {
+    Clear,
+    Collect,
+    Assist,
+}
+
+#[derive(Resource, Default)]
+pub struct ActionState {
+    pressed: HashSet<Action>,
+    previous: HashSet<Action>,
+    // how far an analog input pushed the action, in 0..=1. missing means all the way
+    amounts: HashMap<Action, f32>,
+}
+
+impl ActionState {
+    pub fn press(&mut self, action: Action) {
+        self.pressed.insert(action);
+        self.amounts.remove(&action);
+    }
+
+    // the strongest input wins when several press the same action
+    pub fn press_by(&mut self, action: Action, amount: f32) {
+        if amount <= 0.0 {
+            return;
+        }
+        if self.pressed.insert(action) {
+            self.amounts.insert(action, amount.min(1.0));
+        } else if let Some(current) = self.amounts.get_mut(&action) {
+            *current = current.max(amount.min(1.0));
+        }
+    }
+
+    pub fn release(&mut self, action: Action) {
+        self.pressed.remove(&action);
+        self.amounts.remove(&action);
+    }
+
+    pub fn pressed(&self, action: Action) -> bool {
+        self.pressed.contains(&action)
+    }
+
+    pub fn amount(&self, action: Action) -> f32 {
+        if !self.pressed(action) {
+            return 0.0;
+        }
+        self.amounts.get(&action).copied().unwrap_or(1.0)
+    }
+
+    pub fn just_pressed(&self, action: Action) -> bool {
+        self.pressed.contains(&action) && !self.previous.contains(&action)
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default, Serialize, Deserialize)]
+pub enum KeyboardLayout {
+    #[default]
+    Qwerty,
+    Azerty,
+    Dvorak,
+}
+
+impl KeyboardLayout {
+    pub fn name(self) -> &'static str {
+        match self {
+            KeyboardLayout::Qwerty => "QWERTY",
+            KeyboardLayout::Azerty => "AZERTY",
+            KeyboardLayout::Dvorak => "Dvorak",
+        }
+    }
+}
+
+// None in the settings means "use whatever was detected"
+pub fn cycle_layout(layout: Option<KeyboardLayout>, delta: i32) -> Option<KeyboardLayout> {
+    const ORDER: [Option<KeyboardLayout>; 4] = [
+        None,
+        Some(KeyboardLayout::Qwerty),
+        Some(KeyboardLayout::Azerty),
+        Some(KeyboardLayout::Dvorak),
+    ];
+    let index = ORDER.iter().position(|entry| *entry == layout).unwrap_or(0);
+    ORDER[(index as i32 + delta).rem_euclid(ORDER.len() as i32) as usize]
+}
+
+#[derive(Resource, Default)]
+pub struct DetectedLayout(pub Option<KeyboardLayout>);
+
+pub fn effective_layout(settings: &Settings, detected: &DetectedLayout) -> KeyboardLayout {
+    settings.keyboard_layout.or(detected.0).unwrap_or_default()
+}
+
+#[derive(Resource, Clone)]
+pub struct Bindings {
+    pub layout: KeyboardLayout,
+    keys: HashMap<Action, Vec<KeyCode>>,
+    // the left stick always moves too, these are on top of it
+    buttons: HashMap<Action, Vec<GamepadButton>>,
+}
+
+impl Bindings {
+    // KeyCode is the physical key position, so the WASD cluster is already ZQSD on
+    // AZERTY and ,AOE on Dvorak. the layout is kept so prompts show the printed labels.
+    pub fn defaults(layout: KeyboardLayout) -> Bindings {
+        let keys = HashMap::from_iter([
+            (Action::Left, vec![KeyCode::ArrowLeft, KeyCode::KeyA]),
+            (Action::Right, vec![KeyCode::ArrowRight, KeyCode::KeyD]),
+            (Action::Jump, vec![KeyCode::ArrowUp, KeyCode::KeyW]),
+            (Action::Down, vec![KeyCode::ArrowDown, KeyCode::KeyS]),
+            (Action::Grab, vec![KeyCode::ShiftLeft, KeyCode::KeyK]),
+            (Action::Freeze, vec![KeyCode::KeyE, KeyCode::KeyJ]),
+            (Action::Interact, vec![KeyCode::KeyF, KeyCode::KeyL]),
+            (Action::SaveState, vec![KeyCode::F2]),
+            (Action::LoadState, vec![KeyCode::F3]),
+        ]);
+        let buttons = HashMap::from_iter([
+            (Action::Left, vec![GamepadButton::DPadLeft]),
+            (Action::Right, vec![GamepadButton::DPadRight]),
+            (Action::Jump, vec![GamepadButton::South]),
+            (Action::Down, vec![GamepadButton::DPadDown]),
+            (Action::Grab, vec![GamepadButton::RightTrigger2]),
+            (Action::Freeze, vec![GamepadButton::West]),
+            (Action::Interact, vec![GamepadButton::East]),
+            (Action::SaveState, vec![GamepadButton::Select]),
+            (Action::LoadState, vec![GamepadButton::North]),
+        ]);
+        Bindings {
+            layout,
+            keys,
+            buttons,
+        }
+    }
+
+    fn with_overrides(mut self, overrides: &BindingOverrides) -> Bindings {
+        self.keys.extend(overrides.keys.iter().cloned());
+        self.buttons.extend(overrides.buttons.iter().cloned());
+        self
+    }
+
+    pub fn keys(&self, action: Action) -> &[KeyCode] {
+        self.keys.get(&action).map_or(&[], Vec::as_slice)
+    }
+
+    pub fn buttons(&self, action: Action) -> &[GamepadButton] {
+        self.buttons.get(&action).map_or(&[], Vec::as_slice)
+    }
+}
+
+// rebinds made in the settings, laid over the defaults. only the actions that were
+// changed are in here, so everything else keeps following the defaults
+#[derive(Resource, Serialize, Deserialize, Default)]
+#[serde(default)]
+pub struct BindingOverrides {
+    keys: Vec<(Action, Vec<KeyCode>)>,
+    buttons: Vec<(Action, Vec<GamepadButton>)>,
+}
+
+impl Persistent for BindingOverrides {
+    const KEY: &'static str = "bindings";
+    const VERSION: u32 = 1;
+}
+
+fn set_override<T>(overrides: &mut Vec<(Action, Vec<T>)>, action: Action, inputs: Vec<T>) {
+    match overrides.iter_mut().find(|(other, _)| *other == action) {
+        Some((_, current)) => *current = inputs,
+        None => overrides.push((action, inputs)),
+    }
+}
+
+impl BindingOverrides {
+    pub fn set_keys(&mut self, action: Action, keys: Vec<KeyCode>) {
+        set_override(&mut self.keys, action, keys);
+    }
+
+    pub fn set_buttons(&mut self, action: Action, buttons: Vec<GamepadButton>) {
+        set_override(&mut self.buttons, action, buttons);
+    }
+
+    pub fn reset_keys(&mut self) {
+        self.keys.clear();
+    }
+
+    pub fn reset_buttons(&mut self) {
+        self.buttons.clear();
+    }
+}
+
+// the character printed on a physical key for the given layout
+pub fn key_label(key: KeyCode, layout: KeyboardLayout) -> String {
+    let remapped = match (layout, key) {
+        (KeyboardLayout::Azerty, KeyCode::KeyQ) => Some("A"),
+        (KeyboardLayout::Azerty, KeyCode::KeyA) => Some("Q"),
+        (KeyboardLayout::Azerty, KeyCode::KeyW) => Some("Z"),
+        (KeyboardLayout::Azerty, KeyCode::KeyZ) => Some("W"),
+        (KeyboardLayout::Azerty, KeyCode::Semicolon) => Some("M"),
+        (KeyboardLayout::Dvorak, KeyCode::KeyQ) => Some("'"),
+        (KeyboardLayout::Dvorak, KeyCode::KeyW) => Some(","),
+        (KeyboardLayout::Dvorak, KeyCode::KeyE) => Some("."),
+        (KeyboardLayout::Dvorak, KeyCode::KeyR) => Some("P"),
+        (KeyboardLayout::Dvorak, KeyCode::KeyS) => Some("O"),
+        (KeyboardLayout::Dvorak, KeyCode::KeyD) => Some("E"),
+        (KeyboardLayout::Dvorak, KeyCode::KeyF) => Some("U"),
+        _ => None,
+    };
+    if let Some(label) = remapped {
+        return label.to_string();
+    }
+    match key {
+        KeyCode::ArrowUp => "Up".to_string(),
+        KeyCode::ArrowDown => "Down".to_string(),
+        KeyCode::ArrowLeft => "Left".to_string(),
+        KeyCode::ArrowRight => "Right".to_string(),
+        other => {
+            let name = format!("{other:?}");
+            name.strip_prefix("Key").unwrap_or(&name).to_string()
+        }
+    }
+}
+
+// xbox names, they're what most pads and most players go by
+pub fn button_label(button: GamepadButton) -> String {
+    let label = match button {
+        GamepadButton::South => "A",
+        GamepadButton::East => "B",
+        GamepadButton::West => "X",
+        GamepadButton::North => "Y",
+        GamepadButton::LeftTrigger => "LB",
+        GamepadButton::RightTrigger => "RB",
+        GamepadButton::LeftTrigger2 => "LT",
+        GamepadButton::RightTrigger2 => "RT",
+        GamepadButton::LeftThumb => "LS",
+        GamepadButton::RightThumb => "RS",
+        GamepadButton::Select => "View",
+        GamepadButton::Start => "Menu",
+        GamepadButton::DPadUp => "D-pad Up",
+        GamepadButton::DPadDown => "D-pad Down",
+        GamepadButton::DPadLeft => "D-pad Left",
+        GamepadButton::DPadRight => "D-pad Right",
+        other => return format!("{other:?}"),
+    };
+    label.to_string()
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default, Serialize, Deserialize)]
+pub enum StickCurve {
+    #[default]
+    Linear,
+    // finer control near the center, full speed still at the edge
+    Cubed,
+}
+
+impl StickCurve {
+    pub fn key(self) -> &'static str {
+        match self {
+            StickCurve::Linear => "settings.stick_curve_linear",
+            StickCurve::Cubed => "settings.stick_curve_cubed",
+        }
+    }
+
+    pub fn cycle(self, delta: i32) -> StickCurve {
+        const ORDER: [StickCurve; 2] = [StickCurve::Linear, StickCurve::Cubed];
+        let index = ORDER.iter().position(|curve| *curve == self).unwrap_or(0);
+        ORDER[(index as i32 + delta).rem_euclid(ORDER.len() as i32) as usize]
+    }
+}
+
+pub fn cycle_stick_deadzone(percent: u32, delta: i32) -> u32 {
+    let steps =
+        (MAX_STICK_DEADZONE_PERCENT - MIN_STICK_DEADZONE_PERCENT) / STICK_DEADZONE_STEP_PERCENT + 1;
+    let step = percent.saturating_sub(MIN_STICK_DEADZONE_PERCENT) / STICK_DEADZONE_STEP_PERCENT;
+    let step = (step as i32 + delta).rem_euclid(steps as i32) as u32;
+    MIN_STICK_DEADZONE_PERCENT + step * STICK_DEADZONE_STEP_PERCENT
+}
+
+pub fn cycle_stick_sensitivity(percent: u32, delta: i32) -> u32 {
+    let steps = (MAX_STICK_SENSITIVITY_PERCENT - MIN_STICK_SENSITIVITY_PERCENT)
+        / STICK_SENSITIVITY_STEP_PERCENT
+        + 1;
+    let step =
+        percent.saturating_sub(MIN_STICK_SENSITIVITY_PERCENT) / STICK_SENSITIVITY_STEP_PERCENT;
+    let step = (step as i32 + delta).rem_euclid(steps as i32) as u32;
+    MIN_STICK_SENSITIVITY_PERCENT + step * STICK_SENSITIVITY_STEP_PERCENT
+}
+
+// each axis has its own deadzone, and starts from zero at its edge so there's no jump
+// in speed past it. then the curve, then sensitivity, capped at a full push
+pub fn shape_stick(raw: Vec2, settings: &Settings) -> Vec2 {
+    let sensitivity = settings.stick_sensitivity_percent as f32 / 100.0;
+    let axis = |value: f32, deadzone_percent: u32| {
+        let deadzone = (deadzone_percent as f32 / 100.0).min(0.95);
+        let past = ((value.abs() - deadzone) / (1.0 - deadzone)).clamp(0.0, 1.0);
+        let curved = match settings.stick_curve {
+            StickCurve::Linear => past,
+            StickCurve::Cubed => past.powi(3),
+        };
+        (curved * sensitivity).min(1.0) * value.signum()
+    };
+    Vec2::new(
+        axis(raw.x, settings.stick_deadzone_x_percent),
+        axis(raw.y, settings.stick_deadzone_y_percent),
+    )
+}
+
+fn clear_actions(mut actions: ResMut<ActionState>) {
+    let ActionState {
+        pressed,
+        previous,
+        amounts,
+    } = &mut *actions;
+    std::mem::swap(pressed, previous);
+    pressed.clear();
+    amounts.clear();
+}
+
+fn collect_keyboard_actions(
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    bindings: Res<Bindings>,
+    mut actions: ResMut<ActionState>,
+) {
+    for action in Action::ALL {
+        if keyboard_input.any_pressed(bindings.keys(action).iter().copied()) {
+            actions.press(action);
+        }
+    }
+}
+
+fn collect_gamepad_actions(
+    gamepads: Query<&Gamepad>,
+    bindings: Res<Bindings>,
+    settings: Res<Settings>,
+    mut actions: ResMut<ActionState>,
+) {
+    for gamepad in &gamepads {
+        let stick = shape_stick(gamepad.left_stick(), &settings);
+        actions.press_by(Action::Left, -stick.x);
+        actions.press_by(Action::Right, stick.x);
+        if stick.y < 0.0 {
+            actions.press(Action::Down);
+        }
+        for action in Action::ALL {
+            if gamepad.any_pressed(bindings.buttons(action).iter().copied()) {
+                actions.press(action);
+            }
+        }
+    }
+}
+
+// compares the physical key with the character it produced. only keys whose character
+// tells the layouts apart count: KeyA types "a" on both qwerty and dvorak
+fn detect_keyboard_layout(
+    mut keyboard_events: EventReader<KeyboardInput>,
+    mut detected: ResMut<DetectedLayout>,
+) {
+    for event in keyboard_events.read() {
+        let Key::Character(character) = &event.logical_key else {
+            continue;
+        };
+        let layout = match (event.key_code, character.to_lowercase().as_str()) {
+            (KeyCode::KeyQ, "a") | (KeyCode::KeyW, "z") | (KeyCode::KeyA, "q") => {
+                KeyboardLayout::Azerty
+            }
+            (KeyCode::KeyW, ",") | (KeyCode::KeyS, "o") | (KeyCode::KeyD, "e") => {
+                KeyboardLayout::Dvorak
+            }
+            (KeyCode::KeyQ, "q") | (KeyCode::KeyW, "w") => KeyboardLayout::Qwerty,
+            _ => continue,
+        };
+        if detected.0 != Some(layout) {
+            detected.0 = Some(layout);
+        }
+    }
+}
+
+fn apply_bindings(
+    settings: Res<Settings>,
+    detected: Res<DetectedLayout>,
+    overrides: Res<BindingOverrides>,
+    mut bindings: ResMut<Bindings>,
+) {
+    let layout = effective_layout(&settings, &detected);
+    if bindings.layout != layout || overrides.is_changed() {
+        *bindings = Bindings::defaults(layout).with_overrides(&overrides);
+    }
+}
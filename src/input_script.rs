@@ -0,0 +1,343 @@
+use std::time::Duration;
+
+use bevy::input::InputSystem;
+use bevy::prelude::*;
+use bevy::time::TimeUpdateStrategy;
+use serde::{Deserialize, Serialize};
+
+use crate::input::{Action, ActionSet, ActionState};
+use crate::menu::GameState;
+use crate::player::RespawnPoint;
+use crate::Player;
+
+// every frame is one tick of this long while a script plays, so runs repeat exactly
+const TICK_SECS: f64 = 1.0 / 60.0;
+
+pub struct InputScriptPlugin;
+
+// plays an InputScript resource if one was put in place before the plugin was added.
+// with `--features cheats`, RECTFALL_INPUT_SCRIPT plays one from a file instead of the
+// real devices and RECTFALL_RECORD_INPUT records one from them
+impl Plugin for InputScriptPlugin {
+    fn build(&self, app: &mut App) {
+        match app.world_mut().remove_resource::<InputScript>() {
+            Some(script) => play(app, script),
+            #[cfg(feature = "cheats")]
+            None => backend::build(app),
+            #[cfg(not(feature = "cheats"))]
+            None => {}
+        }
+    }
+}
+
+// the keys the menus read directly instead of through actions
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Debug)]
+enum ScriptKey {
+    Escape,
+    Enter,
+    Up,
+    Down,
+    Left,
+    Right,
+}
+
+impl ScriptKey {
+    fn key_code(self) -> KeyCode {
+        match self {
+            ScriptKey::Escape => KeyCode::Escape,
+            ScriptKey::Enter => KeyCode::Enter,
+            ScriptKey::Up => KeyCode::ArrowUp,
+            ScriptKey::Down => KeyCode::ArrowDown,
+            ScriptKey::Left => KeyCode::ArrowLeft,
+            ScriptKey::Right => KeyCode::ArrowRight,
+        }
+    }
+}
+
+// actions held for a number of ticks, keys tapped on the first of them
+#[derive(Serialize, Deserialize, Default)]
+struct Step {
+    ticks: u32,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    hold: Vec<Action>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    tap: Vec<ScriptKey>,
+}
+
+// distances are in pixels from where the player stood when the script started
+#[derive(Serialize, Deserialize, Clone, Copy, Debug)]
+enum Check {
+    Checkpoint,
+    Right(f32),
+    Left(f32),
+    Up(f32),
+    MenuOpen,
+    MenuClosed,
+}
+
+// has to hold on some tick between after and within
+#[derive(Serialize, Deserialize)]
+struct Expectation {
+    check: Check,
+    #[serde(default)]
+    after: u32,
+    within: u32,
+}
+
+// a script can also be put in place as a resource before the game's plugins are added,
+// which is how the integration tests drive the game
+#[derive(Resource, Serialize, Deserialize, Default)]
+pub struct InputScript {
+    steps: Vec<Step>,
+    #[serde(default)]
+    expect: Vec<Expectation>,
+}
+
+// ticks only count once loading is over, so a script doesn't depend on how long
+// the assets took
+#[derive(Resource)]
+struct Playback {
+    script: InputScript,
+    tick: u32,
+    step: usize,
+    step_tick: u32,
+    met: Vec<bool>,
+    // the player's position and respawn point on the first tick
+    start: Option<(Vec2, Vec2)>,
+}
+
+impl Playback {
+    fn current(&self) -> Option<&Step> {
+        self.start?;
+        self.script.steps.get(self.step)
+    }
+}
+
+fn play(app: &mut App, script: InputScript) {
+    app.insert_resource(Playback {
+        met: vec![false; script.expect.len()],
+        script,
+        tick: 0,
+        step: 0,
+        step_tick: 0,
+        start: None,
+    })
+    .insert_resource(TimeUpdateStrategy::ManualDuration(Duration::from_secs_f64(
+        TICK_SECS,
+    )))
+    .add_systems(
+        PreUpdate,
+        (
+            tap_script_keys.after(InputSystem).before(ActionSet::Clear),
+            hold_script_actions
+                .after(ActionSet::Collect)
+                .before(ActionSet::Assist),
+        ),
+    )
+    .add_systems(PostUpdate, advance_playback);
+}
+
+fn tap_script_keys(playback: Res<Playback>, mut keyboard_input: ResMut<ButtonInput<KeyCode>>) {
+    keyboard_input.reset_all();
+    let Some(step) = playback.current() else {
+        return;
+    };
+    if playback.step_tick == 0 {
+        for key in &step.tap {
+            keyboard_input.press(key.key_code());
+        }
+    }
+}
+
+// runs after every device has had its say, so only the script is left
+fn hold_script_actions(playback: Res<Playback>, mut actions: ResMut<ActionState>) {
+    for action in Action::ALL {
+        actions.release(action);
+    }
+    let Some(step) = playback.current() else {
+        return;
+    };
+    for action in &step.hold {
+        actions.press(*action);
+    }
+}
+
+fn advance_playback(
+    mut playback: ResMut<Playback>,
+    player: Option<Single<&Transform, With<Player>>>,
+    respawn_point: Res<RespawnPoint>,
+    state: Res<State<GameState>>,
+    mut exit: EventWriter<AppExit>,
+) {
+    let position = player.map(|player| player.translation.truncate());
+    if playback.start.is_none() {
+        if *state.get() == GameState::Loading {
+            return;
+        }
+        let Some(position) = position else {
+            return;
+        };
+        playback.start = Some((position, respawn_point.0));
+    }
+    let Some((start, start_respawn)) = playback.start else {
+        return;
+    };
+    let Playback {
+        script, tick, met, ..
+    } = &mut *playback;
+    for (expectation, met) in script.expect.iter().zip(met.iter_mut()) {
+        if *met || *tick < expectation.after || *tick > expectation.within {
+            continue;
+        }
+        let moved = position.map_or(Vec2::ZERO, |position| position - start);
+        *met = match expectation.check {
+            Check::Checkpoint => respawn_point.0 != start_respawn,
+            Check::Right(distance) => moved.x >= distance,
+            Check::Left(distance) => -moved.x >= distance,
+            Check::Up(distance) => moved.y >= distance,
+            Check::MenuOpen => *state.get() == GameState::Paused,
+            Check::MenuClosed => *state.get() == GameState::Playing,
+        };
+        if *met {
+            info!("input script: {:?} met on tick {tick}", expectation.check);
+        }
+    }
+    let missed = script
+        .expect
+        .iter()
+        .zip(met.iter())
+        .find(|(expectation, met)| !**met && *tick >= expectation.within);
+    if let Some((expectation, _)) = missed {
+        error!(
+            "input script: {:?} not met within {} ticks",
+            expectation.check, expectation.within
+        );
+        exit.send(AppExit::error());
+        return;
+    }
+    *tick += 1;
+    playback.step_tick += 1;
+    let step_ticks = playback.current().map_or(0, |step| step.ticks);
+    if playback.step_tick >= step_ticks {
+        playback.step += 1;
+        playback.step_tick = 0;
+    }
+    let steps_done = playback.step >= playback.script.steps.len();
+    if steps_done && playback.met.iter().all(|met| *met) {
+        info!("input script passed after {} ticks", playback.tick);
+        exit.send(AppExit::Success);
+    }
+}
+
+#[cfg(feature = "cheats")]
+mod backend {
+    use std::path::PathBuf;
+
+    use bevy::prelude::*;
+
+    use super::{play, InputScript, ScriptKey, Step};
+    use crate::input::{Action, ActionSet, ActionState};
+    use crate::menu::GameState;
+
+    const PLAYBACK_ENV: &str = "RECTFALL_INPUT_SCRIPT";
+    const RECORD_ENV: &str = "RECTFALL_RECORD_INPUT";
+    const RECORDED_KEYS: [ScriptKey; 6] = [
+        ScriptKey::Escape,
+        ScriptKey::Enter,
+        ScriptKey::Up,
+        ScriptKey::Down,
+        ScriptKey::Left,
+        ScriptKey::Right,
+    ];
+
+    #[derive(Resource)]
+    struct Recording {
+        path: PathBuf,
+        script: InputScript,
+        started: bool,
+    }
+
+    pub fn build(app: &mut App) {
+        if let Ok(path) = std::env::var(PLAYBACK_ENV) {
+            let script = std::fs::read_to_string(&path)
+                .map_err(|err| err.to_string())
+                .and_then(|text| {
+                    ron::from_str::<InputScript>(&text).map_err(|err| err.to_string())
+                });
+            match script {
+                Ok(script) => {
+                    warn!("playing input script {path}, real input is ignored");
+                    play(app, script);
+                }
+                Err(err) => error!("couldn't read input script {path}: {err}"),
+            }
+        } else if let Ok(path) = std::env::var(RECORD_ENV) {
+            warn!("recording input to {path}");
+            app.insert_resource(Recording {
+                path: path.into(),
+                script: InputScript::default(),
+                started: false,
+            })
+            .add_systems(
+                PreUpdate,
+                record_input
+                    .after(ActionSet::Collect)
+                    .before(ActionSet::Assist),
+            )
+            .add_systems(Last, save_recording);
+        }
+    }
+
+    // consecutive ticks holding the same actions share one step
+    fn record_input(
+        mut recording: ResMut<Recording>,
+        actions: Res<ActionState>,
+        keyboard_input: Res<ButtonInput<KeyCode>>,
+        state: Res<State<GameState>>,
+    ) {
+        if !recording.started {
+            if *state.get() == GameState::Loading {
+                return;
+            }
+            recording.started = true;
+        }
+        let hold: Vec<Action> = Action::ALL
+            .into_iter()
+            .filter(|action| actions.pressed(*action))
+            .collect();
+        let tap: Vec<ScriptKey> = RECORDED_KEYS
+            .into_iter()
+            .filter(|key| keyboard_input.just_pressed(key.key_code()))
+            .collect();
+        let steps = &mut recording.script.steps;
+        match steps.last_mut() {
+            Some(last) if last.hold == hold && tap.is_empty() => last.ticks += 1,
+            _ => steps.push(Step {
+                ticks: 1,
+                hold,
+                tap,
+            }),
+        }
+    }
+
+    fn save_recording(mut exits: EventReader<AppExit>, recording: Res<Recording>) {
+        if exits.read().count() == 0 {
+            return;
+        }
+        let text = match ron::ser::to_string_pretty(&recording.script, default()) {
+            Ok(text) => text,
+            Err(err) => {
+                error!("couldn't serialize the input recording: {err}");
+                return;
+            }
+        };
+        match std::fs::write(&recording.path, text) {
+            Ok(()) => info!(
+                "saved {} input steps to {}",
+                recording.script.steps.len(),
+                recording.path.display()
+            ),
+            Err(err) => error!("couldn't save the input recording: {err}"),
+        }
+    }
+}
@@ -0,0 +1,184 @@
+use std::collections::VecDeque;
+
+use bevy::ecs::entity::EntityHashMap;
+use bevy::prelude::*;
+
+use crate::input::{Action, ActionState};
+use crate::menu::GameState;
+use crate::settings::Settings;
+use crate::spawner::FallingRect;
+use crate::{GameSet, Player, PlayerDied};
+
+const BUFFER_SECS: f32 = 3.0;
+// rects further from the player than this aren't worth recording
+const RECORD_RADIUS: f32 = 900.0;
+const REPLAY_SPEED: f32 = 0.35;
+// close in on the player with the camera rolled a little
+const REPLAY_ZOOM: f32 = 0.6;
+const REPLAY_ROLL_DEGREES: f32 = 4.0;
+
+pub struct KillCamPlugin;
+
+impl Plugin for KillCamPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<KillCam>()
+            .add_systems(Update, record_frames.in_set(GameSet::Reactions))
+            .add_systems(OnEnter(GameState::DeathCam), start_kill_cam)
+            .add_systems(Update, play_kill_cam.run_if(in_state(GameState::DeathCam)));
+    }
+}
+
+struct Frame {
+    time: f32,
+    player: Entity,
+    transforms: EntityHashMap<Transform>,
+}
+
+// the last few seconds of the run, played back slowly before the death camera zooms out
+#[derive(Resource, Default)]
+pub struct KillCam {
+    frames: VecDeque<Frame>,
+    // seconds into the recording, None once the replay is over
+    playback: Option<f32>,
+    // how everything looked when the replay started, put back when it ends
+    camera: Transform,
+    camera_scale: f32,
+    restore: EntityHashMap<(Transform, Visibility)>,
+}
+
+impl KillCam {
+    pub fn is_playing(&self) -> bool {
+        self.playback.is_some()
+    }
+}
+
+fn record_frames(
+    mut kill_cam: ResMut<KillCam>,
+    mut died: EventReader<PlayerDied>,
+    player: Single<(Entity, &Transform), With<Player>>,
+    rects: Query<(Entity, &Transform), With<FallingRect>>,
+    time: Res<Time>,
+) {
+    if died.read().count() > 0 {
+        kill_cam.frames.clear();
+        return;
+    }
+    let now = time.elapsed_secs();
+    while kill_cam
+        .frames
+        .front()
+        .is_some_and(|frame| frame.time < now - BUFFER_SECS)
+    {
+        kill_cam.frames.pop_front();
+    }
+    let (player, player_transform) = *player;
+    let center = player_transform.translation.truncate();
+    let mut transforms: EntityHashMap<Transform> = rects
+        .iter()
+        .filter(|(_, transform)| transform.translation.truncate().distance(center) <= RECORD_RADIUS)
+        .map(|(entity, transform)| (entity, *transform))
+        .collect();
+    transforms.insert(player, *player_transform);
+    kill_cam.frames.push_back(Frame {
+        time: now,
+        player,
+        transforms,
+    });
+}
+
+fn start_kill_cam(
+    mut kill_cam: ResMut<KillCam>,
+    settings: Res<Settings>,
+    entities: Query<(&Transform, &Visibility)>,
+    camera: Single<(&Transform, &OrthographicProjection), With<Camera>>,
+) {
+    // a moving, rolling camera is the opposite of reduced motion
+    if settings.reduce_motion || kill_cam.frames.len() < 2 {
+        return;
+    }
+    let (camera_transform, projection) = *camera;
+    kill_cam.camera = *camera_transform;
+    kill_cam.camera_scale = projection.scale;
+    let restore: EntityHashMap<(Transform, Visibility)> = kill_cam
+        .frames
+        .iter()
+        .flat_map(|frame| frame.transforms.keys())
+        .filter_map(|entity| Some((*entity, entities.get(*entity).ok()?)))
+        .map(|(entity, (transform, visibility))| (entity, (*transform, *visibility)))
+        .collect();
+    kill_cam.restore = restore;
+    kill_cam.playback = Some(0.0);
+    debug!(target: "player", "replaying the last {} frames", kill_cam.frames.len());
+}
+
+// moves the recorded entities by hand, physics is paused along with virtual time
+pub fn play_kill_cam(
+    mut kill_cam: ResMut<KillCam>,
+    actions: Res<ActionState>,
+    mut entities: Query<(&mut Transform, &mut Visibility), Without<Camera>>,
+    camera: Single<(&mut Transform, &mut OrthographicProjection), With<Camera>>,
+    time: Res<Time<Real>>,
+) {
+    let Some(mut playback) = kill_cam.playback else {
+        return;
+    };
+    let (mut camera_transform, mut projection) = camera.into_inner();
+    let (Some(first), Some(last)) = (kill_cam.frames.front(), kill_cam.frames.back()) else {
+        return;
+    };
+    let start = first.time;
+    let duration = last.time - start;
+    playback += time.delta_secs() * REPLAY_SPEED;
+    if playback >= duration || actions.just_pressed(Action::Jump) {
+        for (entity, (transform, visibility)) in &kill_cam.restore {
+            if let Ok((mut current, mut current_visibility)) = entities.get_mut(*entity) {
+                *current = *transform;
+                *current_visibility = *visibility;
+            }
+        }
+        *camera_transform = kill_cam.camera;
+        projection.scale = kill_cam.camera_scale;
+        kill_cam.playback = None;
+        return;
+    }
+    kill_cam.playback = Some(playback);
+    // the frames on both sides of the playhead, blended since the replay is slowed down
+    let next = kill_cam
+        .frames
+        .iter()
+        .position(|frame| frame.time - start > playback)
+        .unwrap_or(kill_cam.frames.len() - 1);
+    let (before, after) = (
+        &kill_cam.frames[next.saturating_sub(1)],
+        &kill_cam.frames[next],
+    );
+    let t = if after.time > before.time {
+        ((playback - (before.time - start)) / (after.time - before.time)).clamp(0.0, 1.0)
+    } else {
+        1.0
+    };
+    for (entity, (original, visibility)) in &kill_cam.restore {
+        let Ok((mut transform, mut current_visibility)) = entities.get_mut(*entity) else {
+            continue;
+        };
+        // not spawned yet, or out of reach at this point in the recording
+        let Some(from) = before.transforms.get(entity) else {
+            *current_visibility = Visibility::Hidden;
+            continue;
+        };
+        let to = after.transforms.get(entity).unwrap_or(from);
+        *transform = Transform {
+            translation: from.translation.lerp(to.translation, t),
+            rotation: from.rotation.slerp(to.rotation, t),
+            scale: original.scale,
+        };
+        *current_visibility = *visibility;
+    }
+    if let Some(from) = before.transforms.get(&before.player) {
+        let to = after.transforms.get(&before.player).unwrap_or(from);
+        let focus = from.translation.lerp(to.translation, t);
+        camera_transform.translation = focus.with_z(kill_cam.camera.translation.z);
+        camera_transform.rotation = Quat::from_rotation_z(REPLAY_ROLL_DEGREES.to_radians());
+        projection.scale = kill_cam.camera_scale * REPLAY_ZOOM;
+    }
+}
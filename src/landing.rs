@@ -0,0 +1,104 @@
+use avian2d::prelude::*;
+use bevy::prelude::*;
+
+use crate::input::{Action, ActionSet, ActionState};
+use crate::settings::Settings;
+use crate::{GameSet, Player, PlayerDied, PlayerLanded};
+
+// impacts at or above this fall speed count as a hard landing
+const HARD_LANDING_IMPACT: f32 = 1200.0;
+const LANDING_LAG_SECS: f32 = 0.25;
+
+pub struct LandingPlugin;
+
+impl Plugin for LandingPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<LandingState>()
+            .add_systems(PreUpdate, apply_landing_lag.after(ActionSet::Assist))
+            .add_systems(FixedPreUpdate, track_air_velocity.in_set(GameSet::Movement))
+            .add_systems(Update, react_to_landing.in_set(GameSet::Reactions));
+    }
+}
+
+#[derive(Resource, Default)]
+struct LandingState {
+    // horizontal speed right before touching the ground, friction eats it on contact
+    air_velocity_x: f32,
+    lag: f32,
+}
+
+fn track_air_velocity(player: Single<(&Player, &LinearVelocity)>, mut state: ResMut<LandingState>) {
+    let (player, linear) = player.into_inner();
+    if player.ground_normal.is_none() {
+        state.air_velocity_x = linear.x;
+    }
+}
+
+fn react_to_landing(
+    mut landed: EventReader<PlayerLanded>,
+    mut died: EventReader<PlayerDied>,
+    settings: Res<Settings>,
+    mut state: ResMut<LandingState>,
+    mut linear: Single<&mut LinearVelocity, With<Player>>,
+) {
+    if died.read().count() > 0 {
+        state.lag = 0.0;
+    }
+    for PlayerLanded { impact } in landed.read() {
+        if settings.landing_momentum {
+            linear.x = state.air_velocity_x;
+        }
+        if settings.landing_lag && *impact >= HARD_LANDING_IMPACT {
+            debug!(target: "player", "hard landing, impact {impact:.0}");
+            state.lag = LANDING_LAG_SECS;
+        }
+    }
+}
+
+// the player can't move or jump while recovering, pressing down rolls out of it
+fn apply_landing_lag(
+    mut actions: ResMut<ActionState>,
+    mut state: ResMut<LandingState>,
+    time: Res<Time>,
+) {
+    if state.lag <= 0.0 {
+        return;
+    }
+    if actions.just_pressed(Action::Down) {
+        debug!(target: "player", "rolled out of a hard landing");
+        state.lag = 0.0;
+        return;
+    }
+    state.lag -= time.delta_secs();
+    for action in [Action::Left, Action::Right, Action::Jump] {
+        actions.release(action);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::drop_player;
+
+    fn lag_after_drop(height: f32) -> f32 {
+        let mut app = drop_player(height);
+        app.init_resource::<LandingState>()
+            .add_systems(Update, react_to_landing.after(crate::handle_collision));
+        app.world_mut().resource_mut::<Settings>().landing_lag = true;
+        for _ in 0..150 {
+            app.update();
+        }
+        app.world().resource::<LandingState>().lag
+    }
+
+    #[test]
+    fn hard_landings_lag() {
+        // HARD_LANDING_IMPACT is reached after falling impact² / 2g
+        assert!(lag_after_drop(1000.0) > 0.0);
+    }
+
+    #[test]
+    fn soft_landings_dont_lag() {
+        assert_eq!(lag_after_drop(200.0), 0.0);
+    }
+}
@@ -0,0 +1,365 @@
+use bevy::prelude::*;
+use bevy::tasks::{block_on, poll_once, IoTaskPool, Task};
+use hmac::{Hmac, Mac};
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+
+use crate::http;
+use crate::i18n::Localization;
+use crate::menu::{MenuAction, MenuActivated, MenuPage, MenuRow, MenuRows, MenuSet};
+use crate::save::SaveData;
+use crate::score::RunStats;
+use crate::storage::{Persistent, StorageAppExt};
+use crate::{GameSet, PlayerDied};
+
+// both come from the environment at build time, builds without them never go online
+const LEADERBOARD_URL: Option<&str> = option_env!("RECTFALL_LEADERBOARD_URL");
+// shipped inside the binary, so it only keeps out casual tampering. the server still
+// has to check that scores are plausible
+const SIGNING_KEY: Option<&str> = option_env!("RECTFALL_LEADERBOARD_KEY");
+const RETRY_INTERVAL_SECS: f32 = 30.0;
+const TOP_COUNT: usize = 100;
+const AROUND_COUNT: usize = 10;
+const ROWS_PER_PAGE: usize = 10;
+
+pub struct LeaderboardPlugin;
+
+impl Plugin for LeaderboardPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_persistent_resource::<ScoreQueue>()
+            .init_resource::<Leaderboard>()
+            .add_systems(Startup, assign_player_id)
+            .add_systems(
+                Update,
+                (
+                    (queue_finished_runs, upload_queued_scores)
+                        .chain()
+                        .in_set(GameSet::Reactions),
+                    (
+                        refresh_leaderboard.in_set(MenuSet::Rows),
+                        leaderboard_menu_rows.in_set(MenuSet::Rows),
+                        apply_leaderboard_actions.in_set(MenuSet::Actions),
+                    ),
+                ),
+            );
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct ScoreEntry {
+    pub player: String,
+    pub score: u32,
+    pub height: f32,
+    pub time_secs: f32,
+    // runs aren't seeded or recorded yet, these get filled in once they are
+    pub seed: Option<u64>,
+    pub replay_hash: Option<String>,
+    pub version: String,
+    pub signature: String,
+}
+
+impl ScoreEntry {
+    fn payload(&self) -> String {
+        format!(
+            "{}|{}|{:.0}|{:.2}|{}|{}|{}",
+            self.player,
+            self.score,
+            self.height,
+            self.time_secs,
+            self.seed.map(|seed| seed.to_string()).unwrap_or_default(),
+            self.replay_hash.as_deref().unwrap_or_default(),
+            self.version
+        )
+    }
+
+    fn sign(&mut self, key: &str) {
+        let mut mac =
+            Hmac::<Sha256>::new_from_slice(key.as_bytes()).expect("hmac takes keys of any length");
+        mac.update(self.payload().as_bytes());
+        self.signature = hex::encode(mac.finalize().into_bytes());
+    }
+}
+
+#[derive(Deserialize, Clone, Debug)]
+pub struct RankedEntry {
+    pub rank: u32,
+    pub player: String,
+    pub score: u32,
+}
+
+// finished runs that haven't reached the server yet, kept across restarts
+#[derive(Resource, Serialize, Deserialize, Default)]
+#[serde(default)]
+struct ScoreQueue {
+    entries: Vec<ScoreEntry>,
+}
+
+impl Persistent for ScoreQueue {
+    const KEY: &'static str = "score_queue";
+    const VERSION: u32 = 1;
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Default)]
+enum LeaderboardView {
+    #[default]
+    Top,
+    AroundMe,
+}
+
+#[derive(Default)]
+enum Fetched {
+    #[default]
+    Nothing,
+    Loading(Task<Result<(Vec<RankedEntry>, Vec<RankedEntry>), String>>),
+    Ready {
+        top: Vec<RankedEntry>,
+        around_me: Vec<RankedEntry>,
+    },
+    Failed,
+}
+
+#[derive(Resource, Default)]
+struct Leaderboard {
+    view: LeaderboardView,
+    page: usize,
+    fetched: Fetched,
+    upload: Option<Task<Result<usize, String>>>,
+    // starts out due so anything queued last session goes up right away
+    since_upload: Option<f32>,
+}
+
+fn assign_player_id(mut save: ResMut<SaveData>) {
+    if save.player_id.is_empty() {
+        save.player_id = format!("{:016x}", rand::thread_rng().gen::<u64>());
+    }
+}
+
+// RunStats is already reset on the frame the player dies, so the run is remembered
+// from the frame before
+fn queue_finished_runs(
+    mut died: EventReader<PlayerDied>,
+    stats: Res<RunStats>,
+    save: Res<SaveData>,
+    mut queue: ResMut<ScoreQueue>,
+    mut last_run: Local<Option<(u32, f32, f32)>>,
+) {
+    if died.read().count() == 0 {
+        // assisted runs don't go on the board
+        *last_run = (!stats.assisted && stats.score > 0).then_some((
+            stats.score,
+            stats.max_height,
+            stats.time_survived,
+        ));
+        return;
+    }
+    let (Some((score, height, time_secs)), Some(key)) = (last_run.take(), SIGNING_KEY) else {
+        return;
+    };
+    let mut entry = ScoreEntry {
+        player: save.player_id.clone(),
+        score,
+        height,
+        time_secs,
+        seed: None,
+        replay_hash: None,
+        version: env!("CARGO_PKG_VERSION").to_string(),
+        signature: String::new(),
+    };
+    entry.sign(key);
+    queue.entries.push(entry);
+}
+
+fn upload_queued_scores(
+    mut leaderboard: ResMut<Leaderboard>,
+    mut queue: ResMut<ScoreQueue>,
+    time: Res<Time<Real>>,
+) {
+    let Some(url) = LEADERBOARD_URL else {
+        return;
+    };
+    if let Some(task) = &mut leaderboard.upload {
+        let Some(result) = block_on(poll_once(task)) else {
+            return;
+        };
+        leaderboard.upload = None;
+        match result {
+            Ok(sent) => {
+                queue.entries.drain(..sent);
+            }
+            Err(err) => debug!("leaderboard is unreachable, keeping scores queued: {err}"),
+        }
+    }
+    let now = time.elapsed_secs();
+    let due = leaderboard
+        .since_upload
+        .is_none_or(|last| now - last >= RETRY_INTERVAL_SECS);
+    if queue.entries.is_empty() || !due {
+        return;
+    }
+    leaderboard.since_upload = Some(now);
+    let entries = queue.entries.clone();
+    let url = format!("{url}/scores");
+    leaderboard.upload = Some(IoTaskPool::get().spawn(async move {
+        // one at a time so a failure halfway still lets the sent ones leave the queue
+        let mut sent = 0;
+        for entry in &entries {
+            let body = serde_json::to_string(entry).map_err(|err| err.to_string())?;
+            if let Err(err) = http::post(&url, body).await {
+                return if sent > 0 { Ok(sent) } else { Err(err) };
+            }
+            sent += 1;
+        }
+        Ok(sent)
+    }));
+}
+
+// fetches both views whenever the page is opened, and picks up the answer later
+fn refresh_leaderboard(
+    page: Res<MenuPage>,
+    save: Res<SaveData>,
+    mut leaderboard: ResMut<Leaderboard>,
+) {
+    if *page != MenuPage::Leaderboard {
+        if !matches!(leaderboard.fetched, Fetched::Nothing) {
+            leaderboard.fetched = Fetched::Nothing;
+        }
+        return;
+    }
+    match &mut leaderboard.fetched {
+        Fetched::Nothing => {
+            let Some(url) = LEADERBOARD_URL else {
+                leaderboard.fetched = Fetched::Failed;
+                return;
+            };
+            let top_url = format!("{url}/scores/top?limit={TOP_COUNT}");
+            let around_url = format!(
+                "{url}/scores/around/{}?count={AROUND_COUNT}",
+                save.player_id
+            );
+            leaderboard.fetched = Fetched::Loading(IoTaskPool::get().spawn(async move {
+                let top = http::get(&top_url).await?;
+                let around_me = http::get(&around_url).await?;
+                let parse = |body: String| {
+                    serde_json::from_str::<Vec<RankedEntry>>(&body).map_err(|err| err.to_string())
+                };
+                Ok::<_, String>((parse(top)?, parse(around_me)?))
+            }));
+        }
+        Fetched::Loading(task) => {
+            let Some(result) = block_on(poll_once(task)) else {
+                return;
+            };
+            leaderboard.fetched = match result {
+                Ok((top, around_me)) => Fetched::Ready { top, around_me },
+                Err(err) => {
+                    debug!("failed to fetch the leaderboard: {err}");
+                    Fetched::Failed
+                }
+            };
+        }
+        Fetched::Ready { .. } | Fetched::Failed => {}
+    }
+}
+
+fn leaderboard_menu_rows(
+    page: Res<MenuPage>,
+    leaderboard: Res<Leaderboard>,
+    queue: Res<ScoreQueue>,
+    save: Res<SaveData>,
+    localization: Res<Localization>,
+    mut rows: ResMut<MenuRows>,
+) {
+    if *page != MenuPage::Leaderboard {
+        return;
+    }
+    match &leaderboard.fetched {
+        Fetched::Nothing | Fetched::Loading(_) => {
+            rows.push(MenuRow::new(
+                localization.get("leaderboard.loading"),
+                MenuAction::None,
+            ));
+        }
+        Fetched::Failed => {
+            rows.push(MenuRow::new(
+                localization.format("leaderboard.offline", &[("count", &queue.entries.len())]),
+                MenuAction::None,
+            ));
+        }
+        Fetched::Ready { top, around_me } => {
+            let (label, entries) = match leaderboard.view {
+                LeaderboardView::Top => {
+                    let pages = top.len().div_ceil(ROWS_PER_PAGE).max(1);
+                    let page = leaderboard.page.min(pages - 1);
+                    let label = localization.format(
+                        "leaderboard.top",
+                        &[("page", &(page + 1)), ("pages", &pages)],
+                    );
+                    let start = (page * ROWS_PER_PAGE).min(top.len());
+                    let end = (start + ROWS_PER_PAGE).min(top.len());
+                    (label, &top[start..end])
+                }
+                LeaderboardView::AroundMe => (
+                    localization.get("leaderboard.around_me").to_string(),
+                    around_me.as_slice(),
+                ),
+            };
+            rows.push(MenuRow::new(label, MenuAction::CycleLeaderboardPage));
+            if entries.is_empty() {
+                rows.push(MenuRow::new(
+                    localization.get("leaderboard.empty"),
+                    MenuAction::None,
+                ));
+            }
+            for entry in entries {
+                let mark = if entry.player == save.player_id {
+                    ">"
+                } else {
+                    " "
+                };
+                rows.push(MenuRow::new(
+                    localization.format(
+                        "leaderboard.entry",
+                        &[
+                            ("mark", &mark),
+                            ("rank", &entry.rank),
+                            ("player", &entry.player),
+                            ("score", &entry.score),
+                        ],
+                    ),
+                    MenuAction::None,
+                ));
+            }
+        }
+    }
+    rows.push(MenuRow::new(
+        localization.get("menu.back"),
+        MenuAction::Back,
+    ));
+}
+
+// left and right flip through the top 100 and then over to the scores around the player
+fn apply_leaderboard_actions(
+    mut activated: EventReader<MenuActivated>,
+    mut leaderboard: ResMut<Leaderboard>,
+) {
+    for event in activated.read() {
+        if event.action != MenuAction::CycleLeaderboardPage {
+            continue;
+        }
+        let pages = TOP_COUNT.div_ceil(ROWS_PER_PAGE);
+        // the around me view sits one past the last page of the top 100
+        let current = match leaderboard.view {
+            LeaderboardView::Top => leaderboard.page,
+            LeaderboardView::AroundMe => pages,
+        };
+        let delta = if event.delta == 0 { 1 } else { event.delta };
+        let next = (current as i32 + delta).rem_euclid(pages as i32 + 1) as usize;
+        if next == pages {
+            leaderboard.view = LeaderboardView::AroundMe;
+        } else {
+            leaderboard.view = LeaderboardView::Top;
+            leaderboard.page = next;
+        }
+    }
+}
@@ -0,0 +1,427 @@
+use bevy::asset::io::Reader;
+use bevy::asset::{AssetLoader, LoadContext};
+use bevy::prelude::*;
+use bevy::utils::HashMap;
+use serde::{Deserialize, Serialize};
+
+use avian2d::prelude::*;
+
+use crate::asset_check::TrackedAssets;
+use crate::console::{Console, ConsoleAppExt, ConsoleCommand};
+use crate::hud::{ShowBanner, ShowToast};
+use crate::i18n::Localization;
+use crate::menu::{
+    main_menu_rows, GameState, MenuAction, MenuActivated, MenuPage, MenuRow, MenuRows, MenuSet,
+};
+use crate::player::RespawnPoint;
+use crate::prefab::{Checkpoint, PrefabParams, Prefabs, Spike, TerrainBlock};
+use crate::score::RunStats;
+use crate::spawner::{FallingRect, Spawner};
+use crate::surface::SurfaceMaterial;
+use crate::{
+    spawn_floor, spawn_wall, Player, PlayerJumped, PlayerLanded, PlayerTouchedWall,
+    PlayerWallJumped, PLAYER_SPAWN,
+};
+
+// (name, path)
+const LEVELS: &[(&str, &str)] = &[("tutorial", "levels/tutorial.level.ron")];
+const TUTORIAL: &str = "tutorial";
+
+pub struct LevelPlugin;
+
+impl Plugin for LevelPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_asset::<Level>()
+            .init_asset_loader::<LevelLoader>()
+            .init_resource::<ActiveLevel>()
+            .add_event::<LoadLevel>()
+            .add_console_command("level", "level [name|none]")
+            .add_systems(Startup, load_levels)
+            .add_systems(
+                Update,
+                (run_level_command, load_requested_level, run_level_script).chain(),
+            )
+            .add_systems(
+                Update,
+                (
+                    tutorial_menu_row
+                        .in_set(MenuSet::Rows)
+                        .after(main_menu_rows),
+                    apply_tutorial_action.in_set(MenuSet::Actions),
+                ),
+            );
+    }
+}
+
+// swaps the arena for a level by name, None brings the arena back
+#[derive(Event)]
+pub struct LoadLevel(pub Option<String>);
+
+// an authored level replacing the arena: prefabs to spawn, and a script of steps the
+// player works through in order
+#[derive(Asset, TypePath, Deserialize, Clone)]
+pub struct Level {
+    pub spawn: (f32, f32),
+    // where the camera sits while the level is up, it stays put when not set
+    #[serde(default)]
+    pub camera: Option<(f32, f32)>,
+    pub pieces: Vec<LevelPiece>,
+    #[serde(default)]
+    pub script: Vec<ScriptStep>,
+    // locale key for the banner once the last step is done
+    #[serde(default)]
+    pub done: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct LevelPiece {
+    pub prefab: String,
+    pub position: (f32, f32),
+    #[serde(default)]
+    pub size: Option<(f32, f32)>,
+    // degrees
+    #[serde(default)]
+    pub rotation: Option<f32>,
+    // goes away when a script step opens this id
+    #[serde(default)]
+    pub gate: Option<String>,
+    // for finding the piece in the editor
+    #[serde(default)]
+    pub name: Option<String>,
+    #[serde(default)]
+    pub tags: Vec<String>,
+}
+
+// waits for `count` of the event, then opens the gate. the hint is a locale key shown
+// when the step comes up
+#[derive(Deserialize, Clone, Debug)]
+pub struct ScriptStep {
+    #[serde(default)]
+    pub hint: Option<String>,
+    pub on: ScriptEvent,
+    #[serde(default = "default_count")]
+    pub count: u32,
+    #[serde(default)]
+    pub open: Option<String>,
+}
+
+fn default_count() -> u32 {
+    1
+}
+
+// the gameplay events a script can wait for
+#[derive(Deserialize, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum ScriptEvent {
+    Jumped,
+    WallJumped,
+    TouchedWall,
+    Landed,
+}
+
+#[derive(Default)]
+struct LevelLoader;
+
+impl AssetLoader for LevelLoader {
+    type Asset = Level;
+    type Settings = ();
+    type Error = std::io::Error;
+
+    async fn load(
+        &self,
+        reader: &mut dyn Reader,
+        _settings: &(),
+        _load_context: &mut LoadContext<'_>,
+    ) -> Result<Level, Self::Error> {
+        let mut bytes = Vec::new();
+        reader.read_to_end(&mut bytes).await?;
+        ron::de::from_bytes(&bytes)
+            .map_err(|err| std::io::Error::new(std::io::ErrorKind::InvalidData, err))
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["level.ron"]
+    }
+}
+
+#[derive(Resource)]
+struct LevelHandles(HashMap<&'static str, Handle<Level>>);
+
+#[derive(Resource, Default)]
+struct ActiveLevel {
+    // waiting for its file to load, Some(None) is going back to the arena
+    pending: Option<Option<String>>,
+    name: Option<String>,
+    script: Vec<ScriptStep>,
+    done: Option<String>,
+    step: usize,
+    // events seen for the current step
+    seen: u32,
+    // where the camera was before the level moved it
+    camera_home: Option<Vec3>,
+}
+
+#[derive(Component)]
+struct LevelEntity;
+
+// the piece a level entity was spawned from, its position is wherever the entity is now
+#[derive(Component)]
+pub struct Placed(pub LevelPiece);
+
+#[derive(Component)]
+struct LevelGate(String);
+
+fn load_levels(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    mut tracked: ResMut<TrackedAssets>,
+) {
+    let handles: HashMap<&'static str, Handle<Level>> = LEVELS
+        .iter()
+        .map(|(name, path)| (*name, asset_server.load(*path)))
+        .collect();
+    for handle in handles.values() {
+        tracked.track(handle.clone());
+    }
+    commands.insert_resource(LevelHandles(handles));
+}
+
+fn run_level_command(
+    mut commands: EventReader<ConsoleCommand>,
+    mut console: ResMut<Console>,
+    mut load: EventWriter<LoadLevel>,
+) {
+    for command in commands.read().filter(|command| command.name == "level") {
+        match command.args.first().map(String::as_str) {
+            Some("none") => {
+                load.send(LoadLevel(None));
+                console.print("back to the arena");
+            }
+            Some(name) if LEVELS.iter().any(|(level, _)| *level == name) => {
+                load.send(LoadLevel(Some(name.to_string())));
+                console.print(format!("loading level {name}"));
+            }
+            _ => {
+                let names: Vec<&str> = LEVELS.iter().map(|(name, _)| *name).collect();
+                console.print(format!("levels: {}, none", names.join(", ")));
+            }
+        }
+    }
+}
+
+// everything the arena or the last level put in the world goes, the player stays and
+// gets moved to the new start
+fn load_requested_level(
+    mut commands: Commands,
+    mut requests: EventReader<LoadLevel>,
+    mut active: ResMut<ActiveLevel>,
+    state: Res<State<GameState>>,
+    handles: Res<LevelHandles>,
+    levels: Res<Assets<Level>>,
+    tracked: Res<TrackedAssets>,
+    prefabs: Res<Prefabs>,
+    world: Query<
+        Entity,
+        Or<(
+            With<TerrainBlock>,
+            With<Spike>,
+            With<Checkpoint>,
+            With<SurfaceMaterial>,
+            With<FallingRect>,
+            With<LevelEntity>,
+        )>,
+    >,
+    player: Single<(&mut Transform, &mut LinearVelocity), With<Player>>,
+    mut camera: Single<&mut Transform, (With<Camera>, Without<Player>)>,
+    mut respawn_point: ResMut<RespawnPoint>,
+    mut spawner: ResMut<Spawner>,
+    mut stats: ResMut<RunStats>,
+    mut toasts: EventWriter<ShowToast>,
+    localization: Res<Localization>,
+) {
+    if let Some(LoadLevel(name)) = requests.read().last() {
+        active.pending = Some(name.clone());
+    }
+    if *state.get() != GameState::Playing {
+        return;
+    }
+    let Some(name) = active.pending.clone() else {
+        return;
+    };
+    let level = match &name {
+        Some(name) => {
+            let Some(handle) = handles.0.get(name.as_str()) else {
+                active.pending = None;
+                return;
+            };
+            if tracked.failed(handle) {
+                active.pending = None;
+                return;
+            }
+            let Some(level) = levels.get(handle) else {
+                return;
+            };
+            Some(level.clone())
+        }
+        None => None,
+    };
+    active.pending = None;
+    for entity in &world {
+        commands.entity(entity).despawn_recursive();
+    }
+    let (mut transform, mut linear) = player.into_inner();
+    linear.0 = Vec2::ZERO;
+    let Some(level) = level else {
+        debug!("leaving level {:?} for the arena", active.name);
+        commands.run_system_cached(spawn_floor);
+        commands.run_system_cached(spawn_wall);
+        transform.translation = PLAYER_SPAWN.extend(transform.translation.z);
+        respawn_point.0 = PLAYER_SPAWN;
+        if let Some(home) = active.camera_home {
+            camera.translation = home;
+        }
+        spawner.scripted = false;
+        *active = ActiveLevel::default();
+        return;
+    };
+    debug!("loading level {name:?}, {} pieces", level.pieces.len());
+    for piece in &level.pieces {
+        if spawn_level_piece(&mut commands, &prefabs, piece).is_none() {
+            warn!("level {name:?} has an unknown prefab {}", piece.prefab);
+        }
+    }
+    let spawn = Vec2::from(level.spawn);
+    transform.translation = spawn.extend(transform.translation.z);
+    respawn_point.0 = spawn;
+    if let Some(position) = level.camera {
+        let home = *active.camera_home.get_or_insert(camera.translation);
+        camera.translation = Vec2::from(position).extend(home.z);
+    }
+    // scripted levels are practice, they stay off the leaderboard
+    stats.assisted = true;
+    spawner.scripted = true;
+    if let Some(hint) = level.script.first().and_then(|step| step.hint.as_ref()) {
+        toasts.send(ShowToast(localization.get(hint).to_string()));
+    }
+    *active = ActiveLevel {
+        name,
+        script: level.script,
+        done: level.done,
+        camera_home: active.camera_home,
+        ..default()
+    };
+}
+
+pub fn spawn_level_piece(
+    commands: &mut Commands,
+    prefabs: &Prefabs,
+    piece: &LevelPiece,
+) -> Option<Entity> {
+    let params = PrefabParams {
+        position: Vec2::from(piece.position),
+        size: piece.size.map(Vec2::from),
+        rotation: piece.rotation.map(f32::to_radians),
+        ..default()
+    };
+    let entity = prefabs.spawn(&piece.prefab, commands, &params)?;
+    let mut entity_commands = commands.entity(entity);
+    entity_commands.insert((LevelEntity, Placed(piece.clone())));
+    if let Some(gate) = &piece.gate {
+        entity_commands.insert(LevelGate(gate.clone()));
+    }
+    if let Some(name) = &piece.name {
+        entity_commands.insert(Name::new(name.clone()));
+    }
+    Some(entity)
+}
+
+// the step in progress counts its event, and once it's seen enough it opens its gate
+// and the next step comes up
+fn run_level_script(
+    mut commands: Commands,
+    mut active: ResMut<ActiveLevel>,
+    mut jumped: EventReader<PlayerJumped>,
+    mut wall_jumped: EventReader<PlayerWallJumped>,
+    mut touched_wall: EventReader<PlayerTouchedWall>,
+    mut landed: EventReader<PlayerLanded>,
+    gates: Query<(Entity, &LevelGate)>,
+    localization: Res<Localization>,
+    mut toasts: EventWriter<ShowToast>,
+    mut banners: EventWriter<ShowBanner>,
+) {
+    let events = [
+        (ScriptEvent::Jumped, jumped.read().count()),
+        (ScriptEvent::WallJumped, wall_jumped.read().count()),
+        (ScriptEvent::TouchedWall, touched_wall.read().count()),
+        (ScriptEvent::Landed, landed.read().count()),
+    ];
+    let Some(step) = active.script.get(active.step).cloned() else {
+        return;
+    };
+    let seen = events
+        .iter()
+        .filter(|(event, _)| *event == step.on)
+        .map(|(_, count)| *count as u32)
+        .sum::<u32>();
+    active.seen += seen;
+    if active.seen < step.count {
+        return;
+    }
+    if let Some(open) = &step.open {
+        debug!("level script opening {open}");
+        for (entity, gate) in &gates {
+            if gate.0 == *open {
+                commands.entity(entity).despawn_recursive();
+            }
+        }
+    }
+    active.step += 1;
+    active.seen = 0;
+    match active.script.get(active.step) {
+        Some(next) => {
+            if let Some(hint) = &next.hint {
+                toasts.send(ShowToast(localization.get(hint).to_string()));
+            }
+        }
+        None => {
+            if let Some(done) = &active.done {
+                banners.send(ShowBanner(localization.get(done).to_string()));
+            }
+        }
+    }
+}
+
+fn tutorial_menu_row(
+    page: Res<MenuPage>,
+    active: Res<ActiveLevel>,
+    localization: Res<Localization>,
+    mut rows: ResMut<MenuRows>,
+) {
+    if *page != MenuPage::Main {
+        return;
+    }
+    let key = if active.name.as_deref() == Some(TUTORIAL) {
+        "menu.leave_tutorial"
+    } else {
+        "menu.tutorial"
+    };
+    rows.push(MenuRow::new(
+        localization.get(key),
+        MenuAction::ToggleTutorial,
+    ));
+}
+
+fn apply_tutorial_action(
+    mut activated: EventReader<MenuActivated>,
+    active: Res<ActiveLevel>,
+    mut load: EventWriter<LoadLevel>,
+    mut next_state: ResMut<NextState<GameState>>,
+) {
+    for event in activated.read() {
+        if event.action != MenuAction::ToggleTutorial || event.delta != 0 {
+            continue;
+        }
+        let leaving = active.name.as_deref() == Some(TUTORIAL);
+        load.send(LoadLevel((!leaving).then(|| TUTORIAL.to_string())));
+        next_state.set(GameState::Playing);
+    }
+}
@@ -0,0 +1,1087 @@
+use avian2d::prelude::*;
+use bevy::input::mouse::{AccumulatedMouseMotion, AccumulatedMouseScroll};
+use bevy::prelude::*;
+use bevy::utils::{HashMap, HashSet};
+use bevy::window::PrimaryWindow;
+
+mod achievements;
+mod afk;
+mod afterimage;
+mod ambience;
+mod asset_check;
+mod assist;
+mod attractor;
+mod background;
+mod bake;
+mod boss;
+mod branding;
+mod camera_intro;
+mod cheats;
+mod climb;
+mod clipboard;
+mod collision_layer;
+mod common_assets;
+mod config;
+mod console;
+mod cosmetics;
+mod crash;
+mod cutscene;
+mod damage_feedback;
+mod deathcam;
+mod director;
+mod discord;
+mod editor;
+mod explosion;
+mod focus;
+mod free_camera;
+mod freeze;
+mod glide;
+mod glyphs;
+mod grab;
+mod haptics;
+mod health;
+mod heatmap;
+mod highlight;
+mod history;
+mod hit_stop;
+mod http;
+mod hud;
+mod i18n;
+mod input;
+mod input_script;
+mod killcam;
+mod landing;
+mod leaderboard;
+mod level;
+mod line_clear;
+mod logging;
+mod menu;
+mod meta;
+mod npc;
+mod pace;
+mod palette;
+mod patterns;
+mod player;
+mod popups;
+mod practice;
+mod prefab;
+mod preload;
+mod push_pull;
+mod quality;
+mod rebind;
+mod rumble;
+mod save;
+mod score;
+mod screen_reader;
+mod settings;
+mod sfx;
+mod shake;
+mod shockwave;
+mod shop;
+mod spawner;
+mod stats;
+mod steam;
+mod stick_preview;
+mod stomp;
+mod storage;
+mod storms;
+mod surface;
+mod telegraph;
+mod telemetry;
+mod touch;
+mod trajectory;
+mod ui_layout;
+mod ui_sounds;
+mod voices;
+mod wear;
+mod z_layer;
+
+pub use config::{CameraPanConfig, GameConfig, WallJumpConfig};
+pub use director::DifficultyConfig;
+pub use input_script::InputScript;
+pub use level::LoadLevel;
+pub use spawner::SpawnerConfig;
+pub use storage::{PersistentStorage, Storage};
+
+use achievements::AchievementsPlugin;
+use afk::AfkPlugin;
+use afterimage::AfterimagePlugin;
+use ambience::AmbiencePlugin;
+use asset_check::AssetCheckPlugin;
+use assist::{coyote_secs, AssistPlugin};
+use attractor::AttractorPlugin;
+use background::BackgroundPlugin;
+use bake::BakePlugin;
+use boss::BossPlugin;
+use branding::BrandingPlugin;
+use camera_intro::CameraIntroPlugin;
+use cheats::CheatsPlugin;
+use climb::ClimbPlugin;
+use collision_layer::solid_mask;
+use common_assets::CommonAssetsPlugin;
+use console::ConsolePlugin;
+use cosmetics::CosmeticsPlugin;
+use crash::CrashPlugin;
+use cutscene::CutscenePlugin;
+use damage_feedback::DamageFeedbackPlugin;
+use deathcam::DeathCamPlugin;
+use director::DirectorPlugin;
+use discord::DiscordPlugin;
+use editor::EditorPlugin;
+use explosion::ExplosionPlugin;
+use focus::FocusPlugin;
+use free_camera::{FreeCamera, FreeCameraPlugin};
+use freeze::FreezePlugin;
+use glide::GlidePlugin;
+use glyphs::GlyphsPlugin;
+use grab::GrabPlugin;
+use haptics::HapticsPlugin;
+use health::HealthPlugin;
+use heatmap::HeatmapPlugin;
+use highlight::HighlightPlugin;
+use history::HistoryPlugin;
+use hit_stop::HitStopPlugin;
+use hud::HudPlugin;
+use i18n::I18nPlugin;
+use input::{Action, ActionState, InputPlugin};
+use input_script::InputScriptPlugin;
+use killcam::KillCamPlugin;
+use landing::LandingPlugin;
+use leaderboard::LeaderboardPlugin;
+use level::LevelPlugin;
+use line_clear::LineClearPlugin;
+use logging::GameLogPlugin;
+use menu::{GameState, MenuPlugin};
+use meta::MetaPlugin;
+use npc::NpcPlugin;
+use pace::PacePlugin;
+use palette::PalettePlugin;
+use patterns::PatternsPlugin;
+use player::PlayerLifecyclePlugin;
+use popups::PopupsPlugin;
+use practice::PracticePlugin;
+use prefab::{PrefabParams, PrefabPlugin, Prefabs};
+use preload::PreloadPlugin;
+use push_pull::PushPullPlugin;
+use quality::QualityPlugin;
+use rebind::RebindPlugin;
+use rumble::RumblePlugin;
+use save::SavePlugin;
+use score::ScorePlugin;
+use screen_reader::ScreenReaderPlugin;
+use settings::{Settings, SettingsPlugin};
+use sfx::SfxPlugin;
+use shake::ShakePlugin;
+use shockwave::ShockwavePlugin;
+use shop::{RunUpgrades, ShopPlugin};
+use spawner::{FallingRect, SpawnerPlugin, WeightClass};
+use stats::StatsPlugin;
+use steam::SteamPlugin;
+use stick_preview::StickPreviewPlugin;
+use stomp::StompPlugin;
+use storage::StoragePlugin;
+use storms::StormsPlugin;
+use surface::SurfaceMaterial;
+use telegraph::TelegraphPlugin;
+use telemetry::TelemetryPlugin;
+use touch::TouchControlsPlugin;
+use ui_layout::UiLayoutPlugin;
+use ui_sounds::UiSoundsPlugin;
+use voices::VoicesPlugin;
+use wear::WearPlugin;
+use z_layer::ZLayerPlugin;
+
+const PLAYER_SPAWN: Vec2 = Vec2::new(-300.0, 0.0);
+const KILL_PLANE_Y: f32 = -1500.0;
+// steepest surface that still counts as ground, anything steeper (but not a wall) slides
+const MAX_SLOPE_DEGREES: f32 = 50.0;
+const SLIDE_ACCELERATION: f32 = 1000.0;
+// lips lower than this get stepped over instead of stopping the player
+const MAX_STEP_HEIGHT: f32 = 12.0;
+const STEP_PROBE_DISTANCE: f32 = 4.0;
+
+// the whole game as one plugin, so it can be embedded in other bevy apps. the host adds
+// DefaultPlugins first, GameConfig::default_plugins has them set up the way the game
+// expects
+pub struct RectfallPlugin {
+    config: GameConfig,
+}
+
+impl RectfallPlugin {
+    pub fn new(config: GameConfig) -> RectfallPlugin {
+        RectfallPlugin { config }
+    }
+}
+
+impl Default for RectfallPlugin {
+    fn default() -> RectfallPlugin {
+        RectfallPlugin::new(GameConfig::default())
+    }
+}
+
+impl Plugin for RectfallPlugin {
+    fn build(&self, app: &mut App) {
+        // the spawner and director only init their configs, so these win
+        app.insert_resource(self.config.clone())
+            .insert_resource(self.config.spawner.clone())
+            .insert_resource(self.config.difficulty.clone())
+            // interpolation is opted into per entity: falling rects get it when spawned and
+            // the player gets whatever the smoothing setting asks for. static terrain never
+            // moves, so it doesn't pay for it
+            .add_plugins(PhysicsPlugins::default().set(PhysicsInterpolationPlugin::default()))
+            .add_plugins(PhysicsDebugPlugin::default())
+            .add_plugins((
+                SteamPlugin,
+                StoragePlugin,
+                CrashPlugin,
+                SettingsPlugin,
+                SavePlugin,
+                MenuPlugin,
+                PalettePlugin,
+                SpawnerPlugin,
+                TelegraphPlugin,
+                GrabPlugin,
+                BackgroundPlugin,
+                ScorePlugin,
+                CosmeticsPlugin,
+            ))
+            .add_plugins((
+                HudPlugin,
+                AchievementsPlugin,
+                StatsPlugin,
+                I18nPlugin,
+                InputPlugin,
+                TouchControlsPlugin,
+                HapticsPlugin,
+                GlyphsPlugin,
+                AssistPlugin,
+                ShakePlugin,
+                HealthPlugin,
+                SfxPlugin,
+                ConsolePlugin,
+                GameLogPlugin,
+                AssetCheckPlugin,
+            ))
+            .add_plugins((
+                CommonAssetsPlugin,
+                PlayerLifecyclePlugin,
+                PrefabPlugin,
+                LandingPlugin,
+                ClimbPlugin,
+                LineClearPlugin,
+                ExplosionPlugin,
+                AttractorPlugin,
+                StormsPlugin,
+                DirectorPlugin,
+                DamageFeedbackPlugin,
+                DiscordPlugin,
+                LeaderboardPlugin,
+                TelemetryPlugin,
+                CheatsPlugin,
+            ))
+            .add_plugins((
+                ScreenReaderPlugin,
+                UiLayoutPlugin,
+                PopupsPlugin,
+                HitStopPlugin,
+                AfterimagePlugin,
+                AmbiencePlugin,
+                ZLayerPlugin,
+                WearPlugin,
+                HighlightPlugin,
+                FreezePlugin,
+                StompPlugin,
+                PushPullPlugin,
+            ))
+            .add_plugins((
+                PatternsPlugin,
+                BossPlugin,
+                ShockwavePlugin,
+                PracticePlugin,
+                CutscenePlugin,
+                NpcPlugin,
+                ShopPlugin,
+                MetaPlugin,
+                DeathCamPlugin,
+                KillCamPlugin,
+                VoicesPlugin,
+                PreloadPlugin,
+                RumblePlugin,
+                UiSoundsPlugin,
+                InputScriptPlugin,
+            ))
+            .add_plugins((
+                AfkPlugin,
+                BakePlugin,
+                BrandingPlugin,
+                CameraIntroPlugin,
+                EditorPlugin,
+                FocusPlugin,
+                FreeCameraPlugin,
+                GlidePlugin,
+                HeatmapPlugin,
+                HistoryPlugin,
+                LevelPlugin,
+                PacePlugin,
+                QualityPlugin,
+                RebindPlugin,
+                StickPreviewPlugin,
+            ))
+            .add_event::<PlayerJumped>()
+            .add_event::<PlayerLanded>()
+            .add_event::<PlayerLeftGround>()
+            .add_event::<PlayerTouchedWall>()
+            .add_event::<PlayerWallJumped>()
+            .add_event::<PlayerCrushed>()
+            .add_event::<PlayerKilled>()
+            .add_event::<PlayerDied>()
+            .insert_resource(Gravity(Vec2::NEG_Y * self.config.gravity))
+            .configure_sets(
+                Update,
+                (
+                    GameSet::Input,
+                    GameSet::Movement,
+                    GameSet::PhysicsSync,
+                    GameSet::Reactions,
+                    GameSet::Vfx,
+                )
+                    .chain(),
+            )
+            .configure_sets(
+                Update,
+                (
+                    GameSet::Input,
+                    GameSet::Movement,
+                    GameSet::PhysicsSync,
+                    GameSet::Reactions,
+                )
+                    .run_if(in_state(GameState::Playing)),
+            )
+            .configure_sets(
+                FixedPreUpdate,
+                GameSet::Movement.run_if(in_state(GameState::Playing)),
+            )
+            .configure_sets(
+                FixedPostUpdate,
+                GameSet::PhysicsSync.run_if(in_state(GameState::Playing)),
+            )
+            .add_systems(Startup, (setup, spawn_floor, spawn_wall, spawn_player))
+            .add_systems(Update, move_camera.in_set(GameSet::Input))
+            .add_systems(
+                Update,
+                (handle_collision, start_coyote_time, check_player_death)
+                    .chain()
+                    .in_set(GameSet::PhysicsSync),
+            )
+            .add_systems(
+                FixedPreUpdate,
+                (move_player, step_up_ledges)
+                    .chain()
+                    .in_set(GameSet::Movement),
+            )
+            .add_systems(
+                FixedPostUpdate,
+                player_fast_falling.in_set(GameSet::PhysicsSync),
+            );
+    }
+}
+
+// gameplay systems go in one of these instead of floating around in Update. the fixed
+// schedules use Movement (FixedPreUpdate) and PhysicsSync (FixedPostUpdate) as well.
+// everything but Vfx stops while the game is paused.
+#[derive(SystemSet, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+enum GameSet {
+    // reacting to ActionState and raw input collected in PreUpdate
+    Input,
+    Movement,
+    // reading physics results: contacts, sleeping bodies, kill plane
+    PhysicsSync,
+    // gameplay events turning into score, health, sounds, spawns...
+    Reactions,
+    Vfx,
+}
+
+#[derive(Component)]
+struct Player {
+    can_jump: bool,
+    started_jump_press_duration: f32,
+    finished_jump_press: bool,
+    is_attached_to_wall: bool,
+    // which way is off the wall the player is attached to, -1 or 1
+    wall_away: f32,
+    wall_jump_chain: u32,
+    // the side and time of the last wall jump since landing
+    last_wall_jump: Option<(f32, f32)>,
+    // wall jumps in a row that switched sides
+    alternating_wall_jumps: u32,
+    // how far past move_speed a held jump keeps pushing
+    jump_scale: f32,
+    fall_speed: f32,
+    air_time: f32,
+    // surface normals from this frame's contacts
+    ground_normal: Option<Vec2>,
+    steep_normal: Option<Vec2>,
+    wall_normal: Option<Vec2>,
+    // what's under the player's feet this frame
+    ground_surface: Option<SurfaceMaterial>,
+    // holding onto a wall, set by the climb system before movement runs
+    climbing: bool,
+    // holding onto a loose rect, which takes over grab from climbing
+    pulling: bool,
+    // falling under the canopy, set by the glide system after movement runs
+    gliding: bool,
+}
+
+impl Player {
+    fn reset_jump(&mut self) {
+        *self = Player {
+            ground_normal: self.ground_normal,
+            steep_normal: self.steep_normal,
+            wall_normal: self.wall_normal,
+            ground_surface: self.ground_surface,
+            climbing: self.climbing,
+            pulling: self.pulling,
+            ..Default::default()
+        };
+    }
+}
+
+impl Default for Player {
+    fn default() -> Player {
+        Player {
+            can_jump: false,
+            started_jump_press_duration: 0.0,
+            finished_jump_press: false,
+            is_attached_to_wall: false,
+            wall_away: 0.0,
+            wall_jump_chain: 0,
+            last_wall_jump: None,
+            alternating_wall_jumps: 0,
+            jump_scale: 1.0,
+            fall_speed: 0.0,
+            air_time: 0.0,
+            ground_normal: None,
+            steep_normal: None,
+            wall_normal: None,
+            ground_surface: None,
+            climbing: false,
+            pulling: false,
+            gliding: false,
+        }
+    }
+}
+
+#[derive(Event)]
+struct PlayerJumped;
+
+#[derive(Event)]
+struct PlayerLanded {
+    impact: f32,
+}
+
+// the last thing under the player's feet stopped touching them
+#[derive(Event)]
+struct PlayerLeftGround;
+
+// a new wall contact, the wall's side is in Player::wall_normal
+#[derive(Event)]
+struct PlayerTouchedWall;
+
+#[derive(Event)]
+struct PlayerWallJumped {
+    chain: u32,
+}
+
+#[derive(Event)]
+struct PlayerCrushed {
+    rect: Entity,
+    rect_size: Vec2,
+    class: WeightClass,
+}
+
+// out of health or off the map. the death camera plays before this turns into PlayerDied
+#[derive(Event)]
+struct PlayerKilled {
+    // the rect that crushed the player, if that's what did it
+    by: Option<Entity>,
+}
+
+// the run is over and everything resets for the next one
+#[derive(Event)]
+struct PlayerDied;
+
+fn max_slope_cos() -> f32 {
+    MAX_SLOPE_DEGREES.to_radians().cos()
+}
+
+fn handle_player_collision(player: &mut Player, contact_normal: Vec2) {
+    let dot = contact_normal.dot(Vec2::NEG_Y);
+    if dot.abs() < 0.1 {
+        // wall
+        trace!(target: "physics", "wall contact, normal {contact_normal}");
+        player.is_attached_to_wall = true;
+        player.wall_away = -contact_normal.x.signum();
+        player.wall_normal = Some(-contact_normal);
+    } else if dot >= max_slope_cos() {
+        // ground, flat or a walkable slope
+        trace!(target: "physics", "ground contact, normal {contact_normal}");
+        player.reset_jump();
+        player.can_jump = true;
+        player.ground_normal = Some(-contact_normal);
+    } else if dot < -0.9 {
+        // ceiling
+        trace!(target: "physics", "ceiling contact, normal {contact_normal}");
+        player.reset_jump();
+        player.can_jump = true;
+    } else if dot > 0.0 {
+        trace!(target: "physics", "steep slope contact, normal {contact_normal}");
+        player.steep_normal = Some(-contact_normal);
+    }
+}
+
+// the player's contacts, kept up to date from collision events instead of scanning every
+// collision in the world each frame. each one is marked once its first real (not
+// speculative) contact has been seen, which is when the enter events go out
+#[derive(Component, Default)]
+struct PlayerContacts(HashMap<Entity, bool>);
+
+impl PlayerContacts {
+    fn touching(&self) -> impl Iterator<Item = Entity> + '_ {
+        self.0.keys().copied()
+    }
+}
+
+// a thin sensor under the player's feet, a child of the player. it overlaps the ground a
+// little before and after the body touches it, so leaving the ground isn't missed when
+// the last contact turns speculative
+#[derive(Component, Default)]
+struct GroundSensor(HashSet<Entity>);
+
+fn handle_collision(
+    mut started: EventReader<CollisionStarted>,
+    mut ended: EventReader<CollisionEnded>,
+    collisions: Res<Collisions>,
+    single: Single<(Entity, &mut Player, &mut PlayerContacts)>,
+    sensor: Single<(Entity, &mut GroundSensor)>,
+    sensors: Query<(), With<Sensor>>,
+    rects: Query<(&ColliderAabb, &WeightClass), With<FallingRect>>,
+    surfaces: Query<&SurfaceMaterial>,
+    mut landed: EventWriter<PlayerLanded>,
+    mut left_ground: EventWriter<PlayerLeftGround>,
+    mut touched_wall: EventWriter<PlayerTouchedWall>,
+    mut crushed: EventWriter<PlayerCrushed>,
+) {
+    let (player_entt, mut player, mut contacts) = single.into_inner();
+    let (sensor_entt, mut ground) = sensor.into_inner();
+    let was_grounded = !ground.0.is_empty();
+    let ours = |a: Entity, b: Entity| {
+        if a == player_entt || a == sensor_entt {
+            Some((a, b))
+        } else if b == player_entt || b == sensor_entt {
+            Some((b, a))
+        } else {
+            None
+        }
+    };
+    for CollisionStarted(a, b) in started.read() {
+        let Some((this, other)) = ours(*a, *b) else {
+            continue;
+        };
+        // checkpoints and other triggers aren't something to stand on
+        if sensors.contains(other) {
+            continue;
+        }
+        if this == player_entt {
+            contacts.0.insert(other, false);
+        } else {
+            ground.0.insert(other);
+        }
+    }
+    for CollisionEnded(a, b) in ended.read() {
+        let Some((this, other)) = ours(*a, *b) else {
+            continue;
+        };
+        if this == player_entt {
+            contacts.0.remove(&other);
+        } else {
+            ground.0.remove(&other);
+        }
+    }
+    // a despawned body doesn't always end its collisions
+    contacts
+        .0
+        .retain(|other, _| collisions.get(player_entt, *other).is_some());
+    ground
+        .0
+        .retain(|other| collisions.get(sensor_entt, *other).is_some());
+    if was_grounded && ground.0.is_empty() {
+        debug!(target: "player", "left the ground");
+        left_ground.send(PlayerLeftGround);
+    }
+    player.ground_normal = None;
+    player.steep_normal = None;
+    player.wall_normal = None;
+    player.ground_surface = None;
+    for (other, seen) in contacts.0.iter_mut() {
+        let Some(coll) = collisions.get(player_entt, *other) else {
+            continue;
+        };
+        let Some(contact_data) = coll.find_deepest_contact() else {
+            continue;
+        };
+        // ignore speculative collisions
+        if contact_data.penetration < 0.0 {
+            continue;
+        }
+        // the player never rotates, so its local normal is already in world space
+        // even when the other body is tilted
+        let player_contact_normal = if coll.entity1 == player_entt {
+            contact_data.normal1
+        } else {
+            contact_data.normal2
+        };
+        let entered = !*seen;
+        *seen = true;
+        // a ground contact resets the jump, fall_speed included, so keep it for the event
+        let impact = player.fall_speed;
+        handle_player_collision(&mut player, player_contact_normal);
+        let dot = player_contact_normal.dot(Vec2::NEG_Y);
+        if dot >= max_slope_cos() {
+            let surface = surfaces.get(*other).copied().unwrap_or_default();
+            player.ground_surface = Some(player.ground_surface.map_or(surface, |s| s.max(surface)));
+        }
+        if !entered {
+            continue;
+        }
+        if dot >= max_slope_cos() {
+            debug!(target: "player", "landed, impact {impact:.0}");
+            landed.send(PlayerLanded { impact });
+        } else if dot.abs() < 0.1 {
+            trace!(target: "player", "touched a wall");
+            touched_wall.send(PlayerTouchedWall);
+        }
+        // a falling rect landing on top of the player. tumbling pieces can come down
+        // on a corner or a tilted face, so anything within the slope limit counts
+        if player_contact_normal.dot(Vec2::Y) >= max_slope_cos() {
+            if let Ok((aabb, class)) = rects.get(*other) {
+                debug!(target: "player", "crushed by a {} {class:?} rect", aabb.size());
+                crushed.send(PlayerCrushed {
+                    rect: *other,
+                    rect_size: aabb.size(),
+                    class: *class,
+                });
+            }
+        }
+    }
+}
+
+// the body's contacts can let go a frame before the feet do when walking off an edge, so
+// the coyote window is counted from the sensor leaving the ground
+fn start_coyote_time(
+    mut left_ground: EventReader<PlayerLeftGround>,
+    mut player: Single<&mut Player>,
+) {
+    if left_ground.read().last().is_some() {
+        player.air_time = 0.0;
+    }
+}
+
+fn can_wall_jump(player: &Player, actions: &ActionState, rules: &WallJumpConfig, now: f32) -> bool {
+    let too_soon = player.last_wall_jump.is_some_and(|(side, at)| {
+        side == player.wall_away && now - at < rules.same_wall_cooldown_secs
+    });
+    let away = if player.wall_away > 0.0 {
+        Action::Right
+    } else {
+        Action::Left
+    };
+    !too_soon && (!rules.require_direction || actions.pressed(away))
+}
+
+fn move_player(
+    actions: Res<ActionState>,
+    config: Res<GameConfig>,
+    settings: Res<Settings>,
+    upgrades: Res<RunUpgrades>,
+    query: Single<(&mut LinearVelocity, &mut Player)>,
+    time: Res<Time>,
+    mut jumped: EventWriter<PlayerJumped>,
+    mut wall_jumped: EventWriter<PlayerWallJumped>,
+) {
+    let (mut linear, mut player) = query.into_inner();
+    let delta_secs = time.delta_secs();
+    player.fall_speed = player.fall_speed.max(-linear.y);
+    // grounded frames reset air_time, so can_jump only outlives the ground by the coyote window
+    player.air_time += delta_secs;
+    if player.can_jump && player.air_time > coyote_secs(&settings) + upgrades.extra_coyote_secs() {
+        player.can_jump = false;
+    }
+    let mut direction = Vec2::ZERO;
+    {
+        // while climbing, jump climbs up instead of jumping off the wall
+        if actions.pressed(Action::Jump) && !player.climbing {
+            if player.can_jump {
+                player.can_jump = false;
+                player.started_jump_press_duration = delta_secs;
+                // a coyote jump is off solid ground, whatever the player walked off of
+                let surface = player.ground_surface.unwrap_or_default();
+                player.jump_scale = surface.jump_factor();
+                debug!(target: "player", "jumped off {surface:?}");
+                jumped.send(PlayerJumped);
+                if linear.y < config.move_speed * player.jump_scale {
+                    direction.y = config.jump_boost;
+                }
+            } else if !player.finished_jump_press && player.started_jump_press_duration > 0.5 {
+                player.finished_jump_press = true;
+            } else if player.started_jump_press_duration > 0.0 && !player.finished_jump_press {
+                player.started_jump_press_duration += delta_secs;
+                if linear.y < config.move_speed * player.jump_scale {
+                    direction.y = config.jump_boost;
+                }
+            } else if player.is_attached_to_wall
+                && can_wall_jump(&player, &actions, &config.wall_jump, time.elapsed_secs())
+            {
+                let rules = &config.wall_jump;
+                let side = player.wall_away;
+                let switched = player
+                    .last_wall_jump
+                    .is_some_and(|(last_side, _)| last_side != side);
+                player.alternating_wall_jumps = if switched {
+                    (player.alternating_wall_jumps + 1).min(rules.max_alternating_steps)
+                } else {
+                    0
+                };
+                player.jump_scale =
+                    1.0 + rules.alternating_height_step * player.alternating_wall_jumps as f32;
+                player.last_wall_jump = Some((side, time.elapsed_secs()));
+                player.is_attached_to_wall = false;
+                player.started_jump_press_duration = delta_secs;
+                player.finished_jump_press = false;
+                if linear.y < config.move_speed * player.jump_scale {
+                    direction.y = config.jump_boost;
+                }
+                direction.x += config.jump_boost * upgrades.wall_jump_factor() * side;
+                player.wall_jump_chain += 1;
+                debug!(
+                    target: "player",
+                    "wall jumped, chain {}, {} switching sides",
+                    player.wall_jump_chain,
+                    player.alternating_wall_jumps
+                );
+                wall_jumped.send(PlayerWallJumped {
+                    chain: player.wall_jump_chain,
+                });
+            }
+        } else if player.started_jump_press_duration > 0.0 {
+            player.finished_jump_press = true;
+        }
+        // a half tilted stick runs at half speed
+        if actions.pressed(Action::Right)
+            && linear.x < config.move_speed * actions.amount(Action::Right)
+        {
+            direction.x += config.acceleration;
+        }
+        if actions.pressed(Action::Left)
+            && -linear.x < config.move_speed * actions.amount(Action::Left)
+        {
+            direction.x -= config.acceleration;
+        }
+    }
+    if let Some(normal) = player.ground_normal {
+        // run along the slope instead of into it or off its edge
+        let tangent = -normal.perp();
+        if direction.y == 0.0 {
+            let into_slope = linear.dot(normal);
+            linear.0 -= normal * into_slope;
+        }
+        direction = Vec2::new(0.0, direction.y) + tangent * direction.x;
+    } else if let Some(normal) = player.steep_normal {
+        // too steep to stand on, so gravity pulls the player down along it
+        let downhill = Vec2::NEG_Y.reject_from_normalized(normal);
+        linear.0 += downhill * SLIDE_ACCELERATION * delta_secs;
+    }
+    let move_delta = 100.0 * direction * delta_secs;
+    if move_delta != Vec2::ZERO {
+        linear.0 += move_delta;
+    }
+}
+
+// the player is a box, so it snags on the edge of every settled rect it runs into.
+// a ray at the feet that hits something while one at step height doesn't means a low
+// ledge, which gets climbed if there's room above the player's head
+fn step_up_ledges(
+    actions: Res<ActionState>,
+    spatial_query: SpatialQuery,
+    player: Single<(&mut Transform, &Player)>,
+) {
+    let (mut transform, player) = player.into_inner();
+    if player.ground_normal.is_none() {
+        return;
+    }
+    let direction = match (
+        actions.pressed(Action::Left),
+        actions.pressed(Action::Right),
+    ) {
+        (false, true) => Dir2::X,
+        (true, false) => Dir2::NEG_X,
+        _ => return,
+    };
+    let filter = SpatialQueryFilter::from_mask(solid_mask());
+    let cast = |origin: Vec2, direction: Dir2, distance: f32| {
+        spatial_query.cast_ray(origin, direction, distance, true, &filter)
+    };
+    let position = transform.translation.truncate();
+    let half_size = transform.scale.truncate() / 2.0;
+    let feet_y = position.y - half_size.y;
+    let reach = half_size.x + STEP_PROBE_DISTANCE;
+    if cast(Vec2::new(position.x, feet_y + 1.0), direction, reach).is_none()
+        || cast(
+            Vec2::new(position.x, feet_y + MAX_STEP_HEIGHT),
+            direction,
+            reach,
+        )
+        .is_some()
+    {
+        return;
+    }
+    // find the top of the ledge right in front of the player
+    let above_ledge = Vec2::new(position.x + direction.x * reach, feet_y + MAX_STEP_HEIGHT);
+    let Some(hit) = cast(above_ledge, Dir2::NEG_Y, MAX_STEP_HEIGHT) else {
+        return;
+    };
+    let step = MAX_STEP_HEIGHT - hit.distance;
+    if step <= 0.0 {
+        return;
+    }
+    let head = Vec2::new(position.x, position.y + half_size.y);
+    if cast(head, Dir2::Y, step).is_some() {
+        return;
+    }
+    trace!(target: "player", "stepping up a {step:.1} ledge");
+    transform.translation.y += step;
+}
+
+fn player_fast_falling(
+    query: Single<(&mut Transform, &LinearVelocity, &mut Player)>,
+    config: Res<GameConfig>,
+    time: Res<Time>,
+) {
+    let (mut transform, linear, player) = query.into_inner();
+    let delta = time.delta_secs();
+    if player.started_jump_press_duration > 0.0
+        && linear.y < 0.0
+        && !player.climbing
+        && !player.gliding
+    {
+        transform.translation.y -= (config.gravity / 2.0) * delta * delta
+    }
+}
+
+fn check_player_death(
+    player: Single<&Transform, With<Player>>,
+    mut killed: EventWriter<PlayerKilled>,
+) {
+    if player.translation.y < KILL_PLANE_Y {
+        debug!(target: "player", "fell below the kill plane");
+        killed.send(PlayerKilled { by: None });
+    }
+}
+
+fn move_camera(
+    mouse_input: Res<ButtonInput<MouseButton>>,
+    mouse_motion: Res<AccumulatedMouseMotion>,
+    mouse_scroll: Res<AccumulatedMouseScroll>,
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    window: Single<&Window, With<PrimaryWindow>>,
+    free_camera: Res<FreeCamera>,
+    config: Res<GameConfig>,
+    time: Res<Time<Real>>,
+    camera: Single<(&mut Transform, &mut OrthographicProjection), With<Camera>>,
+    interactions: Query<&Interaction>,
+) {
+    let (mut transform, mut projection) = camera.into_inner();
+    projection.scale *= 1. - mouse_scroll.delta.y * 0.05;
+    // clicking through a panel like the editor's shouldn't drag or push the view
+    let over_ui = interactions
+        .iter()
+        .any(|interaction| *interaction != Interaction::None);
+    if mouse_input.pressed(MouseButton::Left) {
+        if !over_ui {
+            let move_delta =
+                Vec2::new(-mouse_motion.delta.x, mouse_motion.delta.y) * projection.scale;
+            transform.translation += move_delta.extend(0.0);
+        }
+        return;
+    }
+    if !free_camera.0 {
+        return;
+    }
+    let pan = &config.camera_pan;
+    let mut velocity = Vec2::ZERO;
+    if pan.key_panning {
+        for (key, direction) in [
+            (KeyCode::ArrowLeft, Vec2::NEG_X),
+            (KeyCode::ArrowRight, Vec2::X),
+            (KeyCode::ArrowUp, Vec2::Y),
+            (KeyCode::ArrowDown, Vec2::NEG_Y),
+        ] {
+            if keyboard_input.pressed(key) {
+                velocity += direction * pan.key_speed;
+            }
+        }
+    }
+    // window y goes down
+    if let Some(cursor) = window
+        .cursor_position()
+        .filter(|_| pan.edge_panning && !over_ui)
+    {
+        let size = window.size();
+        let edge = Vec2::new(
+            edge_push(cursor.x, size.x, pan.edge_margin),
+            -edge_push(cursor.y, size.y, pan.edge_margin),
+        );
+        velocity += edge * pan.edge_speed;
+    }
+    transform.translation += (velocity * projection.scale * time.delta_secs()).extend(0.0);
+}
+
+// -1 at the low edge, 1 at the high one, 0 anywhere further in than the margin
+fn edge_push(position: f32, size: f32, margin: f32) -> f32 {
+    if position < margin {
+        -1.0
+    } else if position > size - margin {
+        1.0
+    } else {
+        0.0
+    }
+}
+
+fn setup(mut commands: Commands) {
+    commands.spawn((Name::new("Camera"), Camera2d));
+}
+
+fn spawn_floor(mut commands: Commands, prefabs: Res<Prefabs>) {
+    let params = PrefabParams {
+        position: Vec2::new(0.0, -300.0),
+        size: Some(Vec2::new(1000.0, 100.0)),
+        ..default()
+    };
+    if let Some(floor) = prefabs.spawn("block", &mut commands, &params) {
+        commands.entity(floor).insert(Name::new("Floor"));
+    }
+}
+
+fn spawn_wall(mut commands: Commands, prefabs: Res<Prefabs>) {
+    let params = PrefabParams {
+        position: Vec2::new(100.0, -300.0),
+        size: Some(Vec2::new(100.0, 1000.0)),
+        ..default()
+    };
+    if let Some(wall) = prefabs.spawn("block", &mut commands, &params) {
+        commands.entity(wall).insert(Name::new("RightWall"));
+    }
+}
+
+fn spawn_player(mut commands: Commands, prefabs: Res<Prefabs>, config: Res<GameConfig>) {
+    let params = PrefabParams {
+        position: PLAYER_SPAWN,
+        size: Some(config.player.size),
+        shape: config.player.shape,
+        ..default()
+    };
+    prefabs.spawn("player", &mut commands, &params);
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use bevy::scene::ScenePlugin;
+    use bevy::time::TimeUpdateStrategy;
+
+    use super::*;
+    use crate::collision_layer::{ground_sensor_layers, GameLayer};
+
+    #[derive(Resource, Default)]
+    struct Landings(Vec<f32>);
+
+    fn record_landings(mut landed: EventReader<PlayerLanded>, mut landings: ResMut<Landings>) {
+        landings
+            .0
+            .extend(landed.read().map(|landing| landing.impact));
+    }
+
+    // physics, a floor, and a bare player body with its ground sensor dropped from `height`
+    // above it. only the systems that move the player and read its contacts run
+    pub(crate) fn drop_player(height: f32) -> App {
+        let config = GameConfig::default();
+        let mut app = App::new();
+        app.add_plugins((
+            MinimalPlugins,
+            AssetPlugin::default(),
+            ScenePlugin,
+            TransformPlugin,
+            HierarchyPlugin,
+            PhysicsPlugins::default(),
+        ))
+        .init_resource::<Assets<Mesh>>()
+        .insert_resource(TimeUpdateStrategy::ManualDuration(Duration::from_secs_f64(
+            1.0 / 60.0,
+        )))
+        .insert_resource(Gravity(Vec2::NEG_Y * config.gravity))
+        .insert_resource(config)
+        .init_resource::<Settings>()
+        .init_resource::<RunUpgrades>()
+        .init_resource::<ActionState>()
+        .init_resource::<Landings>()
+        .add_event::<PlayerJumped>()
+        .add_event::<PlayerWallJumped>()
+        .add_event::<PlayerLanded>()
+        .add_event::<PlayerLeftGround>()
+        .add_event::<PlayerTouchedWall>()
+        .add_event::<PlayerCrushed>()
+        .add_event::<PlayerDied>()
+        .add_systems(FixedPreUpdate, move_player)
+        .add_systems(
+            Update,
+            (handle_collision, start_coyote_time, record_landings).chain(),
+        );
+        app.world_mut().spawn((
+            RigidBody::Static,
+            Collider::rectangle(1000.0, 100.0),
+            GameLayer::Terrain.layers(),
+            Transform::from_xyz(0.0, -50.0, 0.0),
+        ));
+        app.world_mut()
+            .spawn((
+                Player::default(),
+                PlayerContacts::default(),
+                RigidBody::Dynamic,
+                LockedAxes::ROTATION_LOCKED,
+                Collider::rectangle(1.0, 1.0),
+                GameLayer::Player.layers(),
+                Transform::from_xyz(0.0, 50.0 + height, 0.0).with_scale(Vec3::splat(100.0)),
+            ))
+            .with_child((
+                GroundSensor::default(),
+                Sensor,
+                Collider::rectangle(0.8, 0.1),
+                ground_sensor_layers(),
+                ColliderDensity(0.0),
+                Transform::from_xyz(0.0, -0.5, 0.0),
+            ));
+        app
+    }
+
+    #[test]
+    fn landing_reports_the_fall_speed() {
+        let mut app = drop_player(400.0);
+        for _ in 0..120 {
+            app.update();
+        }
+        let landings = &app.world().resource::<Landings>().0;
+        assert_eq!(landings.len(), 1, "landed {landings:?}");
+        // about sqrt(2 * gravity * height)
+        assert!(landings[0] > 700.0, "impact {}", landings[0]);
+    }
+}
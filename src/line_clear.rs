@@ -0,0 +1,168 @@
+use avian2d::prelude::*;
+use bevy::prelude::*;
+use bevy::utils::{HashMap, HashSet};
+use rand::Rng;
+
+use crate::common_assets::CommonAssets;
+use crate::settings::Settings;
+use crate::spawner::FallingRect;
+use crate::z_layer::ZLayer;
+use crate::{GameSet, PlayerDied};
+
+// the grid starts at the top of the floor and covers its whole width
+const GRID_ORIGIN_Y: f32 = -250.0;
+const GRID_HALF_WIDTH: f32 = 500.0;
+const CELL_WIDTH: f32 = 25.0;
+const BAND_HEIGHT: f32 = 50.0;
+// fraction of a band's cells that has to be covered for it to clear
+const FILL_THRESHOLD: f32 = 0.9;
+// gives the pile time to fall and settle again before the next clear
+const CLEAR_COOLDOWN_SECS: f32 = 1.0;
+const PARTICLES_PER_RECT: usize = 4;
+const PARTICLE_SIZE: f32 = 12.0;
+const PARTICLE_SPEED: f32 = 250.0;
+const PARTICLE_LIFETIME_SECS: f32 = 0.6;
+
+pub struct LineClearPlugin;
+
+impl Plugin for LineClearPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<LineClearState>()
+            .add_event::<LineCleared>()
+            .add_systems(
+                Update,
+                clear_full_bands
+                    .in_set(GameSet::Reactions)
+                    .run_if(|settings: Res<Settings>| settings.line_clear),
+            )
+            .add_systems(Update, update_clear_particles.in_set(GameSet::Vfx));
+    }
+}
+
+#[derive(Event)]
+pub struct LineCleared {
+    pub rects: usize,
+}
+
+#[derive(Resource, Default)]
+struct LineClearState {
+    cooldown: f32,
+}
+
+#[derive(Component)]
+struct ClearParticle {
+    velocity: Vec2,
+    age: f32,
+}
+
+fn columns() -> i32 {
+    (GRID_HALF_WIDTH * 2.0 / CELL_WIDTH) as i32
+}
+
+// cells whose center is covered by the box, as (band, column)
+fn covered_cells(aabb: &ColliderAabb) -> impl Iterator<Item = (i32, i32)> {
+    let first_column = ((aabb.min.x + GRID_HALF_WIDTH) / CELL_WIDTH - 0.5).ceil() as i32;
+    let last_column = ((aabb.max.x + GRID_HALF_WIDTH) / CELL_WIDTH - 0.5).floor() as i32;
+    let first_band = ((aabb.min.y - GRID_ORIGIN_Y) / BAND_HEIGHT - 0.5).ceil() as i32;
+    let last_band = ((aabb.max.y - GRID_ORIGIN_Y) / BAND_HEIGHT - 0.5).floor() as i32;
+    let first_column = first_column.max(0);
+    let last_column = last_column.min(columns() - 1);
+    (first_band.max(0)..=last_band)
+        .flat_map(move |band| (first_column..=last_column).map(move |column| (band, column)))
+}
+
+// only sleeping rects count, anything still moving is either falling or shifting around
+fn clear_full_bands(
+    mut commands: Commands,
+    mut state: ResMut<LineClearState>,
+    mut died: EventReader<PlayerDied>,
+    mut cleared: EventWriter<LineCleared>,
+    resting: Query<(Entity, &ColliderAabb), (With<FallingRect>, With<Sleeping>)>,
+    sleeping: Query<Entity, With<Sleeping>>,
+    settings: Res<Settings>,
+    assets: Res<CommonAssets>,
+    particles: Query<(), With<ClearParticle>>,
+    time: Res<Time>,
+) {
+    if died.read().count() > 0 {
+        state.cooldown = 0.0;
+    }
+    if state.cooldown > 0.0 {
+        state.cooldown -= time.delta_secs();
+        return;
+    }
+    let mut bands: HashMap<i32, HashSet<i32>> = HashMap::new();
+    for (_, aabb) in &resting {
+        for (band, column) in covered_cells(aabb) {
+            bands.entry(band).or_default().insert(column);
+        }
+    }
+    let needed = (columns() as f32 * FILL_THRESHOLD).ceil() as usize;
+    let Some(band) = bands
+        .iter()
+        .filter(|(_, columns)| columns.len() >= needed)
+        .map(|(band, _)| *band)
+        .min()
+    else {
+        return;
+    };
+    let bottom = GRID_ORIGIN_Y + band as f32 * BAND_HEIGHT;
+    let top = bottom + BAND_HEIGHT;
+    let mut rng = rand::thread_rng();
+    let mut particle_budget = settings
+        .particles
+        .max_particles()
+        .saturating_sub(particles.iter().len());
+    let mut removed = 0;
+    for (entity, aabb) in &resting {
+        let center = aabb.center();
+        if center.y < bottom || center.y >= top {
+            continue;
+        }
+        commands.entity(entity).despawn_recursive();
+        removed += 1;
+        for _ in 0..PARTICLES_PER_RECT.min(particle_budget) {
+            let angle = rng.gen_range(0.0..std::f32::consts::TAU);
+            commands.spawn((
+                Name::new("ClearParticle"),
+                ZLayer::Particles,
+                ClearParticle {
+                    velocity: Vec2::from_angle(angle) * PARTICLE_SPEED,
+                    age: 0.0,
+                },
+                Mesh2d(assets.rect.clone()),
+                MeshMaterial2d(assets.debris.clone()),
+                Transform::from_translation(center.extend(0.0))
+                    .with_scale(Vec3::splat(PARTICLE_SIZE)),
+            ));
+            particle_budget -= 1;
+        }
+    }
+    // wake the whole pile so everything above the band drops into the gap
+    for entity in &sleeping {
+        commands
+            .entity(entity)
+            .remove::<Sleeping>()
+            .insert(TimeSleeping(0.0));
+    }
+    debug!(target: "spawner", "cleared band {band}, {removed} rects");
+    state.cooldown = CLEAR_COOLDOWN_SECS;
+    cleared.send(LineCleared { rects: removed });
+}
+
+fn update_clear_particles(
+    mut commands: Commands,
+    mut particles: Query<(Entity, &mut ClearParticle, &mut Transform)>,
+    time: Res<Time>,
+) {
+    for (entity, mut particle, mut transform) in &mut particles {
+        particle.age += time.delta_secs();
+        if particle.age >= PARTICLE_LIFETIME_SECS {
+            commands.entity(entity).despawn();
+            continue;
+        }
+        transform.translation += (particle.velocity * time.delta_secs()).extend(0.0);
+        transform.scale =
+            Vec3::splat(PARTICLE_SIZE * (1.0 - particle.age / PARTICLE_LIFETIME_SECS));
+    }
+}
@@ -0,0 +1,87 @@
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use bevy::log::tracing_subscriber::layer::Context;
+use bevy::log::tracing_subscriber::Layer;
+use bevy::log::{Level, LogPlugin, DEFAULT_FILTER};
+use bevy::prelude::*;
+use bevy::utils::tracing::subscriber::Interest;
+use bevy::utils::tracing::{Metadata, Subscriber};
+
+use crate::console::{Console, ConsoleAppExt, ConsoleCommand};
+use crate::crash::RecentLogs;
+
+// one target per subsystem, e.g. debug!(target: "spawner", ...)
+const GAME_TARGETS: [&str; 4] = ["player", "physics", "spawner", "audio"];
+const LEVELS: [Level; 5] = [
+    Level::ERROR,
+    Level::WARN,
+    Level::INFO,
+    Level::DEBUG,
+    Level::TRACE,
+];
+const DEFAULT_LEVEL_INDEX: usize = 2;
+
+static GAME_LOG_LEVEL: AtomicUsize = AtomicUsize::new(DEFAULT_LEVEL_INDEX);
+
+// the env filter lets everything from the game targets through, the level is checked at
+// runtime by GameLogFilter so the console can change it. on wasm bevy already logs to the
+// browser console. everything that gets through is also kept for crash reports.
+pub fn log_plugin() -> LogPlugin {
+    let targets: Vec<String> = GAME_TARGETS
+        .iter()
+        .map(|target| format!("{target}=trace"))
+        .collect();
+    LogPlugin {
+        filter: format!("{DEFAULT_FILTER},{}", targets.join(",")),
+        custom_layer: |_| Some(Box::new(GameLogFilter.and_then(RecentLogs))),
+        ..default()
+    }
+}
+
+fn game_log_level() -> Level {
+    LEVELS[GAME_LOG_LEVEL.load(Ordering::Relaxed)]
+}
+
+struct GameLogFilter;
+
+impl<S: Subscriber> Layer<S> for GameLogFilter {
+    fn register_callsite(&self, metadata: &'static Metadata<'static>) -> Interest {
+        if GAME_TARGETS.contains(&metadata.target()) {
+            Interest::sometimes()
+        } else {
+            Interest::always()
+        }
+    }
+
+    fn enabled(&self, metadata: &Metadata<'_>, _: Context<'_, S>) -> bool {
+        !GAME_TARGETS.contains(&metadata.target()) || *metadata.level() <= game_log_level()
+    }
+}
+
+pub struct GameLogPlugin;
+
+impl Plugin for GameLogPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_console_command("log", "log [error|warn|info|debug|trace]")
+            .add_systems(Update, run_log_command);
+    }
+}
+
+fn run_log_command(mut commands: EventReader<ConsoleCommand>, mut console: ResMut<Console>) {
+    for command in commands.read().filter(|command| command.name == "log") {
+        let Some(arg) = command.args.first() else {
+            console.print(format!("log level: {}", game_log_level()));
+            continue;
+        };
+        match LEVELS
+            .iter()
+            .position(|level| level.as_str().eq_ignore_ascii_case(arg))
+        {
+            Some(index) => {
+                GAME_LOG_LEVEL.store(index, Ordering::Relaxed);
+                console.print(format!("log level: {}", LEVELS[index]));
+            }
+            None => console.print(format!("unknown log level '{arg}'")),
+        }
+    }
+}
@@ -0,0 +1,428 @@
+use bevy::prelude::*;
+
+use crate::glyphs::{InputDevice, Prompt};
+use crate::i18n::Localization;
+use crate::input::Action;
+use crate::palette::Palette;
+use crate::telemetry::TelemetryConsent;
+
+const MENU_FONT_SIZE: f32 = 32.0;
+const HINT_FONT_SIZE: f32 = 20.0;
+
+pub struct MenuPlugin;
+
+impl Plugin for MenuPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_state::<GameState>()
+            .init_resource::<MenuPage>()
+            .init_resource::<MenuRows>()
+            .init_resource::<MenuSelection>()
+            .init_resource::<MenuInputGrabbed>()
+            .add_event::<MenuActivated>()
+            .configure_sets(
+                Update,
+                (
+                    MenuSet::Rows,
+                    MenuSet::Input,
+                    MenuSet::Actions,
+                    MenuSet::Render,
+                )
+                    .chain()
+                    .run_if(in_state(GameState::Paused)),
+            )
+            .add_systems(Update, open_menu.run_if(in_state(GameState::Playing)))
+            .add_systems(OnEnter(GameState::Paused), (pause_time, spawn_menu_root))
+            .add_systems(OnExit(GameState::Paused), (resume_time, despawn_menu_root))
+            .add_systems(
+                Update,
+                (
+                    main_menu_rows.in_set(MenuSet::Rows),
+                    (navigate_menu, tap_menu_rows)
+                        .in_set(MenuSet::Input)
+                        .run_if(|grabbed: Res<MenuInputGrabbed>| !grabbed.0),
+                    apply_menu_actions.in_set(MenuSet::Actions),
+                    render_menu.in_set(MenuSet::Render),
+                ),
+            );
+    }
+}
+
+#[derive(States, Default, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum GameState {
+    // sounds are still being preloaded, the world waits like it does in the menu
+    #[default]
+    Loading,
+    Playing,
+    Paused,
+    // gameplay stops like in the menu, without the menu
+    Cutscene,
+    // between the player getting killed and the next run starting
+    DeathCam,
+}
+
+#[derive(Resource, Default, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MenuPage {
+    #[default]
+    Main,
+    Settings,
+    KeyboardBindings,
+    GamepadBindings,
+    Skins,
+    Achievements,
+    Stats,
+    History,
+    // one run picked from the history
+    HistoryRun,
+    Leaderboard,
+    Assist,
+    Telemetry,
+    Crash,
+    Shop,
+    Unlocks,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MenuAction {
+    None,
+    Resume,
+    Open(MenuPage),
+    Back,
+    CycleLanguage,
+    CycleKeyboardLayout,
+    Rebind(Action),
+    ResetBindings,
+    CycleTouchControls,
+    CycleStickDeadzoneX,
+    CycleStickDeadzoneY,
+    CycleStickCurve,
+    CycleStickSensitivity,
+    ToggleRumble,
+    CycleTheme,
+    ToggleAnimatedBackground,
+    ToggleOneSwitch,
+    ToggleJumpMode,
+    ToggleGenerousTiming,
+    ToggleLandingMomentum,
+    ToggleLandingLag,
+    ToggleLineClear,
+    ToggleReduceMotion,
+    ToggleReduceFlashing,
+    ToggleScreenReader,
+    CycleUiScale,
+    CycleSafeArea,
+    CycleHitFeedback,
+    CycleParticles,
+    CycleQuality,
+    ToggleAfterimages,
+    TogglePaceGhost,
+    CyclePlayerSmoothing,
+    TogglePauseOnFocusLoss,
+    ToggleDiscordPresence,
+    CycleTelemetry,
+    SetTelemetry(TelemetryConsent),
+    CopyCrashReport,
+    DismissCrashReport,
+    CycleLeaderboardPage,
+    CycleHistoryFilter,
+    CycleHistoryPage,
+    OpenRun(usize),
+    ExportRuns,
+    ExportRun,
+    DeleteRun,
+    CycleGameSpeed,
+    CycleExtraHealth,
+    ToggleCrushInvulnerable,
+    TogglePracticeMode,
+    SelectSkin(usize),
+    BuyUpgrade(usize),
+    BuyUnlock(usize),
+    RespecPerks,
+    ToggleTutorial,
+}
+
+pub struct MenuRow {
+    pub label: String,
+    pub action: MenuAction,
+}
+
+impl MenuPage {
+    // where Back goes, None for the main page that closes the menu
+    fn parent(self) -> Option<MenuPage> {
+        match self {
+            MenuPage::Main => None,
+            MenuPage::HistoryRun => Some(MenuPage::History),
+            MenuPage::KeyboardBindings | MenuPage::GamepadBindings => Some(MenuPage::Settings),
+            _ => Some(MenuPage::Main),
+        }
+    }
+}
+
+impl MenuRow {
+    pub fn new(label: impl Into<String>, action: MenuAction) -> MenuRow {
+        MenuRow {
+            label: label.into(),
+            action,
+        }
+    }
+}
+
+// rebuilt every frame by the systems in MenuSet::Rows for the current page
+#[derive(Resource, Default, Deref, DerefMut)]
+pub struct MenuRows(Vec<MenuRow>);
+
+#[derive(Resource, Default)]
+pub struct MenuSelection(pub usize);
+
+// set while something else reads raw input for itself, like a rebind waiting for a key,
+// so the menu doesn't also act on it
+#[derive(Resource, Default)]
+pub struct MenuInputGrabbed(pub bool);
+
+// delta is -1/+1 when the row is adjusted with left/right and 0 when confirmed
+#[derive(Event)]
+pub struct MenuActivated {
+    pub action: MenuAction,
+    pub delta: i32,
+}
+
+#[derive(SystemSet, Debug, Clone, PartialEq, Eq, Hash)]
+pub enum MenuSet {
+    Rows,
+    Input,
+    Actions,
+    Render,
+}
+
+#[derive(Component)]
+struct MenuRoot;
+
+#[derive(Component)]
+struct MenuRowIndex(usize);
+
+fn open_menu(
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    gamepads: Query<&Gamepad>,
+    mut page: ResMut<MenuPage>,
+    mut selection: ResMut<MenuSelection>,
+    mut next_state: ResMut<NextState<GameState>>,
+) {
+    if keyboard_input.just_pressed(KeyCode::Escape)
+        || gamepads
+            .iter()
+            .any(|gamepad| gamepad.just_pressed(GamepadButton::Start))
+    {
+        *page = MenuPage::Main;
+        selection.0 = 0;
+        next_state.set(GameState::Paused);
+    }
+}
+
+fn pause_time(mut time: ResMut<Time<Virtual>>) {
+    time.pause();
+}
+
+fn resume_time(mut time: ResMut<Time<Virtual>>) {
+    time.unpause();
+}
+
+fn spawn_menu_root(mut commands: Commands) {
+    commands.spawn((
+        Name::new("Menu"),
+        MenuRoot,
+        Node {
+            width: Val::Percent(100.0),
+            height: Val::Percent(100.0),
+            flex_direction: FlexDirection::Column,
+            justify_content: JustifyContent::Center,
+            align_items: AlignItems::Center,
+            row_gap: Val::Px(8.0),
+            ..default()
+        },
+        BackgroundColor(Color::srgba(0.0, 0.0, 0.0, 0.6)),
+        GlobalZIndex(10),
+    ));
+}
+
+fn despawn_menu_root(mut commands: Commands, root: Single<Entity, With<MenuRoot>>) {
+    commands.entity(*root).despawn_recursive();
+}
+
+pub fn main_menu_rows(
+    page: Res<MenuPage>,
+    localization: Res<Localization>,
+    mut rows: ResMut<MenuRows>,
+) {
+    if *page != MenuPage::Main {
+        return;
+    }
+    for (key, action) in [
+        ("menu.resume", MenuAction::Resume),
+        ("menu.skins", MenuAction::Open(MenuPage::Skins)),
+        ("menu.unlocks", MenuAction::Open(MenuPage::Unlocks)),
+        (
+            "menu.achievements",
+            MenuAction::Open(MenuPage::Achievements),
+        ),
+        ("menu.stats", MenuAction::Open(MenuPage::Stats)),
+        ("menu.history", MenuAction::Open(MenuPage::History)),
+        ("menu.leaderboard", MenuAction::Open(MenuPage::Leaderboard)),
+        ("menu.assist", MenuAction::Open(MenuPage::Assist)),
+        ("menu.settings", MenuAction::Open(MenuPage::Settings)),
+    ] {
+        rows.push(MenuRow::new(localization.get(key), action));
+    }
+}
+
+fn navigate_menu(
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    gamepads: Query<&Gamepad>,
+    rows: Res<MenuRows>,
+    mut selection: ResMut<MenuSelection>,
+    mut activated: EventWriter<MenuActivated>,
+) {
+    if rows.is_empty() {
+        return;
+    }
+    let gamepad_pressed = |button| gamepads.iter().any(|gamepad| gamepad.just_pressed(button));
+    if keyboard_input.any_just_pressed([KeyCode::ArrowUp, KeyCode::KeyW])
+        || gamepad_pressed(GamepadButton::DPadUp)
+    {
+        selection.0 = (selection.0 + rows.len() - 1) % rows.len();
+    }
+    if keyboard_input.any_just_pressed([KeyCode::ArrowDown, KeyCode::KeyS])
+        || gamepad_pressed(GamepadButton::DPadDown)
+    {
+        selection.0 = (selection.0 + 1) % rows.len();
+    }
+    selection.0 = selection.0.min(rows.len() - 1);
+    let action = rows[selection.0].action;
+    if keyboard_input.any_just_pressed([KeyCode::Enter, KeyCode::Space])
+        || gamepad_pressed(GamepadButton::South)
+    {
+        activated.send(MenuActivated { action, delta: 0 });
+    } else if keyboard_input.any_just_pressed([KeyCode::ArrowLeft, KeyCode::KeyA])
+        || gamepad_pressed(GamepadButton::DPadLeft)
+    {
+        activated.send(MenuActivated { action, delta: -1 });
+    } else if keyboard_input.any_just_pressed([KeyCode::ArrowRight, KeyCode::KeyD])
+        || gamepad_pressed(GamepadButton::DPadRight)
+    {
+        activated.send(MenuActivated { action, delta: 1 });
+    } else if keyboard_input.just_pressed(KeyCode::Escape) || gamepad_pressed(GamepadButton::East) {
+        activated.send(MenuActivated {
+            action: MenuAction::Back,
+            delta: 0,
+        });
+    }
+}
+
+// rows are buttons, so a tap or click selects and confirms them
+fn tap_menu_rows(
+    tapped: Query<(&Interaction, &MenuRowIndex), Changed<Interaction>>,
+    rows: Res<MenuRows>,
+    mut selection: ResMut<MenuSelection>,
+    mut activated: EventWriter<MenuActivated>,
+) {
+    for (interaction, index) in &tapped {
+        let Some(row) = rows.get(index.0) else {
+            continue;
+        };
+        if *interaction == Interaction::Pressed {
+            selection.0 = index.0;
+            activated.send(MenuActivated {
+                action: row.action,
+                delta: 0,
+            });
+        }
+    }
+}
+
+fn apply_menu_actions(
+    mut activated: EventReader<MenuActivated>,
+    mut page: ResMut<MenuPage>,
+    mut selection: ResMut<MenuSelection>,
+    mut next_state: ResMut<NextState<GameState>>,
+) {
+    for event in activated.read() {
+        match event.action {
+            MenuAction::Resume => next_state.set(GameState::Playing),
+            MenuAction::Open(target) if event.delta == 0 => {
+                *page = target;
+                selection.0 = 0;
+            }
+            MenuAction::Back => match page.parent() {
+                Some(parent) => {
+                    *page = parent;
+                    selection.0 = 0;
+                }
+                None => next_state.set(GameState::Playing),
+            },
+            _ => {}
+        }
+    }
+}
+
+// only respawns the row entities when the visible text actually changed
+fn render_menu(
+    mut commands: Commands,
+    mut rows: ResMut<MenuRows>,
+    selection: Res<MenuSelection>,
+    root: Single<(Entity, Ref<MenuRoot>)>,
+    palette: Res<Palette>,
+    device: Res<InputDevice>,
+    localization: Res<Localization>,
+    mut shown: Local<(Vec<(String, bool)>, String)>,
+) {
+    let lines: Vec<(String, bool)> = rows
+        .drain(..)
+        .enumerate()
+        .map(|(index, row)| (row.label, index == selection.0))
+        .collect();
+    let hint = device.hint(
+        &[
+            (Prompt::Confirm, "prompt.select"),
+            (Prompt::Adjust, "prompt.adjust"),
+            (Prompt::Back, "prompt.back"),
+        ],
+        &localization,
+    );
+    let (root, root_ref) = root.into_inner();
+    if shown.0 == lines && shown.1 == hint && !palette.is_changed() && !root_ref.is_added() {
+        return;
+    }
+    commands
+        .entity(root)
+        .despawn_descendants()
+        .with_children(|parent| {
+            for (index, (label, selected)) in lines.iter().enumerate() {
+                let color = if *selected {
+                    palette.ui_accent
+                } else {
+                    palette.ui_text
+                };
+                parent
+                    .spawn((Button, MenuRowIndex(index), Node::default()))
+                    .with_child((
+                        Text::new(label.clone()),
+                        TextFont {
+                            font_size: MENU_FONT_SIZE,
+                            ..default()
+                        },
+                        TextColor(color),
+                    ));
+            }
+            parent.spawn((
+                Text::new(hint.clone()),
+                TextFont {
+                    font_size: HINT_FONT_SIZE,
+                    ..default()
+                },
+                TextColor(palette.ui_text.with_alpha(0.7)),
+                Node {
+                    margin: UiRect::top(Val::Px(24.0)),
+                    ..default()
+                },
+            ));
+        });
+    *shown = (lines, hint);
+}
@@ -0,0 +1,214 @@
+use bevy::prelude::*;
+
+use crate::hud::ShowToast;
+use crate::i18n::Localization;
+use crate::menu::{MenuAction, MenuActivated, MenuPage, MenuRow, MenuRows, MenuSet};
+use crate::player::Abilities;
+use crate::save::SaveData;
+use crate::score::RunStats;
+use crate::shop::{RunUpgrades, Upgrade};
+use crate::{GameSet, PlayerDied};
+
+const SCORE_PER_SHARD: u32 = 100;
+
+pub struct MetaPlugin;
+
+impl Plugin for MetaPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            Update,
+            (award_shards, sync_perks, sync_abilities).in_set(GameSet::Reactions),
+        )
+        .add_systems(
+            Update,
+            (
+                unlock_menu_rows.in_set(MenuSet::Rows),
+                apply_unlock_actions.in_set(MenuSet::Actions),
+            ),
+        );
+    }
+}
+
+#[derive(Clone, Copy)]
+pub enum UnlockKind {
+    // starts every run with a level of a shop upgrade
+    Perk(Upgrade),
+    // the pattern with this unlock id joins the spawner's pool
+    Pattern,
+    // the skin with this unlock id shows up in the skins menu
+    Cosmetic,
+    // every player spawned from then on has the ability
+    Ability,
+}
+
+pub struct MetaUnlock {
+    pub id: &'static str,
+    pub cost: u32,
+    pub kind: UnlockKind,
+}
+
+pub const UNLOCKS: &[MetaUnlock] = &[
+    MetaUnlock {
+        id: "perk_health",
+        cost: 30,
+        kind: UnlockKind::Perk(Upgrade::Health),
+    },
+    MetaUnlock {
+        id: "perk_coyote",
+        cost: 20,
+        kind: UnlockKind::Perk(Upgrade::Coyote),
+    },
+    MetaUnlock {
+        id: "perk_wall_jump",
+        cost: 20,
+        kind: UnlockKind::Perk(Upgrade::WallJump),
+    },
+    MetaUnlock {
+        id: "pattern_zigzag",
+        cost: 10,
+        kind: UnlockKind::Pattern,
+    },
+    MetaUnlock {
+        id: "ability_glide",
+        cost: 50,
+        kind: UnlockKind::Ability,
+    },
+    MetaUnlock {
+        id: "skin_glacier",
+        cost: 40,
+        kind: UnlockKind::Cosmetic,
+    },
+];
+
+impl MetaUnlock {
+    pub fn is_owned(&self, save: &SaveData) -> bool {
+        save.unlocks.iter().any(|existing| existing == self.id)
+    }
+
+    fn name_key(&self) -> String {
+        format!("unlock.{}", self.id)
+    }
+}
+
+// RunStats is already reset on the frame the player dies, so the run is remembered
+// from the frame before
+fn award_shards(
+    mut died: EventReader<PlayerDied>,
+    stats: Res<RunStats>,
+    mut save: ResMut<SaveData>,
+    localization: Res<Localization>,
+    mut toasts: EventWriter<ShowToast>,
+    mut last_score: Local<u32>,
+) {
+    if died.read().count() == 0 {
+        *last_score = stats.score;
+        return;
+    }
+    let shards = std::mem::take(&mut *last_score) / SCORE_PER_SHARD;
+    if shards == 0 {
+        return;
+    }
+    save.shards += shards;
+    debug!(target: "player", "earned {shards} shards, {} total", save.shards);
+    toasts.send(ShowToast(
+        localization.format("unlock.earned", &[("shards", &shards)]),
+    ));
+}
+
+fn perk_levels(save: &SaveData) -> [u32; Upgrade::ALL.len()] {
+    let mut perks = [0; Upgrade::ALL.len()];
+    for unlock in UNLOCKS.iter().filter(|unlock| unlock.is_owned(save)) {
+        if let UnlockKind::Perk(upgrade) = unlock.kind {
+            perks[upgrade as usize] += 1;
+        }
+    }
+    perks
+}
+
+fn sync_perks(save: Res<SaveData>, mut upgrades: ResMut<RunUpgrades>) {
+    upgrades.set_perks(perk_levels(&save));
+}
+
+fn sync_abilities(save: Res<SaveData>, mut abilities: Query<&mut Abilities>) {
+    let owned = |id: &str| save.unlocks.iter().any(|unlock| unlock == id);
+    for mut abilities in &mut abilities {
+        let glide = owned("ability_glide");
+        if abilities.glide != glide {
+            abilities.glide = glide;
+        }
+    }
+}
+
+// what respeccing gives back, only perks are refunded
+fn perk_refund(save: &SaveData) -> u32 {
+    UNLOCKS
+        .iter()
+        .filter(|unlock| matches!(unlock.kind, UnlockKind::Perk(_)) && unlock.is_owned(save))
+        .map(|unlock| unlock.cost)
+        .sum()
+}
+
+fn unlock_menu_rows(
+    page: Res<MenuPage>,
+    save: Res<SaveData>,
+    localization: Res<Localization>,
+    mut rows: ResMut<MenuRows>,
+) {
+    if *page != MenuPage::Unlocks {
+        return;
+    }
+    rows.push(MenuRow::new(
+        localization.format("unlock.shards", &[("shards", &save.shards)]),
+        MenuAction::None,
+    ));
+    for (index, unlock) in UNLOCKS.iter().enumerate() {
+        let name = localization.get(&unlock.name_key()).to_string();
+        let label = if unlock.is_owned(&save) {
+            localization.format("unlock.owned", &[("name", &name)])
+        } else {
+            localization.format("unlock.offer", &[("name", &name), ("shards", &unlock.cost)])
+        };
+        rows.push(MenuRow::new(label, MenuAction::BuyUnlock(index)));
+    }
+    rows.push(MenuRow::new(
+        localization.format("unlock.respec", &[("shards", &perk_refund(&save))]),
+        MenuAction::RespecPerks,
+    ));
+    rows.push(MenuRow::new(
+        localization.get("menu.back"),
+        MenuAction::Back,
+    ));
+}
+
+fn apply_unlock_actions(mut activated: EventReader<MenuActivated>, mut save: ResMut<SaveData>) {
+    for event in activated.read() {
+        if event.delta != 0 {
+            continue;
+        }
+        match event.action {
+            MenuAction::BuyUnlock(index) => {
+                let unlock = &UNLOCKS[index];
+                if unlock.is_owned(&save) || save.shards < unlock.cost {
+                    continue;
+                }
+                save.shards -= unlock.cost;
+                save.unlocks.push(unlock.id.to_string());
+                debug!(target: "player", "unlocked {}", unlock.id);
+            }
+            MenuAction::RespecPerks => {
+                let refund = perk_refund(&save);
+                if refund == 0 {
+                    continue;
+                }
+                save.shards += refund;
+                save.unlocks.retain(|id| {
+                    !UNLOCKS
+                        .iter()
+                        .any(|unlock| unlock.id == id && matches!(unlock.kind, UnlockKind::Perk(_)))
+                });
+                debug!(target: "player", "respecced perks for {refund} shards");
+            }
+            _ => {}
+        }
+    }
+}
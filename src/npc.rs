@@ -0,0 +1,144 @@
+use bevy::prelude::*;
+
+use crate::cutscene::PlayCutscene;
+use crate::glyphs::{InputDevice, Prompt};
+use crate::highlight::Interactable;
+use crate::i18n::Localization;
+use crate::input::{Action, ActionState};
+use crate::palette::{Palette, PaletteRole};
+use crate::prefab::{add_inset, spawn_rect, PrefabAppExt, PrefabParams, Prefabs};
+use crate::z_layer::ZLayer;
+use crate::{GameSet, Player};
+
+// from the player's center to the npc's
+const INTERACT_RADIUS: f32 = 120.0;
+const PROMPT_FONT_SIZE: f32 = 18.0;
+const PROMPT_OFFSET: f32 = 20.0;
+// standing on the starting floor, out of the player's way
+const GUIDE_POSITION: Vec2 = Vec2::new(-440.0, -210.0);
+
+pub struct NpcPlugin;
+
+impl Plugin for NpcPlugin {
+    fn build(&self, app: &mut App) {
+        app.register_prefab("npc", spawn_npc)
+            .add_systems(Startup, (spawn_prompt, spawn_guide))
+            .add_systems(
+                Update,
+                (
+                    talk_to_npcs.in_set(GameSet::Input),
+                    show_prompt.in_set(GameSet::Vfx),
+                ),
+            );
+    }
+}
+
+// someone to talk to, interacting plays their dialogue cutscene
+#[derive(Component)]
+pub struct Npc {
+    pub dialogue: &'static str,
+}
+
+// shared by every npc, only the closest one in reach shows it
+#[derive(Component)]
+struct InteractPrompt;
+
+fn spawn_npc(commands: &mut Commands, params: &PrefabParams) -> Entity {
+    let size = params.size.unwrap_or(Vec2::new(50.0, 80.0));
+    let entity = spawn_rect(
+        commands,
+        "Npc",
+        PaletteRole::Player,
+        size,
+        params.position,
+        |assets| &assets.checkpoint,
+    );
+    commands.entity(entity).insert((
+        Npc {
+            dialogue: "npc_hello",
+        },
+        ZLayer::Terrain,
+        Interactable::Touch,
+    ));
+    add_inset(commands, entity, 0.4, |assets| &assets.accent);
+    entity
+}
+
+fn spawn_guide(mut commands: Commands, prefabs: Res<Prefabs>) {
+    let params = PrefabParams {
+        position: GUIDE_POSITION,
+        ..default()
+    };
+    prefabs.spawn("npc", &mut commands, &params);
+}
+
+fn spawn_prompt(mut commands: Commands, palette: Res<Palette>) {
+    commands.spawn((
+        Name::new("InteractPrompt"),
+        InteractPrompt,
+        ZLayer::Ui,
+        Text2d::new(""),
+        TextFont {
+            font_size: PROMPT_FONT_SIZE,
+            ..default()
+        },
+        TextColor(palette.ui_text),
+        Transform::default(),
+        Visibility::Hidden,
+    ));
+}
+
+fn nearest_npc<'a>(
+    npcs: impl Iterator<Item = (&'a Npc, &'a Transform)>,
+    player: Vec2,
+) -> Option<(&'a Npc, &'a Transform)> {
+    npcs.map(|(npc, transform)| {
+        let distance = transform.translation.truncate().distance(player);
+        (npc, transform, distance)
+    })
+    .filter(|(_, _, distance)| *distance <= INTERACT_RADIUS)
+    .min_by(|a, b| a.2.total_cmp(&b.2))
+    .map(|(npc, transform, _)| (npc, transform))
+}
+
+fn talk_to_npcs(
+    actions: Res<ActionState>,
+    npcs: Query<(&Npc, &Transform)>,
+    player: Single<&Transform, With<Player>>,
+    mut cutscenes: EventWriter<PlayCutscene>,
+) {
+    if !actions.just_pressed(Action::Interact) {
+        return;
+    }
+    if let Some((npc, _)) = nearest_npc(npcs.iter(), player.translation.truncate()) {
+        debug!(target: "player", "talking to an npc");
+        cutscenes.send(PlayCutscene(npc.dialogue.to_string()));
+    }
+}
+
+fn show_prompt(
+    npcs: Query<(&Npc, &Transform), Without<InteractPrompt>>,
+    player: Single<&Transform, (With<Player>, Without<InteractPrompt>)>,
+    prompt: Single<(&mut Text2d, &mut Transform, &mut Visibility), With<InteractPrompt>>,
+    device: Res<InputDevice>,
+    localization: Res<Localization>,
+) {
+    let (mut text, mut transform, mut visibility) = prompt.into_inner();
+    let hint = device.hint(&[(Prompt::Interact, "prompt.talk")], &localization);
+    let near = nearest_npc(npcs.iter(), player.translation.truncate());
+    let Some((_, npc)) = near.filter(|_| !hint.is_empty()) else {
+        if *visibility != Visibility::Hidden {
+            *visibility = Visibility::Hidden;
+        }
+        return;
+    };
+    let above = npc.translation.y + npc.scale.y / 2.0 + PROMPT_OFFSET;
+    transform.translation.x = npc.translation.x;
+    transform.translation.y = above;
+    if text.0 != hint {
+        text.0 = hint;
+    }
+    if *visibility != Visibility::Inherited {
+        *visibility = Visibility::Inherited;
+    }
+}
@@ -0,0 +1,180 @@
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::history::{run_mode, RunMode};
+use crate::i18n::Localization;
+use crate::palette::Palette;
+use crate::score::RunStats;
+use crate::settings::Settings;
+use crate::storage::{Persistent, StorageAppExt};
+use crate::ui_layout::UiRoot;
+use crate::{GameSet, PlayerDied};
+
+// the best run's score is remembered this often
+const SAMPLE_SECS: f32 = 5.0;
+const PACE_FONT_SIZE: f32 = 20.0;
+
+pub struct PacePlugin;
+
+impl Plugin for PacePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_persistent_resource::<BestPaces>()
+            .init_resource::<PaceRecorder>()
+            .add_systems(Startup, spawn_pace_text)
+            .add_systems(Update, record_pace.in_set(GameSet::Reactions))
+            .add_systems(Update, update_pace_text.in_set(GameSet::Vfx));
+    }
+}
+
+// the score every SAMPLE_SECS from the start of the best run in each mode, ending with
+// its final score. runs aren't seeded yet, once they are this can be kept per seed too
+#[derive(Resource, Serialize, Deserialize, Default)]
+#[serde(default)]
+struct BestPaces {
+    curves: Vec<(RunMode, Vec<u32>)>,
+}
+
+impl Persistent for BestPaces {
+    const KEY: &'static str = "best_paces";
+    const VERSION: u32 = 1;
+}
+
+impl BestPaces {
+    fn curve(&self, mode: RunMode) -> Option<&[u32]> {
+        self.curves
+            .iter()
+            .find(|(curve_mode, _)| *curve_mode == mode)
+            .map(|(_, samples)| samples.as_slice())
+    }
+
+    fn set_curve(&mut self, mode: RunMode, samples: Vec<u32>) {
+        self.curves.retain(|(curve_mode, _)| *curve_mode != mode);
+        self.curves.push((mode, samples));
+    }
+}
+
+// the best run's score this far in, eased between samples. past its end it stays at
+// its final score
+fn pace_at(samples: &[u32], secs: f32) -> Option<f32> {
+    let last = *samples.last()?;
+    let position = secs / SAMPLE_SECS;
+    let index = position as usize;
+    if index + 1 >= samples.len() {
+        return Some(last as f32);
+    }
+    let (from, to) = (samples[index] as f32, samples[index + 1] as f32);
+    Some(from + (to - from) * position.fract())
+}
+
+// the run in progress, as it'll be stored if it beats the best one
+#[derive(Resource, Default)]
+struct PaceRecorder {
+    mode: Option<RunMode>,
+    samples: Vec<u32>,
+    score: u32,
+}
+
+#[derive(Component)]
+struct PaceText;
+
+// under the score, clear of the stamina bar
+fn spawn_pace_text(
+    mut commands: Commands,
+    palette: Res<Palette>,
+    root: Single<Entity, With<UiRoot>>,
+) {
+    commands
+        .spawn((
+            Name::new("PaceText"),
+            PaceText,
+            Node {
+                position_type: PositionType::Absolute,
+                left: Val::Px(12.0),
+                top: Val::Px(58.0),
+                ..default()
+            },
+            Text::new(""),
+            TextFont {
+                font_size: PACE_FONT_SIZE,
+                ..default()
+            },
+            TextColor(palette.ui_text),
+            Visibility::Hidden,
+        ))
+        .set_parent(*root);
+}
+
+// RunStats is already reset on the frame the player dies, so the run is remembered
+// from the frame before
+fn record_pace(
+    mut died: EventReader<PlayerDied>,
+    stats: Res<RunStats>,
+    settings: Res<Settings>,
+    mut recorder: ResMut<PaceRecorder>,
+    mut best: ResMut<BestPaces>,
+) {
+    if died.read().count() > 0 {
+        let PaceRecorder {
+            mode,
+            mut samples,
+            score,
+        } = std::mem::take(&mut *recorder);
+        let Some(mode) = mode else {
+            return;
+        };
+        let beaten = best
+            .curve(mode)
+            .is_none_or(|curve| curve.last().is_none_or(|best| score > *best));
+        if score > 0 && beaten {
+            debug!(
+                "new best pace for {mode:?}, {score} over {} samples",
+                samples.len()
+            );
+            samples.push(score);
+            best.set_curve(mode, samples);
+        }
+        return;
+    }
+    recorder.mode = Some(run_mode(&settings, &stats));
+    recorder.score = stats.score;
+    while recorder.samples.len() as f32 * SAMPLE_SECS <= stats.time_survived {
+        recorder.samples.push(stats.score);
+    }
+}
+
+fn update_pace_text(
+    stats: Res<RunStats>,
+    settings: Res<Settings>,
+    best: Res<BestPaces>,
+    palette: Res<Palette>,
+    localization: Res<Localization>,
+    text: Single<(&mut Text, &mut TextColor, &mut Visibility), With<PaceText>>,
+) {
+    let (mut text, mut color, mut visibility) = text.into_inner();
+    let pace = pace_at(
+        best.curve(run_mode(&settings, &stats)).unwrap_or_default(),
+        stats.time_survived,
+    );
+    let Some(pace) = pace.filter(|_| settings.pace_ghost && stats.time_survived > 0.0) else {
+        if *visibility != Visibility::Hidden {
+            *visibility = Visibility::Hidden;
+        }
+        return;
+    };
+    let delta = stats.score as f32 - pace;
+    let (key, wanted) = if delta >= 0.0 {
+        ("hud.pace_ahead", palette.ui_accent)
+    } else {
+        ("hud.pace_behind", palette.hazard)
+    };
+    let wanted_text = localization.format(key, &[("points", &format!("{:.0}", delta.abs()))]);
+    if text.0 != wanted_text {
+        text.0 = wanted_text;
+    }
+    if color.0 != wanted {
+        color.0 = wanted;
+    }
+    if *visibility != Visibility::Inherited {
+        *visibility = Visibility::Inherited;
+    }
+}
@@ -0,0 +1,170 @@
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::settings::Settings;
+
+pub struct PalettePlugin;
+
+impl Plugin for PalettePlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(Palette::from_theme(Theme::default()))
+            .add_systems(PreUpdate, update_palette)
+            .add_systems(PostUpdate, apply_palette);
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default, Serialize, Deserialize)]
+pub enum Theme {
+    #[default]
+    Classic,
+    Night,
+    Deuteranopia,
+    Protanopia,
+}
+
+impl Theme {
+    pub const ALL: [Theme; 4] = [
+        Theme::Classic,
+        Theme::Night,
+        Theme::Deuteranopia,
+        Theme::Protanopia,
+    ];
+
+    pub fn key(self) -> &'static str {
+        match self {
+            Theme::Classic => "theme.classic",
+            Theme::Night => "theme.night",
+            Theme::Deuteranopia => "theme.deuteranopia",
+            Theme::Protanopia => "theme.protanopia",
+        }
+    }
+
+    pub fn cycle(self, delta: i32) -> Theme {
+        let index = Theme::ALL
+            .iter()
+            .position(|theme| *theme == self)
+            .unwrap_or(0);
+        let len = Theme::ALL.len() as i32;
+        Theme::ALL[(index as i32 + delta).rem_euclid(len) as usize]
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum PaletteRole {
+    Terrain,
+    Debris,
+    Player,
+    Hazard,
+}
+
+#[derive(Component)]
+pub struct Themed(pub PaletteRole);
+
+#[derive(Resource, Clone, Copy)]
+pub struct Palette {
+    pub background: Color,
+    pub terrain: Color,
+    pub debris: Color,
+    pub player: Color,
+    pub hazard: Color,
+    pub ui_text: Color,
+    pub ui_accent: Color,
+}
+
+impl Palette {
+    pub fn from_theme(theme: Theme) -> Palette {
+        match theme {
+            Theme::Classic => Palette {
+                background: Color::srgb(0.3, 0.3, 0.3),
+                terrain: Color::WHITE,
+                debris: Color::WHITE,
+                player: Color::BLACK,
+                hazard: Color::srgb(1.0, 0.2, 0.1),
+                ui_text: Color::WHITE,
+                ui_accent: Color::srgb(1.0, 0.8, 0.2),
+            },
+            Theme::Night => Palette {
+                background: Color::srgb(0.05, 0.06, 0.1),
+                terrain: Color::srgb(0.35, 0.38, 0.45),
+                debris: Color::srgb(0.55, 0.58, 0.65),
+                player: Color::srgb(0.95, 0.95, 0.85),
+                hazard: Color::srgb(1.0, 0.35, 0.2),
+                ui_text: Color::srgb(0.9, 0.9, 0.95),
+                ui_accent: Color::srgb(0.4, 0.8, 1.0),
+            },
+            // okabe-ito colors, avoiding red/green pairs
+            Theme::Deuteranopia => Palette {
+                background: Color::srgb(0.2, 0.2, 0.22),
+                terrain: Color::srgb(0.85, 0.85, 0.85),
+                debris: Color::srgb(0.34, 0.71, 0.91),
+                player: Color::srgb(0.0, 0.45, 0.7),
+                hazard: Color::srgb(0.9, 0.62, 0.0),
+                ui_text: Color::WHITE,
+                ui_accent: Color::srgb(0.94, 0.89, 0.26),
+            },
+            Theme::Protanopia => Palette {
+                background: Color::srgb(0.2, 0.2, 0.22),
+                terrain: Color::srgb(0.85, 0.85, 0.85),
+                debris: Color::srgb(0.8, 0.6, 0.7),
+                player: Color::srgb(0.0, 0.45, 0.7),
+                hazard: Color::srgb(0.94, 0.89, 0.26),
+                ui_text: Color::WHITE,
+                ui_accent: Color::srgb(0.34, 0.71, 0.91),
+            },
+        }
+    }
+
+    // same lightness with the color drained out, an amount of 1 is fully grey
+    pub fn desaturated(self, amount: f32) -> Palette {
+        let drain = |color: Color| {
+            let mut oklch = Oklcha::from(color);
+            oklch.chroma *= 1.0 - amount;
+            Color::from(oklch)
+        };
+        Palette {
+            background: drain(self.background),
+            terrain: drain(self.terrain),
+            debris: drain(self.debris),
+            player: drain(self.player),
+            hazard: drain(self.hazard),
+            ..self
+        }
+    }
+
+    pub fn color(&self, role: PaletteRole) -> Color {
+        match role {
+            PaletteRole::Terrain => self.terrain,
+            PaletteRole::Debris => self.debris,
+            PaletteRole::Player => self.player,
+            PaletteRole::Hazard => self.hazard,
+        }
+    }
+}
+
+fn update_palette(
+    settings: Res<Settings>,
+    mut palette: ResMut<Palette>,
+    mut clear_color: ResMut<ClearColor>,
+) {
+    if !settings.is_changed() {
+        return;
+    }
+    *palette = Palette::from_theme(settings.theme);
+    clear_color.0 = palette.background;
+}
+
+// keeps the alpha of the material so translucent markers stay translucent
+fn apply_palette(
+    palette: Res<Palette>,
+    themed: Query<(Ref<Themed>, &MeshMaterial2d<ColorMaterial>)>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+) {
+    for (themed, material) in &themed {
+        if !palette.is_changed() && !themed.is_added() {
+            continue;
+        }
+        if let Some(material) = materials.get_mut(material) {
+            material.color = palette.color(themed.0).with_alpha(material.color.alpha());
+        }
+    }
+}
@@ -0,0 +1,267 @@
+use bevy::asset::io::Reader;
+use bevy::asset::{AssetLoader, LoadContext};
+use bevy::prelude::*;
+use rand::Rng;
+use serde::Deserialize;
+
+use crate::asset_check::TrackedAssets;
+use crate::console::{Console, ConsoleAppExt, ConsoleCommand};
+use crate::director::Director;
+use crate::prefab::PieceShape;
+use crate::save::SaveData;
+use crate::score::RunStats;
+use crate::spawner::{
+    plan_drops, spawn_planned_drops, Difficulty, PlannedDrop, Spawner, WeightClass,
+    SPAWN_HALF_WIDTH,
+};
+use crate::{GameSet, PlayerDied};
+
+// every file's patterns go into the same pool, drop a new file in and list it here
+const PATTERN_FILES: &[&str] = &["patterns/default.patterns.ron"];
+// seconds between patterns, shorter as the level goes up
+const BASE_PATTERN_GAP: f32 = 30.0;
+const MIN_PATTERN_GAP: f32 = 10.0;
+// a player the director sees struggling only gets random drops
+const MIN_PERFORMANCE: f32 = -0.3;
+
+pub struct PatternsPlugin;
+
+impl Plugin for PatternsPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_asset::<PatternFile>()
+            .init_asset_loader::<PatternLoader>()
+            .init_resource::<PatternDirector>()
+            .add_console_command("pattern", "pattern [name]")
+            .add_systems(Startup, load_patterns)
+            .add_systems(Update, run_pattern_command)
+            .add_systems(
+                Update,
+                queue_patterns
+                    .after(plan_drops)
+                    .before(spawn_planned_drops)
+                    .in_set(GameSet::Reactions),
+            );
+    }
+}
+
+// an authored sequence of drops, like a staircase or a wall of planks
+#[derive(Deserialize, Clone, Debug)]
+pub struct Pattern {
+    pub name: String,
+    // the difficulty level it starts showing up at
+    #[serde(default)]
+    pub min_level: f32,
+    #[serde(default = "default_weight")]
+    pub weight: f32,
+    // lets it come flipped left to right too
+    #[serde(default)]
+    pub mirror: bool,
+    // an id from the unlocks menu, it stays out of the pool until that's bought
+    #[serde(default)]
+    pub unlock: Option<String>,
+    pub drops: Vec<PatternDrop>,
+}
+
+#[derive(Deserialize, Clone, Copy, Debug)]
+pub struct PatternDrop {
+    // seconds after the pattern's first drop
+    #[serde(default)]
+    pub delay: f32,
+    // -1 and 1 are the edges of the spawn area
+    pub x: f32,
+    pub size: (f32, f32),
+    // left out, it's rolled like a random drop
+    #[serde(default)]
+    pub class: Option<WeightClass>,
+    #[serde(default)]
+    pub shape: PieceShape,
+    #[serde(default)]
+    pub spin: f32,
+    #[serde(default)]
+    pub bomb: bool,
+}
+
+fn default_weight() -> f32 {
+    1.0
+}
+
+#[derive(Asset, TypePath)]
+pub struct PatternFile(Vec<Pattern>);
+
+#[derive(Default)]
+struct PatternLoader;
+
+impl AssetLoader for PatternLoader {
+    type Asset = PatternFile;
+    type Settings = ();
+    type Error = std::io::Error;
+
+    async fn load(
+        &self,
+        reader: &mut dyn Reader,
+        _settings: &(),
+        _load_context: &mut LoadContext<'_>,
+    ) -> Result<PatternFile, Self::Error> {
+        let mut bytes = Vec::new();
+        reader.read_to_end(&mut bytes).await?;
+        let mut patterns: Vec<Pattern> = ron::de::from_bytes(&bytes)
+            .map_err(|err| std::io::Error::new(std::io::ErrorKind::InvalidData, err))?;
+        // the spawner's queue has to stay in spawn order
+        for pattern in &mut patterns {
+            pattern.drops.sort_by(|a, b| a.delay.total_cmp(&b.delay));
+        }
+        Ok(PatternFile(patterns))
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["patterns.ron"]
+    }
+}
+
+#[derive(Resource)]
+struct PatternHandles(Vec<Handle<PatternFile>>);
+
+#[derive(Resource, Default)]
+struct PatternDirector {
+    next_in: f32,
+    // queued by the console, skips the wait and the dice
+    forced: Option<String>,
+}
+
+fn pattern_gap(difficulty: &Difficulty) -> f32 {
+    (BASE_PATTERN_GAP / difficulty.level).max(MIN_PATTERN_GAP)
+}
+
+// a file that fails to load just leaves its patterns out
+fn load_patterns(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    mut tracked: ResMut<TrackedAssets>,
+) {
+    let handles: Vec<Handle<PatternFile>> = PATTERN_FILES
+        .iter()
+        .map(|path| asset_server.load(*path))
+        .collect();
+    for handle in &handles {
+        tracked.track(handle.clone());
+    }
+    commands.insert_resource(PatternHandles(handles));
+}
+
+fn all_patterns<'a>(
+    handles: &'a PatternHandles,
+    files: &'a Assets<PatternFile>,
+) -> impl Iterator<Item = &'a Pattern> {
+    handles
+        .0
+        .iter()
+        .filter_map(|handle| files.get(handle))
+        .flat_map(|file| file.0.iter())
+}
+
+// every so often the random drops make way for a pattern that suits the level. it goes
+// in after what's already planned, and the random drops pick up after its last rect
+fn queue_patterns(
+    mut director: ResMut<PatternDirector>,
+    mut died: EventReader<PlayerDied>,
+    mut spawner: ResMut<Spawner>,
+    difficulty: Res<Difficulty>,
+    difficulty_director: Res<Director>,
+    handles: Res<PatternHandles>,
+    files: Res<Assets<PatternFile>>,
+    save: Res<SaveData>,
+    time: Res<Time>,
+) {
+    if died.read().count() > 0 {
+        director.next_in = pattern_gap(&difficulty);
+        director.forced = None;
+        return;
+    }
+    director.next_in -= time.delta_secs();
+    let mut rng = rand::thread_rng();
+    let pattern = if let Some(name) = director.forced.take() {
+        all_patterns(&handles, &files).find(|pattern| pattern.name == name)
+    } else {
+        if director.next_in > 0.0 {
+            return;
+        }
+        director.next_in = pattern_gap(&difficulty);
+        if spawner.calm || spawner.held || spawner.scripted {
+            return;
+        }
+        if difficulty_director.performance() < MIN_PERFORMANCE {
+            debug!(target: "spawner", "skipping a pattern, the player is struggling");
+            return;
+        }
+        let eligible: Vec<&Pattern> = all_patterns(&handles, &files)
+            .filter(|pattern| pattern.min_level <= difficulty.level && pattern.weight > 0.0)
+            .filter(|pattern| {
+                pattern
+                    .unlock
+                    .as_ref()
+                    .is_none_or(|id| save.unlocks.contains(id))
+            })
+            .collect();
+        let total: f32 = eligible.iter().map(|pattern| pattern.weight).sum();
+        let mut roll = rng.gen_range(0.0..=total);
+        eligible.into_iter().find(|pattern| {
+            roll -= pattern.weight;
+            roll <= 0.0
+        })
+    };
+    let Some(pattern) = pattern else {
+        return;
+    };
+    let lead_time = difficulty.lead_time();
+    let start = match spawner.last_spawn_at() {
+        Some(last) => last + difficulty.spawn_interval(),
+        None => time.elapsed_secs() + lead_time,
+    };
+    let flip = if pattern.mirror && rng.gen_bool(0.5) {
+        -1.0
+    } else {
+        1.0
+    };
+    debug!(target: "spawner", "queued pattern {} at {start:.2}s", pattern.name);
+    for drop in &pattern.drops {
+        let spawn_at = start + drop.delay;
+        spawner.push(PlannedDrop {
+            id: 0,
+            x: drop.x.clamp(-1.0, 1.0) * flip * SPAWN_HALF_WIDTH,
+            size: Vec2::new(drop.size.0, drop.size.1),
+            class: drop
+                .class
+                .unwrap_or_else(|| difficulty.pick_weight_class(rng.gen())),
+            shape: drop.shape,
+            spin: drop.spin * flip,
+            bomb: drop.bomb,
+            boss: false,
+            warn_at: spawn_at - lead_time,
+            spawn_at,
+        });
+    }
+}
+
+fn run_pattern_command(
+    mut commands: EventReader<ConsoleCommand>,
+    mut console: ResMut<Console>,
+    mut director: ResMut<PatternDirector>,
+    handles: Res<PatternHandles>,
+    files: Res<Assets<PatternFile>>,
+    mut stats: ResMut<RunStats>,
+) {
+    for command in commands.read().filter(|command| command.name == "pattern") {
+        let names: Vec<&str> = all_patterns(&handles, &files)
+            .map(|pattern| pattern.name.as_str())
+            .collect();
+        match command.args.first().map(String::as_str) {
+            Some(name) if names.contains(&name) => {
+                director.forced = Some(name.to_string());
+                // picking the drops is practice, the run stays off the leaderboard
+                stats.assisted = true;
+                console.print(format!("queued pattern {name}"));
+            }
+            _ => console.print(format!("patterns: {}", names.join(", "))),
+        }
+    }
+}
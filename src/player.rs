@@ -0,0 +1,282 @@
+use std::f32::consts::{FRAC_PI_2, PI};
+
+use avian2d::prelude::*;
+use bevy::prelude::*;
+
+use crate::climb::Stamina;
+use crate::collision_layer::{ground_sensor_layers, solid_mask, GameLayer};
+use crate::common_assets::polygon_mesh;
+use crate::health::{Health, SpawnProtection};
+use crate::palette::PaletteRole;
+use crate::prefab::{spawn_rect, Checkpoint, PieceShape};
+use crate::push_pull::PlayerStrength;
+use crate::settings::{PlayerSmoothing, Settings};
+use crate::z_layer::ZLayer;
+use crate::{GameSet, GroundSensor, Player, PlayerContacts, PlayerDied, PLAYER_SPAWN};
+
+pub struct PlayerLifecyclePlugin;
+
+impl Plugin for PlayerLifecyclePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<DespawnPlayer>()
+            .insert_resource(RespawnPoint(PLAYER_SPAWN))
+            .add_systems(
+                Update,
+                (activate_checkpoints, respawn_player, despawn_players)
+                    .chain()
+                    .in_set(GameSet::Reactions),
+            )
+            .add_systems(Update, smooth_player.in_set(GameSet::Vfx));
+    }
+}
+
+// moved by touching a checkpoint
+#[derive(Resource)]
+pub struct RespawnPoint(pub Vec2);
+
+// points on each rounded end of a capsule player
+const CAPSULE_SEGMENTS: u32 = 8;
+// how far from the checkpoint a respawn looks for room, in steps of this many pixels
+const SAFE_SPAWN_STEP: f32 = 50.0;
+const SAFE_SPAWN_STEPS: u32 = 12;
+
+// kept on the player so a respawn comes back with the same body, whether it came from
+// the game config or from level data
+#[derive(Component, Clone, Copy, Debug)]
+pub struct PlayerConfig {
+    pub size: Vec2,
+    // only Rect and Capsule, anything else is treated as a rect. a capsule's rounded
+    // bottom rides over the edges of a rough pile instead of catching on them
+    pub shape: PieceShape,
+}
+
+impl Default for PlayerConfig {
+    fn default() -> PlayerConfig {
+        PlayerConfig {
+            size: Vec2::splat(100.0),
+            shape: PieceShape::Rect,
+        }
+    }
+}
+
+// a capsule filling the player's box, rounded across its shorter side. it's built in
+// world units and divided back into the unit box, so the player's scale stretches it
+// back into shape without squashing the round ends
+fn capsule_outline(size: Vec2) -> Vec<Vec2> {
+    let radius = size.min_element() / 2.0;
+    let axis = if size.y >= size.x { Vec2::Y } else { Vec2::X };
+    let half_span = (size.max_element() / 2.0 - radius) * axis;
+    let mut outline = Vec::new();
+    for (center, start) in [
+        (half_span, axis.to_angle() - FRAC_PI_2),
+        (-half_span, axis.to_angle() + FRAC_PI_2),
+    ] {
+        for i in 0..=CAPSULE_SEGMENTS {
+            let angle = start + PI * i as f32 / CAPSULE_SEGMENTS as f32;
+            outline.push((center + radius * Vec2::from_angle(angle)) / size);
+        }
+    }
+    outline
+}
+
+// removes the player along with its children and everything tagged with PlayerOwned
+#[derive(Event)]
+pub struct DespawnPlayer(pub Entity);
+
+// for entities living outside the player's hierarchy (trail particles, sound emitters...)
+// that should go away with it
+#[derive(Component)]
+pub struct PlayerOwned(pub Entity);
+
+// movement abilities beyond the basics, meta progression unlocks fill these in
+#[derive(Component, Default)]
+pub struct Abilities {
+    pub glide: bool,
+}
+
+pub fn spawn_player_at(commands: &mut Commands, position: Vec2, config: PlayerConfig) -> Entity {
+    let size = config.size;
+    let player = spawn_rect(
+        commands,
+        "Player",
+        PaletteRole::Player,
+        size,
+        position,
+        |assets| &assets.player,
+    );
+    commands.entity(player).insert((
+        Player::default(),
+        PlayerContacts::default(),
+        ZLayer::Player,
+        Health::default(),
+        Stamina::default(),
+        PlayerStrength::default(),
+        Abilities::default(),
+        GravityScale(1.0),
+        RigidBody::Dynamic,
+        LockedAxes::ROTATION_LOCKED,
+        GameLayer::Player.layers(),
+        config,
+    ));
+    // in the player's unit box, just under its feet and narrower than it so walls
+    // don't count
+    commands.entity(player).with_child((
+        Name::new("GroundSensor"),
+        GroundSensor::default(),
+        Sensor,
+        Collider::rectangle(0.8, 0.1),
+        ground_sensor_layers(),
+        ColliderDensity(0.0),
+        Transform::from_xyz(0.0, -0.5, 0.0),
+    ));
+    let capsule = match config.shape {
+        PieceShape::Capsule => {
+            let outline = capsule_outline(size);
+            Collider::convex_hull(outline.clone()).map(|collider| (collider, outline))
+        }
+        _ => None,
+    };
+    match capsule {
+        Some((collider, outline)) => {
+            commands.entity(player).insert(collider);
+            // after spawn_rect's own mesh goes in
+            commands.queue(move |world: &mut World| {
+                let mesh = world
+                    .resource_mut::<Assets<Mesh>>()
+                    .add(polygon_mesh(&outline));
+                if let Ok(mut entity) = world.get_entity_mut(player) {
+                    entity.insert(Mesh2d(mesh));
+                }
+            });
+        }
+        None => {
+            commands
+                .entity(player)
+                .insert(Collider::rectangle(1.0, 1.0));
+        }
+    }
+    debug!(target: "player", "spawning {:?} player at {position}", config.shape);
+    player
+}
+
+fn activate_checkpoints(
+    mut collisions: EventReader<CollisionStarted>,
+    checkpoints: Query<&Transform, With<Checkpoint>>,
+    player: Single<Entity, With<Player>>,
+    mut respawn_point: ResMut<RespawnPoint>,
+) {
+    for CollisionStarted(a, b) in collisions.read() {
+        let other = if *a == *player {
+            *b
+        } else if *b == *player {
+            *a
+        } else {
+            continue;
+        };
+        if let Ok(transform) = checkpoints.get(other) {
+            let position = transform.translation.truncate();
+            if respawn_point.0 != position {
+                debug!(target: "player", "checkpoint reached at {position}");
+                respawn_point.0 = position;
+            }
+        }
+    }
+}
+
+// the pile keeps growing over a checkpoint, so the player might not fit on it anymore.
+// looks above it first, where the pile usually has room, then out to either side
+fn find_safe_spawn(spatial_query: &SpatialQuery, point: Vec2, size: Vec2) -> Option<Vec2> {
+    let body = Collider::rectangle(size.x, size.y);
+    let filter = SpatialQueryFilter::from_mask(solid_mask());
+    (0..=SAFE_SPAWN_STEPS)
+        .flat_map(|step| {
+            let offset = step as f32 * SAFE_SPAWN_STEP;
+            [
+                Vec2::new(0.0, offset),
+                Vec2::new(-offset, 0.0),
+                Vec2::new(offset, 0.0),
+            ]
+        })
+        .map(|offset| point + offset)
+        .find(|position| {
+            spatial_query
+                .shape_intersections(&body, *position, 0.0, &filter)
+                .is_empty()
+        })
+}
+
+// a fresh player entity, so nothing from the previous life carries over
+fn respawn_player(
+    mut commands: Commands,
+    mut died: EventReader<PlayerDied>,
+    player: Single<(Entity, &PlayerConfig), With<Player>>,
+    respawn_point: Res<RespawnPoint>,
+    spatial_query: SpatialQuery,
+    mut despawn: EventWriter<DespawnPlayer>,
+) {
+    if died.read().count() == 0 {
+        return;
+    }
+    let (player, config) = *player;
+    despawn.send(DespawnPlayer(player));
+    let position =
+        find_safe_spawn(&spatial_query, respawn_point.0, config.size).unwrap_or_else(|| {
+            warn!(
+                "no room to respawn near {}, spawning inside the pile",
+                respawn_point.0
+            );
+            respawn_point.0
+        });
+    let player = spawn_player_at(&mut commands, position, *config);
+    commands.entity(player).insert(SpawnProtection::default());
+}
+
+// a fresh player starts without any easing, so this also picks up every respawn
+fn smooth_player(
+    mut commands: Commands,
+    settings: Res<Settings>,
+    added: Query<Entity, Added<Player>>,
+    players: Query<Entity, With<Player>>,
+    mut applied: Local<Option<PlayerSmoothing>>,
+) {
+    let changed = *applied != Some(settings.player_smoothing);
+    *applied = Some(settings.player_smoothing);
+    let players: Vec<Entity> = if changed {
+        players.iter().collect()
+    } else {
+        added.iter().collect()
+    };
+    for player in players {
+        let mut player = commands.entity(player);
+        player.remove::<(
+            TransformInterpolation,
+            TransformHermiteEasing,
+            TransformExtrapolation,
+        )>();
+        match settings.player_smoothing {
+            PlayerSmoothing::Interpolated => player.insert(TransformInterpolation),
+            PlayerSmoothing::Smoothed => {
+                player.insert((TransformInterpolation, TransformHermiteEasing))
+            }
+            PlayerSmoothing::Extrapolated => player.insert(TransformExtrapolation),
+        };
+    }
+}
+
+fn despawn_players(
+    mut commands: Commands,
+    mut despawn: EventReader<DespawnPlayer>,
+    owned: Query<(Entity, &PlayerOwned)>,
+) {
+    for DespawnPlayer(player) in despawn.read() {
+        debug!(target: "player", "despawning player {player}");
+        for (entity, owner) in &owned {
+            if owner.0 == *player {
+                commands.entity(entity).despawn_recursive();
+            }
+        }
+        if let Some(entity) = commands.get_entity(*player) {
+            entity.despawn_recursive();
+        }
+    }
+}
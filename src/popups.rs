@@ -0,0 +1,173 @@
+use bevy::prelude::*;
+
+use crate::i18n::Localization;
+use crate::palette::Palette;
+use crate::settings::Settings;
+use crate::z_layer::ZLayer;
+use crate::{GameSet, Player, PlayerWallJumped};
+
+const POPUP_FONT_SIZE: f32 = 28.0;
+const POPUP_LIFETIME_SECS: f32 = 0.9;
+const POPUP_RISE: f32 = 80.0;
+// the oldest popup is reused once this many are on screen
+const MAX_POPUPS: usize = 16;
+const COMBO_OFFSET: Vec2 = Vec2::new(0.0, 40.0);
+
+pub struct PopupsPlugin;
+
+impl Plugin for PopupsPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<ShowPopup>()
+            .init_resource::<PopupPool>()
+            .add_systems(Update, show_wall_jump_combos.in_set(GameSet::Reactions))
+            .add_systems(
+                Update,
+                (spawn_popups, animate_popups).chain().in_set(GameSet::Vfx),
+            );
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum PopupStyle {
+    Score,
+    Damage,
+    Combo,
+}
+
+impl PopupStyle {
+    fn color(self, palette: &Palette) -> Color {
+        match self {
+            PopupStyle::Score | PopupStyle::Combo => palette.ui_accent,
+            PopupStyle::Damage => palette.hazard,
+        }
+    }
+
+    fn scale(self) -> f32 {
+        match self {
+            PopupStyle::Score => 1.0,
+            PopupStyle::Damage | PopupStyle::Combo => 1.25,
+        }
+    }
+}
+
+// floating text in world space, e.g. `+50` where points were scored
+#[derive(Event)]
+pub struct ShowPopup {
+    pub text: String,
+    pub position: Vec2,
+    pub style: PopupStyle,
+}
+
+#[derive(Component)]
+struct Popup {
+    age: f32,
+    origin: Vec2,
+    style: PopupStyle,
+}
+
+// popups come in bursts, so the entities are kept around hidden instead of respawned
+#[derive(Resource, Default)]
+struct PopupPool {
+    free: Vec<Entity>,
+    // oldest first
+    active: Vec<Entity>,
+}
+
+fn ease_out_cubic(t: f32) -> f32 {
+    1.0 - (1.0 - t).powi(3)
+}
+
+fn show_wall_jump_combos(
+    mut wall_jumps: EventReader<PlayerWallJumped>,
+    player: Single<&Transform, With<Player>>,
+    localization: Res<Localization>,
+    mut popups: EventWriter<ShowPopup>,
+) {
+    for PlayerWallJumped { chain } in wall_jumps.read() {
+        if *chain < 2 {
+            continue;
+        }
+        popups.send(ShowPopup {
+            text: localization.format("popup.combo", &[("chain", chain)]),
+            position: player.translation.truncate() + COMBO_OFFSET,
+            style: PopupStyle::Combo,
+        });
+    }
+}
+
+fn spawn_popups(
+    mut commands: Commands,
+    mut requests: EventReader<ShowPopup>,
+    mut pool: ResMut<PopupPool>,
+    palette: Res<Palette>,
+) {
+    for request in requests.read() {
+        let entity = if let Some(entity) = pool.free.pop() {
+            entity
+        } else if pool.active.len() + pool.free.len() < MAX_POPUPS {
+            commands
+                .spawn((Name::new("Popup"), ZLayer::Ui, Text2d::default()))
+                .id()
+        } else {
+            pool.active.remove(0)
+        };
+        pool.active.push(entity);
+        commands.entity(entity).insert((
+            Popup {
+                age: 0.0,
+                origin: request.position,
+                style: request.style,
+            },
+            Text2d::new(request.text.clone()),
+            TextFont {
+                font_size: POPUP_FONT_SIZE,
+                ..default()
+            },
+            TextColor(request.style.color(&palette)),
+            Transform::from_translation(request.position.extend(0.0)),
+            Visibility::Inherited,
+        ));
+    }
+}
+
+// rises with an ease out, pops in slightly larger and fades over the second half.
+// real time, so popups finish even when the game gets paused under them
+fn animate_popups(
+    mut popups: Query<(
+        Entity,
+        &mut Popup,
+        &mut Transform,
+        &mut TextColor,
+        &mut Visibility,
+    )>,
+    mut pool: ResMut<PopupPool>,
+    settings: Res<Settings>,
+    time: Res<Time<Real>>,
+) {
+    for (entity, mut popup, mut transform, mut color, mut visibility) in &mut popups {
+        if *visibility == Visibility::Hidden {
+            continue;
+        }
+        popup.age += time.delta_secs();
+        let t = (popup.age / POPUP_LIFETIME_SECS).min(1.0);
+        if t >= 1.0 {
+            *visibility = Visibility::Hidden;
+            pool.active.retain(|active| *active != entity);
+            pool.free.push(entity);
+            continue;
+        }
+        let rise = if settings.reduce_motion {
+            0.0
+        } else {
+            ease_out_cubic(t) * POPUP_RISE
+        };
+        transform.translation = (popup.origin + Vec2::Y * rise).extend(transform.translation.z);
+        let pop = if settings.reduce_motion {
+            1.0
+        } else {
+            1.0 + 0.3 * (1.0 - ease_out_cubic((t * 4.0).min(1.0)))
+        };
+        transform.scale = Vec3::splat(popup.style.scale() * pop);
+        color.0 = color.0.with_alpha(((1.0 - t) * 2.0).min(1.0));
+    }
+}
@@ -0,0 +1,198 @@
+use avian2d::prelude::*;
+use bevy::prelude::*;
+
+use crate::boss::BossRect;
+use crate::climb::Stamina;
+use crate::explosion::Bomb;
+use crate::health::Health;
+use crate::hud::ShowToast;
+use crate::i18n::Localization;
+use crate::input::{Action, ActionState};
+use crate::prefab::{PieceShape, PrefabParams, Prefabs};
+use crate::settings::Settings;
+use crate::spawner::{FallingRect, WeightClass};
+use crate::{GameSet, Player, PlayerDied};
+
+// rects further than this from the player aren't part of a save state, and are left
+// alone when one is restored
+const SNAPSHOT_RADIUS: f32 = 800.0;
+
+pub struct PracticePlugin;
+
+impl Plugin for PracticePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<SaveState>().add_systems(
+            Update,
+            (clear_on_death, save_state, load_state)
+                .chain()
+                .in_set(GameSet::Reactions),
+        );
+    }
+}
+
+struct PlayerSnapshot {
+    position: Vec2,
+    linear: Vec2,
+    health: u32,
+    stamina: f32,
+}
+
+// frozen rects come back as regular ones
+struct RectSnapshot {
+    prefab: &'static str,
+    shape: PieceShape,
+    // its scale is the rect's size
+    transform: Transform,
+    linear: Vec2,
+    angular: f32,
+    sleeping: bool,
+}
+
+// a single slot, saving again overwrites it
+#[derive(Resource, Default)]
+struct SaveState(Option<(PlayerSnapshot, Vec<RectSnapshot>)>);
+
+fn clear_on_death(mut died: EventReader<PlayerDied>, mut state: ResMut<SaveState>) {
+    if died.read().count() > 0 {
+        state.0 = None;
+    }
+}
+
+fn save_state(
+    settings: Res<Settings>,
+    actions: Res<ActionState>,
+    mut state: ResMut<SaveState>,
+    player: Single<(&Transform, &LinearVelocity, &Health, &Stamina), With<Player>>,
+    rects: Query<
+        (
+            &Transform,
+            &LinearVelocity,
+            &AngularVelocity,
+            &RigidBody,
+            &WeightClass,
+            &PieceShape,
+            Has<Bomb>,
+            Has<BossRect>,
+            Has<Sleeping>,
+        ),
+        With<FallingRect>,
+    >,
+    localization: Res<Localization>,
+    mut toasts: EventWriter<ShowToast>,
+) {
+    if !settings.practice_mode || !actions.just_pressed(Action::SaveState) {
+        return;
+    }
+    let (transform, linear, health, stamina) = *player;
+    let center = transform.translation.truncate();
+    let player = PlayerSnapshot {
+        position: center,
+        linear: linear.0,
+        health: health.current,
+        stamina: stamina.current,
+    };
+    let rects: Vec<RectSnapshot> = rects
+        .iter()
+        .filter(|(transform, ..)| {
+            transform.translation.truncate().distance(center) <= SNAPSHOT_RADIUS
+        })
+        .map(
+            |(transform, linear, angular, body, class, shape, bomb, boss, sleeping)| {
+                let prefab = if boss {
+                    "boss"
+                } else if bomb {
+                    "bomb"
+                } else {
+                    class.prefab()
+                };
+                RectSnapshot {
+                    prefab,
+                    shape: *shape,
+                    transform: *transform,
+                    linear: if body.is_dynamic() {
+                        linear.0
+                    } else {
+                        Vec2::ZERO
+                    },
+                    angular: if body.is_dynamic() { angular.0 } else { 0.0 },
+                    sleeping,
+                }
+            },
+        )
+        .collect();
+    debug!(target: "player", "saved a practice state with {} rects", rects.len());
+    state.0 = Some((player, rects));
+    toasts.send(ShowToast(localization.get("practice.saved").to_string()));
+}
+
+// swaps the rects around the saved spot for the saved ones and puts the player back
+fn load_state(
+    mut commands: Commands,
+    settings: Res<Settings>,
+    actions: Res<ActionState>,
+    state: Res<SaveState>,
+    player: Single<
+        (
+            &mut Player,
+            &mut Transform,
+            &mut LinearVelocity,
+            &mut Health,
+            &mut Stamina,
+        ),
+        Without<FallingRect>,
+    >,
+    rects: Query<(Entity, &Transform), With<FallingRect>>,
+    prefabs: Res<Prefabs>,
+    localization: Res<Localization>,
+    mut toasts: EventWriter<ShowToast>,
+) {
+    if !settings.practice_mode || !actions.just_pressed(Action::LoadState) {
+        return;
+    }
+    let Some((saved_player, saved_rects)) = &state.0 else {
+        toasts.send(ShowToast(
+            localization.get("practice.nothing_saved").to_string(),
+        ));
+        return;
+    };
+    for (entity, transform) in &rects {
+        if transform
+            .translation
+            .truncate()
+            .distance(saved_player.position)
+            <= SNAPSHOT_RADIUS
+        {
+            commands.entity(entity).despawn_recursive();
+        }
+    }
+    for rect in saved_rects {
+        let params = PrefabParams {
+            position: rect.transform.translation.truncate(),
+            size: Some(rect.transform.scale.truncate()),
+            shape: rect.shape,
+            ..default()
+        };
+        let Some(entity) = prefabs.spawn(rect.prefab, &mut commands, &params) else {
+            continue;
+        };
+        let mut entity = commands.entity(entity);
+        // falling rects spawn upright, the saved one may have been tumbling
+        entity.insert((
+            rect.transform,
+            LinearVelocity(rect.linear),
+            AngularVelocity(rect.angular),
+        ));
+        if rect.sleeping {
+            entity.insert(Sleeping);
+        }
+    }
+    let (mut player, mut transform, mut linear, mut health, mut stamina) = player.into_inner();
+    player.reset_jump();
+    transform.translation.x = saved_player.position.x;
+    transform.translation.y = saved_player.position.y;
+    linear.0 = saved_player.linear;
+    health.current = saved_player.health;
+    stamina.current = saved_player.stamina;
+    debug!(target: "player", "restored a practice state");
+    toasts.send(ShowToast(localization.get("practice.restored").to_string()));
+}
@@ -0,0 +1,503 @@
+use avian2d::prelude::*;
+use bevy::prelude::*;
+use bevy::utils::HashMap;
+use serde::Deserialize;
+
+use crate::collision_layer::GameLayer;
+use crate::common_assets::CommonAssets;
+use crate::console::{Console, ConsoleAppExt, ConsoleCommand};
+use crate::explosion::Breakable;
+use crate::highlight::Interactable;
+use crate::palette::{PaletteRole, Themed};
+use crate::player::{spawn_player_at, PlayerConfig};
+use crate::score::RunStats;
+use crate::spawner::{FallingRect, PendingBody, WeightClass};
+use crate::surface::SurfaceMaterial;
+use crate::z_layer::ZLayer;
+
+const RAMP_ANGLE_DEGREES: f32 = 30.0;
+
+pub struct PrefabPlugin;
+
+impl Plugin for PrefabPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Prefabs>()
+            .register_prefab("block", spawn_block)
+            .register_prefab("ramp", spawn_ramp)
+            .register_prefab("player", spawn_player_prefab)
+            .register_prefab("crate", spawn_crate)
+            .register_prefab("foam", spawn_foam)
+            .register_prefab("heavy", spawn_heavy)
+            .register_prefab("spike", spawn_spike)
+            .register_prefab("spring", spawn_spring)
+            .register_prefab("checkpoint", spawn_checkpoint)
+            .add_console_command("spawn", "spawn <prefab> [x y]")
+            .add_systems(Update, (run_spawn_command, finish_falling_rects));
+    }
+}
+
+#[derive(Clone, Copy, Default)]
+pub struct PrefabParams {
+    pub position: Vec2,
+    // None uses the prefab's own size
+    pub size: Option<Vec2>,
+    // radians, None uses the prefab's own rotation
+    pub rotation: Option<f32>,
+    // only falling rects and the player look at this
+    pub shape: PieceShape,
+}
+
+// everything is built inside a unit box and scaled to the piece's size. round shapes
+// keep their proportions, so they get a uniform scale
+#[derive(Component, Clone, Copy, PartialEq, Eq, Debug, Default, Deserialize)]
+pub enum PieceShape {
+    #[default]
+    Rect,
+    Circle,
+    Triangle,
+    Capsule,
+    LPiece,
+    TPiece,
+}
+
+impl PieceShape {
+    pub const ALL: [PieceShape; 6] = [
+        PieceShape::Rect,
+        PieceShape::Circle,
+        PieceShape::Triangle,
+        PieceShape::Capsule,
+        PieceShape::LPiece,
+        PieceShape::TPiece,
+    ];
+
+    pub fn is_round(self) -> bool {
+        matches!(self, PieceShape::Circle | PieceShape::Capsule)
+    }
+
+    // compound pieces as (center, size) bars, the same list builds the mesh and the collider
+    pub fn bars(self) -> &'static [(Vec2, Vec2)] {
+        const THIRD: f32 = 1.0 / 3.0;
+        const L_PIECE: &[(Vec2, Vec2)] = &[
+            (Vec2::new(-0.25, 0.0), Vec2::new(0.5, 1.0)),
+            (Vec2::new(0.25, -THIRD), Vec2::new(0.5, THIRD)),
+        ];
+        const T_PIECE: &[(Vec2, Vec2)] = &[
+            (Vec2::new(0.0, 0.25), Vec2::new(1.0, 0.5)),
+            (Vec2::new(0.0, -0.25), Vec2::new(THIRD, 0.5)),
+        ];
+        match self {
+            PieceShape::LPiece => L_PIECE,
+            PieceShape::TPiece => T_PIECE,
+            _ => &[(Vec2::ZERO, Vec2::ONE)],
+        }
+    }
+
+    pub fn collider(self) -> Collider {
+        match self {
+            PieceShape::Rect => Collider::rectangle(1.0, 1.0),
+            PieceShape::Circle => Collider::circle(0.5),
+            PieceShape::Triangle => Collider::triangle(
+                Vec2::new(-0.5, -0.5),
+                Vec2::new(0.5, -0.5),
+                Vec2::new(0.0, 0.5),
+            ),
+            PieceShape::Capsule => Collider::capsule(0.25, 0.5),
+            PieceShape::LPiece | PieceShape::TPiece => Collider::compound(
+                self.bars()
+                    .iter()
+                    .map(|(center, size)| {
+                        (
+                            Position(*center),
+                            Rotation::default(),
+                            Collider::rectangle(size.x, size.y),
+                        )
+                    })
+                    .collect(),
+            ),
+        }
+    }
+
+    pub fn mesh(self, assets: &CommonAssets) -> Handle<Mesh> {
+        match self {
+            PieceShape::Rect => assets.rect.clone(),
+            PieceShape::Circle => assets.circle.clone(),
+            PieceShape::Triangle => assets.triangle.clone(),
+            PieceShape::Capsule => assets.capsule.clone(),
+            PieceShape::LPiece => assets.l_piece.clone(),
+            PieceShape::TPiece => assets.t_piece.clone(),
+        }
+    }
+}
+
+pub type PrefabSpawner = fn(&mut Commands, &PrefabParams) -> Entity;
+
+// named entity templates, shared by everything that puts things in the world
+#[derive(Resource, Default)]
+pub struct Prefabs(HashMap<&'static str, PrefabSpawner>);
+
+impl Prefabs {
+    pub fn spawn(
+        &self,
+        name: &str,
+        commands: &mut Commands,
+        params: &PrefabParams,
+    ) -> Option<Entity> {
+        self.0.get(name).map(|spawner| spawner(commands, params))
+    }
+
+    pub fn names(&self) -> impl Iterator<Item = &&'static str> {
+        self.0.keys()
+    }
+}
+
+pub trait PrefabAppExt {
+    fn register_prefab(&mut self, name: &'static str, spawner: PrefabSpawner) -> &mut Self;
+}
+
+impl PrefabAppExt for App {
+    fn register_prefab(&mut self, name: &'static str, spawner: PrefabSpawner) -> &mut Self {
+        self.init_resource::<Prefabs>();
+        self.world_mut()
+            .resource_mut::<Prefabs>()
+            .0
+            .insert(name, spawner);
+        self
+    }
+}
+
+// solid static terrain, the level bake merges these
+#[derive(Component)]
+pub struct TerrainBlock;
+
+#[derive(Component)]
+pub struct Spike;
+
+#[derive(Component)]
+pub struct Checkpoint;
+
+// a themed rectangle, scaled from the shared unit mesh. colliders on it should be unit
+// sized too, since they get scaled along with it
+pub fn spawn_rect(
+    commands: &mut Commands,
+    name: &'static str,
+    role: PaletteRole,
+    size: Vec2,
+    position: Vec2,
+    material: fn(&CommonAssets) -> &Handle<ColorMaterial>,
+) -> Entity {
+    spawn_piece(
+        commands,
+        name,
+        role,
+        PieceShape::Rect,
+        size,
+        position,
+        material,
+    )
+}
+
+pub fn spawn_piece(
+    commands: &mut Commands,
+    name: &'static str,
+    role: PaletteRole,
+    shape: PieceShape,
+    size: Vec2,
+    position: Vec2,
+    material: fn(&CommonAssets) -> &Handle<ColorMaterial>,
+) -> Entity {
+    let entity = commands
+        .spawn((
+            Name::new(name),
+            Themed(role),
+            Transform::from_translation(position.extend(0.0)).with_scale(size.extend(1.0)),
+        ))
+        .id();
+    commands.queue(move |world: &mut World| {
+        let assets = world.resource::<CommonAssets>();
+        let bundle = (
+            Mesh2d(shape.mesh(assets)),
+            MeshMaterial2d(material(assets).clone()),
+        );
+        if let Ok(mut entity) = world.get_entity_mut(entity) {
+            entity.insert(bundle);
+        }
+    });
+    entity
+}
+
+fn spawn_block(commands: &mut Commands, params: &PrefabParams) -> Entity {
+    let size = params.size.unwrap_or(Vec2::splat(100.0));
+    let entity = spawn_rect(
+        commands,
+        "Block",
+        PaletteRole::Terrain,
+        size,
+        params.position,
+        |assets| &assets.terrain,
+    );
+    commands.entity(entity).insert((
+        TerrainBlock,
+        ZLayer::Terrain,
+        RigidBody::Static,
+        Collider::rectangle(1.0, 1.0),
+        GameLayer::Terrain.layers(),
+    ));
+    entity
+}
+
+// a tilted block, positive rotation rises to the right
+fn spawn_ramp(commands: &mut Commands, params: &PrefabParams) -> Entity {
+    let size = params.size.unwrap_or(Vec2::new(400.0, 40.0));
+    let rotation = params.rotation.unwrap_or(RAMP_ANGLE_DEGREES.to_radians());
+    let entity = spawn_block(
+        commands,
+        &PrefabParams {
+            size: Some(size),
+            ..*params
+        },
+    );
+    commands.entity(entity).insert((
+        Name::new("Ramp"),
+        Transform::from_translation(params.position.extend(0.0))
+            .with_rotation(Quat::from_rotation_z(rotation))
+            .with_scale(size.extend(1.0)),
+    ));
+    entity
+}
+
+fn spawn_player_prefab(commands: &mut Commands, params: &PrefabParams) -> Entity {
+    let mut config = PlayerConfig {
+        shape: params.shape,
+        ..default()
+    };
+    if let Some(size) = params.size {
+        config.size = size;
+    }
+    spawn_player_at(commands, params.position, config)
+}
+
+fn spawn_crate(commands: &mut Commands, params: &PrefabParams) -> Entity {
+    spawn_falling_rect(commands, params, WeightClass::Normal)
+}
+
+fn spawn_foam(commands: &mut Commands, params: &PrefabParams) -> Entity {
+    spawn_falling_rect(commands, params, WeightClass::Foam)
+}
+
+// shoves everything else out of the way
+fn spawn_heavy(commands: &mut Commands, params: &PrefabParams) -> Entity {
+    let params = PrefabParams {
+        size: params.size.or(Some(Vec2::splat(150.0))),
+        ..*params
+    };
+    spawn_falling_rect(commands, &params, WeightClass::Steel)
+}
+
+pub fn spawn_falling_rect(
+    commands: &mut Commands,
+    params: &PrefabParams,
+    class: WeightClass,
+) -> Entity {
+    let entity = commands.spawn_empty().id();
+    let params = *params;
+    commands.queue(move |world: &mut World| {
+        let bundle = falling_rect_bundle(world.resource::<CommonAssets>(), &params, class, true);
+        if let Ok(mut entity) = world.get_entity_mut(entity) {
+            entity.insert(bundle);
+        }
+    });
+    entity
+}
+
+// everything a falling rect needs in one bundle so a wave of them can go through spawn_batch,
+// the extras that differ per class are added by finish_falling_rects
+pub fn falling_rect_bundle(
+    assets: &CommonAssets,
+    params: &PrefabParams,
+    class: WeightClass,
+    upright: bool,
+) -> impl Bundle {
+    let shape = params.shape;
+    let mut size = params.size.unwrap_or(Vec2::splat(100.0));
+    if shape.is_round() {
+        size = Vec2::splat(size.max_element());
+    }
+    let (name, material) = match class {
+        WeightClass::Foam => ("FoamRect", &assets.foam),
+        WeightClass::Normal => ("FallingRect", &assets.debris),
+        WeightClass::Steel => ("HeavyRect", &assets.debris),
+    };
+    // plain rects stay upright like they always have, other shapes tumble into the pile
+    let locked_axes = if upright && shape == PieceShape::Rect {
+        LockedAxes::ROTATION_LOCKED
+    } else {
+        LockedAxes::new()
+    };
+    (
+        Name::new(name),
+        Themed(PaletteRole::Debris),
+        Transform::from_translation(params.position.extend(0.0)).with_scale(size.extend(1.0)),
+        Mesh2d(shape.mesh(assets)),
+        MeshMaterial2d(material.clone()),
+        (
+            FallingRect,
+            ZLayer::Pile,
+            Interactable::Grabbable,
+            class,
+            shape,
+            // the body and collider come a few per tick, see activate_pending_bodies
+            PendingBody,
+            GameLayer::FallingRect.layers(),
+            ColliderDensity(class.density()),
+            TransformInterpolation,
+            locked_axes,
+        ),
+    )
+}
+
+fn finish_falling_rects(
+    mut commands: Commands,
+    rects: Query<(Entity, &WeightClass), Added<FallingRect>>,
+) {
+    for (entity, class) in &rects {
+        match class {
+            // a darker plate in the middle so steel reads as heavy at a glance
+            WeightClass::Steel => add_inset(&mut commands, entity, 0.7, |assets| &assets.shadow),
+            WeightClass::Foam => {
+                commands
+                    .entity(entity)
+                    .insert((Breakable, SurfaceMaterial::Soft));
+            }
+            WeightClass::Normal => {}
+        }
+    }
+}
+
+// a smaller rect drawn on top of the middle of a piece
+pub fn add_inset(
+    commands: &mut Commands,
+    entity: Entity,
+    scale: f32,
+    material: fn(&CommonAssets) -> &Handle<ColorMaterial>,
+) {
+    commands.queue(move |world: &mut World| {
+        let assets = world.resource::<CommonAssets>();
+        let inset = (
+            Mesh2d(assets.rect.clone()),
+            MeshMaterial2d(material(assets).clone()),
+            Transform::from_xyz(0.0, 0.0, 0.1).with_scale(Vec3::new(scale, scale, 1.0)),
+        );
+        if let Ok(mut entity) = world.get_entity_mut(entity) {
+            entity.with_child(inset);
+        }
+    });
+}
+
+fn spawn_spike(commands: &mut Commands, params: &PrefabParams) -> Entity {
+    let size = params.size.unwrap_or(Vec2::new(100.0, 30.0));
+    let entity = spawn_rect(
+        commands,
+        "Spike",
+        PaletteRole::Hazard,
+        size,
+        params.position,
+        |assets| &assets.hazard,
+    );
+    commands.entity(entity).insert((
+        Spike,
+        ZLayer::Terrain,
+        RigidBody::Static,
+        Sensor,
+        Collider::rectangle(1.0, 1.0),
+        GameLayer::Hazard.layers(),
+    ));
+    entity
+}
+
+// a pad that launches whoever jumps off it higher
+fn spawn_spring(commands: &mut Commands, params: &PrefabParams) -> Entity {
+    let size = params.size.unwrap_or(Vec2::new(100.0, 20.0));
+    let entity = spawn_rect(
+        commands,
+        "Spring",
+        PaletteRole::Terrain,
+        size,
+        params.position,
+        |assets| &assets.terrain,
+    );
+    commands.entity(entity).insert((
+        SurfaceMaterial::Spring,
+        ZLayer::Terrain,
+        RigidBody::Static,
+        Collider::rectangle(1.0, 1.0),
+        GameLayer::Terrain.layers(),
+    ));
+    // the accent plate tells it apart from plain terrain
+    add_inset(commands, entity, 0.6, |assets| &assets.accent);
+    entity
+}
+
+fn spawn_checkpoint(commands: &mut Commands, params: &PrefabParams) -> Entity {
+    let size = params.size.unwrap_or(Vec2::new(40.0, 120.0));
+    let entity = spawn_rect(
+        commands,
+        "Checkpoint",
+        PaletteRole::Player,
+        size,
+        params.position,
+        |assets| &assets.checkpoint,
+    );
+    commands.entity(entity).insert((
+        Checkpoint,
+        ZLayer::Terrain,
+        Interactable::Touch,
+        RigidBody::Static,
+        Sensor,
+        Collider::rectangle(1.0, 1.0),
+        GameLayer::Sensor.layers(),
+    ));
+    entity
+}
+
+fn run_spawn_command(
+    mut commands: Commands,
+    mut console_commands: EventReader<ConsoleCommand>,
+    mut console: ResMut<Console>,
+    prefabs: Res<Prefabs>,
+    camera: Single<&Transform, With<Camera>>,
+    mut stats: ResMut<RunStats>,
+) {
+    for command in console_commands
+        .read()
+        .filter(|command| command.name == "spawn")
+    {
+        let Some(name) = command.args.first() else {
+            let mut names: Vec<&str> = prefabs.names().copied().collect();
+            names.sort();
+            console.print(format!("prefabs: {}", names.join(", ")));
+            continue;
+        };
+        let position = match command.args[1..] {
+            [ref x, ref y] => match (x.parse(), y.parse()) {
+                (Ok(x), Ok(y)) => Vec2::new(x, y),
+                _ => {
+                    console.print("spawn: x and y must be numbers");
+                    continue;
+                }
+            },
+            _ => camera.translation.truncate(),
+        };
+        let params = PrefabParams {
+            position,
+            ..default()
+        };
+        match prefabs.spawn(name, &mut commands, &params) {
+            Some(entity) => {
+                // free springs and checkpoints, the run stays off the leaderboard
+                stats.assisted = true;
+                console.print(format!("spawned {name} as {entity}"));
+            }
+            None => console.print(format!("unknown prefab '{name}'")),
+        }
+    }
+}
@@ -0,0 +1,304 @@
+use std::any::TypeId;
+use std::f32::consts::TAU;
+use std::sync::Arc;
+use std::time::Duration;
+
+use bevy::asset::io::Reader;
+use bevy::asset::{AssetLoader, AssetPath, LoadContext, LoadState};
+use bevy::audio::{AddAudioSource, Decodable, Source};
+use bevy::ecs::system::EntityCommands;
+use bevy::prelude::*;
+use bevy::utils::HashMap;
+use serde::Deserialize;
+
+use crate::asset_check::{AssetFailed, TrackedAssets};
+use crate::menu::GameState;
+
+const MANIFEST_PATH: &str = "audio.manifest.ron";
+// what plays in place of a sound that couldn't be loaded: a short, quiet tick, so the
+// cue is still there without pretending to be the real thing
+const PLACEHOLDER_SAMPLE_RATE: u32 = 22050;
+const PLACEHOLDER_SECS: f32 = 0.06;
+const PLACEHOLDER_PITCH_HZ: f32 = 660.0;
+const PLACEHOLDER_VOLUME: f32 = 0.2;
+
+pub struct PreloadPlugin;
+
+impl Plugin for PreloadPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_asset::<AudioManifest>()
+            .init_asset_loader::<AudioManifestLoader>()
+            .add_audio_source::<DecodedAudio>()
+            .add_systems(Startup, load_manifest)
+            .add_systems(Update, (preload_audio, substitute_failed_sounds))
+            .add_systems(OnEnter(GameState::Loading), pause_time)
+            .add_systems(OnExit(GameState::Loading), resume_time);
+    }
+}
+
+// which sounds get decoded before the first run starts. anything not listed is loaded
+// the first time it plays, the stream list just records that this is on purpose
+#[derive(Asset, TypePath, Deserialize)]
+struct AudioManifest {
+    preload: Vec<String>,
+    #[serde(default)]
+    stream: Vec<String>,
+}
+
+#[derive(Default)]
+struct AudioManifestLoader;
+
+impl AssetLoader for AudioManifestLoader {
+    type Asset = AudioManifest;
+    type Settings = ();
+    type Error = std::io::Error;
+
+    async fn load(
+        &self,
+        reader: &mut dyn Reader,
+        _settings: &(),
+        _load_context: &mut LoadContext<'_>,
+    ) -> Result<AudioManifest, Self::Error> {
+        let mut bytes = Vec::new();
+        reader.read_to_end(&mut bytes).await?;
+        ron::de::from_bytes(&bytes)
+            .map_err(|err| std::io::Error::new(std::io::ErrorKind::InvalidData, err))
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["manifest.ron"]
+    }
+}
+
+// a sound decoded to samples ahead of time. ogg is otherwise decoded as it plays, which
+// on wasm happens on the main thread and hitches the frame the first impact lands on
+#[derive(Asset, TypePath, Clone)]
+pub struct DecodedAudio {
+    channels: u16,
+    sample_rate: u32,
+    samples: Arc<[i16]>,
+}
+
+impl DecodedAudio {
+    fn decode(source: &AudioSource) -> DecodedAudio {
+        let decoder = source.decoder();
+        DecodedAudio {
+            channels: decoder.channels(),
+            sample_rate: decoder.sample_rate(),
+            samples: decoder.collect(),
+        }
+    }
+
+    fn placeholder() -> DecodedAudio {
+        let len = (PLACEHOLDER_SAMPLE_RATE as f32 * PLACEHOLDER_SECS) as usize;
+        let samples = (0..len)
+            .map(|i| {
+                let t = i as f32 / PLACEHOLDER_SAMPLE_RATE as f32;
+                let fade = 1.0 - i as f32 / len as f32;
+                let wave = (t * PLACEHOLDER_PITCH_HZ * TAU).sin();
+                (wave * fade * PLACEHOLDER_VOLUME * i16::MAX as f32) as i16
+            })
+            .collect();
+        DecodedAudio {
+            channels: 1,
+            sample_rate: PLACEHOLDER_SAMPLE_RATE,
+            samples,
+        }
+    }
+}
+
+impl Decodable for DecodedAudio {
+    type DecoderItem = i16;
+    type Decoder = DecodedSamples;
+
+    fn decoder(&self) -> DecodedSamples {
+        DecodedSamples {
+            audio: self.clone(),
+            position: 0,
+        }
+    }
+}
+
+pub struct DecodedSamples {
+    audio: DecodedAudio,
+    position: usize,
+}
+
+impl Iterator for DecodedSamples {
+    type Item = i16;
+
+    fn next(&mut self) -> Option<i16> {
+        let sample = self.audio.samples.get(self.position).copied();
+        self.position += 1;
+        sample
+    }
+}
+
+impl Source for DecodedSamples {
+    fn current_frame_len(&self) -> Option<usize> {
+        Some(self.audio.samples.len().saturating_sub(self.position))
+    }
+
+    fn channels(&self) -> u16 {
+        self.audio.channels
+    }
+
+    fn sample_rate(&self) -> u32 {
+        self.audio.sample_rate
+    }
+
+    fn total_duration(&self) -> Option<Duration> {
+        let frames = self.audio.samples.len() / self.audio.channels.max(1) as usize;
+        Some(Duration::from_secs_f64(
+            frames as f64 / self.audio.sample_rate.max(1) as f64,
+        ))
+    }
+}
+
+#[derive(Resource)]
+pub struct PreloadedAudio {
+    manifest: Handle<AudioManifest>,
+    // None until the manifest is in and its sounds are requested
+    pending: Option<Vec<Handle<AudioSource>>>,
+    // by path, the file handles are dropped once decoded and can come back with new ids.
+    // sounds that failed to load map to the placeholder
+    decoded: HashMap<AssetPath<'static>, Handle<DecodedAudio>>,
+    placeholder: Handle<DecodedAudio>,
+    done: bool,
+}
+
+impl PreloadedAudio {
+    // plays the decoded copy once there is one, and the file as it is until then or
+    // when it's streamed. a file that failed plays the placeholder instead
+    pub fn insert_player(&self, sound: &mut EntityCommands, source: &Handle<AudioSource>) {
+        match source.path().and_then(|path| self.decoded.get(path)) {
+            Some(decoded) => sound.insert(AudioPlayer(decoded.clone())),
+            None => sound.insert(AudioPlayer(source.clone())),
+        };
+    }
+}
+
+fn load_manifest(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    mut tracked: ResMut<TrackedAssets>,
+    mut decoded: ResMut<Assets<DecodedAudio>>,
+) {
+    let manifest = asset_server.load(MANIFEST_PATH);
+    tracked.track(manifest.clone());
+    commands.insert_resource(PreloadedAudio {
+        manifest,
+        pending: None,
+        decoded: HashMap::default(),
+        placeholder: decoded.add(DecodedAudio::placeholder()),
+        done: false,
+    });
+}
+
+// one missing sound shouldn't leave a hole in every impact, or take down a run that
+// expects the file to be there
+fn substitute_failed_sounds(
+    mut failed: EventReader<AssetFailed>,
+    mut preloaded: ResMut<PreloadedAudio>,
+) {
+    for event in failed.read() {
+        if event.id.type_id() != TypeId::of::<AudioSource>() {
+            continue;
+        }
+        let Ok(path) = AssetPath::try_parse(event.error.path()) else {
+            continue;
+        };
+        warn!("playing a placeholder for '{path}'");
+        let placeholder = preloaded.placeholder.clone();
+        preloaded.decoded.insert(path.into_owned(), placeholder);
+    }
+}
+
+// keeps going after a startup dialog takes the game out of Loading, sounds played
+// before it's done just fall back to the files
+fn preload_audio(
+    mut preloaded: ResMut<PreloadedAudio>,
+    asset_server: Res<AssetServer>,
+    manifests: Res<Assets<AudioManifest>>,
+    sources: Res<Assets<AudioSource>>,
+    mut decoded: ResMut<Assets<DecodedAudio>>,
+    mut tracked: ResMut<TrackedAssets>,
+    state: Res<State<GameState>>,
+    mut next_state: ResMut<NextState<GameState>>,
+    time: Res<Time<Real>>,
+) {
+    if preloaded.done {
+        return;
+    }
+    let PreloadedAudio {
+        manifest,
+        pending,
+        decoded: decoded_handles,
+        done,
+        ..
+    } = &mut *preloaded;
+    let pending = match pending {
+        Some(pending) => pending,
+        None => {
+            if let Some(manifest) = manifests.get(&*manifest) {
+                for path in manifest
+                    .preload
+                    .iter()
+                    .filter(|path| manifest.stream.contains(path))
+                {
+                    warn!("'{path}' is both preloaded and streamed, preloading it");
+                }
+                debug!(
+                    target: "audio",
+                    "preloading {} sounds, streaming {}",
+                    manifest.preload.len(),
+                    manifest.stream.len()
+                );
+                let handles: Vec<Handle<AudioSource>> = manifest
+                    .preload
+                    .iter()
+                    .map(|path| asset_server.load(path))
+                    .collect();
+                for handle in &handles {
+                    tracked.track(handle.clone());
+                }
+                pending.insert(handles)
+            } else if asset_server.load_state(&*manifest).is_failed() {
+                // without a manifest every sound streams
+                pending.insert(Vec::new())
+            } else {
+                return;
+            }
+        }
+    };
+    pending.retain(|handle| {
+        if let (Some(source), Some(path)) = (sources.get(handle), handle.path()) {
+            let audio = DecodedAudio::decode(source);
+            trace!(target: "audio", "decoded {path}, {} samples", audio.samples.len());
+            decoded_handles.insert(path.clone(), decoded.add(audio));
+            return false;
+        }
+        !matches!(asset_server.load_state(handle), LoadState::Failed(_))
+    });
+    if !pending.is_empty() {
+        return;
+    }
+    *done = true;
+    debug!(
+        target: "audio",
+        "preloaded {} sounds in {:.2}s",
+        decoded_handles.len(),
+        time.elapsed_secs()
+    );
+    if *state.get() == GameState::Loading {
+        next_state.set(GameState::Playing);
+    }
+}
+
+fn pause_time(mut time: ResMut<Time<Virtual>>) {
+    time.pause();
+}
+
+fn resume_time(mut time: ResMut<Time<Virtual>>) {
+    time.unpause();
+}
@@ -0,0 +1,169 @@
+use avian2d::prelude::*;
+use bevy::prelude::*;
+
+use crate::climb::climb_walls;
+use crate::input::{Action, ActionState};
+use crate::spawner::{FallingRect, WeightClass};
+use crate::{move_player, GameSet, Player, PlayerContacts};
+
+// per point of strength, a normal rect speeds up by about 200 px/s every second
+const PUSH_FORCE: f32 = 2_000_000.0;
+// rects never get shoved faster than the player walks
+const MAX_PUSH_SPEED: f32 = 250.0;
+const SIDE_CONTACT_COS: f32 = 0.7;
+
+pub struct PushPullPlugin;
+
+impl Plugin for PushPullPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Pull>().add_systems(
+            FixedPreUpdate,
+            (
+                grab_loose_rects.before(climb_walls),
+                push_loose_rects.after(move_player),
+            )
+                .in_set(GameSet::Movement),
+        );
+    }
+}
+
+#[derive(Component)]
+pub struct PlayerStrength(pub f32);
+
+impl Default for PlayerStrength {
+    fn default() -> PlayerStrength {
+        PlayerStrength(1.0)
+    }
+}
+
+// the rect being pulled and the joint tying it to the player
+#[derive(Resource, Default)]
+struct Pull(Option<(Entity, Entity)>);
+
+// a rect the player is touching from the side, optionally only on one side
+fn side_contact(
+    collisions: &Collisions,
+    player: Entity,
+    contacts: &PlayerContacts,
+    direction: Option<f32>,
+    is_loose: impl Fn(Entity) -> bool,
+) -> Option<Entity> {
+    contacts.touching().find_map(|other| {
+        let coll = collisions.get(player, other)?;
+        let contact = coll.find_deepest_contact()?;
+        if contact.penetration < 0.0 {
+            return None;
+        }
+        let normal = if coll.entity1 == player {
+            contact.normal1
+        } else {
+            contact.normal2
+        };
+        let side = normal.x.abs() >= SIDE_CONTACT_COS
+            && direction.is_none_or(|direction| normal.x * direction > 0.0);
+        (side && is_loose(other)).then_some(other)
+    })
+}
+
+fn is_loose(
+    rects: &Query<(&WeightClass, &LinearVelocity), With<FallingRect>>,
+    entity: Entity,
+) -> bool {
+    rects
+        .get(entity)
+        .is_ok_and(|(class, _)| *class != WeightClass::Steel)
+}
+
+// holding grab against a light rect while standing ties it to the player, so walking
+// away drags it along. it takes over from climbing, which uses the same button
+fn grab_loose_rects(
+    mut commands: Commands,
+    actions: Res<ActionState>,
+    collisions: Res<Collisions>,
+    player: Single<(Entity, &mut Player, &PlayerContacts, &Transform)>,
+    rects: Query<(&WeightClass, &LinearVelocity), With<FallingRect>>,
+    transforms: Query<&Transform, Without<Player>>,
+    mut pull: ResMut<Pull>,
+) {
+    let (player_entity, mut player, contacts, player_transform) = player.into_inner();
+    let holding = actions.pressed(Action::Grab) && player.ground_normal.is_some();
+    if let Some((rect, joint)) = pull.0 {
+        if holding && rects.contains(rect) {
+            player.pulling = true;
+            return;
+        }
+        debug!(target: "player", "let go of a rect");
+        commands.entity(joint).despawn();
+        pull.0 = None;
+    }
+    player.pulling = false;
+    if !holding {
+        return;
+    }
+    let Some(rect) = side_contact(&collisions, player_entity, contacts, None, |entity| {
+        is_loose(&rects, entity)
+    }) else {
+        return;
+    };
+    let Ok(rect_transform) = transforms.get(rect) else {
+        return;
+    };
+    let distance = player_transform
+        .translation
+        .truncate()
+        .distance(rect_transform.translation.truncate());
+    let joint = commands
+        .spawn((
+            Name::new("PullJoint"),
+            DistanceJoint::new(player_entity, rect).with_rest_length(distance),
+        ))
+        .id();
+    debug!(target: "player", "grabbed a rect");
+    pull.0 = Some((rect, joint));
+    player.pulling = true;
+}
+
+// walking into a light rect, or away from one being pulled, moves it along with a push
+// that scales with the player's strength
+fn push_loose_rects(
+    mut commands: Commands,
+    actions: Res<ActionState>,
+    collisions: Res<Collisions>,
+    pull: Res<Pull>,
+    player: Single<(Entity, &Player, &PlayerContacts, &PlayerStrength)>,
+    rects: Query<(&WeightClass, &LinearVelocity), With<FallingRect>>,
+    time: Res<Time>,
+) {
+    let (player_entity, player, contacts, strength) = *player;
+    if player.ground_normal.is_none() {
+        return;
+    }
+    let direction = match (
+        actions.pressed(Action::Left),
+        actions.pressed(Action::Right),
+    ) {
+        (true, false) => -1.0,
+        (false, true) => 1.0,
+        _ => return,
+    };
+    let target = pull.0.map(|(rect, _)| rect).or_else(|| {
+        side_contact(
+            &collisions,
+            player_entity,
+            contacts,
+            Some(direction),
+            |entity| is_loose(&rects, entity),
+        )
+    });
+    let Some(rect) = target else {
+        return;
+    };
+    let Ok((_, linear)) = rects.get(rect) else {
+        return;
+    };
+    if linear.x * direction >= MAX_PUSH_SPEED {
+        return;
+    }
+    let impulse = Vec2::X * direction * strength.0 * PUSH_FORCE * time.delta_secs();
+    commands.entity(rect).insert(ExternalImpulse::new(impulse));
+}
@@ -0,0 +1,169 @@
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::menu::GameState;
+use crate::settings::{ParticleLimit, Settings};
+
+// frame times are smoothed over roughly this long, so a single hitch doesn't count
+const SMOOTHING_SECS: f32 = 1.0;
+// a little under 45 fps
+const OVER_BUDGET_SECS: f32 = 1.0 / 45.0;
+// back up only once it's comfortably under the budget again, so it doesn't flip between
+// two levels that both sit right at the edge
+const UNDER_BUDGET_SECS: f32 = 1.0 / 57.0;
+const STEP_DOWN_AFTER_SECS: f32 = 3.0;
+const STEP_UP_AFTER_SECS: f32 = 15.0;
+// longer frames are a tab switch or a loading stall, not slow rendering
+const MAX_COUNTED_FRAME_SECS: f32 = 0.25;
+
+pub struct QualityPlugin;
+
+impl Plugin for QualityPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Quality>()
+            .init_resource::<FrameBudget>()
+            .add_systems(
+                Update,
+                (
+                    govern_quality.run_if(in_state(GameState::Playing)),
+                    apply_quality_setting,
+                )
+                    .chain(),
+            );
+    }
+}
+
+// what the game is currently drawn at, picked by the governor unless the settings fix it.
+// every level drops the things of the one above it
+#[derive(
+    Resource, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Debug, Default, Serialize, Deserialize,
+)]
+pub enum Quality {
+    #[default]
+    High,
+    // fewer particles
+    Reduced,
+    // no drop shadows
+    Low,
+    // no particles or animated background
+    Minimal,
+}
+
+impl Quality {
+    const ORDER: [Quality; 4] = [
+        Quality::High,
+        Quality::Reduced,
+        Quality::Low,
+        Quality::Minimal,
+    ];
+
+    pub fn key(self) -> &'static str {
+        match self {
+            Quality::High => "settings.quality_high",
+            Quality::Reduced => "settings.quality_reduced",
+            Quality::Low => "settings.quality_low",
+            Quality::Minimal => "settings.quality_minimal",
+        }
+    }
+
+    fn step(self, delta: i32) -> Quality {
+        let index = Quality::ORDER.iter().position(|q| *q == self).unwrap_or(0) as i32;
+        Quality::ORDER[(index + delta).clamp(0, Quality::ORDER.len() as i32 - 1) as usize]
+    }
+
+    // never more than the player's own particle setting asks for
+    pub fn particles(self, limit: ParticleLimit) -> ParticleLimit {
+        match self {
+            Quality::High => limit,
+            Quality::Reduced | Quality::Low if limit == ParticleLimit::Full => {
+                ParticleLimit::Reduced
+            }
+            Quality::Reduced | Quality::Low => limit,
+            Quality::Minimal => ParticleLimit::Off,
+        }
+    }
+
+    pub fn shadows(self) -> bool {
+        self < Quality::Low
+    }
+
+    pub fn animated_background(self) -> bool {
+        self < Quality::Minimal
+    }
+}
+
+// None in the settings means the governor picks
+pub fn cycle_quality(quality: Option<Quality>, delta: i32) -> Option<Quality> {
+    const ORDER: [Option<Quality>; 5] = [
+        None,
+        Some(Quality::High),
+        Some(Quality::Reduced),
+        Some(Quality::Low),
+        Some(Quality::Minimal),
+    ];
+    let index = ORDER
+        .iter()
+        .position(|entry| *entry == quality)
+        .unwrap_or(0);
+    ORDER[(index as i32 + delta).rem_euclid(ORDER.len() as i32) as usize]
+}
+
+#[derive(Resource, Default)]
+struct FrameBudget {
+    average_secs: f32,
+    // how long the average has been over or under the budget
+    over_secs: f32,
+    under_secs: f32,
+    // the governor's own pick, kept while the settings override it
+    picked: Quality,
+}
+
+// only watches gameplay, the menus and cutscenes aren't what needs to be smooth
+fn govern_quality(settings: Res<Settings>, time: Res<Time<Real>>, mut budget: ResMut<FrameBudget>) {
+    let frame_secs = time.delta_secs();
+    if settings.quality.is_some() || frame_secs <= 0.0 || frame_secs > MAX_COUNTED_FRAME_SECS {
+        return;
+    }
+    if budget.average_secs == 0.0 {
+        budget.average_secs = frame_secs;
+    }
+    budget.average_secs += (frame_secs - budget.average_secs) * (frame_secs / SMOOTHING_SECS);
+    if budget.average_secs > OVER_BUDGET_SECS {
+        budget.over_secs += frame_secs;
+        budget.under_secs = 0.0;
+    } else if budget.average_secs < UNDER_BUDGET_SECS {
+        budget.under_secs += frame_secs;
+        budget.over_secs = 0.0;
+    } else {
+        budget.over_secs = 0.0;
+        budget.under_secs = 0.0;
+    }
+    let delta = if budget.over_secs >= STEP_DOWN_AFTER_SECS {
+        1
+    } else if budget.under_secs >= STEP_UP_AFTER_SECS {
+        -1
+    } else {
+        return;
+    };
+    budget.over_secs = 0.0;
+    budget.under_secs = 0.0;
+    let picked = budget.picked.step(delta);
+    if picked != budget.picked {
+        info!(
+            "frames averaging {:.1}ms, quality now {picked:?}",
+            budget.average_secs * 1000.0
+        );
+        budget.picked = picked;
+    }
+}
+
+fn apply_quality_setting(
+    settings: Res<Settings>,
+    budget: Res<FrameBudget>,
+    mut quality: ResMut<Quality>,
+) {
+    let wanted = settings.quality.unwrap_or(budget.picked);
+    if *quality != wanted {
+        *quality = wanted;
+    }
+}
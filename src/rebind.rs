@@ -0,0 +1,207 @@
+use bevy::prelude::*;
+
+use crate::i18n::Localization;
+use crate::input::{button_label, key_label, Action, BindingOverrides, Bindings};
+use crate::menu::{
+    GameState, MenuAction, MenuActivated, MenuInputGrabbed, MenuPage, MenuRow, MenuRows, MenuSet,
+};
+
+pub struct RebindPlugin;
+
+impl Plugin for RebindPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Rebinding>()
+            .add_systems(OnExit(GameState::Paused), stop_capture)
+            .add_systems(
+                Update,
+                (
+                    rebind_menu_rows.in_set(MenuSet::Rows),
+                    // the press that starts a capture mustn't be captured too
+                    (capture_binding, apply_rebind_actions)
+                        .chain()
+                        .in_set(MenuSet::Actions),
+                ),
+            );
+    }
+}
+
+#[derive(Resource, Default)]
+struct Rebinding {
+    // waiting for a key or button on the current page
+    capturing: Option<Action>,
+    // what the last rebind took away from another action
+    notice: Option<String>,
+}
+
+fn keyboard_page(page: MenuPage) -> bool {
+    page == MenuPage::KeyboardBindings
+}
+
+fn bound_labels(page: MenuPage, bindings: &Bindings, action: Action) -> Vec<String> {
+    if keyboard_page(page) {
+        bindings
+            .keys(action)
+            .iter()
+            .map(|key| key_label(*key, bindings.layout))
+            .collect()
+    } else {
+        bindings
+            .buttons(action)
+            .iter()
+            .map(|button| button_label(*button))
+            .collect()
+    }
+}
+
+fn rebind_menu_rows(
+    page: Res<MenuPage>,
+    rebinding: Res<Rebinding>,
+    bindings: Res<Bindings>,
+    localization: Res<Localization>,
+    mut rows: ResMut<MenuRows>,
+) {
+    if !matches!(
+        *page,
+        MenuPage::KeyboardBindings | MenuPage::GamepadBindings
+    ) {
+        return;
+    }
+    for action in Action::ALL {
+        let name = localization.get(action.key());
+        let label = if rebinding.capturing == Some(action) {
+            let key = if keyboard_page(*page) {
+                "rebind.press_key"
+            } else {
+                "rebind.press_button"
+            };
+            localization.format(key, &[("action", &name)])
+        } else {
+            let bound = bound_labels(*page, &bindings, action);
+            let bound = if bound.is_empty() {
+                localization.get("rebind.unbound").to_string()
+            } else {
+                bound.join(" / ")
+            };
+            localization.format("rebind.binding", &[("action", &name), ("value", &bound)])
+        };
+        rows.push(MenuRow::new(label, MenuAction::Rebind(action)));
+    }
+    // under the actions, so it doesn't move the selection when it shows up
+    if let Some(notice) = &rebinding.notice {
+        rows.push(MenuRow::new(notice.clone(), MenuAction::None));
+    }
+    rows.push(MenuRow::new(
+        localization.get("rebind.reset"),
+        MenuAction::ResetBindings,
+    ));
+    rows.push(MenuRow::new(
+        localization.get("menu.back"),
+        MenuAction::Back,
+    ));
+}
+
+fn stop_capture(mut rebinding: ResMut<Rebinding>, mut grabbed: ResMut<MenuInputGrabbed>) {
+    rebinding.capturing = None;
+    rebinding.notice = None;
+    grabbed.0 = false;
+}
+
+// the new key or button replaces the action's old ones. if another action had it, it's
+// taken from that one, so no press ever means two things
+fn capture_binding(
+    page: Res<MenuPage>,
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    gamepads: Query<&Gamepad>,
+    bindings: Res<Bindings>,
+    localization: Res<Localization>,
+    mut rebinding: ResMut<Rebinding>,
+    mut overrides: ResMut<BindingOverrides>,
+    mut grabbed: ResMut<MenuInputGrabbed>,
+) {
+    let Some(action) = rebinding.capturing else {
+        return;
+    };
+    // escape and start are what open the menu, they can't be bound
+    let cancelled = keyboard_input.just_pressed(KeyCode::Escape)
+        || gamepads
+            .iter()
+            .any(|gamepad| gamepad.just_pressed(GamepadButton::Start));
+    let taken_from = if cancelled {
+        None
+    } else if keyboard_page(*page) {
+        let Some(key) = keyboard_input.get_just_pressed().next().copied() else {
+            return;
+        };
+        let other = Action::ALL
+            .into_iter()
+            .find(|other| *other != action && bindings.keys(*other).contains(&key));
+        if let Some(other) = other {
+            let keys = bindings.keys(other).iter().copied().filter(|k| *k != key);
+            overrides.set_keys(other, keys.collect());
+        }
+        overrides.set_keys(action, vec![key]);
+        debug!("bound {key:?} to {action:?}");
+        other.map(|other| (other, key_label(key, bindings.layout)))
+    } else {
+        let Some(button) = gamepads
+            .iter()
+            .find_map(|gamepad| gamepad.get_just_pressed().next().copied())
+        else {
+            return;
+        };
+        let other = Action::ALL
+            .into_iter()
+            .find(|other| *other != action && bindings.buttons(*other).contains(&button));
+        if let Some(other) = other {
+            let buttons = bindings
+                .buttons(other)
+                .iter()
+                .copied()
+                .filter(|b| *b != button);
+            overrides.set_buttons(other, buttons.collect());
+        }
+        overrides.set_buttons(action, vec![button]);
+        debug!("bound {button:?} to {action:?}");
+        other.map(|other| (other, button_label(button)))
+    };
+    rebinding.notice = taken_from.map(|(other, input)| {
+        localization.format(
+            "rebind.moved",
+            &[
+                ("input", &input),
+                ("from", &localization.get(other.key())),
+                ("to", &localization.get(action.key())),
+            ],
+        )
+    });
+    rebinding.capturing = None;
+    grabbed.0 = false;
+}
+
+fn apply_rebind_actions(
+    mut activated: EventReader<MenuActivated>,
+    page: Res<MenuPage>,
+    mut rebinding: ResMut<Rebinding>,
+    mut overrides: ResMut<BindingOverrides>,
+    mut grabbed: ResMut<MenuInputGrabbed>,
+) {
+    for event in activated.read() {
+        match event.action {
+            MenuAction::Rebind(action) if event.delta == 0 => {
+                rebinding.capturing = Some(action);
+                rebinding.notice = None;
+                grabbed.0 = true;
+            }
+            MenuAction::ResetBindings if event.delta == 0 => {
+                if keyboard_page(*page) {
+                    overrides.reset_keys();
+                } else {
+                    overrides.reset_buttons();
+                }
+                rebinding.notice = None;
+            }
+            MenuAction::Back => rebinding.notice = None,
+            _ => {}
+        }
+    }
+}
@@ -0,0 +1,160 @@
+use std::time::Duration;
+
+use avian2d::prelude::*;
+use bevy::audio::{AddAudioSource, AudioSinkPlayback, Decodable, Source, Volume};
+use bevy::prelude::*;
+
+use crate::menu::GameState;
+use crate::spawner::{FallingRect, WeightClass};
+use crate::GameSet;
+
+// how often the pile's energy is summed, the rumble is smoothed anyway
+const SAMPLE_SECS: f32 = 0.1;
+// half m v^2 summed over every awake rect. silent at the bottom, as loud as it gets at
+// the top, and log scaled in between like the impact layers
+const QUIET_PILE_ENERGY: f32 = 1e8;
+const LOUD_PILE_ENERGY: f32 = 1e11;
+const MAX_VOLUME: f32 = 0.8;
+// a collapse swells in quickly and dies down slowly
+const ATTACK_PER_SEC: f32 = 4.0;
+const RELEASE_PER_SEC: f32 = 0.6;
+// played slower it's darker, a big collapse opens it up
+const QUIET_SPEED: f32 = 0.6;
+const LOUD_SPEED: f32 = 1.2;
+const NOISE_SAMPLE_RATE: u32 = 22050;
+
+pub struct RumblePlugin;
+
+impl Plugin for RumblePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_audio_source::<RumbleNoise>()
+            .init_resource::<PileRumble>()
+            .add_systems(Startup, spawn_rumble)
+            .add_systems(Update, track_pile_energy.in_set(GameSet::Vfx));
+    }
+}
+
+// endless brown noise, made on the fly instead of shipping a long loop
+#[derive(Asset, TypePath)]
+struct RumbleNoise;
+
+impl Decodable for RumbleNoise {
+    type DecoderItem = f32;
+    type Decoder = BrownNoise;
+
+    fn decoder(&self) -> BrownNoise {
+        BrownNoise {
+            seed: 0x2545_f491,
+            last: 0.0,
+        }
+    }
+}
+
+struct BrownNoise {
+    seed: u32,
+    last: f32,
+}
+
+impl Iterator for BrownNoise {
+    type Item = f32;
+
+    // integrated white noise, leaking back towards zero so it doesn't wander off
+    fn next(&mut self) -> Option<f32> {
+        self.seed ^= self.seed << 13;
+        self.seed ^= self.seed >> 17;
+        self.seed ^= self.seed << 5;
+        let white = self.seed as f32 / u32::MAX as f32 * 2.0 - 1.0;
+        self.last = (self.last + 0.02 * white) / 1.02;
+        Some(self.last * 3.5)
+    }
+}
+
+impl Source for BrownNoise {
+    fn current_frame_len(&self) -> Option<usize> {
+        None
+    }
+
+    fn channels(&self) -> u16 {
+        1
+    }
+
+    fn sample_rate(&self) -> u32 {
+        NOISE_SAMPLE_RATE
+    }
+
+    fn total_duration(&self) -> Option<Duration> {
+        None
+    }
+}
+
+#[derive(Component)]
+struct Rumble;
+
+#[derive(Resource, Default)]
+struct PileRumble {
+    until_sample: f32,
+    // 0 to 1, what the rumble is heading towards and where it is now
+    target: f32,
+    level: f32,
+}
+
+// never given a Voice, the limiter shouldn't cut the one sound that's always on
+fn spawn_rumble(mut commands: Commands, mut noise: ResMut<Assets<RumbleNoise>>) {
+    commands.spawn((
+        Name::new("Rumble"),
+        Rumble,
+        AudioPlayer(noise.add(RumbleNoise)),
+        PlaybackSettings::ONCE.with_volume(Volume::new(0.0)),
+    ));
+}
+
+fn pile_loudness(energy: f32) -> f32 {
+    if energy <= QUIET_PILE_ENERGY {
+        return 0.0;
+    }
+    ((energy.log10() - QUIET_PILE_ENERGY.log10())
+        / (LOUD_PILE_ENERGY.log10() - QUIET_PILE_ENERGY.log10()))
+    .min(1.0)
+}
+
+// runs in every state so the rumble fades out under the menu instead of cutting off
+fn track_pile_energy(
+    mut rumble: ResMut<PileRumble>,
+    rects: Query<
+        (&ColliderAabb, &LinearVelocity, &WeightClass),
+        (With<FallingRect>, Without<Sleeping>),
+    >,
+    sink: Option<Single<&AudioSink, With<Rumble>>>,
+    state: Res<State<GameState>>,
+    time: Res<Time<Real>>,
+) {
+    let delta_secs = time.delta_secs();
+    if *state.get() != GameState::Playing {
+        rumble.target = 0.0;
+    } else {
+        rumble.until_sample -= delta_secs;
+        if rumble.until_sample <= 0.0 {
+            rumble.until_sample = SAMPLE_SECS;
+            let energy: f32 = rects
+                .iter()
+                .map(|(aabb, velocity, class)| {
+                    let mass = aabb.size().x * aabb.size().y * class.density();
+                    0.5 * mass * velocity.length_squared()
+                })
+                .sum();
+            rumble.target = pile_loudness(energy);
+        }
+    }
+    let rate = if rumble.target > rumble.level {
+        ATTACK_PER_SEC
+    } else {
+        RELEASE_PER_SEC
+    };
+    rumble.level += (rumble.target - rumble.level) * (rate * delta_secs).min(1.0);
+    // bevy only adds the sink once the noise starts playing
+    let Some(sink) = sink else {
+        return;
+    };
+    sink.set_volume(rumble.level * MAX_VOLUME);
+    sink.set_speed(QUIET_SPEED + (LOUD_SPEED - QUIET_SPEED) * rumble.level);
+}
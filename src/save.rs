@@ -0,0 +1,67 @@
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::stats::LifetimeStats;
+use crate::storage::{Persistent, PersistentStorage, StorageAppExt};
+
+// saves used to be written next to the game before they moved to the config dir
+const LEGACY_SAVE_PATH: &str = "save.ron";
+
+pub struct SavePlugin;
+
+impl Plugin for SavePlugin {
+    fn build(&self, app: &mut App) {
+        migrate_legacy_save(app.world().resource::<PersistentStorage>());
+        app.add_persistent_resource::<SaveData>();
+    }
+}
+
+#[derive(Resource, Serialize, Deserialize, Clone)]
+#[serde(default)]
+pub struct SaveData {
+    pub best_score: u32,
+    pub selected_skin: String,
+    pub achievements: Vec<String>,
+    pub stats: LifetimeStats,
+    // anonymous id the leaderboard knows this install by
+    pub player_id: String,
+    // played through or skipped, so they don't play again
+    pub seen_cutscenes: Vec<String>,
+    // meta progression currency, earned at the end of every run
+    pub shards: u32,
+    // ids bought in the unlocks menu
+    pub unlocks: Vec<String>,
+}
+
+impl Default for SaveData {
+    fn default() -> SaveData {
+        SaveData {
+            best_score: 0,
+            selected_skin: "classic".to_string(),
+            achievements: Vec::new(),
+            stats: LifetimeStats::default(),
+            player_id: String::new(),
+            seen_cutscenes: Vec::new(),
+            shards: 0,
+            unlocks: Vec::new(),
+        }
+    }
+}
+
+impl Persistent for SaveData {
+    const KEY: &'static str = "save";
+    const VERSION: u32 = 1;
+}
+
+fn migrate_legacy_save(storage: &PersistentStorage) {
+    if cfg!(target_arch = "wasm32") || storage.0.read(SaveData::KEY).is_some() {
+        return;
+    }
+    let Ok(contents) = std::fs::read_to_string(LEGACY_SAVE_PATH) else {
+        return;
+    };
+    match storage.0.write(SaveData::KEY, &contents) {
+        Ok(()) => info!("moved {LEGACY_SAVE_PATH} into the config directory"),
+        Err(err) => warn!("failed to migrate {LEGACY_SAVE_PATH}: {err}"),
+    }
+}
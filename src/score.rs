@@ -0,0 +1,95 @@
+use bevy::prelude::*;
+
+use crate::assist::assists_active;
+use crate::line_clear::LineCleared;
+use crate::popups::{PopupStyle, ShowPopup};
+use crate::save::SaveData;
+use crate::settings::Settings;
+use crate::{GameSet, Player, PlayerDied};
+
+const HEIGHT_PER_POINT: f32 = 10.0;
+const POINTS_PER_SECOND: f32 = 5.0;
+const POINTS_PER_CLEARED_RECT: u32 = 20;
+
+pub struct ScorePlugin;
+
+impl Plugin for ScorePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<RunStats>()
+            .add_event::<ScoreChanged>()
+            .add_systems(
+                Update,
+                (reset_on_death, track_run, record_best_score)
+                    .chain()
+                    .in_set(GameSet::Reactions),
+            );
+    }
+}
+
+#[derive(Event)]
+pub struct ScoreChanged {
+    pub score: u32,
+}
+
+#[derive(Resource, Default)]
+pub struct RunStats {
+    pub time_survived: f32,
+    pub start_height: Option<f32>,
+    pub max_height: f32,
+    pub score: u32,
+    // points that don't come from height or time, like line clears
+    pub bonus: u32,
+    // sticks for the rest of the run once any assist was turned on
+    pub assisted: bool,
+}
+
+fn reset_on_death(
+    mut died: EventReader<PlayerDied>,
+    mut stats: ResMut<RunStats>,
+    mut changed: EventWriter<ScoreChanged>,
+) {
+    if died.read().count() > 0 {
+        *stats = RunStats::default();
+        changed.send(ScoreChanged { score: 0 });
+    }
+}
+
+fn track_run(
+    mut stats: ResMut<RunStats>,
+    player: Single<&Transform, With<Player>>,
+    settings: Res<Settings>,
+    time: Res<Time>,
+    mut cleared: EventReader<LineCleared>,
+    mut changed: EventWriter<ScoreChanged>,
+    mut popups: EventWriter<ShowPopup>,
+) {
+    for LineCleared { rects } in cleared.read() {
+        let points = *rects as u32 * POINTS_PER_CLEARED_RECT;
+        stats.bonus += points;
+        popups.send(ShowPopup {
+            text: format!("+{points}"),
+            position: player.translation.truncate(),
+            style: PopupStyle::Score,
+        });
+    }
+    stats.assisted |= assists_active(&settings);
+    let y = player.translation.y;
+    let start_height = *stats.start_height.get_or_insert(y);
+    stats.time_survived += time.delta_secs();
+    stats.max_height = stats.max_height.max(y - start_height);
+    let score = (stats.max_height / HEIGHT_PER_POINT + stats.time_survived * POINTS_PER_SECOND)
+        as u32
+        + stats.bonus;
+    if score != stats.score {
+        stats.score = score;
+        changed.send(ScoreChanged { score });
+    }
+}
+
+fn record_best_score(mut changed: EventReader<ScoreChanged>, mut save: ResMut<SaveData>) {
+    for ScoreChanged { score } in changed.read() {
+        if *score > save.best_score {
+            save.best_score = *score;
+        }
+    }
+}
@@ -0,0 +1,126 @@
+use bevy::prelude::*;
+use tts::Tts;
+
+use crate::i18n::Localization;
+use crate::menu::{GameState, MenuRows, MenuSelection, MenuSet};
+use crate::settings::Settings;
+
+// works anywhere, so players who can't see the settings menu can still turn it on
+const TOGGLE_KEY: KeyCode = KeyCode::F1;
+
+pub struct ScreenReaderPlugin;
+
+impl Plugin for ScreenReaderPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_non_send_resource(Speaker::default())
+            .add_systems(
+                Update,
+                (
+                    toggle_screen_reader,
+                    announce_menu_focus
+                        .after(MenuSet::Input)
+                        .before(MenuSet::Actions),
+                ),
+            )
+            .add_systems(OnExit(GameState::Paused), announce_resume);
+    }
+}
+
+// the speech backend only starts once something is said, so players who never turn
+// this on don't pay for it
+#[derive(Default)]
+struct Speaker {
+    tts: Option<Tts>,
+    failed: bool,
+    // the last row read out, to only speak when focus or its value changes
+    spoken: Option<String>,
+}
+
+impl Speaker {
+    fn say(&mut self, text: &str) {
+        if self.tts.is_none() && !self.failed {
+            match Tts::default() {
+                Ok(tts) => self.tts = Some(tts),
+                Err(err) => {
+                    warn!("no text to speech available: {err}");
+                    self.failed = true;
+                }
+            }
+        }
+        let Some(tts) = &mut self.tts else {
+            return;
+        };
+        // a new announcement cuts off the old one, menus are navigated faster than read
+        if let Err(err) = tts.speak(text, true) {
+            debug!("failed to speak: {err}");
+        }
+    }
+}
+
+// the arrows around cycling values are only there for sighted players
+fn speakable(label: &str) -> String {
+    label
+        .replace(['<', '>'], " ")
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+fn toggle_screen_reader(
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    mut settings: ResMut<Settings>,
+    localization: Res<Localization>,
+    mut speaker: NonSendMut<Speaker>,
+) {
+    if !keyboard_input.just_pressed(TOGGLE_KEY) {
+        return;
+    }
+    settings.screen_reader = !settings.screen_reader;
+    speaker.spoken = None;
+    speaker.say(localization.get(if settings.screen_reader {
+        "screen_reader.on"
+    } else {
+        "screen_reader.off"
+    }));
+}
+
+fn announce_menu_focus(
+    settings: Res<Settings>,
+    rows: Res<MenuRows>,
+    selection: Res<MenuSelection>,
+    localization: Res<Localization>,
+    mut speaker: NonSendMut<Speaker>,
+) {
+    if !settings.screen_reader {
+        return;
+    }
+    let Some(row) = rows.get(selection.0) else {
+        return;
+    };
+    let label = speakable(&row.label);
+    if speaker.spoken.as_ref() == Some(&label) {
+        return;
+    }
+    let announcement = localization.format(
+        "screen_reader.row",
+        &[
+            ("label", &label),
+            ("position", &(selection.0 + 1)),
+            ("count", &rows.len()),
+        ],
+    );
+    speaker.say(&announcement);
+    speaker.spoken = Some(label);
+}
+
+fn announce_resume(
+    settings: Res<Settings>,
+    localization: Res<Localization>,
+    mut speaker: NonSendMut<Speaker>,
+) {
+    // the menu reads its focused row again the next time it opens
+    speaker.spoken = None;
+    if settings.screen_reader {
+        speaker.say(localization.get("screen_reader.resumed"));
+    }
+}
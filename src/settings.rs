@@ -0,0 +1,580 @@
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::damage_feedback::HitFeedback;
+use crate::i18n::{cycle_language, language_name, Localization, FALLBACK_LANGUAGE};
+use crate::input::{
+    cycle_layout, cycle_stick_deadzone, cycle_stick_sensitivity, key_label, Action, Bindings,
+    DetectedLayout, KeyboardLayout, StickCurve,
+};
+use crate::menu::{MenuAction, MenuActivated, MenuPage, MenuRow, MenuRows, MenuSet};
+use crate::palette::Theme;
+use crate::quality::{cycle_quality, Quality};
+use crate::storage::{Persistent, StorageAppExt};
+use crate::telemetry::TelemetryConsent;
+use crate::touch::TouchControls;
+use crate::ui_layout::{cycle_safe_area, cycle_ui_scale};
+
+pub struct SettingsPlugin;
+
+impl Plugin for SettingsPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_persistent_resource::<Settings>().add_systems(
+            Update,
+            (
+                settings_menu_rows.in_set(MenuSet::Rows),
+                apply_settings_actions.in_set(MenuSet::Actions),
+            ),
+        );
+    }
+}
+
+#[derive(Resource, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Settings {
+    pub animated_background: bool,
+    pub theme: Theme,
+    pub language: String,
+    pub keyboard_layout: Option<KeyboardLayout>,
+    pub touch_controls: TouchControls,
+    // the left stick, shaped by input::shape_stick
+    pub stick_deadzone_x_percent: u32,
+    pub stick_deadzone_y_percent: u32,
+    pub stick_curve: StickCurve,
+    pub stick_sensitivity_percent: u32,
+    pub rumble: bool,
+    pub one_switch: bool,
+    pub toggle_jump: bool,
+    pub generous_timing: bool,
+    pub landing_momentum: bool,
+    pub landing_lag: bool,
+    pub line_clear: bool,
+    pub reduce_motion: bool,
+    pub reduce_flashing: bool,
+    pub screen_reader: bool,
+    pub ui_scale_percent: u32,
+    pub safe_area_percent: u32,
+    pub hit_feedback: HitFeedback,
+    pub particles: ParticleLimit,
+    // None lets the frame time governor pick
+    pub quality: Option<Quality>,
+    pub afterimages: bool,
+    // how the run compares to the best one so far, on the hud
+    pub pace_ghost: bool,
+    pub player_smoothing: PlayerSmoothing,
+    pub pause_on_focus_loss: bool,
+    pub discord_presence: bool,
+    pub telemetry: TelemetryConsent,
+    // only set by editing the settings file, for sending telemetry to your own server
+    pub telemetry_endpoint: Option<String>,
+    pub game_speed_percent: u32,
+    pub extra_health: u32,
+    pub crush_invulnerable: bool,
+    pub practice_mode: bool,
+}
+
+impl Default for Settings {
+    fn default() -> Settings {
+        Settings {
+            animated_background: true,
+            theme: Theme::default(),
+            language: FALLBACK_LANGUAGE.to_string(),
+            keyboard_layout: None,
+            touch_controls: TouchControls::default(),
+            stick_deadzone_x_percent: 30,
+            stick_deadzone_y_percent: 30,
+            stick_curve: StickCurve::default(),
+            stick_sensitivity_percent: 100,
+            rumble: true,
+            one_switch: false,
+            toggle_jump: false,
+            generous_timing: false,
+            landing_momentum: true,
+            landing_lag: false,
+            line_clear: false,
+            reduce_motion: false,
+            reduce_flashing: false,
+            screen_reader: false,
+            ui_scale_percent: 100,
+            safe_area_percent: 0,
+            hit_feedback: HitFeedback::default(),
+            particles: ParticleLimit::default(),
+            quality: None,
+            afterimages: true,
+            pace_ghost: true,
+            player_smoothing: PlayerSmoothing::default(),
+            pause_on_focus_loss: true,
+            discord_presence: true,
+            telemetry: TelemetryConsent::default(),
+            telemetry_endpoint: None,
+            game_speed_percent: 100,
+            extra_health: 0,
+            crush_invulnerable: false,
+            practice_mode: false,
+        }
+    }
+}
+
+impl Persistent for Settings {
+    const KEY: &'static str = "settings";
+    const VERSION: u32 = 1;
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default, Serialize, Deserialize)]
+pub enum ParticleLimit {
+    #[default]
+    Full,
+    Reduced,
+    Off,
+}
+
+impl ParticleLimit {
+    pub fn key(self) -> &'static str {
+        match self {
+            ParticleLimit::Full => "settings.particles_full",
+            ParticleLimit::Reduced => "settings.particles_reduced",
+            ParticleLimit::Off => "common.off",
+        }
+    }
+
+    pub fn max_particles(self) -> usize {
+        match self {
+            ParticleLimit::Full => 64,
+            ParticleLimit::Reduced => 4,
+            ParticleLimit::Off => 0,
+        }
+    }
+
+    // weather and ambience, scaled rather than capped so it still covers the screen
+    pub fn ambient_density(self) -> f32 {
+        match self {
+            ParticleLimit::Full => 1.0,
+            ParticleLimit::Reduced => 0.3,
+            ParticleLimit::Off => 0.0,
+        }
+    }
+
+    pub fn cycle(self, delta: i32) -> ParticleLimit {
+        const ORDER: [ParticleLimit; 3] = [
+            ParticleLimit::Full,
+            ParticleLimit::Reduced,
+            ParticleLimit::Off,
+        ];
+        let index = ORDER.iter().position(|limit| *limit == self).unwrap_or(0);
+        ORDER[(index as i32 + delta).rem_euclid(ORDER.len() as i32) as usize]
+    }
+}
+
+// how the player is drawn between physics steps. rects are always interpolated, only
+// the player (and so everything that follows it) gets the choice
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default, Serialize, Deserialize)]
+pub enum PlayerSmoothing {
+    // a step behind, exact
+    #[default]
+    Interpolated,
+    // a step behind, eased with the velocities so it curves instead of zigzagging
+    Smoothed,
+    // predicted ahead from the velocity, no lag but it can overshoot on a sudden stop
+    Extrapolated,
+}
+
+impl PlayerSmoothing {
+    pub fn key(self) -> &'static str {
+        match self {
+            PlayerSmoothing::Interpolated => "settings.player_smoothing_interpolated",
+            PlayerSmoothing::Smoothed => "settings.player_smoothing_smoothed",
+            PlayerSmoothing::Extrapolated => "settings.player_smoothing_extrapolated",
+        }
+    }
+
+    pub fn cycle(self, delta: i32) -> PlayerSmoothing {
+        const ORDER: [PlayerSmoothing; 3] = [
+            PlayerSmoothing::Interpolated,
+            PlayerSmoothing::Smoothed,
+            PlayerSmoothing::Extrapolated,
+        ];
+        let index = ORDER.iter().position(|mode| *mode == self).unwrap_or(0);
+        ORDER[(index as i32 + delta).rem_euclid(ORDER.len() as i32) as usize]
+    }
+}
+
+pub fn on_off(value: bool, localization: &Localization) -> &str {
+    if value {
+        localization.get("common.on")
+    } else {
+        localization.get("common.off")
+    }
+}
+
+fn settings_menu_rows(
+    page: Res<MenuPage>,
+    settings: Res<Settings>,
+    localization: Res<Localization>,
+    detected: Res<DetectedLayout>,
+    bindings: Res<Bindings>,
+    quality: Res<Quality>,
+    mut rows: ResMut<MenuRows>,
+) {
+    if *page != MenuPage::Settings {
+        return;
+    }
+    let layout = match settings.keyboard_layout {
+        Some(layout) => layout.name().to_string(),
+        None => localization.format(
+            "settings.keyboard_layout_auto",
+            &[("layout", &detected.0.unwrap_or_default().name())],
+        ),
+    };
+    let movement_keys: Vec<String> = [Action::Jump, Action::Left, Action::Down, Action::Right]
+        .into_iter()
+        .filter_map(|action| bindings.keys(action).last().copied())
+        .map(|key| key_label(key, bindings.layout))
+        .collect();
+    rows.push(MenuRow::new(
+        localization.format(
+            "settings.language",
+            &[("value", &language_name(&settings.language))],
+        ),
+        MenuAction::CycleLanguage,
+    ));
+    rows.push(MenuRow::new(
+        localization.format(
+            "settings.keyboard_layout",
+            &[("value", &layout), ("keys", &movement_keys.join(" "))],
+        ),
+        MenuAction::CycleKeyboardLayout,
+    ));
+    rows.push(MenuRow::new(
+        localization.get("settings.keyboard_bindings"),
+        MenuAction::Open(MenuPage::KeyboardBindings),
+    ));
+    rows.push(MenuRow::new(
+        localization.get("settings.gamepad_bindings"),
+        MenuAction::Open(MenuPage::GamepadBindings),
+    ));
+    rows.push(MenuRow::new(
+        localization.format(
+            "settings.stick_deadzone_x",
+            &[("value", &settings.stick_deadzone_x_percent)],
+        ),
+        MenuAction::CycleStickDeadzoneX,
+    ));
+    rows.push(MenuRow::new(
+        localization.format(
+            "settings.stick_deadzone_y",
+            &[("value", &settings.stick_deadzone_y_percent)],
+        ),
+        MenuAction::CycleStickDeadzoneY,
+    ));
+    rows.push(MenuRow::new(
+        localization.format(
+            "settings.stick_curve",
+            &[("value", &localization.get(settings.stick_curve.key()))],
+        ),
+        MenuAction::CycleStickCurve,
+    ));
+    rows.push(MenuRow::new(
+        localization.format(
+            "settings.stick_sensitivity",
+            &[("value", &settings.stick_sensitivity_percent)],
+        ),
+        MenuAction::CycleStickSensitivity,
+    ));
+    rows.push(MenuRow::new(
+        localization.format(
+            "settings.touch_controls",
+            &[("value", &localization.get(settings.touch_controls.key()))],
+        ),
+        MenuAction::CycleTouchControls,
+    ));
+    rows.push(MenuRow::new(
+        localization.format(
+            "settings.rumble",
+            &[("value", &on_off(settings.rumble, &localization))],
+        ),
+        MenuAction::ToggleRumble,
+    ));
+    rows.push(MenuRow::new(
+        localization.format(
+            "settings.theme",
+            &[("value", &localization.get(settings.theme.key()))],
+        ),
+        MenuAction::CycleTheme,
+    ));
+    rows.push(MenuRow::new(
+        localization.format(
+            "settings.animated_background",
+            &[(
+                "value",
+                &on_off(settings.animated_background, &localization),
+            )],
+        ),
+        MenuAction::ToggleAnimatedBackground,
+    ));
+    rows.push(MenuRow::new(
+        localization.format(
+            "settings.ui_scale",
+            &[("value", &settings.ui_scale_percent)],
+        ),
+        MenuAction::CycleUiScale,
+    ));
+    rows.push(MenuRow::new(
+        localization.format(
+            "settings.safe_area",
+            &[("value", &settings.safe_area_percent)],
+        ),
+        MenuAction::CycleSafeArea,
+    ));
+    rows.push(MenuRow::new(
+        localization.format(
+            "settings.one_switch",
+            &[("value", &on_off(settings.one_switch, &localization))],
+        ),
+        MenuAction::ToggleOneSwitch,
+    ));
+    rows.push(MenuRow::new(
+        localization.format(
+            "settings.jump_mode",
+            &[(
+                "value",
+                &localization.get(if settings.toggle_jump {
+                    "settings.jump_mode_toggle"
+                } else {
+                    "settings.jump_mode_hold"
+                }),
+            )],
+        ),
+        MenuAction::ToggleJumpMode,
+    ));
+    rows.push(MenuRow::new(
+        localization.format(
+            "settings.generous_timing",
+            &[("value", &on_off(settings.generous_timing, &localization))],
+        ),
+        MenuAction::ToggleGenerousTiming,
+    ));
+    rows.push(MenuRow::new(
+        localization.format(
+            "settings.landing_momentum",
+            &[("value", &on_off(settings.landing_momentum, &localization))],
+        ),
+        MenuAction::ToggleLandingMomentum,
+    ));
+    rows.push(MenuRow::new(
+        localization.format(
+            "settings.landing_lag",
+            &[("value", &on_off(settings.landing_lag, &localization))],
+        ),
+        MenuAction::ToggleLandingLag,
+    ));
+    rows.push(MenuRow::new(
+        localization.format(
+            "settings.line_clear",
+            &[("value", &on_off(settings.line_clear, &localization))],
+        ),
+        MenuAction::ToggleLineClear,
+    ));
+    rows.push(MenuRow::new(
+        localization.format(
+            "settings.reduce_motion",
+            &[("value", &on_off(settings.reduce_motion, &localization))],
+        ),
+        MenuAction::ToggleReduceMotion,
+    ));
+    rows.push(MenuRow::new(
+        localization.format(
+            "settings.reduce_flashing",
+            &[("value", &on_off(settings.reduce_flashing, &localization))],
+        ),
+        MenuAction::ToggleReduceFlashing,
+    ));
+    rows.push(MenuRow::new(
+        localization.format(
+            "settings.screen_reader",
+            &[("value", &on_off(settings.screen_reader, &localization))],
+        ),
+        MenuAction::ToggleScreenReader,
+    ));
+    rows.push(MenuRow::new(
+        localization.format(
+            "settings.hit_feedback",
+            &[("value", &localization.get(settings.hit_feedback.key()))],
+        ),
+        MenuAction::CycleHitFeedback,
+    ));
+    rows.push(MenuRow::new(
+        localization.format(
+            "settings.particles",
+            &[("value", &localization.get(settings.particles.key()))],
+        ),
+        MenuAction::CycleParticles,
+    ));
+    let quality_value = match settings.quality {
+        Some(fixed) => localization.get(fixed.key()).to_string(),
+        None => localization.format(
+            "settings.quality_auto",
+            &[("value", &localization.get(quality.key()))],
+        ),
+    };
+    rows.push(MenuRow::new(
+        localization.format("settings.quality", &[("value", &quality_value)]),
+        MenuAction::CycleQuality,
+    ));
+    rows.push(MenuRow::new(
+        localization.format(
+            "settings.afterimages",
+            &[("value", &on_off(settings.afterimages, &localization))],
+        ),
+        MenuAction::ToggleAfterimages,
+    ));
+    rows.push(MenuRow::new(
+        localization.format(
+            "settings.pace_ghost",
+            &[("value", &on_off(settings.pace_ghost, &localization))],
+        ),
+        MenuAction::TogglePaceGhost,
+    ));
+    rows.push(MenuRow::new(
+        localization.format(
+            "settings.player_smoothing",
+            &[("value", &localization.get(settings.player_smoothing.key()))],
+        ),
+        MenuAction::CyclePlayerSmoothing,
+    ));
+    rows.push(MenuRow::new(
+        localization.format(
+            "settings.pause_on_focus_loss",
+            &[(
+                "value",
+                &on_off(settings.pause_on_focus_loss, &localization),
+            )],
+        ),
+        MenuAction::TogglePauseOnFocusLoss,
+    ));
+    // only builds that can talk to discord get the privacy toggle
+    if cfg!(feature = "discord") {
+        rows.push(MenuRow::new(
+            localization.format(
+                "settings.discord_presence",
+                &[("value", &on_off(settings.discord_presence, &localization))],
+            ),
+            MenuAction::ToggleDiscordPresence,
+        ));
+    }
+    rows.push(MenuRow::new(
+        localization.format(
+            "settings.telemetry",
+            &[("value", &localization.get(settings.telemetry.key()))],
+        ),
+        MenuAction::CycleTelemetry,
+    ));
+    rows.push(MenuRow::new(
+        localization.get("menu.back"),
+        MenuAction::Back,
+    ));
+}
+
+fn apply_settings_actions(
+    mut activated: EventReader<MenuActivated>,
+    mut settings: ResMut<Settings>,
+) {
+    for event in activated.read() {
+        match event.action {
+            MenuAction::CycleLanguage => {
+                settings.language = cycle_language(&settings.language, event.delta).to_string();
+            }
+            MenuAction::CycleKeyboardLayout => {
+                settings.keyboard_layout = cycle_layout(settings.keyboard_layout, event.delta);
+            }
+            MenuAction::CycleStickDeadzoneX => {
+                settings.stick_deadzone_x_percent =
+                    cycle_stick_deadzone(settings.stick_deadzone_x_percent, event.delta);
+            }
+            MenuAction::CycleStickDeadzoneY => {
+                settings.stick_deadzone_y_percent =
+                    cycle_stick_deadzone(settings.stick_deadzone_y_percent, event.delta);
+            }
+            MenuAction::CycleStickCurve => {
+                settings.stick_curve = settings.stick_curve.cycle(event.delta);
+            }
+            MenuAction::CycleStickSensitivity => {
+                settings.stick_sensitivity_percent =
+                    cycle_stick_sensitivity(settings.stick_sensitivity_percent, event.delta);
+            }
+            MenuAction::CycleTouchControls => {
+                settings.touch_controls = settings.touch_controls.cycle(event.delta);
+            }
+            MenuAction::ToggleRumble => {
+                settings.rumble = !settings.rumble;
+            }
+            MenuAction::CycleTheme => {
+                settings.theme = settings.theme.cycle(event.delta);
+            }
+            MenuAction::ToggleAnimatedBackground => {
+                settings.animated_background = !settings.animated_background;
+            }
+            MenuAction::CycleUiScale => {
+                settings.ui_scale_percent = cycle_ui_scale(settings.ui_scale_percent, event.delta);
+            }
+            MenuAction::CycleSafeArea => {
+                settings.safe_area_percent =
+                    cycle_safe_area(settings.safe_area_percent, event.delta);
+            }
+            MenuAction::ToggleOneSwitch => {
+                settings.one_switch = !settings.one_switch;
+            }
+            MenuAction::ToggleJumpMode => {
+                settings.toggle_jump = !settings.toggle_jump;
+            }
+            MenuAction::ToggleGenerousTiming => {
+                settings.generous_timing = !settings.generous_timing;
+            }
+            MenuAction::ToggleLandingMomentum => {
+                settings.landing_momentum = !settings.landing_momentum;
+            }
+            MenuAction::ToggleLandingLag => {
+                settings.landing_lag = !settings.landing_lag;
+            }
+            MenuAction::ToggleLineClear => {
+                settings.line_clear = !settings.line_clear;
+            }
+            MenuAction::ToggleReduceMotion => {
+                settings.reduce_motion = !settings.reduce_motion;
+            }
+            MenuAction::ToggleReduceFlashing => {
+                settings.reduce_flashing = !settings.reduce_flashing;
+            }
+            MenuAction::ToggleScreenReader => {
+                settings.screen_reader = !settings.screen_reader;
+            }
+            MenuAction::CycleHitFeedback => {
+                settings.hit_feedback = settings.hit_feedback.cycle(event.delta);
+            }
+            MenuAction::CycleParticles => {
+                settings.particles = settings.particles.cycle(event.delta);
+            }
+            MenuAction::CycleQuality => {
+                settings.quality = cycle_quality(settings.quality, event.delta);
+            }
+            MenuAction::ToggleAfterimages => {
+                settings.afterimages = !settings.afterimages;
+            }
+            MenuAction::TogglePaceGhost => {
+                settings.pace_ghost = !settings.pace_ghost;
+            }
+            MenuAction::CyclePlayerSmoothing => {
+                settings.player_smoothing = settings.player_smoothing.cycle(event.delta);
+            }
+            MenuAction::TogglePauseOnFocusLoss => {
+                settings.pause_on_focus_loss = !settings.pause_on_focus_loss;
+            }
+            MenuAction::ToggleDiscordPresence => {
+                settings.discord_presence = !settings.discord_presence;
+            }
+            MenuAction::CycleTelemetry => {
+                settings.telemetry = settings.telemetry.cycle(event.delta);
+            }
+            _ => {}
+        }
+    }
+}
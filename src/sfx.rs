@@ -0,0 +1,321 @@
+use avian2d::prelude::*;
+use bevy::audio::Volume;
+use bevy::prelude::*;
+use rand::Rng;
+
+use crate::asset_check::TrackedAssets;
+use crate::explosion::Explosion;
+use crate::player::PlayerOwned;
+use crate::preload::PreloadedAudio;
+use crate::shockwave::{detect_massive_impacts, LastSpeed};
+use crate::spawner::{FallingRect, WeightClass};
+use crate::storms::StormStarted;
+use crate::voices::{Voice, VoicePriority};
+use crate::{GameSet, Player, PlayerCrushed, PlayerLanded, PlayerTouchedWall};
+
+const MIN_LANDING_IMPACT: f32 = 300.0;
+const HARD_LANDING_IMPACT: f32 = 1500.0;
+const WALL_TOUCH_VOLUME: f32 = 0.25;
+// half m v^2 like the shockwaves use. each layer is a power of ten above the last, and
+// anything in between plays a mix of its two neighbours
+const MIN_RECT_IMPACT_ENERGY: f32 = 2e7;
+const IMPACT_LAYER_ENERGIES: [f32; 3] = [1e8, 1e9, 1e10];
+// random pitch so a pile settling doesn't sound like one sample on repeat
+const IMPACT_PITCH_JITTER: f32 = 0.06;
+// impacts closer to the player than this are loud and hard to steal, further out they
+// fade into the background of the pile
+const NEAR_IMPACT_DISTANCE: f32 = 300.0;
+const FAR_IMPACT_DISTANCE: f32 = 1500.0;
+const FAR_IMPACT_VOLUME: f32 = 0.3;
+
+pub struct SfxPlugin;
+
+impl Plugin for SfxPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Startup, load_sounds)
+            .add_systems(
+                Update,
+                (
+                    play_landing_sounds,
+                    play_wall_touch_sounds,
+                    play_crush_sounds,
+                    play_explosion_sounds,
+                    play_storm_stings,
+                )
+                    .in_set(GameSet::Reactions),
+            )
+            .add_systems(
+                Update,
+                play_impact_sounds
+                    .before(detect_massive_impacts)
+                    .in_set(GameSet::Reactions),
+            );
+    }
+}
+
+#[derive(Resource)]
+struct CollisionSound(Handle<AudioSource>);
+
+struct ImpactSample {
+    path: &'static str,
+    speed: f32,
+}
+
+// low, medium and high impact layers per material: a soft thud for foam, a wooden thunk
+// for regular rects and a metal clang for steel. they all pitch the one hit sample for
+// now, recorded samples only need their path changed here
+const IMPACT_SOUNDS: [(WeightClass, [ImpactSample; 3]); 3] = [
+    (
+        WeightClass::Foam,
+        [
+            ImpactSample {
+                path: "sounds/hitHurt.ogg",
+                speed: 0.55,
+            },
+            ImpactSample {
+                path: "sounds/hitHurt.ogg",
+                speed: 0.5,
+            },
+            ImpactSample {
+                path: "sounds/hitHurt.ogg",
+                speed: 0.45,
+            },
+        ],
+    ),
+    (
+        WeightClass::Normal,
+        [
+            ImpactSample {
+                path: "sounds/hitHurt.ogg",
+                speed: 0.9,
+            },
+            ImpactSample {
+                path: "sounds/hitHurt.ogg",
+                speed: 0.8,
+            },
+            ImpactSample {
+                path: "sounds/hitHurt.ogg",
+                speed: 0.7,
+            },
+        ],
+    ),
+    (
+        WeightClass::Steel,
+        [
+            ImpactSample {
+                path: "sounds/hitHurt.ogg",
+                speed: 1.5,
+            },
+            ImpactSample {
+                path: "sounds/hitHurt.ogg",
+                speed: 1.3,
+            },
+            ImpactSample {
+                path: "sounds/hitHurt.ogg",
+                speed: 1.1,
+            },
+        ],
+    ),
+];
+
+// handles in the same order as IMPACT_SOUNDS
+#[derive(Resource)]
+struct ImpactSounds(Vec<[Handle<AudioSource>; 3]>);
+
+impl ImpactSounds {
+    fn layers(
+        &self,
+        class: WeightClass,
+    ) -> Option<(&[Handle<AudioSource>; 3], &[ImpactSample; 3])> {
+        IMPACT_SOUNDS
+            .iter()
+            .position(|(material, _)| *material == class)
+            .map(|index| (&self.0[index], &IMPACT_SOUNDS[index].1))
+    }
+}
+
+fn load_sounds(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    mut tracked: ResMut<TrackedAssets>,
+) {
+    let sound = asset_server.load("sounds/hitHurt.ogg");
+    tracked.track(sound.clone());
+    commands.insert_resource(CollisionSound(sound));
+    let impacts = IMPACT_SOUNDS
+        .iter()
+        .map(|(_, samples)| {
+            samples.each_ref().map(|sample| {
+                let handle: Handle<AudioSource> = asset_server.load(sample.path);
+                tracked.track(handle.clone());
+                handle
+            })
+        })
+        .collect();
+    commands.insert_resource(ImpactSounds(impacts));
+}
+
+// how much of each layer to play, two neighbouring layers crossfade in log space
+fn impact_layer_volumes(energy: f32) -> [f32; 3] {
+    let position = (energy.log10() - IMPACT_LAYER_ENERGIES[0].log10())
+        .clamp(0.0, (IMPACT_LAYER_ENERGIES.len() - 1) as f32);
+    let mut volumes = [0.0; 3];
+    let lower = position.floor() as usize;
+    let blend = position - lower as f32;
+    volumes[lower] = 1.0 - blend;
+    if lower + 1 < volumes.len() {
+        volumes[lower + 1] = blend;
+    }
+    // quieter than the first layer fades out towards the minimum
+    let loudness = ((energy.log10() - MIN_RECT_IMPACT_ENERGY.log10())
+        / (IMPACT_LAYER_ENERGIES[0].log10() - MIN_RECT_IMPACT_ENERGY.log10()))
+    .clamp(0.0, 1.0);
+    volumes.map(|volume| volume * loudness)
+}
+
+fn play_landing_sounds(
+    mut commands: Commands,
+    mut landed: EventReader<PlayerLanded>,
+    sound: Res<CollisionSound>,
+    preloaded: Res<PreloadedAudio>,
+    player: Single<Entity, With<Player>>,
+) {
+    for landing in landed.read() {
+        if landing.impact < MIN_LANDING_IMPACT {
+            continue;
+        }
+        let volume = (landing.impact / HARD_LANDING_IMPACT).min(1.0);
+        trace!(target: "audio", "landing sound at volume {volume:.2}");
+        let mut voice = commands.spawn((
+            PlaybackSettings::DESPAWN.with_volume(Volume::new(volume)),
+            Voice::new(VoicePriority::Player, volume),
+            PlayerOwned(*player),
+        ));
+        preloaded.insert_player(&mut voice, &sound.0);
+    }
+}
+
+// a light, higher tap, so sliding into a wall isn't silent next to landing
+fn play_wall_touch_sounds(
+    mut commands: Commands,
+    mut touched: EventReader<PlayerTouchedWall>,
+    sound: Res<CollisionSound>,
+    preloaded: Res<PreloadedAudio>,
+    player: Single<Entity, With<Player>>,
+) {
+    for _ in touched.read() {
+        trace!(target: "audio", "wall touch sound");
+        let mut voice = commands.spawn((
+            PlaybackSettings::DESPAWN
+                .with_volume(Volume::new(WALL_TOUCH_VOLUME))
+                .with_speed(1.6),
+            Voice::new(VoicePriority::Player, WALL_TOUCH_VOLUME),
+            PlayerOwned(*player),
+        ));
+        preloaded.insert_player(&mut voice, &sound.0);
+    }
+}
+
+fn play_crush_sounds(
+    mut commands: Commands,
+    mut crushed: EventReader<PlayerCrushed>,
+    sound: Res<CollisionSound>,
+    preloaded: Res<PreloadedAudio>,
+    player: Single<Entity, With<Player>>,
+) {
+    for _ in crushed.read() {
+        trace!(target: "audio", "crush sound");
+        let mut voice = commands.spawn((
+            PlaybackSettings::DESPAWN,
+            Voice::new(VoicePriority::Player, 1.0),
+            PlayerOwned(*player),
+        ));
+        preloaded.insert_player(&mut voice, &sound.0);
+    }
+}
+
+// not tied to the player, a bomb going off keeps sounding through a respawn
+fn play_explosion_sounds(
+    mut commands: Commands,
+    mut explosions: EventReader<Explosion>,
+    sound: Res<CollisionSound>,
+    preloaded: Res<PreloadedAudio>,
+) {
+    for _ in explosions.read() {
+        trace!(target: "audio", "explosion sound");
+        let mut voice = commands.spawn((
+            PlaybackSettings::DESPAWN,
+            Voice::new(VoicePriority::World, 1.0),
+        ));
+        preloaded.insert_player(&mut voice, &sound.0);
+    }
+}
+
+fn play_storm_stings(
+    mut commands: Commands,
+    mut started: EventReader<StormStarted>,
+    sound: Res<CollisionSound>,
+    preloaded: Res<PreloadedAudio>,
+) {
+    for StormStarted { kind } in started.read() {
+        trace!(target: "audio", "storm sting for {kind:?}");
+        let mut voice = commands.spawn((
+            PlaybackSettings::DESPAWN.with_speed(0.5),
+            Voice::new(VoicePriority::World, 1.0),
+        ));
+        preloaded.insert_player(&mut voice, &sound.0);
+    }
+}
+
+// every rect plays its own material, so a foam rect landing on steel gets both
+fn play_impact_sounds(
+    mut commands: Commands,
+    mut collisions: EventReader<CollisionStarted>,
+    rects: Query<(&ColliderAabb, &WeightClass, &LastSpeed), With<FallingRect>>,
+    player: Single<&Transform, With<Player>>,
+    sounds: Res<ImpactSounds>,
+    preloaded: Res<PreloadedAudio>,
+) {
+    let player = player.translation.truncate();
+    let mut rng = rand::thread_rng();
+    for CollisionStarted(a, b) in collisions.read() {
+        for entity in [*a, *b] {
+            let Ok((aabb, class, last_speed)) = rects.get(entity) else {
+                continue;
+            };
+            let mass = aabb.size().x * aabb.size().y * class.density();
+            let energy = 0.5 * mass * last_speed.0 * last_speed.0;
+            if energy < MIN_RECT_IMPACT_ENERGY {
+                continue;
+            }
+            let Some((handles, samples)) = sounds.layers(*class) else {
+                continue;
+            };
+            let jitter = 1.0 + rng.gen_range(-IMPACT_PITCH_JITTER..IMPACT_PITCH_JITTER);
+            let distance = (player - player.clamp(aabb.min, aabb.max)).length();
+            let far = ((distance - NEAR_IMPACT_DISTANCE)
+                / (FAR_IMPACT_DISTANCE - NEAR_IMPACT_DISTANCE))
+                .clamp(0.0, 1.0);
+            let priority = if distance <= NEAR_IMPACT_DISTANCE {
+                VoicePriority::World
+            } else {
+                VoicePriority::Ambient
+            };
+            let volumes = impact_layer_volumes(energy)
+                .map(|volume| volume * (1.0 + (FAR_IMPACT_VOLUME - 1.0) * far));
+            trace!(target: "audio", "{class:?} impact, energy {energy:.2e}, layers {volumes:.2?}");
+            for ((handle, sample), volume) in handles.iter().zip(samples).zip(volumes) {
+                if volume < 0.05 {
+                    continue;
+                }
+                let mut voice = commands.spawn((
+                    PlaybackSettings::DESPAWN
+                        .with_volume(Volume::new(volume))
+                        .with_speed(sample.speed * jitter),
+                    Voice::new(priority, volume),
+                ));
+                preloaded.insert_player(&mut voice, handle);
+            }
+        }
+    }
+}
@@ -0,0 +1,76 @@
+use bevy::prelude::*;
+use bevy::transform::TransformSystem;
+
+use crate::explosion::Explosion;
+use crate::settings::Settings;
+use crate::{GameSet, PlayerCrushed, PlayerLanded};
+
+const MAX_SHAKE_OFFSET: f32 = 25.0;
+const TRAUMA_DECAY_PER_SEC: f32 = 1.5;
+const SHAKE_FREQUENCY: f32 = 30.0;
+const HARD_LANDING_IMPACT: f32 = 1500.0;
+const EXPLOSION_TRAUMA: f32 = 0.7;
+
+pub struct ShakePlugin;
+
+impl Plugin for ShakePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<ScreenShake>()
+            .add_systems(Update, add_trauma.in_set(GameSet::Reactions))
+            .add_systems(
+                PostUpdate,
+                shake_camera.before(TransformSystem::TransformPropagate),
+            );
+    }
+}
+
+#[derive(Resource, Default)]
+pub struct ScreenShake {
+    trauma: f32,
+    // removed again next frame so the shake never moves the camera for good
+    offset: Vec2,
+}
+
+impl ScreenShake {
+    pub fn add(&mut self, trauma: f32) {
+        self.trauma = (self.trauma + trauma).min(1.0);
+    }
+}
+
+fn add_trauma(
+    mut crushed: EventReader<PlayerCrushed>,
+    mut landed: EventReader<PlayerLanded>,
+    mut explosions: EventReader<Explosion>,
+    mut shake: ResMut<ScreenShake>,
+) {
+    for _ in explosions.read() {
+        shake.add(EXPLOSION_TRAUMA);
+    }
+    for _ in crushed.read() {
+        shake.trauma = 1.0;
+    }
+    for landing in landed.read() {
+        let trauma = (landing.impact / HARD_LANDING_IMPACT - 0.5).max(0.0);
+        shake.add(trauma);
+    }
+}
+
+fn shake_camera(
+    settings: Res<Settings>,
+    mut shake: ResMut<ScreenShake>,
+    mut camera: Single<&mut Transform, With<Camera>>,
+    time: Res<Time>,
+) {
+    let previous = shake.offset;
+    shake.trauma = (shake.trauma - TRAUMA_DECAY_PER_SEC * time.delta_secs()).max(0.0);
+    shake.offset = if settings.reduce_motion {
+        Vec2::ZERO
+    } else {
+        // trauma squared keeps small hits subtle
+        let t = time.elapsed_secs() * SHAKE_FREQUENCY;
+        Vec2::new(t.sin(), (t * 1.3).cos()) * shake.trauma * shake.trauma * MAX_SHAKE_OFFSET
+    };
+    if previous != shake.offset {
+        camera.translation += (shake.offset - previous).extend(0.0);
+    }
+}
@@ -0,0 +1,236 @@
+use avian2d::prelude::*;
+use bevy::prelude::*;
+
+use crate::boss::BossRect;
+use crate::input::{Action, ActionState};
+use crate::palette::Palette;
+use crate::spawner::{FallingRect, WeightClass};
+use crate::z_layer::ZLayer;
+use crate::{GameSet, Player, PlayerDied};
+
+// half m v^2, with mass as area times density. a 100x100 steel rect needs to land at
+// about 775 px/s, a regular one never gets there
+const MIN_SHOCKWAVE_ENERGY: f32 = 1.5e10;
+// strength 1 is a wave right at the threshold, harder hits reach further
+const MAX_STRENGTH: f32 = 3.0;
+const BASE_RADIUS: f32 = 400.0;
+const WAVE_SPEED: f32 = 900.0;
+// the wave runs along the ground, things well above or below the impact miss it
+const GROUND_BAND: f32 = 150.0;
+// px/s kicked into rects and the player as the front passes, less further out
+const RECT_SHAKE_SPEED: f32 = 250.0;
+const PLAYER_KNOCK_SPEED: f32 = 500.0;
+// holding down on the ground braces against the wave
+const BRACED_FACTOR: f32 = 0.2;
+const RING_ALPHA: f32 = 0.6;
+
+pub struct ShockwavePlugin;
+
+impl Plugin for ShockwavePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<RingMesh>()
+            .add_event::<EmitShockwave>()
+            .add_systems(
+                Update,
+                (
+                    clear_on_death,
+                    detect_massive_impacts,
+                    spawn_shockwaves,
+                    propagate_shockwaves,
+                )
+                    .chain()
+                    .in_set(GameSet::Reactions),
+            );
+    }
+}
+
+#[derive(Event)]
+pub struct EmitShockwave {
+    pub origin: Vec2,
+    pub strength: f32,
+}
+
+// the front of a wave and the ring drawn along it
+#[derive(Component)]
+struct Shockwave {
+    origin: Vec2,
+    radius: f32,
+    max_radius: f32,
+    strength: f32,
+}
+
+// speed during the previous frame, the collision has already slowed it down
+#[derive(Component, Default)]
+pub struct LastSpeed(pub f32);
+
+// a thin ring in a unit box, scaled up as it spreads
+#[derive(Resource)]
+struct RingMesh(Handle<Mesh>);
+
+impl FromWorld for RingMesh {
+    fn from_world(world: &mut World) -> RingMesh {
+        RingMesh(
+            world
+                .resource_mut::<Assets<Mesh>>()
+                .add(Annulus::new(0.45, 0.5)),
+        )
+    }
+}
+
+// bosses send their own, bigger wave. anything else reading LastSpeed for this frame's
+// impacts has to run before this, it updates them for the next frame
+pub fn detect_massive_impacts(
+    mut commands: Commands,
+    mut collisions: EventReader<CollisionStarted>,
+    mut rects: Query<
+        (
+            Entity,
+            &LinearVelocity,
+            &ColliderAabb,
+            &WeightClass,
+            Option<&mut LastSpeed>,
+        ),
+        (With<FallingRect>, Without<BossRect>),
+    >,
+    mut shockwaves: EventWriter<EmitShockwave>,
+) {
+    for CollisionStarted(a, b) in collisions.read() {
+        for entity in [*a, *b] {
+            let Ok((_, _, aabb, class, Some(last_speed))) = rects.get(entity) else {
+                continue;
+            };
+            let mass = aabb.size().x * aabb.size().y * class.density();
+            let energy = 0.5 * mass * last_speed.0 * last_speed.0;
+            if energy < MIN_SHOCKWAVE_ENERGY {
+                continue;
+            }
+            // from the bottom of the rect, where it met the ground
+            let origin = Vec2::new(aabb.center().x, aabb.min.y);
+            debug!(target: "physics", "massive impact at {origin}, energy {energy:.2e}");
+            shockwaves.send(EmitShockwave {
+                origin,
+                strength: (energy / MIN_SHOCKWAVE_ENERGY).min(MAX_STRENGTH),
+            });
+        }
+    }
+    for (entity, linear, _, _, last_speed) in &mut rects {
+        match last_speed {
+            Some(mut last_speed) => last_speed.0 = linear.length(),
+            None => {
+                commands.entity(entity).insert(LastSpeed(linear.length()));
+            }
+        }
+    }
+}
+
+fn spawn_shockwaves(
+    mut commands: Commands,
+    mut shockwaves: EventReader<EmitShockwave>,
+    ring: Res<RingMesh>,
+    palette: Res<Palette>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+) {
+    for EmitShockwave { origin, strength } in shockwaves.read() {
+        // rare enough for every ring to get its own material to fade out
+        commands.spawn((
+            Name::new("Shockwave"),
+            ZLayer::Particles,
+            Shockwave {
+                origin: *origin,
+                radius: 0.0,
+                max_radius: BASE_RADIUS * strength,
+                strength: *strength,
+            },
+            Mesh2d(ring.0.clone()),
+            MeshMaterial2d(materials.add(palette.ui_accent.with_alpha(RING_ALPHA))),
+            Transform::from_translation(origin.extend(0.0)).with_scale(Vec3::ZERO),
+        ));
+    }
+}
+
+// everything the front passed over this frame gets kicked up and away from the origin
+fn propagate_shockwaves(
+    mut commands: Commands,
+    mut shockwaves: Query<(
+        Entity,
+        &mut Shockwave,
+        &mut Transform,
+        &MeshMaterial2d<ColorMaterial>,
+    )>,
+    mut rects: Query<
+        (
+            Entity,
+            &Transform,
+            &mut LinearVelocity,
+            &RigidBody,
+            Has<Sleeping>,
+        ),
+        (With<FallingRect>, Without<Shockwave>, Without<Player>),
+    >,
+    player: Single<
+        (&Player, &Transform, &mut LinearVelocity),
+        (Without<Shockwave>, Without<FallingRect>),
+    >,
+    actions: Res<ActionState>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+    time: Res<Time>,
+) {
+    let (player, player_transform, mut player_linear) = player.into_inner();
+    let braced = player.ground_normal.is_some() && actions.pressed(Action::Down);
+    for (entity, mut wave, mut transform, material) in &mut shockwaves {
+        let inner = wave.radius;
+        wave.radius += WAVE_SPEED * time.delta_secs();
+        let outer = wave.radius.min(wave.max_radius);
+        let passed = |position: Vec2| {
+            let offset = position - wave.origin;
+            let crossed = offset.x.abs() > inner && offset.x.abs() <= outer;
+            (crossed && offset.y.abs() <= GROUND_BAND)
+                .then(|| (offset.x.signum(), 1.0 - offset.x.abs() / wave.max_radius))
+        };
+        for (rect, rect_transform, mut linear, body, sleeping) in &mut rects {
+            if !body.is_dynamic() {
+                continue;
+            }
+            let Some((_, falloff)) = passed(rect_transform.translation.truncate()) else {
+                continue;
+            };
+            if sleeping {
+                commands.entity(rect).remove::<Sleeping>();
+            }
+            linear.y += RECT_SHAKE_SPEED * falloff;
+        }
+        if let Some((direction, falloff)) = passed(player_transform.translation.truncate()) {
+            if player.ground_normal.is_some() {
+                let factor = if braced { BRACED_FACTOR } else { 1.0 };
+                debug!(target: "player", "hit by a shockwave, braced: {braced}");
+                player_linear.0 += Vec2::new(direction, 1.0).normalize()
+                    * PLAYER_KNOCK_SPEED
+                    * wave.strength.sqrt()
+                    * falloff
+                    * factor;
+            }
+        }
+        if wave.radius >= wave.max_radius {
+            commands.entity(entity).despawn();
+            continue;
+        }
+        let t = wave.radius / wave.max_radius;
+        transform.scale = Vec3::splat(wave.radius * 2.0);
+        if let Some(material) = materials.get_mut(material) {
+            material.color.set_alpha(RING_ALPHA * (1.0 - t));
+        }
+    }
+}
+
+fn clear_on_death(
+    mut commands: Commands,
+    mut died: EventReader<PlayerDied>,
+    shockwaves: Query<Entity, With<Shockwave>>,
+) {
+    if died.read().count() == 0 {
+        return;
+    }
+    for entity in &shockwaves {
+        commands.entity(entity).despawn();
+    }
+}
@@ -0,0 +1,229 @@
+use bevy::prelude::*;
+
+use crate::hud::ShowBanner;
+use crate::i18n::Localization;
+use crate::menu::{
+    GameState, MenuAction, MenuActivated, MenuPage, MenuRow, MenuRows, MenuSelection, MenuSet,
+};
+use crate::score::RunStats;
+use crate::spawner::Spawner;
+use crate::{GameSet, PlayerDied};
+
+// seconds of drops between calm phases
+const WAVE_SECS: f32 = 75.0;
+// long enough for the last planned drops to land and the pile to settle
+const CALM_SECS: f32 = 12.0;
+const COYOTE_SECS_PER_LEVEL: f32 = 0.05;
+const WALL_JUMP_BOOST_PER_LEVEL: f32 = 0.15;
+
+pub struct ShopPlugin;
+
+impl Plugin for ShopPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<RunUpgrades>()
+            .init_resource::<CalmPhases>()
+            .add_systems(Update, run_calm_phases.in_set(GameSet::Reactions))
+            .add_systems(
+                Update,
+                (
+                    shop_menu_rows.in_set(MenuSet::Rows),
+                    buy_upgrades.in_set(MenuSet::Actions),
+                ),
+            );
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Upgrade {
+    Health,
+    Coyote,
+    WallJump,
+}
+
+impl Upgrade {
+    pub const ALL: [Upgrade; 3] = [Upgrade::Health, Upgrade::Coyote, Upgrade::WallJump];
+
+    fn max_level(self) -> u32 {
+        3
+    }
+
+    // every level costs more than the last
+    fn cost(self, level: u32) -> u32 {
+        let base = match self {
+            Upgrade::Health => 400,
+            Upgrade::Coyote => 250,
+            Upgrade::WallJump => 300,
+        };
+        base * (level + 1)
+    }
+
+    fn key(self) -> &'static str {
+        match self {
+            Upgrade::Health => "shop.health",
+            Upgrade::Coyote => "shop.coyote",
+            Upgrade::WallJump => "shop.wall_jump",
+        }
+    }
+}
+
+// bought in the shop, kept until the player dies
+#[derive(Resource, Default)]
+pub struct RunUpgrades {
+    levels: [u32; Upgrade::ALL.len()],
+    // free levels from meta progression perks, they survive the reset on death
+    perks: [u32; Upgrade::ALL.len()],
+    // score already spent, the score itself never goes down
+    spent: u32,
+}
+
+impl RunUpgrades {
+    pub fn level(&self, upgrade: Upgrade) -> u32 {
+        (self.levels[upgrade as usize] + self.perks[upgrade as usize]).min(upgrade.max_level())
+    }
+
+    pub fn set_perks(&mut self, perks: [u32; Upgrade::ALL.len()]) {
+        if self.perks != perks {
+            self.perks = perks;
+        }
+    }
+
+    pub fn extra_health(&self) -> u32 {
+        self.level(Upgrade::Health)
+    }
+
+    pub fn extra_coyote_secs(&self) -> f32 {
+        self.level(Upgrade::Coyote) as f32 * COYOTE_SECS_PER_LEVEL
+    }
+
+    pub fn wall_jump_factor(&self) -> f32 {
+        1.0 + self.level(Upgrade::WallJump) as f32 * WALL_JUMP_BOOST_PER_LEVEL
+    }
+
+    fn budget(&self, stats: &RunStats) -> u32 {
+        stats.score.saturating_sub(self.spent)
+    }
+}
+
+#[derive(Resource)]
+struct CalmPhases {
+    until_calm: f32,
+    // seconds left of the current calm phase
+    calm: Option<f32>,
+}
+
+impl Default for CalmPhases {
+    fn default() -> CalmPhases {
+        CalmPhases {
+            until_calm: WAVE_SECS,
+            calm: None,
+        }
+    }
+}
+
+// between waves the spawner stops planning drops and the shop opens over the game
+fn run_calm_phases(
+    mut phases: ResMut<CalmPhases>,
+    mut upgrades: ResMut<RunUpgrades>,
+    mut spawner: ResMut<Spawner>,
+    mut died: EventReader<PlayerDied>,
+    mut page: ResMut<MenuPage>,
+    mut selection: ResMut<MenuSelection>,
+    mut next_state: ResMut<NextState<GameState>>,
+    mut banners: EventWriter<ShowBanner>,
+    localization: Res<Localization>,
+    time: Res<Time>,
+) {
+    if died.read().count() > 0 {
+        *phases = CalmPhases::default();
+        *upgrades = RunUpgrades {
+            perks: upgrades.perks,
+            ..default()
+        };
+        spawner.calm = false;
+        return;
+    }
+    let delta_secs = time.delta_secs();
+    if let Some(remaining) = &mut phases.calm {
+        *remaining -= delta_secs;
+        if *remaining <= 0.0 {
+            debug!(target: "spawner", "calm phase over");
+            phases.calm = None;
+            spawner.calm = false;
+            banners.send(ShowBanner(localization.get("shop.wave").to_string()));
+        }
+        return;
+    }
+    phases.until_calm -= delta_secs;
+    if phases.until_calm > 0.0 {
+        return;
+    }
+    debug!(target: "spawner", "calm phase started");
+    phases.until_calm = WAVE_SECS;
+    phases.calm = Some(CALM_SECS);
+    spawner.calm = true;
+    *page = MenuPage::Shop;
+    selection.0 = 0;
+    next_state.set(GameState::Paused);
+}
+
+fn shop_menu_rows(
+    page: Res<MenuPage>,
+    upgrades: Res<RunUpgrades>,
+    stats: Res<RunStats>,
+    localization: Res<Localization>,
+    mut rows: ResMut<MenuRows>,
+) {
+    if *page != MenuPage::Shop {
+        return;
+    }
+    rows.push(MenuRow::new(
+        localization.format("shop.budget", &[("points", &upgrades.budget(&stats))]),
+        MenuAction::None,
+    ));
+    for (index, upgrade) in Upgrade::ALL.into_iter().enumerate() {
+        let level = upgrades.level(upgrade);
+        let name = localization.get(upgrade.key()).to_string();
+        let label = if level >= upgrade.max_level() {
+            localization.format("shop.maxed", &[("name", &name)])
+        } else {
+            localization.format(
+                "shop.offer",
+                &[
+                    ("name", &name),
+                    ("level", &level),
+                    ("max", &upgrade.max_level()),
+                    ("cost", &upgrade.cost(level)),
+                ],
+            )
+        };
+        rows.push(MenuRow::new(label, MenuAction::BuyUpgrade(index)));
+    }
+    rows.push(MenuRow::new(
+        localization.get("shop.leave"),
+        MenuAction::Resume,
+    ));
+}
+
+fn buy_upgrades(
+    mut activated: EventReader<MenuActivated>,
+    mut upgrades: ResMut<RunUpgrades>,
+    stats: Res<RunStats>,
+) {
+    for event in activated.read() {
+        let MenuAction::BuyUpgrade(index) = event.action else {
+            continue;
+        };
+        let upgrade = Upgrade::ALL[index];
+        let level = upgrades.level(upgrade);
+        if event.delta != 0 || level >= upgrade.max_level() {
+            continue;
+        }
+        let cost = upgrade.cost(level);
+        if upgrades.budget(&stats) < cost {
+            continue;
+        }
+        upgrades.spent += cost;
+        upgrades.levels[index] += 1;
+        debug!(target: "player", "bought {upgrade:?} level {}", level + 1);
+    }
+}
@@ -0,0 +1,580 @@
+use std::collections::VecDeque;
+
+use avian2d::prelude::*;
+use bevy::prelude::*;
+use rand::Rng;
+use serde::Deserialize;
+
+use crate::common_assets::CommonAssets;
+use crate::director::{DifficultyConfig, Director};
+use crate::prefab::{falling_rect_bundle, PieceShape, PrefabParams, Prefabs};
+use crate::score::RunStats;
+use crate::{GameSet, PlayerDied};
+
+pub const SPAWN_HALF_WIDTH: f32 = 450.0;
+const SPAWN_MARGIN_ABOVE_VIEW: f32 = 200.0;
+const MIN_RECT_SIZE: f32 = 40.0;
+const MAX_RECT_SIZE: f32 = 200.0;
+const BASE_SPAWN_INTERVAL: f32 = 2.0;
+const MIN_SPAWN_INTERVAL: f32 = 0.3;
+const BASE_LEAD_TIME: f32 = 1.5;
+const MIN_LEAD_TIME: f32 = 0.4;
+// foam gets rarer and steel more common as the difficulty ramps up
+const BASE_FOAM_CHANCE: f32 = 0.3;
+const BASE_STEEL_CHANCE: f32 = 0.05;
+const MAX_STEEL_CHANCE: f32 = 0.4;
+// the rest are plain rects
+const ODD_SHAPE_CHANCE: f32 = 0.4;
+const BOMB_CHANCE: f32 = 0.05;
+// a pattern can drop dozens of rects on the same tick, the physics step only takes on
+// this many new bodies at a time so it doesn't spike
+const MAX_NEW_BODIES_PER_TICK: usize = 6;
+
+pub struct SpawnerPlugin;
+
+impl Plugin for SpawnerPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Difficulty>()
+            .init_resource::<Spawner>()
+            .init_resource::<SpawnerConfig>()
+            .add_event::<RectSpawned>()
+            .add_event::<RectSettled>()
+            .add_systems(
+                Update,
+                (
+                    clear_on_death,
+                    ramp_difficulty,
+                    plan_drops,
+                    spawn_planned_drops,
+                )
+                    .chain()
+                    .in_set(GameSet::Reactions),
+            )
+            // counted in physics ticks rather than frames, so a slow frame that runs several
+            // steps also spreads a batch over them
+            .add_systems(FixedUpdate, activate_pending_bodies)
+            .add_systems(
+                Update,
+                (mark_settled, limit_spin).in_set(GameSet::PhysicsSync),
+            );
+        #[cfg(any(debug_assertions, feature = "cheats"))]
+        stress::build(app);
+    }
+}
+
+#[derive(Resource)]
+pub struct Difficulty {
+    pub level: f32,
+    // temporary multiplier on top of the level, rect rain storms raise it
+    pub spawn_rate: f32,
+}
+
+impl Default for Difficulty {
+    fn default() -> Difficulty {
+        Difficulty {
+            level: 1.0,
+            spawn_rate: 1.0,
+        }
+    }
+}
+
+impl Difficulty {
+    pub fn spawn_interval(&self) -> f32 {
+        (BASE_SPAWN_INTERVAL / self.level).max(MIN_SPAWN_INTERVAL) / self.spawn_rate
+    }
+
+    // harder levels give less warning before a drop
+    pub fn lead_time(&self) -> f32 {
+        (BASE_LEAD_TIME / self.level.sqrt()).max(MIN_LEAD_TIME)
+    }
+
+    pub fn weight_class_chances(&self) -> [(WeightClass, f32); 3] {
+        let foam = BASE_FOAM_CHANCE / self.level;
+        let steel = (BASE_STEEL_CHANCE * self.level).min(MAX_STEEL_CHANCE);
+        [
+            (WeightClass::Foam, foam),
+            (WeightClass::Steel, steel),
+            (WeightClass::Normal, 1.0 - foam - steel),
+        ]
+    }
+
+    pub fn pick_weight_class(&self, roll: f32) -> WeightClass {
+        let mut total = 0.0;
+        for (class, chance) in self.weight_class_chances() {
+            total += chance;
+            if roll < total {
+                return class;
+            }
+        }
+        WeightClass::Normal
+    }
+}
+
+// how pieces tumble as they fall
+#[derive(Resource, Clone)]
+pub struct SpawnerConfig {
+    // radians per second, each drop starts with a random spin up to this
+    pub max_initial_spin: f32,
+    pub max_angular_velocity: f32,
+    pub angular_damping: f32,
+    // plain rects don't rotate unless this is off
+    pub upright_rects: bool,
+}
+
+impl Default for SpawnerConfig {
+    fn default() -> SpawnerConfig {
+        SpawnerConfig {
+            max_initial_spin: 3.0,
+            max_angular_velocity: 10.0,
+            angular_damping: 0.5,
+            upright_rects: true,
+        }
+    }
+}
+
+#[derive(Component)]
+pub struct FallingRect;
+
+// a falling rect that doesn't have its rigid body and collider yet
+#[derive(Component)]
+pub struct PendingBody;
+
+// light rects can be pushed around by the player, heavy ones can't and hurt more
+#[derive(Component, Clone, Copy, PartialEq, Eq, Debug, Deserialize)]
+pub enum WeightClass {
+    Foam,
+    Normal,
+    Steel,
+}
+
+impl WeightClass {
+    pub fn density(self) -> f32 {
+        match self {
+            WeightClass::Foam => 0.2,
+            WeightClass::Normal => 1.0,
+            WeightClass::Steel => 5.0,
+        }
+    }
+
+    pub fn crush_damage(self) -> u32 {
+        match self {
+            WeightClass::Foam => 0,
+            WeightClass::Normal => 1,
+            WeightClass::Steel => 2,
+        }
+    }
+
+    pub fn prefab(self) -> &'static str {
+        match self {
+            WeightClass::Foam => "foam",
+            WeightClass::Normal => "crate",
+            WeightClass::Steel => "heavy",
+        }
+    }
+}
+
+// a falling rect that came to rest on the pile at least once
+#[derive(Component)]
+pub struct Settled;
+
+#[derive(Event)]
+pub struct RectSpawned {
+    pub drop_id: u64,
+}
+
+#[derive(Event)]
+pub struct RectSettled;
+
+#[derive(Clone, Copy)]
+pub struct PlannedDrop {
+    pub id: u64,
+    pub x: f32,
+    pub size: Vec2,
+    pub class: WeightClass,
+    pub shape: PieceShape,
+    pub spin: f32,
+    pub bomb: bool,
+    pub boss: bool,
+    pub warn_at: f32,
+    pub spawn_at: f32,
+}
+
+#[derive(Resource, Default)]
+pub struct Spawner {
+    planned: VecDeque<PlannedDrop>,
+    next_id: u64,
+    // nothing new gets planned, the drops already planned still fall
+    pub calm: bool,
+    // nothing gets planned or dropped, the planned drops wait until it's let go
+    pub held: bool,
+    // a scripted level is up, nothing gets planned or dropped
+    pub scripted: bool,
+}
+
+impl Spawner {
+    pub fn planned(&self) -> impl Iterator<Item = &PlannedDrop> {
+        self.planned.iter()
+    }
+
+    pub fn last_spawn_at(&self) -> Option<f32> {
+        self.planned.back().map(|drop| drop.spawn_at)
+    }
+
+    // queues a drop behind the planned ones and gives it its id, drops have to be
+    // pushed in spawn order
+    pub fn push(&mut self, mut drop: PlannedDrop) -> u64 {
+        drop.id = self.next_id;
+        self.next_id += 1;
+        self.planned.push_back(drop);
+        drop.id
+    }
+}
+
+fn clear_on_death(
+    mut commands: Commands,
+    mut died: EventReader<PlayerDied>,
+    mut spawner: ResMut<Spawner>,
+    rects: Query<Entity, With<FallingRect>>,
+) {
+    if died.read().count() == 0 {
+        return;
+    }
+    debug!(target: "spawner", "clearing {} planned drops", spawner.planned.len());
+    spawner.planned.clear();
+    for entity in &rects {
+        commands.entity(entity).despawn_recursive();
+    }
+}
+
+// the time based ramp, scaled by how the director thinks the player is doing
+fn ramp_difficulty(
+    mut difficulty: ResMut<Difficulty>,
+    stats: Res<RunStats>,
+    config: Res<DifficultyConfig>,
+    director: Res<Director>,
+) {
+    difficulty.level = (1.0 + stats.time_survived / config.ramp_secs) * director.intensity;
+}
+
+pub fn plan_drops(
+    mut spawner: ResMut<Spawner>,
+    difficulty: Res<Difficulty>,
+    config: Res<SpawnerConfig>,
+    time: Res<Time>,
+) {
+    if spawner.calm || spawner.held || spawner.scripted {
+        return;
+    }
+    let now = time.elapsed_secs();
+    let lead_time = difficulty.lead_time();
+    let mut rng = rand::thread_rng();
+    loop {
+        let spawn_at = match spawner.planned.back() {
+            Some(last) => last.spawn_at + difficulty.spawn_interval(),
+            None => now + lead_time,
+        };
+        if spawn_at > now + lead_time {
+            break;
+        }
+        let id = spawner.next_id;
+        spawner.next_id += 1;
+        let class = difficulty.pick_weight_class(rng.gen());
+        let shape = if rng.gen_bool(ODD_SHAPE_CHANCE as f64) {
+            PieceShape::ALL[rng.gen_range(1..PieceShape::ALL.len())]
+        } else {
+            PieceShape::Rect
+        };
+        trace!(target: "spawner", "planned {class:?} drop {id} at {spawn_at:.2}s");
+        spawner.planned.push_back(PlannedDrop {
+            id,
+            x: rng.gen_range(-SPAWN_HALF_WIDTH..SPAWN_HALF_WIDTH),
+            size: Vec2::new(
+                rng.gen_range(MIN_RECT_SIZE..MAX_RECT_SIZE),
+                rng.gen_range(MIN_RECT_SIZE..MAX_RECT_SIZE),
+            ),
+            class,
+            shape,
+            spin: rng.gen_range(-1.0..=1.0) * config.max_initial_spin,
+            bomb: rng.gen_bool(BOMB_CHANCE as f64),
+            boss: false,
+            warn_at: spawn_at - lead_time,
+            spawn_at,
+        });
+    }
+}
+
+pub fn spawn_planned_drops(
+    mut commands: Commands,
+    mut spawner: ResMut<Spawner>,
+    prefabs: Res<Prefabs>,
+    assets: Res<CommonAssets>,
+    config: Res<SpawnerConfig>,
+    camera: Single<(&Transform, &OrthographicProjection), With<Camera>>,
+    time: Res<Time>,
+    mut spawned: EventWriter<RectSpawned>,
+) {
+    if spawner.held || spawner.scripted {
+        let delta_secs = time.delta_secs();
+        for drop in &mut spawner.planned {
+            drop.warn_at += delta_secs;
+            drop.spawn_at += delta_secs;
+        }
+        return;
+    }
+    let (camera_transform, projection) = camera.into_inner();
+    let spawn_y = camera_transform.translation.y + projection.area.max.y + SPAWN_MARGIN_ABOVE_VIEW;
+    let now = time.elapsed_secs();
+    // a pattern can be due all at once, its plain rects are built up front and spawned together
+    let mut rects = Vec::new();
+    while spawner
+        .planned
+        .front()
+        .is_some_and(|drop| drop.spawn_at <= now)
+    {
+        let Some(drop) = spawner.planned.pop_front() else {
+            break;
+        };
+        let params = PrefabParams {
+            position: Vec2::new(drop.x, spawn_y + drop.size.y / 2.0),
+            size: Some(drop.size),
+            shape: drop.shape,
+            ..default()
+        };
+        let motion = (
+            AngularVelocity(drop.spin),
+            AngularDamping(config.angular_damping),
+        );
+        if drop.boss || drop.bomb {
+            let prefab = if drop.boss { "boss" } else { "bomb" };
+            if let Some(entity) = prefabs.spawn(prefab, &mut commands, &params) {
+                let mut entity = commands.entity(entity);
+                entity.insert(motion);
+                if !config.upright_rects {
+                    entity.remove::<LockedAxes>();
+                }
+            }
+        } else {
+            rects.push((
+                falling_rect_bundle(&assets, &params, drop.class, config.upright_rects),
+                motion,
+            ));
+        }
+        debug!(
+            target: "spawner",
+            "spawned drop {} ({:?} {:?} {} at x {:.0})",
+            drop.id, drop.class, drop.shape, drop.size, drop.x
+        );
+        spawned.send(RectSpawned { drop_id: drop.id });
+    }
+    if !rects.is_empty() {
+        commands.spawn_batch(rects);
+    }
+}
+
+// oldest first, so a big batch lands in the order it was dropped
+fn activate_pending_bodies(
+    mut commands: Commands,
+    pending: Query<(Entity, &PieceShape), With<PendingBody>>,
+) {
+    let mut pending: Vec<_> = pending.iter().collect();
+    pending.sort_unstable_by_key(|(entity, _)| *entity);
+    for (entity, shape) in pending.into_iter().take(MAX_NEW_BODIES_PER_TICK) {
+        commands
+            .entity(entity)
+            .remove::<PendingBody>()
+            .insert((RigidBody::Dynamic, shape.collider()));
+    }
+}
+
+// a load test for dev builds, it floods the scene with bodies
+#[cfg(any(debug_assertions, feature = "cheats"))]
+mod stress {
+    use bevy::prelude::*;
+    use rand::Rng;
+
+    use super::{
+        Difficulty, PieceShape, PlannedDrop, RunStats, Spawner, WeightClass, MAX_RECT_SIZE,
+        MIN_RECT_SIZE, SPAWN_HALF_WIDTH,
+    };
+    use crate::console::{Console, ConsoleAppExt, ConsoleCommand};
+
+    // how long the stress command keeps watching frame times after its drops start landing
+    const STRESS_WATCH_SECS: f32 = 5.0;
+    const DEFAULT_STRESS_COUNT: usize = 60;
+
+    pub fn build(app: &mut App) {
+        app.init_resource::<StressTest>()
+            .add_console_command("stress", "stress [count]")
+            .add_systems(Update, (run_stress_command, watch_stress_test).chain());
+    }
+
+    #[derive(Resource, Default)]
+    struct StressTest {
+        count: usize,
+        // when the watch started and stops, in game time
+        from: f32,
+        until: Option<f32>,
+        worst_frame_secs: f32,
+    }
+
+    // drops a whole batch on the same tick, then reports the worst frame while it lands
+    fn run_stress_command(
+        mut commands: EventReader<ConsoleCommand>,
+        mut console: ResMut<Console>,
+        mut spawner: ResMut<Spawner>,
+        mut stress: ResMut<StressTest>,
+        difficulty: Res<Difficulty>,
+        time: Res<Time>,
+        mut stats: ResMut<RunStats>,
+    ) {
+        for command in commands.read().filter(|command| command.name == "stress") {
+            let count = match command.args.first().map(|arg| arg.parse::<usize>()) {
+                None => DEFAULT_STRESS_COUNT,
+                Some(Ok(count)) if count > 0 => count,
+                Some(_) => {
+                    console.print("usage: stress [count]");
+                    continue;
+                }
+            };
+            let now = time.elapsed_secs();
+            let spawn_at = spawner
+                .last_spawn_at()
+                .unwrap_or(now)
+                .max(now + difficulty.lead_time());
+            let mut rng = rand::thread_rng();
+            for _ in 0..count {
+                let size = rng.gen_range(MIN_RECT_SIZE..MAX_RECT_SIZE);
+                spawner.push(PlannedDrop {
+                    id: 0,
+                    x: rng.gen_range(-SPAWN_HALF_WIDTH..SPAWN_HALF_WIDTH),
+                    size: Vec2::splat(size),
+                    class: WeightClass::Normal,
+                    shape: PieceShape::Rect,
+                    spin: 0.0,
+                    bomb: false,
+                    boss: false,
+                    warn_at: now,
+                    spawn_at,
+                });
+            }
+            stats.assisted = true;
+            *stress = StressTest {
+                count,
+                from: spawn_at,
+                until: Some(spawn_at + STRESS_WATCH_SECS),
+                worst_frame_secs: 0.0,
+            };
+            console.print(format!("dropping {count} rects in {:.1}s", spawn_at - now));
+        }
+    }
+
+    fn watch_stress_test(
+        mut stress: ResMut<StressTest>,
+        mut console: ResMut<Console>,
+        time: Res<Time>,
+        real_time: Res<Time<Real>>,
+    ) {
+        let Some(until) = stress.until else {
+            return;
+        };
+        let now = time.elapsed_secs();
+        if now < stress.from {
+            return;
+        }
+        stress.worst_frame_secs = stress.worst_frame_secs.max(real_time.delta_secs());
+        if now >= until {
+            console.print(format!(
+                "worst frame while {} rects landed: {:.1}ms",
+                stress.count,
+                stress.worst_frame_secs * 1000.0
+            ));
+            stress.until = None;
+        }
+    }
+}
+
+fn mark_settled(
+    mut commands: Commands,
+    rects: Query<Entity, (With<FallingRect>, Added<Sleeping>, Without<Settled>)>,
+    mut settled: EventWriter<RectSettled>,
+) {
+    for entity in &rects {
+        commands.entity(entity).insert(Settled);
+        settled.send(RectSettled);
+    }
+}
+
+fn limit_spin(
+    config: Res<SpawnerConfig>,
+    mut rects: Query<&mut AngularVelocity, (With<FallingRect>, Without<Sleeping>)>,
+) {
+    let max = config.max_angular_velocity;
+    for mut angular in &mut rects {
+        if angular.0.abs() > max {
+            angular.0 = angular.0.clamp(-max, max);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use bevy::time::TimeUpdateStrategy;
+
+    use super::*;
+    use crate::palette::{Palette, Theme};
+
+    #[test]
+    fn a_big_pattern_gets_its_bodies_a_few_per_tick() {
+        const DROPS: usize = 60;
+        let mut app = App::new();
+        app.add_plugins((MinimalPlugins, AssetPlugin::default()))
+            .init_resource::<Assets<Mesh>>()
+            .init_resource::<Assets<ColorMaterial>>()
+            .insert_resource(Palette::from_theme(Theme::Classic))
+            .init_resource::<CommonAssets>()
+            .init_resource::<Prefabs>()
+            .init_resource::<Spawner>()
+            .init_resource::<SpawnerConfig>()
+            .add_event::<RectSpawned>()
+            // one fixed tick per update
+            .insert_resource(TimeUpdateStrategy::ManualDuration(Duration::from_secs_f64(
+                1.0 / 64.0,
+            )))
+            .add_systems(Update, spawn_planned_drops)
+            .add_systems(FixedUpdate, activate_pending_bodies);
+        app.world_mut()
+            .spawn((Camera2d, OrthographicProjection::default_2d()));
+        let mut spawner = app.world_mut().resource_mut::<Spawner>();
+        for i in 0..DROPS {
+            spawner.push(PlannedDrop {
+                id: 0,
+                x: i as f32 * 10.0 - SPAWN_HALF_WIDTH,
+                size: Vec2::splat(MIN_RECT_SIZE),
+                class: WeightClass::Normal,
+                shape: PieceShape::Rect,
+                spin: 0.0,
+                bomb: false,
+                boss: false,
+                warn_at: 0.0,
+                spawn_at: 0.0,
+            });
+        }
+
+        let mut bodies = Vec::new();
+        for _ in 0..=DROPS / MAX_NEW_BODIES_PER_TICK {
+            app.update();
+            let world = app.world_mut();
+            let active = world.query::<&RigidBody>().iter(world).count();
+            let waiting = world.query::<&PendingBody>().iter(world).count();
+            assert_eq!(active + waiting, DROPS);
+            bodies.push(active);
+        }
+        // the whole pattern is spawned on the first frame, then a batch per tick follows it
+        let expected: Vec<_> = (0..=DROPS / MAX_NEW_BODIES_PER_TICK)
+            .map(|tick| tick * MAX_NEW_BODIES_PER_TICK)
+            .collect();
+        assert_eq!(bodies, expected);
+    }
+}
@@ -0,0 +1,138 @@
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::i18n::Localization;
+use crate::menu::{MenuAction, MenuPage, MenuRow, MenuRows, MenuSet};
+use crate::save::SaveData;
+use crate::score::RunStats;
+use crate::spawner::RectSettled;
+use crate::{GameSet, Player, PlayerDied, PlayerJumped, PlayerWallJumped};
+
+// play time is flushed into the save in chunks instead of every frame
+const PLAY_TIME_FLUSH_SECS: f32 = 10.0;
+
+pub struct StatsPlugin;
+
+impl Plugin for StatsPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            Update,
+            (
+                (
+                    count_jumps,
+                    count_deaths,
+                    count_dodged_rects,
+                    track_climb,
+                    record_best_height,
+                    track_play_time,
+                )
+                    .in_set(GameSet::Reactions),
+                stats_menu_rows.in_set(MenuSet::Rows),
+            ),
+        );
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Default)]
+#[serde(default)]
+pub struct LifetimeStats {
+    pub jumps: u32,
+    pub deaths: u32,
+    pub rects_dodged: u32,
+    pub distance_climbed: f32,
+    pub best_height: f32,
+    pub play_time_secs: f32,
+}
+
+fn count_jumps(
+    mut jumps: EventReader<PlayerJumped>,
+    mut wall_jumps: EventReader<PlayerWallJumped>,
+    mut save: ResMut<SaveData>,
+) {
+    let count = jumps.read().count() + wall_jumps.read().count();
+    if count > 0 {
+        save.stats.jumps += count as u32;
+    }
+}
+
+fn count_deaths(mut died: EventReader<PlayerDied>, mut save: ResMut<SaveData>) {
+    let count = died.read().count();
+    if count > 0 {
+        save.stats.deaths += count as u32;
+    }
+}
+
+fn count_dodged_rects(mut settled: EventReader<RectSettled>, mut save: ResMut<SaveData>) {
+    let count = settled.read().count();
+    if count > 0 {
+        save.stats.rects_dodged += count as u32;
+    }
+}
+
+fn track_climb(
+    player: Single<&Transform, With<Player>>,
+    mut died: EventReader<PlayerDied>,
+    mut save: ResMut<SaveData>,
+    mut last_y: Local<Option<f32>>,
+) {
+    let y = player.translation.y;
+    // a respawn teleports the player, don't count it as climbing
+    if died.read().count() > 0 {
+        *last_y = None;
+    }
+    if let Some(last) = *last_y {
+        if y > last {
+            save.stats.distance_climbed += y - last;
+        }
+    }
+    *last_y = Some(y);
+}
+
+// only touches the save when the record is actually beaten
+fn record_best_height(stats: Res<RunStats>, mut save: ResMut<SaveData>) {
+    if stats.max_height > save.stats.best_height {
+        save.stats.best_height = stats.max_height;
+    }
+}
+
+fn track_play_time(time: Res<Time<Real>>, mut save: ResMut<SaveData>, mut unflushed: Local<f32>) {
+    *unflushed += time.delta_secs();
+    if *unflushed >= PLAY_TIME_FLUSH_SECS {
+        save.stats.play_time_secs += *unflushed;
+        *unflushed = 0.0;
+    }
+}
+
+fn stats_menu_rows(
+    page: Res<MenuPage>,
+    save: Res<SaveData>,
+    localization: Res<Localization>,
+    mut rows: ResMut<MenuRows>,
+) {
+    if *page != MenuPage::Stats {
+        return;
+    }
+    let stats = &save.stats;
+    let minutes = (stats.play_time_secs / 60.0) as u32;
+    let distance = format!("{:.0}", stats.distance_climbed);
+    let best_height = format!("{:.0}", stats.best_height);
+    let play_minutes = format!("{:02}", minutes % 60);
+    for label in [
+        localization.format("stats.best_score", &[("value", &save.best_score)]),
+        localization.format("stats.jumps", &[("value", &stats.jumps)]),
+        localization.format("stats.deaths", &[("value", &stats.deaths)]),
+        localization.format("stats.rects_dodged", &[("value", &stats.rects_dodged)]),
+        localization.format("stats.distance_climbed", &[("value", &distance)]),
+        localization.format("stats.best_height", &[("value", &best_height)]),
+        localization.format(
+            "stats.play_time",
+            &[("hours", &(minutes / 60)), ("minutes", &play_minutes)],
+        ),
+    ] {
+        rows.push(MenuRow::new(label, MenuAction::None));
+    }
+    rows.push(MenuRow::new(
+        localization.get("menu.back"),
+        MenuAction::Back,
+    ));
+}
@@ -0,0 +1,158 @@
+use bevy::prelude::*;
+
+pub struct SteamPlugin;
+
+// does nothing unless the game is built with `--features steam`, so itch and web builds
+// never link against steamworks
+impl Plugin for SteamPlugin {
+    fn build(&self, _app: &mut App) {
+        #[cfg(feature = "steam")]
+        backend::build(_app);
+    }
+}
+
+#[cfg(feature = "steam")]
+mod backend {
+    use std::io::{Read, Write};
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use std::sync::Arc;
+
+    use bevy::prelude::*;
+    use steamworks::{Client, SingleClient, UserStatsReceived};
+
+    use crate::achievements::{AchievementUnlocked, ACHIEVEMENTS};
+    use crate::save::SaveData;
+    use crate::score::RunStats;
+    use crate::storage::{platform_storage, PersistentStorage, Storage};
+    use crate::GameSet;
+
+    const RICH_PRESENCE_INTERVAL_SECS: f32 = 5.0;
+
+    #[derive(Resource)]
+    struct Steam {
+        client: Client,
+        // achievements can only be set once steam has sent the current stats
+        stats_ready: Arc<AtomicBool>,
+    }
+
+    pub fn build(app: &mut App) {
+        // not running through steam is fine, the game just goes on without it
+        let (client, single) = match Client::init() {
+            Ok(clients) => clients,
+            Err(err) => {
+                warn!("steam is unavailable, running without it: {err}");
+                return;
+            }
+        };
+        let remote = client.remote_storage();
+        if remote.is_cloud_enabled_for_app() && remote.is_cloud_enabled_for_account() {
+            app.insert_resource(PersistentStorage(Arc::new(CloudStorage {
+                client: client.clone(),
+                local: platform_storage(),
+            })));
+        }
+        let stats_ready = Arc::new(AtomicBool::new(false));
+        let ready = stats_ready.clone();
+        // the handle unregisters the callback when dropped, so it's leaked on purpose
+        std::mem::forget(client.register_callback(move |_: UserStatsReceived| {
+            ready.store(true, Ordering::Relaxed);
+        }));
+        client.user_stats().request_current_stats();
+        app.insert_resource(Steam {
+            client,
+            stats_ready,
+        })
+        .insert_non_send_resource(single)
+        .add_systems(PreUpdate, run_callbacks)
+        .add_systems(
+            Update,
+            (push_achievements, update_rich_presence).in_set(GameSet::Reactions),
+        );
+    }
+
+    fn run_callbacks(single: NonSend<SingleClient>) {
+        single.run_callbacks();
+    }
+
+    // also pushes everything unlocked before steam was around, once the stats arrive
+    fn push_achievements(
+        steam: Res<Steam>,
+        save: Res<SaveData>,
+        mut unlocked: EventReader<AchievementUnlocked>,
+        mut synced: Local<bool>,
+    ) {
+        if !steam.stats_ready.load(Ordering::Relaxed) {
+            return;
+        }
+        let stats = steam.client.user_stats();
+        let mut changed = false;
+        if !*synced {
+            *synced = true;
+            for achievement in ACHIEVEMENTS {
+                let done = save.achievements.iter().any(|id| id == achievement.id);
+                let helper = stats.achievement(achievement.id);
+                if done && helper.get() == Ok(false) {
+                    changed |= helper.set().is_ok();
+                }
+            }
+        }
+        for AchievementUnlocked(id) in unlocked.read() {
+            changed |= stats.achievement(id).set().is_ok();
+        }
+        if changed && stats.store_stats().is_err() {
+            warn!("failed to store steam achievements");
+        }
+    }
+
+    // the #Climbing token lives in the rich presence localization file on steamworks:
+    // "#Climbing" "Climbing, {%height%} high with {%score%} points"
+    fn update_rich_presence(
+        steam: Res<Steam>,
+        stats: Res<RunStats>,
+        time: Res<Time<Real>>,
+        mut last_update: Local<Option<f32>>,
+    ) {
+        let now = time.elapsed_secs();
+        if last_update.is_some_and(|last| now - last < RICH_PRESENCE_INTERVAL_SECS) {
+            return;
+        }
+        *last_update = Some(now);
+        let friends = steam.client.friends();
+        friends.set_rich_presence("height", Some(&format!("{:.0}", stats.max_height)));
+        friends.set_rich_presence("score", Some(&stats.score.to_string()));
+        friends.set_rich_presence("steam_display", Some("#Climbing"));
+    }
+
+    // keeps a local copy too, so the game still has its data if the cloud is switched
+    // off later or the write to steam fails
+    struct CloudStorage {
+        client: Client,
+        local: Arc<dyn Storage>,
+    }
+
+    fn file_name(key: &str) -> String {
+        format!("{key}.ron")
+    }
+
+    impl Storage for CloudStorage {
+        fn read(&self, key: &str) -> Option<String> {
+            let file = self.client.remote_storage().file(&file_name(key));
+            if !file.exists() {
+                return self.local.read(key);
+            }
+            let mut contents = String::new();
+            match file.read().read_to_string(&mut contents) {
+                Ok(_) => Some(contents),
+                Err(_) => self.local.read(key),
+            }
+        }
+
+        fn write(&self, key: &str, contents: &str) -> Result<(), String> {
+            self.local.write(key, contents)?;
+            let mut writer = self.client.remote_storage().file(&file_name(key)).write();
+            writer
+                .write_all(contents.as_bytes())
+                .map_err(|err| err.to_string())
+        }
+    }
+}
@@ -0,0 +1,157 @@
+use bevy::prelude::*;
+
+use crate::input::shape_stick;
+use crate::menu::{GameState, MenuAction, MenuPage, MenuRows, MenuSelection, MenuSet};
+use crate::palette::Palette;
+use crate::settings::Settings;
+use crate::ui_layout::UiRoot;
+
+const PREVIEW_SIZE: f32 = 160.0;
+const DOT_SIZE: f32 = 10.0;
+
+pub struct StickPreviewPlugin;
+
+impl Plugin for StickPreviewPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Startup, spawn_stick_preview)
+            .add_systems(
+                Update,
+                render_stick_preview
+                    .after(MenuSet::Input)
+                    .before(MenuSet::Render)
+                    .run_if(in_state(GameState::Paused)),
+            )
+            .add_systems(OnExit(GameState::Paused), hide_stick_preview);
+    }
+}
+
+// next to the settings while one of the stick rows is selected: the deadzone, where the
+// stick really is, and what the game makes of it
+#[derive(Component)]
+struct StickPreview;
+
+#[derive(Component)]
+struct DeadzoneBox;
+
+#[derive(Component)]
+struct RawDot;
+
+#[derive(Component)]
+struct ShapedDot;
+
+fn spawn_stick_preview(
+    mut commands: Commands,
+    palette: Res<Palette>,
+    root: Single<Entity, With<UiRoot>>,
+) {
+    let dot = |color: Color| {
+        (
+            Node {
+                position_type: PositionType::Absolute,
+                width: Val::Px(DOT_SIZE),
+                height: Val::Px(DOT_SIZE),
+                ..default()
+            },
+            BorderRadius::MAX,
+            BackgroundColor(color),
+        )
+    };
+    commands
+        .spawn((
+            Name::new("StickPreview"),
+            StickPreview,
+            Node {
+                position_type: PositionType::Absolute,
+                right: Val::Px(48.0),
+                top: Val::Percent(50.0),
+                margin: UiRect::top(Val::Px(-PREVIEW_SIZE / 2.0)),
+                width: Val::Px(PREVIEW_SIZE),
+                height: Val::Px(PREVIEW_SIZE),
+                border: UiRect::all(Val::Px(2.0)),
+                ..default()
+            },
+            BorderColor(palette.ui_text.with_alpha(0.6)),
+            BackgroundColor(Color::BLACK.with_alpha(0.4)),
+            GlobalZIndex(11),
+            Visibility::Hidden,
+        ))
+        .set_parent(*root)
+        .with_children(|parent| {
+            parent.spawn((
+                DeadzoneBox,
+                Node {
+                    position_type: PositionType::Absolute,
+                    ..default()
+                },
+                BackgroundColor(palette.ui_text.with_alpha(0.15)),
+            ));
+            parent.spawn((RawDot, dot(palette.ui_text.with_alpha(0.5))));
+            parent.spawn((ShapedDot, dot(palette.ui_accent)));
+        });
+}
+
+// -1..=1 on both axes to the inside of the box, ui y going down
+fn preview_point(stick: Vec2) -> Vec2 {
+    let inner = PREVIEW_SIZE - 4.0;
+    Vec2::new(stick.x + 1.0, 1.0 - stick.y) / 2.0 * inner
+}
+
+fn render_stick_preview(
+    page: Res<MenuPage>,
+    rows: Res<MenuRows>,
+    selection: Res<MenuSelection>,
+    settings: Res<Settings>,
+    gamepads: Query<&Gamepad>,
+    mut preview: Single<&mut Visibility, With<StickPreview>>,
+    mut deadzone: Single<&mut Node, With<DeadzoneBox>>,
+    mut raw_dot: Single<&mut Node, (With<RawDot>, Without<DeadzoneBox>)>,
+    mut shaped_dot: Single<&mut Node, (With<ShapedDot>, Without<DeadzoneBox>, Without<RawDot>)>,
+) {
+    let selected = rows.get(selection.0).map(|row| row.action);
+    let shown = *page == MenuPage::Settings
+        && matches!(
+            selected,
+            Some(
+                MenuAction::CycleStickDeadzoneX
+                    | MenuAction::CycleStickDeadzoneY
+                    | MenuAction::CycleStickCurve
+                    | MenuAction::CycleStickSensitivity
+            )
+        );
+    let visibility = if shown {
+        Visibility::Inherited
+    } else {
+        Visibility::Hidden
+    };
+    if **preview != visibility {
+        **preview = visibility;
+    }
+    if !shown {
+        return;
+    }
+    let zone = Vec2::new(
+        settings.stick_deadzone_x_percent as f32,
+        settings.stick_deadzone_y_percent as f32,
+    ) / 100.0;
+    let min = preview_point(Vec2::new(-zone.x, zone.y));
+    let max = preview_point(Vec2::new(zone.x, -zone.y));
+    deadzone.left = Val::Px(min.x);
+    deadzone.top = Val::Px(min.y);
+    deadzone.width = Val::Px(max.x - min.x);
+    deadzone.height = Val::Px(max.y - min.y);
+    let raw = gamepads
+        .iter()
+        .map(Gamepad::left_stick)
+        .max_by(|a, b| a.length_squared().total_cmp(&b.length_squared()))
+        .unwrap_or_default();
+    let raw_point = preview_point(raw.clamp(Vec2::NEG_ONE, Vec2::ONE)) - DOT_SIZE / 2.0;
+    raw_dot.left = Val::Px(raw_point.x);
+    raw_dot.top = Val::Px(raw_point.y);
+    let shaped_point = preview_point(shape_stick(raw, &settings)) - DOT_SIZE / 2.0;
+    shaped_dot.left = Val::Px(shaped_point.x);
+    shaped_dot.top = Val::Px(shaped_point.y);
+}
+
+fn hide_stick_preview(mut preview: Single<&mut Visibility, With<StickPreview>>) {
+    **preview = Visibility::Hidden;
+}
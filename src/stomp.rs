@@ -0,0 +1,122 @@
+use avian2d::prelude::*;
+use bevy::prelude::*;
+
+use crate::collision_layer::GameLayer;
+use crate::input::{Action, ActionState};
+use crate::shake::ScreenShake;
+use crate::spawner::WeightClass;
+use crate::{step_up_ledges, GameSet, Player, PlayerDied, PlayerJumped, PlayerLanded};
+
+const STOMP_SPEED: f32 = 1000.0;
+const STOMP_RADIUS: f32 = 180.0;
+const STOMP_IMPULSE: f32 = 3_000_000.0;
+const STOMP_TRAUMA: f32 = 0.2;
+// jumping this soon after a stomp lands springs the player higher
+const REBOUND_WINDOW_SECS: f32 = 0.3;
+const REBOUND_BOOST: f32 = 350.0;
+
+pub struct StompPlugin;
+
+impl Plugin for StompPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Stomp>()
+            .add_systems(
+                FixedPreUpdate,
+                slam.in_set(GameSet::Movement).after(step_up_ledges),
+            )
+            .add_systems(
+                Update,
+                (land_stomp, rebound_jump)
+                    .chain()
+                    .in_set(GameSet::Reactions),
+            );
+    }
+}
+
+#[derive(Resource, Default)]
+struct Stomp {
+    slamming: bool,
+    rebound: f32,
+}
+
+// down in midair drops the player straight down until it lands on something
+fn slam(
+    actions: Res<ActionState>,
+    mut stomp: ResMut<Stomp>,
+    player: Single<(&Player, &mut LinearVelocity)>,
+) {
+    let (player, mut linear) = player.into_inner();
+    let airborne = player.ground_normal.is_none()
+        && player.steep_normal.is_none()
+        && !player.can_jump
+        && !player.climbing
+        && !player.is_attached_to_wall;
+    if !airborne {
+        return;
+    }
+    if !stomp.slamming && actions.pressed(Action::Down) {
+        debug!(target: "player", "stomping");
+        stomp.slamming = true;
+    }
+    if stomp.slamming {
+        linear.0 = Vec2::new(0.0, -STOMP_SPEED);
+    }
+}
+
+// light rects around the landing get knocked away, steel doesn't budge
+fn land_stomp(
+    mut commands: Commands,
+    mut landed: EventReader<PlayerLanded>,
+    mut died: EventReader<PlayerDied>,
+    mut stomp: ResMut<Stomp>,
+    mut shake: ResMut<ScreenShake>,
+    spatial_query: SpatialQuery,
+    player: Single<&Transform, With<Player>>,
+    rects: Query<(&Transform, &RigidBody, &WeightClass)>,
+) {
+    if died.read().count() > 0 {
+        *stomp = Stomp::default();
+    }
+    if landed.read().count() == 0 || !stomp.slamming {
+        return;
+    }
+    stomp.slamming = false;
+    stomp.rebound = REBOUND_WINDOW_SECS;
+    shake.add(STOMP_TRAUMA);
+    let center = player.translation.truncate();
+    let caught = spatial_query.shape_intersections(
+        &Collider::circle(STOMP_RADIUS),
+        center,
+        0.0,
+        &SpatialQueryFilter::from_mask(GameLayer::FallingRect),
+    );
+    for entity in caught {
+        let Ok((transform, body, class)) = rects.get(entity) else {
+            continue;
+        };
+        if !body.is_dynamic() || *class == WeightClass::Steel {
+            continue;
+        }
+        let offset = transform.translation.truncate() - center;
+        let falloff = (1.0 - offset.length() / STOMP_RADIUS).clamp(0.0, 1.0);
+        // always a bit upwards, so rects beside the player pop out instead of sliding
+        let direction = (offset.normalize_or(Vec2::Y) + Vec2::Y).normalize();
+        commands
+            .entity(entity)
+            .insert(ExternalImpulse::new(direction * STOMP_IMPULSE * falloff));
+    }
+}
+
+fn rebound_jump(
+    mut jumped: EventReader<PlayerJumped>,
+    mut stomp: ResMut<Stomp>,
+    mut linear: Single<&mut LinearVelocity, With<Player>>,
+    time: Res<Time>,
+) {
+    if jumped.read().count() > 0 && stomp.rebound > 0.0 {
+        debug!(target: "player", "rebound jump");
+        linear.y += REBOUND_BOOST;
+        stomp.rebound = 0.0;
+    }
+    stomp.rebound = (stomp.rebound - time.delta_secs()).max(0.0);
+}
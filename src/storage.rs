@@ -0,0 +1,403 @@
+use std::sync::Arc;
+
+use bevy::prelude::*;
+use bevy::tasks::IoTaskPool;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+
+const WRITE_INTERVAL_SECS: f32 = 2.0;
+// the previous good copy of every key, read back when the main one doesn't parse
+const BACKUP_SUFFIX: &str = ".bak";
+
+pub struct StoragePlugin;
+
+impl Plugin for StoragePlugin {
+    fn build(&self, app: &mut App) {
+        // a platform plugin may have put its own storage in place already
+        if !app.world().contains_resource::<PersistentStorage>() {
+            app.insert_resource(PersistentStorage(platform_storage()));
+        }
+    }
+}
+
+// a small key value store for everything that should outlive the process
+pub trait Storage: Send + Sync + 'static {
+    fn read(&self, key: &str) -> Option<String>;
+    fn write(&self, key: &str, contents: &str) -> Result<(), String>;
+}
+
+#[derive(Resource, Clone)]
+pub struct PersistentStorage(pub Arc<dyn Storage>);
+
+// a resource that's loaded from storage on startup and written back whenever it changes.
+// it's stored as `(version: VERSION, data: ...)`, bump VERSION whenever the data changes
+// in a way `#[serde(default)]` can't absorb and teach `migrate` about the old shape
+pub trait Persistent: Resource + Serialize + DeserializeOwned + Default {
+    const KEY: &'static str;
+    const VERSION: u32;
+
+    // loads a file written with an older `version`. version 0 is from before the
+    // envelope existed and holds the bare data, fields added since get their defaults
+    fn migrate(version: u32, contents: &str) -> ron::error::SpannedResult<Self> {
+        if version == 0 {
+            ron::from_str(contents)
+        } else {
+            ron::from_str::<Envelope<Self>>(contents).map(|envelope| envelope.data)
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+struct Envelope<T> {
+    version: u32,
+    data: T,
+}
+
+// only the version, everything else in the file is skipped
+#[derive(Deserialize)]
+struct Header {
+    #[serde(default)]
+    version: u32,
+}
+
+pub trait StorageAppExt {
+    fn add_persistent_resource<T: Persistent>(&mut self) -> &mut Self;
+}
+
+impl StorageAppExt for App {
+    fn add_persistent_resource<T: Persistent>(&mut self) -> &mut Self {
+        let storage = self.world().resource::<PersistentStorage>().clone();
+        let value: T = load(&storage);
+        self.insert_resource(value)
+            .add_systems(Last, persist_resource::<T>)
+    }
+}
+
+fn encode<T: Persistent>(value: &T) -> ron::Result<String> {
+    let envelope = Envelope {
+        version: T::VERSION,
+        data: value,
+    };
+    ron::ser::to_string_pretty(&envelope, ron::ser::PrettyConfig::default())
+}
+
+fn decode<T: Persistent>(contents: &str) -> ron::error::SpannedResult<T> {
+    let Header { version } = ron::from_str(contents)?;
+    if version < T::VERSION {
+        info!("migrating {} from version {version}", T::KEY);
+        return T::migrate(version, contents);
+    }
+    if version > T::VERSION {
+        warn!("{} was written by a newer version of the game", T::KEY);
+    }
+    ron::from_str::<Envelope<T>>(contents).map(|envelope| envelope.data)
+}
+
+// keeps every top level field that still parses on its own, for files that were cut
+// short or edited by hand. the rest of the fields get their defaults
+fn recover<T: Persistent>(contents: &str) -> Option<T> {
+    let mut fields = top_level_fields(contents);
+    if let Some(data) = fields.iter().find_map(|field| field.strip_prefix("data:")) {
+        fields = top_level_fields(data);
+    }
+    let kept: Vec<&str> = fields
+        .into_iter()
+        .filter(|field| ron::from_str::<T>(&format!("({field})")).is_ok())
+        .collect();
+    if kept.is_empty() {
+        return None;
+    }
+    ron::from_str(&format!("({})", kept.join(","))).ok()
+}
+
+// splits the first parenthesized struct into its `name: value` fields, tolerating a
+// missing closing paren
+fn top_level_fields(text: &str) -> Vec<&str> {
+    let Some(open) = text.find('(') else {
+        return Vec::new();
+    };
+    let mut fields = Vec::new();
+    let mut depth = 0;
+    let mut in_string = false;
+    let mut escaped = false;
+    let mut start = open + 1;
+    for (index, c) in text.char_indices().skip_while(|(index, _)| *index <= open) {
+        if in_string {
+            match c {
+                _ if escaped => escaped = false,
+                '\\' => escaped = true,
+                '"' => in_string = false,
+                _ => {}
+            }
+            continue;
+        }
+        match c {
+            '"' => in_string = true,
+            '(' | '[' | '{' => depth += 1,
+            ')' | ']' | '}' if depth > 0 => depth -= 1,
+            ')' => {
+                fields.push(&text[start..index]);
+                return clean_fields(fields);
+            }
+            ',' if depth == 0 => {
+                fields.push(&text[start..index]);
+                start = index + 1;
+            }
+            _ => {}
+        }
+    }
+    // cut short, the last field is most likely incomplete but parsing will tell
+    fields.push(&text[start..]);
+    clean_fields(fields)
+}
+
+fn clean_fields(fields: Vec<&str>) -> Vec<&str> {
+    fields
+        .into_iter()
+        .map(str::trim)
+        .filter(|field| !field.is_empty())
+        .collect()
+}
+
+// falls back to the backup, then to whatever survives in the main copy, then to the
+// default. a broken file never stops the game
+pub fn load<T: Persistent>(storage: &PersistentStorage) -> T {
+    let backup = format!("{}{BACKUP_SUFFIX}", T::KEY);
+    for key in [T::KEY, backup.as_str()] {
+        let Some(contents) = storage.0.read(key) else {
+            continue;
+        };
+        match decode(&contents) {
+            Ok(value) => return value,
+            Err(err) => warn!("ignoring unreadable {key}: {err}"),
+        }
+    }
+    if let Some(value) = storage
+        .0
+        .read(T::KEY)
+        .and_then(|contents| recover(&contents))
+    {
+        warn!("recovered what was readable from {}", T::KEY);
+        return value;
+    }
+    T::default()
+}
+
+// writes happen off the main thread, except on exit where the process would not wait
+pub fn store<T: Persistent>(storage: &PersistentStorage, value: &T, blocking: bool) {
+    let contents = match encode(value) {
+        Ok(contents) => contents,
+        Err(err) => {
+            warn!("failed to serialize {}: {err}", T::KEY);
+            return;
+        }
+    };
+    let storage = storage.0.clone();
+    let write = move || {
+        if let Err(err) = storage.write(T::KEY, &contents) {
+            warn!("failed to write {}: {err}", T::KEY);
+        }
+    };
+    if blocking || cfg!(target_arch = "wasm32") {
+        write();
+    } else {
+        IoTaskPool::get().spawn(async move { write() }).detach();
+    }
+}
+
+// batches writes so a score ticking up every frame doesn't hit the disk every frame
+fn persist_resource<T: Persistent>(
+    value: Res<T>,
+    storage: Res<PersistentStorage>,
+    time: Res<Time<Real>>,
+    mut exit: EventReader<AppExit>,
+    mut dirty: Local<bool>,
+    mut last_write: Local<f32>,
+) {
+    if value.is_changed() && !value.is_added() {
+        *dirty = true;
+    }
+    let now = time.elapsed_secs();
+    let exiting = exit.read().count() > 0;
+    if *dirty && (exiting || now - *last_write > WRITE_INTERVAL_SECS) {
+        store(&storage, &*value, exiting);
+        *dirty = false;
+        *last_write = now;
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+pub fn platform_storage() -> Arc<dyn Storage> {
+    Arc::new(native::FileStorage::new())
+}
+
+#[cfg(target_arch = "wasm32")]
+pub fn platform_storage() -> Arc<dyn Storage> {
+    Arc::new(web::LocalStorage)
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+mod native {
+    use std::fs;
+    use std::path::PathBuf;
+    use std::sync::Mutex;
+
+    use bevy::log::warn;
+    use directories::ProjectDirs;
+
+    use super::{Storage, BACKUP_SUFFIX};
+
+    // one ron file per key in the platform's config directory
+    pub struct FileStorage {
+        dir: PathBuf,
+        // writes for the same key could otherwise race on the temporary file
+        writing: Mutex<()>,
+    }
+
+    impl FileStorage {
+        pub fn new() -> FileStorage {
+            let dir = match ProjectDirs::from("", "", "rectfall") {
+                Some(dirs) => dirs.config_dir().to_path_buf(),
+                None => {
+                    warn!("no config directory on this platform, storing next to the game");
+                    PathBuf::from(".")
+                }
+            };
+            FileStorage {
+                dir,
+                writing: Mutex::new(()),
+            }
+        }
+
+        fn path(&self, key: &str) -> PathBuf {
+            self.dir.join(format!("{key}.ron"))
+        }
+    }
+
+    impl Storage for FileStorage {
+        fn read(&self, key: &str) -> Option<String> {
+            fs::read_to_string(self.path(key)).ok()
+        }
+
+        // the new contents go to a temporary file that's renamed over the old one, so a
+        // crash mid-write leaves either the old or the new file and never half of one
+        fn write(&self, key: &str, contents: &str) -> Result<(), String> {
+            let _guard = self.writing.lock().map_err(|err| err.to_string())?;
+            fs::create_dir_all(&self.dir).map_err(|err| err.to_string())?;
+            let path = self.path(key);
+            let temporary = self.path(&format!("{key}.tmp"));
+            fs::write(&temporary, contents).map_err(|err| err.to_string())?;
+            if path.exists() {
+                let backup = self.path(&format!("{key}{BACKUP_SUFFIX}"));
+                fs::rename(&path, backup).map_err(|err| err.to_string())?;
+            }
+            fs::rename(&temporary, &path).map_err(|err| err.to_string())
+        }
+    }
+}
+
+#[cfg(target_arch = "wasm32")]
+mod web {
+    use super::{Storage, BACKUP_SUFFIX};
+
+    // browsers keep localStorage per origin, the prefix keeps us clear of other pages
+    pub struct LocalStorage;
+
+    fn local_storage() -> Option<web_sys::Storage> {
+        web_sys::window()?.local_storage().ok().flatten()
+    }
+
+    fn item_key(key: &str) -> String {
+        format!("rectfall.{key}")
+    }
+
+    impl Storage for LocalStorage {
+        fn read(&self, key: &str) -> Option<String> {
+            local_storage()?.get_item(&item_key(key)).ok().flatten()
+        }
+
+        fn write(&self, key: &str, contents: &str) -> Result<(), String> {
+            let storage = local_storage().ok_or("localStorage is unavailable")?;
+            if let Ok(Some(previous)) = storage.get_item(&item_key(key)) {
+                let _ = storage.set_item(&item_key(&format!("{key}{BACKUP_SUFFIX}")), &previous);
+            }
+            storage
+                .set_item(&item_key(key), contents)
+                .map_err(|err| format!("{err:?}"))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::save::SaveData;
+    use crate::settings::Settings;
+
+    fn round_trip<T: Persistent>(value: &T) {
+        let encoded = encode(value).unwrap();
+        let decoded: T = decode(&encoded).unwrap();
+        assert_eq!(encode(&decoded).unwrap(), encoded);
+    }
+
+    fn sample_save() -> SaveData {
+        SaveData {
+            best_score: 1234,
+            selected_skin: "outline".to_string(),
+            achievements: vec!["first_jump".to_string(), "dodger, \"pro\"".to_string()],
+            ..default()
+        }
+    }
+
+    #[test]
+    fn save_round_trips() {
+        round_trip(&SaveData::default());
+        round_trip(&sample_save());
+    }
+
+    #[test]
+    fn settings_round_trip() {
+        round_trip(&Settings::default());
+        round_trip(&Settings {
+            language: "pt-BR".to_string(),
+            extra_health: 2,
+            reduce_flashing: true,
+            ..default()
+        });
+    }
+
+    #[test]
+    fn loads_saves_from_before_the_envelope() {
+        let save = sample_save();
+        let bare = ron::ser::to_string_pretty(&save, ron::ser::PrettyConfig::default()).unwrap();
+        let loaded: SaveData = decode(&bare).unwrap();
+        assert_eq!(loaded.best_score, 1234);
+        assert_eq!(loaded.achievements, save.achievements);
+    }
+
+    #[test]
+    fn recovers_fields_from_a_truncated_file() {
+        let encoded = encode(&sample_save()).unwrap();
+        let cut = &encoded[..encoded.find("achievements").unwrap() + 20];
+        assert!(decode::<SaveData>(cut).is_err());
+        let recovered: SaveData = recover(cut).unwrap();
+        assert_eq!(recovered.best_score, 1234);
+        assert_eq!(recovered.selected_skin, "outline");
+        assert!(recovered.achievements.is_empty());
+    }
+
+    #[test]
+    fn recovers_around_a_bad_field() {
+        let encoded = encode(&sample_save()).unwrap();
+        let broken = encoded.replace("best_score: 1234", "best_score: \"lots\"");
+        assert!(decode::<SaveData>(&broken).is_err());
+        let recovered: SaveData = recover(&broken).unwrap();
+        assert_eq!(recovered.best_score, 0);
+        assert_eq!(recovered.achievements.len(), 2);
+    }
+
+    #[test]
+    fn garbage_recovers_nothing() {
+        assert!(recover::<SaveData>("not a save file").is_none());
+    }
+}
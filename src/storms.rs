@@ -0,0 +1,191 @@
+use avian2d::prelude::*;
+use bevy::prelude::*;
+use rand::Rng;
+
+use crate::hud::ShowBanner;
+use crate::i18n::Localization;
+use crate::shake::ScreenShake;
+use crate::spawner::{Difficulty, FallingRect};
+use crate::{GameSet, Player, PlayerDied};
+
+// time between storms at difficulty 1, it shrinks as the level ramps up
+const BASE_STORM_INTERVAL_SECS: f32 = 45.0;
+const MIN_STORM_INTERVAL_SECS: f32 = 20.0;
+const WIND_DURATION_SECS: f32 = 4.0;
+const WIND_ACCELERATION: f32 = 400.0;
+const EARTHQUAKE_DURATION_SECS: f32 = 2.5;
+const EARTHQUAKE_FREQUENCY: f32 = 12.0;
+const EARTHQUAKE_ACCELERATION: f32 = 600.0;
+const EARTHQUAKE_TRAUMA_PER_SEC: f32 = 2.0;
+const RAIN_DURATION_SECS: f32 = 5.0;
+const RAIN_SPAWN_RATE: f32 = 4.0;
+
+pub struct StormsPlugin;
+
+impl Plugin for StormsPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<StormScheduler>()
+            .add_event::<StormStarted>()
+            .add_systems(
+                Update,
+                (schedule_storms, announce_storms)
+                    .chain()
+                    .in_set(GameSet::Reactions),
+            )
+            .add_systems(
+                FixedPreUpdate,
+                (blow_wind, shake_pile).in_set(GameSet::Movement),
+            );
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum StormKind {
+    Wind { direction: i8 },
+    Earthquake,
+    RectRain,
+}
+
+impl StormKind {
+    fn duration_secs(self) -> f32 {
+        match self {
+            StormKind::Wind { .. } => WIND_DURATION_SECS,
+            StormKind::Earthquake => EARTHQUAKE_DURATION_SECS,
+            StormKind::RectRain => RAIN_DURATION_SECS,
+        }
+    }
+
+    fn banner_key(self) -> &'static str {
+        match self {
+            StormKind::Wind { direction } if direction < 0 => "storm.wind_left",
+            StormKind::Wind { .. } => "storm.wind_right",
+            StormKind::Earthquake => "storm.earthquake",
+            StormKind::RectRain => "storm.rect_rain",
+        }
+    }
+}
+
+#[derive(Event)]
+pub struct StormStarted {
+    pub kind: StormKind,
+}
+
+// part of the difficulty director: picks the next storm and how long until it hits
+#[derive(Resource)]
+struct StormScheduler {
+    until_next: f32,
+    active: Option<(StormKind, f32)>,
+}
+
+impl Default for StormScheduler {
+    fn default() -> StormScheduler {
+        StormScheduler {
+            until_next: BASE_STORM_INTERVAL_SECS,
+            active: None,
+        }
+    }
+}
+
+impl StormScheduler {
+    fn active(&self) -> Option<StormKind> {
+        self.active.map(|(kind, _)| kind)
+    }
+}
+
+fn storm_interval(difficulty: &Difficulty) -> f32 {
+    (BASE_STORM_INTERVAL_SECS / difficulty.level).max(MIN_STORM_INTERVAL_SECS)
+}
+
+fn schedule_storms(
+    mut scheduler: ResMut<StormScheduler>,
+    mut difficulty: ResMut<Difficulty>,
+    mut died: EventReader<PlayerDied>,
+    mut started: EventWriter<StormStarted>,
+    time: Res<Time>,
+) {
+    if died.read().count() > 0 {
+        *scheduler = StormScheduler::default();
+    }
+    let delta_secs = time.delta_secs();
+    if let Some((kind, remaining)) = &mut scheduler.active {
+        *remaining -= delta_secs;
+        if *remaining <= 0.0 {
+            debug!(target: "spawner", "{kind:?} storm over");
+            scheduler.active = None;
+        }
+    } else {
+        scheduler.until_next -= delta_secs;
+        if scheduler.until_next <= 0.0 {
+            let mut rng = rand::thread_rng();
+            let kind = match rng.gen_range(0..3) {
+                0 => StormKind::Wind {
+                    direction: if rng.gen_bool(0.5) { 1 } else { -1 },
+                },
+                1 => StormKind::Earthquake,
+                _ => StormKind::RectRain,
+            };
+            debug!(target: "spawner", "{kind:?} storm started");
+            scheduler.active = Some((kind, kind.duration_secs()));
+            scheduler.until_next = storm_interval(&difficulty);
+            started.send(StormStarted { kind });
+        }
+    }
+    let spawn_rate = if scheduler.active() == Some(StormKind::RectRain) {
+        RAIN_SPAWN_RATE
+    } else {
+        1.0
+    };
+    if difficulty.spawn_rate != spawn_rate {
+        difficulty.spawn_rate = spawn_rate;
+    }
+}
+
+fn announce_storms(
+    mut started: EventReader<StormStarted>,
+    mut banners: EventWriter<ShowBanner>,
+    localization: Res<Localization>,
+) {
+    for StormStarted { kind } in started.read() {
+        banners.send(ShowBanner(localization.get(kind.banner_key()).to_string()));
+    }
+}
+
+// only pushes things that are in the air, the pile and a grounded player stay put
+fn blow_wind(
+    scheduler: Res<StormScheduler>,
+    mut rects: Query<&mut LinearVelocity, (With<FallingRect>, Without<Sleeping>)>,
+    player: Single<(&Player, &mut LinearVelocity), Without<FallingRect>>,
+    time: Res<Time>,
+) {
+    let Some(StormKind::Wind { direction }) = scheduler.active() else {
+        return;
+    };
+    let push = direction as f32 * WIND_ACCELERATION * time.delta_secs();
+    for mut linear in &mut rects {
+        linear.x += push;
+    }
+    let (player, mut linear) = player.into_inner();
+    if player.ground_normal.is_none() {
+        linear.x += push;
+    }
+}
+
+fn shake_pile(
+    mut commands: Commands,
+    scheduler: Res<StormScheduler>,
+    mut shake: ResMut<ScreenShake>,
+    mut rects: Query<(Entity, &mut LinearVelocity, Has<Sleeping>), With<FallingRect>>,
+    time: Res<Time>,
+) {
+    if scheduler.active() != Some(StormKind::Earthquake) {
+        return;
+    }
+    shake.add(EARTHQUAKE_TRAUMA_PER_SEC * time.delta_secs());
+    let sway = (time.elapsed_secs() * EARTHQUAKE_FREQUENCY).sin();
+    for (entity, mut linear, sleeping) in &mut rects {
+        if sleeping {
+            commands.entity(entity).remove::<Sleeping>();
+        }
+        linear.x += sway * EARTHQUAKE_ACCELERATION * time.delta_secs();
+    }
+}
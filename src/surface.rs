@@ -0,0 +1,23 @@
+use bevy::prelude::*;
+
+// what a surface does to a jump taken off it. anything without one is solid. when the
+// player stands on more than one, the springiest wins
+#[derive(Component, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Debug, Default)]
+pub enum SurfaceMaterial {
+    // foam gives under the player's feet
+    Soft,
+    #[default]
+    Solid,
+    Spring,
+}
+
+impl SurfaceMaterial {
+    // scales how fast a held jump keeps pushing the player up
+    pub fn jump_factor(self) -> f32 {
+        match self {
+            SurfaceMaterial::Soft => 0.8,
+            SurfaceMaterial::Solid => 1.0,
+            SurfaceMaterial::Spring => 1.6,
+        }
+    }
+}
@@ -0,0 +1,152 @@
+use avian2d::prelude::*;
+use bevy::prelude::*;
+use bevy::utils::HashSet;
+
+use crate::collision_layer::solid_mask;
+use crate::common_assets::CommonAssets;
+use crate::palette::{PaletteRole, Themed};
+use crate::quality::Quality;
+use crate::settings::Settings;
+use crate::spawner::{RectSpawned, Spawner};
+use crate::z_layer::ZLayer;
+use crate::{GameSet, PlayerDied};
+
+const MARKER_HEIGHT: f32 = 20.0;
+const MARKER_MARGIN: f32 = 10.0;
+const SHADOW_HEIGHT: f32 = 8.0;
+const SHADOW_MAX_DISTANCE: f32 = 5000.0;
+const FLASH_FREQUENCY: f32 = 8.0;
+
+pub struct TelegraphPlugin;
+
+impl Plugin for TelegraphPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            Update,
+            (clear_warnings, show_warnings, update_warnings)
+                .chain()
+                .in_set(GameSet::Vfx),
+        );
+    }
+}
+
+#[derive(Component)]
+struct DropWarning {
+    drop_id: u64,
+    kind: WarningKind,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum WarningKind {
+    Marker,
+    Shadow,
+}
+
+// a warning lives until its rect spawns, or until a death wipes the planned drops
+fn clear_warnings(
+    mut commands: Commands,
+    mut spawned: EventReader<RectSpawned>,
+    mut died: EventReader<PlayerDied>,
+    warnings: Query<(Entity, &DropWarning)>,
+) {
+    let died = died.read().count() > 0;
+    let spawned: HashSet<u64> = spawned.read().map(|event| event.drop_id).collect();
+    for (entity, warning) in &warnings {
+        if died || spawned.contains(&warning.drop_id) {
+            commands.entity(entity).despawn();
+        }
+    }
+}
+
+fn show_warnings(
+    mut commands: Commands,
+    spawner: Res<Spawner>,
+    warnings: Query<&DropWarning>,
+    assets: Res<CommonAssets>,
+    time: Res<Time>,
+) {
+    let now = time.elapsed_secs();
+    let shown: HashSet<u64> = warnings.iter().map(|warning| warning.drop_id).collect();
+    for drop in spawner.planned() {
+        if drop.warn_at > now || shown.contains(&drop.id) {
+            continue;
+        }
+        commands.spawn((
+            Name::new("DropMarker"),
+            ZLayer::Warnings,
+            Themed(PaletteRole::Hazard),
+            DropWarning {
+                drop_id: drop.id,
+                kind: WarningKind::Marker,
+            },
+            Mesh2d(assets.rect.clone()),
+            MeshMaterial2d(assets.marker.clone()),
+            Transform::from_xyz(drop.x, 0.0, 0.0).with_scale(Vec3::new(
+                drop.size.x,
+                MARKER_HEIGHT,
+                1.0,
+            )),
+        ));
+        commands.spawn((
+            Name::new("DropShadow"),
+            ZLayer::Decals,
+            DropWarning {
+                drop_id: drop.id,
+                kind: WarningKind::Shadow,
+            },
+            Mesh2d(assets.rect.clone()),
+            MeshMaterial2d(assets.shadow.clone()),
+            Transform::from_xyz(drop.x, 0.0, 0.0).with_scale(Vec3::new(
+                drop.size.x,
+                SHADOW_HEIGHT,
+                1.0,
+            )),
+            Visibility::Hidden,
+        ));
+    }
+}
+
+fn update_warnings(
+    mut warnings: Query<(&DropWarning, &mut Transform, &mut Visibility), Without<Camera>>,
+    camera: Single<(&Transform, &OrthographicProjection), With<Camera>>,
+    spatial_query: SpatialQuery,
+    settings: Res<Settings>,
+    quality: Res<Quality>,
+    time: Res<Time>,
+) {
+    let (camera_transform, projection) = camera.into_inner();
+    let marker_y = camera_transform.translation.y + projection.area.max.y
+        - (MARKER_HEIGHT / 2.0 + MARKER_MARGIN) * projection.scale;
+    let flash_on = settings.reduce_flashing || (time.elapsed_secs() * FLASH_FREQUENCY).sin() > 0.0;
+    for (warning, mut transform, mut visibility) in &mut warnings {
+        match warning.kind {
+            WarningKind::Marker => {
+                transform.translation.y = marker_y;
+                transform.scale.y = MARKER_HEIGHT * projection.scale;
+                *visibility = if flash_on {
+                    Visibility::Inherited
+                } else {
+                    Visibility::Hidden
+                };
+            }
+            WarningKind::Shadow if !quality.shadows() => *visibility = Visibility::Hidden,
+            WarningKind::Shadow => {
+                // project the shadow onto whatever surface is below the drop
+                let origin = Vec2::new(transform.translation.x, marker_y);
+                match spatial_query.cast_ray(
+                    origin,
+                    Dir2::NEG_Y,
+                    SHADOW_MAX_DISTANCE,
+                    true,
+                    &SpatialQueryFilter::from_mask(solid_mask()),
+                ) {
+                    Some(hit) => {
+                        transform.translation.y = origin.y - hit.distance + SHADOW_HEIGHT / 2.0;
+                        *visibility = Visibility::Inherited;
+                    }
+                    None => *visibility = Visibility::Hidden,
+                }
+            }
+        }
+    }
+}
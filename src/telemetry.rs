@@ -0,0 +1,319 @@
+use std::collections::BTreeMap;
+
+use bevy::prelude::*;
+use bevy::tasks::{block_on, poll_once, IoTaskPool, Task};
+use serde::{Deserialize, Serialize};
+
+use crate::console::{Console, ConsoleAppExt, ConsoleCommand};
+use crate::http;
+use crate::i18n::Localization;
+use crate::menu::{GameState, MenuAction, MenuActivated, MenuPage, MenuRow, MenuRows, MenuSet};
+use crate::score::RunStats;
+use crate::settings::Settings;
+use crate::storage::{Persistent, StorageAppExt};
+use crate::{GameSet, Player, PlayerDied, PlayerJumped, PlayerWallJumped};
+
+// the default endpoint comes from the environment at build time. self-hosters can point
+// telemetry_endpoint in the settings file at their own server instead
+const TELEMETRY_URL: Option<&str> = option_env!("RECTFALL_TELEMETRY_URL");
+const SEND_INTERVAL_SECS: f32 = 300.0;
+// a handful of runs are batched together so a report never describes a single run
+const MIN_RUNS_PER_REPORT: u32 = 5;
+const HEIGHT_BAND: f32 = 500.0;
+
+pub struct TelemetryPlugin;
+
+impl Plugin for TelemetryPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_persistent_resource::<Telemetry>()
+            .init_resource::<TelemetryUpload>()
+            .add_console_command("telemetry", "telemetry [reset]")
+            .add_systems(Startup, ask_for_consent)
+            .add_systems(
+                Update,
+                (
+                    (count_runs, count_abilities, send_telemetry)
+                        .in_set(GameSet::Reactions)
+                        .run_if(collecting),
+                    run_telemetry_command,
+                    consent_menu_rows.in_set(MenuSet::Rows),
+                    apply_consent_actions.in_set(MenuSet::Actions),
+                ),
+            );
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default, Serialize, Deserialize)]
+pub enum TelemetryConsent {
+    #[default]
+    Unasked,
+    Declined,
+    LocalOnly,
+    Shared,
+}
+
+impl TelemetryConsent {
+    pub fn key(self) -> &'static str {
+        match self {
+            TelemetryConsent::Unasked | TelemetryConsent::Declined => "common.off",
+            TelemetryConsent::LocalOnly => "telemetry.local_only",
+            TelemetryConsent::Shared => "telemetry.shared",
+        }
+    }
+
+    // unasked isn't something the player can pick again
+    pub fn cycle(self, delta: i32) -> TelemetryConsent {
+        const ORDER: [TelemetryConsent; 3] = [
+            TelemetryConsent::Declined,
+            TelemetryConsent::LocalOnly,
+            TelemetryConsent::Shared,
+        ];
+        let index = ORDER
+            .iter()
+            .position(|consent| *consent == self)
+            .unwrap_or(0);
+        ORDER[(index as i32 + delta).rem_euclid(ORDER.len() as i32) as usize]
+    }
+}
+
+// only aggregates, nothing in here can tell players or runs apart
+#[derive(Serialize, Deserialize, Clone, Default)]
+#[serde(default)]
+struct TelemetryReport {
+    runs: u32,
+    run_secs: f32,
+    // keyed by the bottom of each HEIGHT_BAND tall band
+    deaths_per_band: BTreeMap<u32, u32>,
+    jumps: u32,
+    wall_jumps: u32,
+    climbs: u32,
+}
+
+impl TelemetryReport {
+    fn merge(&mut self, other: TelemetryReport) {
+        self.runs += other.runs;
+        self.run_secs += other.run_secs;
+        for (band, deaths) in other.deaths_per_band {
+            *self.deaths_per_band.entry(band).or_default() += deaths;
+        }
+        self.jumps += other.jumps;
+        self.wall_jumps += other.wall_jumps;
+        self.climbs += other.climbs;
+    }
+
+    fn to_json(&self) -> serde_json::Result<String> {
+        let average_run_secs = if self.runs > 0 {
+            self.run_secs / self.runs as f32
+        } else {
+            0.0
+        };
+        serde_json::to_string(&serde_json::json!({
+            "version": env!("CARGO_PKG_VERSION"),
+            "runs": self.runs,
+            "average_run_secs": average_run_secs,
+            "deaths_per_band": self.deaths_per_band,
+            "band_height": HEIGHT_BAND,
+            "jumps": self.jumps,
+            "wall_jumps": self.wall_jumps,
+            "climbs": self.climbs,
+        }))
+    }
+}
+
+// whatever hasn't been sent yet, or everything so far when it stays local
+#[derive(Resource, Serialize, Deserialize, Default)]
+#[serde(default)]
+struct Telemetry {
+    pending: TelemetryReport,
+}
+
+impl Persistent for Telemetry {
+    const KEY: &'static str = "telemetry";
+    const VERSION: u32 = 1;
+}
+
+#[derive(Resource, Default)]
+struct TelemetryUpload {
+    task: Option<Task<Result<(), String>>>,
+    // the report in flight, merged back if sending it fails
+    sending: Option<TelemetryReport>,
+    since_send: f32,
+}
+
+fn collecting(settings: Res<Settings>) -> bool {
+    matches!(
+        settings.telemetry,
+        TelemetryConsent::LocalOnly | TelemetryConsent::Shared
+    )
+}
+
+fn endpoint(settings: &Settings) -> Option<&str> {
+    settings.telemetry_endpoint.as_deref().or(TELEMETRY_URL)
+}
+
+fn ask_for_consent(
+    settings: Res<Settings>,
+    mut page: ResMut<MenuPage>,
+    mut next_state: ResMut<NextState<GameState>>,
+) {
+    // something more urgent, like the crash dialog, gets to go first
+    if settings.telemetry == TelemetryConsent::Unasked && *page == MenuPage::Main {
+        *page = MenuPage::Telemetry;
+        next_state.set(GameState::Paused);
+    }
+}
+
+// RunStats is already reset on the frame the player dies, so the run is remembered
+// from the frame before
+fn count_runs(
+    mut died: EventReader<PlayerDied>,
+    stats: Res<RunStats>,
+    mut telemetry: ResMut<Telemetry>,
+    mut last_run: Local<(f32, f32)>,
+) {
+    if died.read().count() == 0 {
+        *last_run = (stats.max_height, stats.time_survived);
+        return;
+    }
+    let (height, run_secs) = *last_run;
+    let band = (height.max(0.0) / HEIGHT_BAND) as u32 * HEIGHT_BAND as u32;
+    let pending = &mut telemetry.pending;
+    pending.runs += 1;
+    pending.run_secs += run_secs;
+    *pending.deaths_per_band.entry(band).or_default() += 1;
+}
+
+fn count_abilities(
+    mut jumps: EventReader<PlayerJumped>,
+    mut wall_jumps: EventReader<PlayerWallJumped>,
+    player: Single<&Player>,
+    mut telemetry: ResMut<Telemetry>,
+    mut was_climbing: Local<bool>,
+) {
+    let jumps = jumps.read().count() as u32;
+    let wall_jumps = wall_jumps.read().count() as u32;
+    let started_climb = player.climbing && !*was_climbing;
+    *was_climbing = player.climbing;
+    if jumps == 0 && wall_jumps == 0 && !started_climb {
+        return;
+    }
+    let pending = &mut telemetry.pending;
+    pending.jumps += jumps;
+    pending.wall_jumps += wall_jumps;
+    pending.climbs += started_climb as u32;
+}
+
+fn send_telemetry(
+    settings: Res<Settings>,
+    mut telemetry: ResMut<Telemetry>,
+    mut upload: ResMut<TelemetryUpload>,
+    time: Res<Time<Real>>,
+) {
+    if let Some(task) = &mut upload.task {
+        let Some(result) = block_on(poll_once(task)) else {
+            return;
+        };
+        upload.task = None;
+        let sent = upload.sending.take().unwrap_or_default();
+        if let Err(err) = result {
+            debug!("failed to send telemetry, keeping it for later: {err}");
+            telemetry.pending.merge(sent);
+        }
+    }
+    upload.since_send += time.delta_secs();
+    let Some(url) = endpoint(&settings) else {
+        return;
+    };
+    if settings.telemetry != TelemetryConsent::Shared
+        || upload.since_send < SEND_INTERVAL_SECS
+        || telemetry.pending.runs < MIN_RUNS_PER_REPORT
+    {
+        return;
+    }
+    upload.since_send = 0.0;
+    let report = std::mem::take(&mut telemetry.pending);
+    let body = match report.to_json() {
+        Ok(body) => body,
+        Err(err) => {
+            warn!("failed to encode telemetry: {err}");
+            return;
+        }
+    };
+    let url = url.to_string();
+    upload.sending = Some(report);
+    upload.task = Some(IoTaskPool::get().spawn(async move { http::post(&url, body).await }));
+}
+
+// prints what has been collected so far as json, which is also the local only export
+fn run_telemetry_command(
+    mut commands: EventReader<ConsoleCommand>,
+    mut console: ResMut<Console>,
+    mut telemetry: ResMut<Telemetry>,
+) {
+    for command in commands
+        .read()
+        .filter(|command| command.name == "telemetry")
+    {
+        match command.args.first().map(String::as_str) {
+            None => match telemetry.pending.to_json() {
+                Ok(json) => {
+                    info!("telemetry: {json}");
+                    console.print(json);
+                }
+                Err(err) => console.print(format!("failed to encode telemetry: {err}")),
+            },
+            Some("reset") => {
+                telemetry.pending = TelemetryReport::default();
+                console.print("telemetry cleared");
+            }
+            Some(arg) => console.print(format!("unknown telemetry argument '{arg}'")),
+        }
+    }
+}
+
+fn consent_menu_rows(
+    page: Res<MenuPage>,
+    localization: Res<Localization>,
+    mut rows: ResMut<MenuRows>,
+) {
+    if *page != MenuPage::Telemetry {
+        return;
+    }
+    for (key, action) in [
+        ("telemetry.prompt", MenuAction::None),
+        ("telemetry.details", MenuAction::None),
+        (
+            "telemetry.choose_shared",
+            MenuAction::SetTelemetry(TelemetryConsent::Shared),
+        ),
+        (
+            "telemetry.choose_local_only",
+            MenuAction::SetTelemetry(TelemetryConsent::LocalOnly),
+        ),
+        (
+            "telemetry.choose_declined",
+            MenuAction::SetTelemetry(TelemetryConsent::Declined),
+        ),
+    ] {
+        rows.push(MenuRow::new(localization.get(key), action));
+    }
+}
+
+fn apply_consent_actions(
+    mut activated: EventReader<MenuActivated>,
+    mut settings: ResMut<Settings>,
+    mut page: ResMut<MenuPage>,
+    mut next_state: ResMut<NextState<GameState>>,
+) {
+    for event in activated.read() {
+        let MenuAction::SetTelemetry(consent) = event.action else {
+            continue;
+        };
+        if event.delta != 0 {
+            continue;
+        }
+        settings.telemetry = consent;
+        *page = MenuPage::Main;
+        next_state.set(GameState::Playing);
+    }
+}
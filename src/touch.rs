@@ -0,0 +1,207 @@
+use bevy::prelude::*;
+use bevy::window::PrimaryWindow;
+use serde::{Deserialize, Serialize};
+
+use crate::input::{Action, ActionSet, ActionState};
+use crate::settings::Settings;
+use crate::ui_layout::UiRoot;
+
+const JOYSTICK_RADIUS: f32 = 70.0;
+const KNOB_RADIUS: f32 = 30.0;
+const JOYSTICK_DEADZONE: f32 = 0.25;
+const JUMP_BUTTON_RADIUS: f32 = 60.0;
+const CONTROL_MARGIN: f32 = 40.0;
+
+pub struct TouchControlsPlugin;
+
+impl Plugin for TouchControlsPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<TouchDetected>()
+            .add_systems(Startup, spawn_touch_controls)
+            .add_systems(
+                PreUpdate,
+                (detect_touch, collect_touch_actions)
+                    .chain()
+                    .in_set(ActionSet::Collect),
+            )
+            .add_systems(Update, (toggle_touch_controls, update_joystick_visuals));
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default, Serialize, Deserialize)]
+pub enum TouchControls {
+    #[default]
+    Auto,
+    Always,
+    Never,
+}
+
+impl TouchControls {
+    pub fn key(self) -> &'static str {
+        match self {
+            TouchControls::Auto => "settings.touch_controls_auto",
+            TouchControls::Always => "common.on",
+            TouchControls::Never => "common.off",
+        }
+    }
+
+    pub fn cycle(self, delta: i32) -> TouchControls {
+        const ORDER: [TouchControls; 3] = [
+            TouchControls::Auto,
+            TouchControls::Always,
+            TouchControls::Never,
+        ];
+        let index = ORDER.iter().position(|mode| *mode == self).unwrap_or(0);
+        ORDER[(index as i32 + delta).rem_euclid(ORDER.len() as i32) as usize]
+    }
+}
+
+#[derive(Resource, Default)]
+struct TouchDetected(bool);
+
+#[derive(Component)]
+struct TouchOverlay;
+
+#[derive(Component)]
+struct JoystickBase;
+
+#[derive(Component)]
+struct JoystickKnob;
+
+fn touch_enabled(settings: &Settings, detected: &TouchDetected) -> bool {
+    match settings.touch_controls {
+        TouchControls::Auto => detected.0,
+        TouchControls::Always => true,
+        TouchControls::Never => false,
+    }
+}
+
+fn circle_node(radius: f32) -> Node {
+    Node {
+        position_type: PositionType::Absolute,
+        width: Val::Px(radius * 2.0),
+        height: Val::Px(radius * 2.0),
+        ..default()
+    }
+}
+
+fn spawn_touch_controls(mut commands: Commands, root: Single<Entity, With<UiRoot>>) {
+    commands
+        .spawn((
+            Name::new("TouchControls"),
+            TouchOverlay,
+            Node {
+                position_type: PositionType::Absolute,
+                width: Val::Percent(100.0),
+                height: Val::Percent(100.0),
+                ..default()
+            },
+            Visibility::Hidden,
+        ))
+        .set_parent(*root)
+        .with_children(|parent| {
+            parent
+                .spawn((
+                    JoystickBase,
+                    Node {
+                        left: Val::Px(CONTROL_MARGIN),
+                        bottom: Val::Px(CONTROL_MARGIN),
+                        ..circle_node(JOYSTICK_RADIUS)
+                    },
+                    BackgroundColor(Color::srgba(1.0, 1.0, 1.0, 0.15)),
+                    BorderRadius::MAX,
+                ))
+                .with_child((
+                    JoystickKnob,
+                    Node {
+                        left: Val::Px(JOYSTICK_RADIUS - KNOB_RADIUS),
+                        top: Val::Px(JOYSTICK_RADIUS - KNOB_RADIUS),
+                        ..circle_node(KNOB_RADIUS)
+                    },
+                    BackgroundColor(Color::srgba(1.0, 1.0, 1.0, 0.4)),
+                    BorderRadius::MAX,
+                ));
+            parent.spawn((
+                Node {
+                    right: Val::Px(CONTROL_MARGIN),
+                    bottom: Val::Px(CONTROL_MARGIN),
+                    ..circle_node(JUMP_BUTTON_RADIUS)
+                },
+                BackgroundColor(Color::srgba(1.0, 1.0, 1.0, 0.25)),
+                BorderRadius::MAX,
+            ));
+        });
+}
+
+fn detect_touch(touches: Res<Touches>, mut detected: ResMut<TouchDetected>) {
+    if !detected.0 && touches.any_just_pressed() {
+        detected.0 = true;
+    }
+}
+
+// touches that start on the left half drive the joystick, the right half is the jump button
+fn joystick_direction(touches: &Touches, window: &Window) -> Option<Vec2> {
+    touches
+        .iter()
+        .find(|touch| touch.start_position().x < window.width() / 2.0)
+        .map(|touch| {
+            let offset = touch.position() - touch.start_position();
+            (offset / JOYSTICK_RADIUS).clamp_length_max(1.0)
+        })
+}
+
+fn collect_touch_actions(
+    touches: Res<Touches>,
+    settings: Res<Settings>,
+    detected: Res<TouchDetected>,
+    window: Single<&Window, With<PrimaryWindow>>,
+    mut actions: ResMut<ActionState>,
+) {
+    if !touch_enabled(&settings, &detected) {
+        return;
+    }
+    if let Some(direction) = joystick_direction(&touches, &window) {
+        if direction.x < -JOYSTICK_DEADZONE {
+            actions.press(Action::Left);
+        }
+        if direction.x > JOYSTICK_DEADZONE {
+            actions.press(Action::Right);
+        }
+        // screen space y grows downwards
+        if direction.y > JOYSTICK_DEADZONE {
+            actions.press(Action::Down);
+        }
+    }
+    if touches
+        .iter()
+        .any(|touch| touch.start_position().x >= window.width() / 2.0)
+    {
+        actions.press(Action::Jump);
+    }
+}
+
+fn toggle_touch_controls(
+    settings: Res<Settings>,
+    detected: Res<TouchDetected>,
+    mut overlay: Single<&mut Visibility, With<TouchOverlay>>,
+) {
+    if !settings.is_changed() && !detected.is_changed() {
+        return;
+    }
+    **overlay = if touch_enabled(&settings, &detected) {
+        Visibility::Inherited
+    } else {
+        Visibility::Hidden
+    };
+}
+
+fn update_joystick_visuals(
+    touches: Res<Touches>,
+    window: Single<&Window, With<PrimaryWindow>>,
+    mut knob: Single<&mut Node, With<JoystickKnob>>,
+) {
+    let direction = joystick_direction(&touches, &window).unwrap_or(Vec2::ZERO);
+    let offset = direction * (JOYSTICK_RADIUS - KNOB_RADIUS);
+    knob.left = Val::Px(JOYSTICK_RADIUS - KNOB_RADIUS + offset.x);
+    knob.top = Val::Px(JOYSTICK_RADIUS - KNOB_RADIUS + offset.y);
+}
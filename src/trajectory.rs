@@ -0,0 +1,22 @@
+use bevy::prelude::*;
+
+// integrates the same way the physics step does (semi-implicit euler), so the
+// predicted arc lines up with where the body actually goes
+pub fn predict_trajectory(
+    start: Vec2,
+    velocity: Vec2,
+    gravity: Vec2,
+    step_secs: f32,
+    steps: usize,
+) -> Vec<Vec2> {
+    let mut points = Vec::with_capacity(steps + 1);
+    let mut position = start;
+    let mut velocity = velocity;
+    points.push(position);
+    for _ in 0..steps {
+        velocity += gravity * step_secs;
+        position += velocity * step_secs;
+        points.push(position);
+    }
+    points
+}
@@ -0,0 +1,76 @@
+use bevy::prelude::*;
+
+use crate::settings::Settings;
+
+const MIN_UI_SCALE_PERCENT: u32 = 75;
+const MAX_UI_SCALE_PERCENT: u32 = 200;
+const UI_SCALE_STEP_PERCENT: u32 = 25;
+const MAX_SAFE_AREA_PERCENT: u32 = 10;
+const SAFE_AREA_STEP_PERCENT: u32 = 2;
+
+pub struct UiLayoutPlugin;
+
+impl Plugin for UiLayoutPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(PreStartup, spawn_ui_root)
+            .add_systems(Update, apply_ui_layout);
+    }
+}
+
+// hud, menus and touch controls all hang off this node, so the safe area moves them
+// in together. it's spawned before Startup for the other modules to parent to.
+#[derive(Component)]
+pub struct UiRoot;
+
+pub fn cycle_ui_scale(percent: u32, delta: i32) -> u32 {
+    let steps = (MAX_UI_SCALE_PERCENT - MIN_UI_SCALE_PERCENT) / UI_SCALE_STEP_PERCENT + 1;
+    let step = percent.saturating_sub(MIN_UI_SCALE_PERCENT) / UI_SCALE_STEP_PERCENT;
+    let step = (step as i32 + delta).rem_euclid(steps as i32) as u32;
+    MIN_UI_SCALE_PERCENT + step * UI_SCALE_STEP_PERCENT
+}
+
+pub fn cycle_safe_area(percent: u32, delta: i32) -> u32 {
+    let steps = MAX_SAFE_AREA_PERCENT / SAFE_AREA_STEP_PERCENT + 1;
+    let step = percent / SAFE_AREA_STEP_PERCENT;
+    let step = (step as i32 + delta).rem_euclid(steps as i32) as u32;
+    step * SAFE_AREA_STEP_PERCENT
+}
+
+// absolutely positioned children ignore padding, so the margin is the root's inset
+fn safe_area_node(percent: u32) -> Node {
+    let margin = Val::Percent(percent as f32);
+    Node {
+        position_type: PositionType::Absolute,
+        left: margin,
+        right: margin,
+        top: margin,
+        bottom: margin,
+        ..default()
+    }
+}
+
+fn spawn_ui_root(mut commands: Commands, settings: Res<Settings>) {
+    commands.spawn((
+        Name::new("UiRoot"),
+        UiRoot,
+        safe_area_node(settings.safe_area_percent),
+    ));
+}
+
+fn apply_ui_layout(
+    settings: Res<Settings>,
+    mut ui_scale: ResMut<UiScale>,
+    mut root: Single<&mut Node, With<UiRoot>>,
+) {
+    if !settings.is_changed() {
+        return;
+    }
+    let scale = settings.ui_scale_percent as f32 / 100.0;
+    if ui_scale.0 != scale {
+        ui_scale.0 = scale;
+    }
+    let node = safe_area_node(settings.safe_area_percent);
+    if root.left != node.left {
+        **root = node;
+    }
+}
@@ -0,0 +1,240 @@
+use bevy::asset::io::Reader;
+use bevy::asset::{AssetLoader, LoadContext};
+use bevy::audio::Volume;
+use bevy::prelude::*;
+use bevy::utils::HashMap;
+use serde::Deserialize;
+
+use crate::asset_check::TrackedAssets;
+use crate::hud::{ShowBanner, ShowToast};
+use crate::menu::{GameState, MenuAction, MenuActivated, MenuPage, MenuSelection, MenuSet};
+use crate::preload::PreloadedAudio;
+use crate::voices::{Voice, VoicePriority};
+
+const THEME_PATH: &str = "ui.sounds.ron";
+
+pub struct UiSoundsPlugin;
+
+impl Plugin for UiSoundsPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_asset::<UiSoundTheme>()
+            .init_asset_loader::<UiSoundThemeLoader>()
+            .init_resource::<MenuCursor>()
+            .add_systems(Startup, load_theme)
+            .add_systems(OnExit(GameState::Paused), forget_cursor)
+            .add_systems(Update, (load_theme_sounds, play_hud_sounds))
+            .add_systems(Update, play_menu_sounds.in_set(MenuSet::Actions));
+    }
+}
+
+#[derive(Deserialize)]
+struct UiSound {
+    path: String,
+    #[serde(default = "default_speed")]
+    speed: f32,
+    #[serde(default = "default_volume")]
+    volume: f32,
+}
+
+fn default_speed() -> f32 {
+    1.0
+}
+
+fn default_volume() -> f32 {
+    1.0
+}
+
+// one sound per kind of interaction, swapped by editing the theme file
+#[derive(Asset, TypePath, Deserialize)]
+struct UiSoundTheme {
+    navigate: UiSound,
+    confirm: UiSound,
+    cancel: UiSound,
+    // a row adjusted with left or right
+    slider: UiSound,
+    // toasts and banners popping up on the hud
+    notify: UiSound,
+}
+
+#[derive(Default)]
+struct UiSoundThemeLoader;
+
+impl AssetLoader for UiSoundThemeLoader {
+    type Asset = UiSoundTheme;
+    type Settings = ();
+    type Error = std::io::Error;
+
+    async fn load(
+        &self,
+        reader: &mut dyn Reader,
+        _settings: &(),
+        _load_context: &mut LoadContext<'_>,
+    ) -> Result<UiSoundTheme, Self::Error> {
+        let mut bytes = Vec::new();
+        reader.read_to_end(&mut bytes).await?;
+        ron::de::from_bytes(&bytes)
+            .map_err(|err| std::io::Error::new(std::io::ErrorKind::InvalidData, err))
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["sounds.ron"]
+    }
+}
+
+#[derive(Clone, Copy)]
+enum UiCue {
+    Navigate,
+    Confirm,
+    Cancel,
+    Slider,
+    Notify,
+}
+
+impl UiSoundTheme {
+    fn sound(&self, cue: UiCue) -> &UiSound {
+        match cue {
+            UiCue::Navigate => &self.navigate,
+            UiCue::Confirm => &self.confirm,
+            UiCue::Cancel => &self.cancel,
+            UiCue::Slider => &self.slider,
+            UiCue::Notify => &self.notify,
+        }
+    }
+}
+
+#[derive(Resource)]
+struct UiSounds {
+    theme: Handle<UiSoundTheme>,
+    // held for as long as the theme uses them, so a sound isn't reloaded every press
+    handles: HashMap<String, Handle<AudioSource>>,
+}
+
+fn load_theme(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    mut tracked: ResMut<TrackedAssets>,
+) {
+    let theme = asset_server.load(THEME_PATH);
+    tracked.track(theme.clone());
+    commands.insert_resource(UiSounds {
+        theme,
+        handles: HashMap::default(),
+    });
+}
+
+// also picks up edits to the theme while the game runs
+fn load_theme_sounds(
+    mut events: EventReader<AssetEvent<UiSoundTheme>>,
+    mut sounds: ResMut<UiSounds>,
+    themes: Res<Assets<UiSoundTheme>>,
+    asset_server: Res<AssetServer>,
+    mut tracked: ResMut<TrackedAssets>,
+) {
+    let changed = events.read().any(|event| match event {
+        AssetEvent::LoadedWithDependencies { id } | AssetEvent::Modified { id } => {
+            *id == sounds.theme.id()
+        }
+        _ => false,
+    });
+    let Some(theme) = changed.then(|| themes.get(&sounds.theme)).flatten() else {
+        return;
+    };
+    let cues = [
+        UiCue::Navigate,
+        UiCue::Confirm,
+        UiCue::Cancel,
+        UiCue::Slider,
+        UiCue::Notify,
+    ];
+    let handles = cues
+        .into_iter()
+        .map(|cue| {
+            let path = theme.sound(cue).path.clone();
+            let handle: Handle<AudioSource> = asset_server.load(&path);
+            tracked.track(handle.clone());
+            (path, handle)
+        })
+        .collect();
+    sounds.handles = handles;
+    debug!(target: "audio", "ui sound theme loaded");
+}
+
+fn play_cue(
+    commands: &mut Commands,
+    cue: UiCue,
+    sounds: &UiSounds,
+    themes: &Assets<UiSoundTheme>,
+    preloaded: &PreloadedAudio,
+) {
+    let Some(sound) = themes.get(&sounds.theme).map(|theme| theme.sound(cue)) else {
+        return;
+    };
+    let Some(handle) = sounds.handles.get(&sound.path) else {
+        return;
+    };
+    let mut voice = commands.spawn((
+        PlaybackSettings::DESPAWN
+            .with_volume(Volume::new(sound.volume))
+            .with_speed(sound.speed),
+        Voice::new(VoicePriority::Interface, sound.volume),
+    ));
+    preloaded.insert_player(&mut voice, handle);
+}
+
+// where the selection was last frame, forgotten when the menu closes so opening it
+// again doesn't count as moving
+#[derive(Resource, Default)]
+struct MenuCursor(Option<(MenuPage, usize)>);
+
+fn forget_cursor(mut cursor: ResMut<MenuCursor>) {
+    cursor.0 = None;
+}
+
+// a selection that moved without a page change is navigation, a new page plays the
+// confirm or cancel that opened it instead
+fn play_menu_sounds(
+    mut commands: Commands,
+    mut activated: EventReader<MenuActivated>,
+    page: Res<MenuPage>,
+    selection: Res<MenuSelection>,
+    sounds: Res<UiSounds>,
+    themes: Res<Assets<UiSoundTheme>>,
+    preloaded: Res<PreloadedAudio>,
+    mut cursor: ResMut<MenuCursor>,
+) {
+    let mut cues = Vec::new();
+    for event in activated.read() {
+        cues.push(match event.action {
+            // info rows do nothing when pressed
+            MenuAction::None => continue,
+            _ if event.delta != 0 => UiCue::Slider,
+            MenuAction::Back => UiCue::Cancel,
+            _ => UiCue::Confirm,
+        });
+    }
+    let current = (*page, selection.0);
+    let moved = cursor
+        .0
+        .is_some_and(|(page, index)| page == current.0 && index != current.1);
+    if cues.is_empty() && moved {
+        cues.push(UiCue::Navigate);
+    }
+    cursor.0 = Some(current);
+    for cue in cues {
+        play_cue(&mut commands, cue, &sounds, &themes, &preloaded);
+    }
+}
+
+fn play_hud_sounds(
+    mut commands: Commands,
+    mut toasts: EventReader<ShowToast>,
+    mut banners: EventReader<ShowBanner>,
+    sounds: Res<UiSounds>,
+    themes: Res<Assets<UiSoundTheme>>,
+    preloaded: Res<PreloadedAudio>,
+) {
+    // several at once still only chime once
+    if toasts.read().count() + banners.read().count() > 0 {
+        play_cue(&mut commands, UiCue::Notify, &sounds, &themes, &preloaded);
+    }
+}
@@ -0,0 +1,85 @@
+use std::cmp::Ordering;
+
+use bevy::prelude::*;
+use bevy::transform::TransformSystem;
+
+// past this, new and playing sounds get cut until it fits again
+const MAX_VOICES: usize = 24;
+
+pub struct VoicesPlugin;
+
+impl Plugin for VoicesPlugin {
+    fn build(&self, app: &mut App) {
+        // bevy starts queued sounds after transform propagation, a voice stolen before
+        // then never plays at all
+        app.add_systems(
+            PostUpdate,
+            limit_voices.before(TransformSystem::TransformPropagate),
+        );
+    }
+}
+
+// lower priorities are stolen first
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Debug)]
+pub enum VoicePriority {
+    // rects knocking together away from the player
+    Ambient,
+    World,
+    // things happening to the player, like getting hurt
+    Player,
+    // menu and hud feedback, short and always answering a press
+    Interface,
+}
+
+// every sound effect gets one, anything playing without it is never stolen
+#[derive(Component)]
+pub struct Voice {
+    priority: VoicePriority,
+    volume: f32,
+    started: Option<f32>,
+}
+
+impl Voice {
+    pub fn new(priority: VoicePriority, volume: f32) -> Voice {
+        Voice {
+            priority,
+            volume,
+            started: None,
+        }
+    }
+}
+
+// the least important goes first, then the quietest, then the oldest
+fn steal_order(a: &Voice, b: &Voice) -> Ordering {
+    a.priority
+        .cmp(&b.priority)
+        .then(a.volume.total_cmp(&b.volume))
+        .then(
+            a.started
+                .unwrap_or(0.0)
+                .total_cmp(&b.started.unwrap_or(0.0)),
+        )
+}
+
+fn limit_voices(
+    mut commands: Commands,
+    mut voices: Query<(Entity, &mut Voice)>,
+    time: Res<Time<Real>>,
+) {
+    let now = time.elapsed_secs();
+    for (_, mut voice) in &mut voices {
+        if voice.started.is_none() {
+            voice.started = Some(now);
+        }
+    }
+    let excess = voices.iter().len().saturating_sub(MAX_VOICES);
+    if excess == 0 {
+        return;
+    }
+    let mut playing: Vec<(Entity, &Voice)> = voices.iter().collect();
+    playing.sort_by(|(_, a), (_, b)| steal_order(a, b));
+    trace!(target: "audio", "stealing {excess} voices");
+    for (entity, _) in playing.into_iter().take(excess) {
+        commands.entity(entity).despawn();
+    }
+}
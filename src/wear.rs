@@ -0,0 +1,164 @@
+use avian2d::prelude::*;
+use bevy::prelude::*;
+use bevy::render::render_resource::{AsBindGroup, ShaderRef};
+use bevy::sprite::{AlphaMode2d, Material2d, Material2dPlugin};
+use rand::Rng;
+
+use crate::asset_check::TrackedAssets;
+use crate::palette::{Palette, Themed};
+use crate::spawner::FallingRect;
+use crate::GameSet;
+use uniform::WearUniform;
+
+const SHADER_PATH: &str = "shaders/wear.wgsl";
+// impact speed, relative to whatever got hit, that adds a full WEAR_PER_HIT
+const WEAR_SPEED: f32 = 600.0;
+const WEAR_PER_HIT: f32 = 0.15;
+// taps from settling into the pile don't scuff anything
+const MIN_WEAR_SPEED: f32 = 150.0;
+
+pub struct WearPlugin;
+
+impl Plugin for WearPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_plugins(Material2dPlugin::<WearMaterial>::default())
+            .add_systems(Startup, load_wear_shader)
+            .add_systems(
+                Update,
+                (
+                    (add_wear_materials, accumulate_wear)
+                        .chain()
+                        .in_set(GameSet::Reactions),
+                    sync_wear_materials.in_set(GameSet::Vfx),
+                ),
+            );
+    }
+}
+
+// every rect gets its own instance, the wear and the crack pattern are per rect
+#[derive(Asset, TypePath, AsBindGroup, Debug, Clone)]
+pub struct WearMaterial {
+    #[uniform(0)]
+    uniform: WearUniform,
+}
+
+// the ShaderType derive leaves an uncalled `check` fn behind for every field, which the
+// compiler reports as dead code
+#[allow(dead_code)]
+mod uniform {
+    use bevy::prelude::*;
+    use bevy::render::render_resource::ShaderType;
+
+    #[derive(ShaderType, Debug, Clone, Copy, Default)]
+    pub struct WearUniform {
+        pub color: Vec4,
+        // 0 is untouched, 1 is as cracked as a rect gets
+        pub wear: f32,
+        // moves the crack pattern so no two rects look alike
+        pub seed: f32,
+    }
+}
+
+impl Material2d for WearMaterial {
+    fn fragment_shader() -> ShaderRef {
+        SHADER_PATH.into()
+    }
+
+    // foam is see-through
+    fn alpha_mode(&self) -> AlphaMode2d {
+        AlphaMode2d::Blend
+    }
+}
+
+#[derive(Component, Default)]
+struct Wear(f32);
+
+#[derive(Resource)]
+struct WearShader(Handle<Shader>);
+
+fn load_wear_shader(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    mut tracked: ResMut<TrackedAssets>,
+) {
+    let shader = asset_server.load(SHADER_PATH);
+    tracked.track(shader.clone());
+    commands.insert_resource(WearShader(shader));
+}
+
+// swaps the shared color material a rect spawns with for its own wear material. without
+// the shader the rects would be invisible, so they keep the plain material instead
+fn add_wear_materials(
+    mut commands: Commands,
+    rects: Query<(Entity, &MeshMaterial2d<ColorMaterial>), (With<FallingRect>, Without<Wear>)>,
+    color_materials: Res<Assets<ColorMaterial>>,
+    mut wear_materials: ResMut<Assets<WearMaterial>>,
+    shader: Res<WearShader>,
+    tracked: Res<TrackedAssets>,
+) {
+    if tracked.failed(&shader.0) {
+        return;
+    }
+    let mut rng = rand::thread_rng();
+    for (entity, material) in &rects {
+        let Some(color) = color_materials.get(material).map(|material| material.color) else {
+            continue;
+        };
+        let material = wear_materials.add(WearMaterial {
+            uniform: WearUniform {
+                color: color.to_linear().to_vec4(),
+                wear: 0.0,
+                seed: rng.gen_range(0.0..100.0),
+            },
+        });
+        commands
+            .entity(entity)
+            .remove::<MeshMaterial2d<ColorMaterial>>()
+            .insert((Wear::default(), MeshMaterial2d(material)));
+    }
+}
+
+fn accumulate_wear(
+    mut collisions: EventReader<CollisionStarted>,
+    velocities: Query<&LinearVelocity>,
+    mut rects: Query<&mut Wear>,
+) {
+    for CollisionStarted(a, b) in collisions.read() {
+        let velocity =
+            |entity: Entity| velocities.get(entity).map_or(Vec2::ZERO, |linear| linear.0);
+        let speed = (velocity(*a) - velocity(*b)).length();
+        if speed < MIN_WEAR_SPEED {
+            continue;
+        }
+        // energy goes with the square of the speed, hard hits crack far more than taps
+        let wear = WEAR_PER_HIT * (speed / WEAR_SPEED).powi(2);
+        for entity in [*a, *b] {
+            if let Ok(mut rect) = rects.get_mut(entity) {
+                rect.0 = (rect.0 + wear).min(1.0);
+            }
+        }
+    }
+}
+
+// keeps the alpha, like the palette does for color materials
+fn sync_wear_materials(
+    palette: Res<Palette>,
+    rects: Query<(Ref<Wear>, &Themed, &MeshMaterial2d<WearMaterial>)>,
+    mut materials: ResMut<Assets<WearMaterial>>,
+) {
+    for (wear, themed, material) in &rects {
+        if !wear.is_changed() && !palette.is_changed() {
+            continue;
+        }
+        let Some(material) = materials.get_mut(material) else {
+            continue;
+        };
+        let alpha = material.uniform.color.w;
+        material.uniform.color = palette
+            .color(themed.0)
+            .to_linear()
+            .with_alpha(alpha)
+            .to_vec4();
+        material.uniform.wear = wear.0;
+    }
+}
@@ -0,0 +1,62 @@
+use bevy::prelude::*;
+use bevy::transform::TransformSystem;
+
+pub struct ZLayerPlugin;
+
+impl Plugin for ZLayerPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            PostUpdate,
+            apply_z_layers.before(TransformSystem::TransformPropagate),
+        );
+    }
+}
+
+// draw order for everything in the world, back to front. anything with a layer gets its
+// translation.z from it, so spawning and moving code never picks a z. layers are 50
+// apart, children can sit a little above or below their parent (insets at +0.1) without
+// reaching the next layer
+#[derive(Component, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum ZLayer {
+    // relative to the camera it's parented to, which sits at z 0
+    Background,
+    Ambience,
+    Terrain,
+    Trail,
+    Pile,
+    Player,
+    // drop shadows and splashes drawn over whatever they land on
+    Decals,
+    Warnings,
+    Particles,
+    // world space text like popups, the real ui draws over all of this anyway
+    Ui,
+}
+
+impl ZLayer {
+    pub fn z(self) -> f32 {
+        match self {
+            ZLayer::Background => -900.0,
+            ZLayer::Ambience => -300.0,
+            ZLayer::Terrain => -250.0,
+            ZLayer::Trail => -200.0,
+            ZLayer::Pile => -150.0,
+            ZLayer::Player => -100.0,
+            ZLayer::Decals => -50.0,
+            ZLayer::Warnings => 0.0,
+            ZLayer::Particles => 50.0,
+            ZLayer::Ui => 100.0,
+        }
+    }
+}
+
+fn apply_z_layers(
+    mut layered: Query<(&ZLayer, &mut Transform), Or<(Changed<ZLayer>, Changed<Transform>)>>,
+) {
+    for (layer, mut transform) in &mut layered {
+        let z = layer.z();
+        if transform.translation.z != z {
+            transform.translation.z = z;
+        }
+    }
+}
@@ -0,0 +1,103 @@
+use std::sync::{Arc, Mutex};
+
+use bevy::prelude::*;
+use bevy::render::settings::{RenderCreation, WgpuSettings};
+use bevy::render::RenderPlugin;
+use bevy::utils::HashMap;
+use bevy::window::ExitCondition;
+use bevy::winit::WinitPlugin;
+use rectfall::{GameConfig, InputScript, LoadLevel, PersistentStorage, RectfallPlugin, Storage};
+
+// a returning player's profile kept in memory, so nothing opens over the first run and
+// the real one on disk is never touched
+struct MemoryStorage(Mutex<HashMap<String, String>>);
+
+impl MemoryStorage {
+    fn returning_player() -> MemoryStorage {
+        let files = [
+            ("settings", "(version: 1, data: (telemetry: Declined))"),
+            ("save", "(version: 1, data: (seen_cutscenes: [\"intro\"]))"),
+        ];
+        MemoryStorage(Mutex::new(
+            files
+                .into_iter()
+                .map(|(key, contents)| (key.to_string(), contents.to_string()))
+                .collect(),
+        ))
+    }
+}
+
+impl Storage for MemoryStorage {
+    fn read(&self, key: &str) -> Option<String> {
+        self.0.lock().unwrap().get(key).cloned()
+    }
+
+    fn write(&self, key: &str, contents: &str) -> Result<(), String> {
+        self.0
+            .lock()
+            .unwrap()
+            .insert(key.to_string(), contents.to_string());
+        Ok(())
+    }
+}
+
+// the whole game without an os window or a gpu, playing `script` on a fixed tick from the
+// start of `level`
+pub fn headless_game(level: &str, script: &str) -> App {
+    let config = GameConfig::default();
+    let mut app = App::new();
+    app.add_plugins(
+        config
+            .default_plugins()
+            // the primary window is only an entity here, nothing ever opens it
+            .set(WindowPlugin {
+                exit_condition: ExitCondition::DontExit,
+                close_when_requested: false,
+                ..default()
+            })
+            .set(RenderPlugin {
+                render_creation: RenderCreation::Automatic(WgpuSettings {
+                    backends: None,
+                    ..default()
+                }),
+                ..default()
+            })
+            .disable::<WinitPlugin>(),
+    )
+    .insert_resource(PersistentStorage(Arc::new(
+        MemoryStorage::returning_player(),
+    )))
+    .insert_resource(ron::from_str::<InputScript>(script).unwrap())
+    .add_plugins(RectfallPlugin::new(config));
+    // run() would do this, the tests step the app themselves
+    app.finish();
+    app.cleanup();
+    app.world_mut()
+        .send_event(LoadLevel(Some(level.to_string())));
+    app
+}
+
+// loading happens in the background in real time while frames go by as fast as they can,
+// so this is only a guard against a game that never gets going. the script's expectations
+// are what bound the ticks once it does
+const MAX_FRAMES: u32 = 100_000;
+
+// steps the game until the script passes or fails
+pub fn run(app: &mut App) -> Option<AppExit> {
+    for _ in 0..MAX_FRAMES {
+        app.update();
+        if let Some(exit) = app.should_exit() {
+            return Some(exit);
+        }
+    }
+    None
+}
+
+pub fn player_position(app: &mut App) -> Vec2 {
+    let mut players = app.world_mut().query::<(&Name, &Transform)>();
+    players
+        .iter(app.world())
+        .find(|(name, _)| name.as_str() == "Player")
+        .map(|(_, transform)| transform.translation.truncate())
+        .unwrap()
+}
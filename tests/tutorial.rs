@@ -0,0 +1,50 @@
+// the tutorial level played through from code. the level's gates only open once the
+// move in front of them has been done, so reaching one is proof the move worked
+mod common;
+
+use bevy::prelude::*;
+use common::{headless_game, player_position, run};
+
+// the first gate stands at x -250, 200 to the right of the start
+const THROUGH_THE_JUMP_GATE: &str = "(
+    steps: [
+        (ticks: 10),
+        (ticks: 20, hold: [Right, Jump]),
+        (ticks: 60, hold: [Right]),
+    ],
+    expect: [(check: Right(300.0), within: 150)],
+)";
+
+// jumps at the second gate, kicks off it on the way down and again on the way back up,
+// which opens it, then runs on over the spring
+const TO_THE_CHECKPOINT: &str = "(
+    steps: [
+        (ticks: 10),
+        (ticks: 20, hold: [Right, Jump]),
+        (ticks: 100, hold: [Right]),
+        (ticks: 20, hold: [Right, Jump]),
+        (ticks: 5, hold: [Right]),
+        (ticks: 10, hold: [Right, Jump]),
+        (ticks: 25, hold: [Right]),
+        (ticks: 10, hold: [Right, Jump]),
+        (ticks: 15, hold: [Right]),
+        (ticks: 8, hold: [Right, Jump]),
+        (ticks: 177, hold: [Right]),
+    ],
+    expect: [(check: Checkpoint, within: 400)],
+)";
+
+#[test]
+fn jumps_through_the_first_gate() {
+    let mut app = headless_game("tutorial", THROUGH_THE_JUMP_GATE);
+    assert_eq!(run(&mut app), Some(AppExit::Success));
+    assert!(player_position(&mut app).x > -250.0);
+}
+
+#[test]
+fn wall_jumps_to_the_checkpoint() {
+    let mut app = headless_game("tutorial", TO_THE_CHECKPOINT);
+    assert_eq!(run(&mut app), Some(AppExit::Success));
+    // past the wall jump gate
+    assert!(player_position(&mut app).x > 250.0);
+}